@@ -0,0 +1,79 @@
+use std::{
+    fs::{self, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+use crate::dirs::get_data_dir;
+
+/// Name of the file inside the data directory holding the daemon's bearer token.
+pub const TOKEN_FILE: &str = "daemon.token";
+
+/// Returns where the daemon's bearer token is kept.
+pub fn token_path() -> PathBuf {
+    get_data_dir().join(TOKEN_FILE)
+}
+
+/// Returns a fresh random token, 32 bytes written out as hex.
+pub fn generate_token() -> String {
+    let bytes: [u8; 32] = rand::random();
+
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+/// Reads the token at `path`, `None` if there is no token yet.
+///
+/// # Errors
+///
+/// This function will return an error if the file exists but can't be read.
+pub fn load_token(path: &Path) -> io::Result<Option<String>> {
+    match fs::read_to_string(path) {
+        Ok(token) => Ok(Some(token.trim().to_owned()).filter(|t| !t.is_empty())),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// Writes `token` to `path`, readable and writable by the current user only.
+///
+/// # Errors
+///
+/// This function will return an error if the file or its parent directory can't be written.
+pub fn write_token(path: &Path, token: &str) -> io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let mut options = OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+
+        options.mode(0o600);
+        let mut file = options.open(path)?;
+        // the mode only applies to new files, tighten one that was already there too
+        file.set_permissions(fs::Permissions::from_mode(0o600))?;
+        file.write_all(token.as_bytes())
+    }
+    #[cfg(not(unix))]
+    {
+        options.open(path)?.write_all(token.as_bytes())
+    }
+}
+
+/// Reads the token at `path`, generating and writing one first if there isn't any.
+///
+/// # Errors
+///
+/// This function will return an error if the token can't be read or written.
+pub fn load_or_create_token(path: &Path) -> io::Result<String> {
+    if let Some(token) = load_token(path)? {
+        return Ok(token);
+    }
+
+    let token = generate_token();
+    write_token(path, &token)?;
+
+    Ok(token)
+}
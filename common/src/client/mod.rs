@@ -1,11 +1,15 @@
-use std::str::FromStr;
+use std::{str::FromStr, time::Duration};
 
-use reqwest::{Client, ClientBuilder, Url};
+use reqwest::{
+    Client, ClientBuilder, Response, Url,
+    header::{AUTHORIZATION, HeaderMap, HeaderValue},
+};
 use serde::{Deserialize, Serialize};
+use tracing::{error, warn};
 
 use crate::{
-    TarsError,
-    types::{Group, Id, Task},
+    ApiError, DAEMON_ADDR, ParseError, TarsError, auth, dirs, discovery, tls,
+    types::{Group, GroupPatch, Id, Notification, ScoringStrategyKind, Task, TaskPatch},
 };
 /// Holds the reqwest `Client` and the base path for accessing the `TarsDaemon`
 #[derive(Debug, Clone)]
@@ -14,37 +18,333 @@ pub struct TarsClient {
     pub conn: Client,
 }
 
+/// Environment variable pointing `TarsClient::default` at a daemon, ex. a remote one.
+pub const ADDR_VAR: &str = "TARS_ADDR";
+
+/// How a `TarsClient` authenticates with the daemon and checks who it's talking to.
+#[derive(Debug, Clone, Default)]
+pub struct ConnectOptions {
+    /// Sent as the bearer token on every request, no token at all if `None`.
+    pub token: Option<String>,
+    /// SHA-256 fingerprint of the daemon's certificate. When set, that certificate is trusted
+    /// over `https://` instead of the system's certificate authorities.
+    pub fingerprint: Option<String>,
+}
+
+/// Turns error responses of the `TarsDaemon` back into the `TarsError` they were sent as.
+pub trait DaemonResponse: Sized {
+    /// Returns the response as is if it's a success, the error it carries otherwise.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the response has an error status.
+    fn error_for_daemon(self) -> impl Future<Output = Result<Self, TarsError>> + Send;
+}
+
+impl DaemonResponse for Response {
+    async fn error_for_daemon(self) -> Result<Self, TarsError> {
+        let status = self.status();
+        if status.is_success() {
+            return Ok(self);
+        }
+
+        match self.json::<ApiError>().await {
+            Ok(err) => Err(err.into()),
+            // not the daemon answering, ex. a proxy in front of it
+            Err(_) => Err(TarsError::Internal(format!(
+                "unexpected response with status {status}"
+            ))),
+        }
+    }
+}
+
+/// Name of the event on `/subscribe` telling a client the diffs it missed are gone, it has
+/// to fetch everything again instead.
+pub const RESYNC_EVENT: &str = "resync";
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Diff {
     Added(DiffInner),
     Updated(DiffInner),
+    /// Only the fields a partial update changed, along with the new version.
+    Patched(Patch),
     Deleted(Id),
+    /// Not a change to the data itself, but a due-date reminder fired by the daemon.
+    Notification(Box<Notification>),
 }
 
+// tasks are by far the most common diff, boxing them would only add an allocation to each one
+#[allow(clippy::large_enum_variant)]
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum DiffInner {
     Task(Task),
     Group(Group),
 }
 
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Patch {
+    Task(TaskPatch),
+    Group(GroupPatch),
+}
+
 impl TarsClient {
-    /// Creates a new TarsClient with the provided base_url.
+    /// Creates a new TarsClient with the provided base_url, authenticating with the token
+    /// the daemon keeps in the data directory.
+    ///
+    /// The base_url is either an `http://` or `https://` url, or `unix://<path>` for a daemon
+    /// listening on a Unix socket. Over `https://` the certificate is pinned to the
+    /// fingerprint the daemon keeps in the data directory, if there is one.
     ///
     /// # Errors
     ///
     /// This function will return an error if .
     /// + Connecting to the daemon fails.
+    /// + The token or the fingerprint exists but can't be read.
     pub async fn new(base_url: String) -> Result<Self, TarsError> {
+        let token = auth::load_token(&auth::token_path())?;
+        let fingerprint = if base_url.starts_with("https://") {
+            tls::load_fingerprint(&tls::fingerprint_path())?
+        } else {
+            None
+        };
+
+        Self::connect(base_url, ConnectOptions { token, fingerprint }).await
+    }
+
+    /// Creates a new TarsClient with the provided base_url, sending `token` as the bearer
+    /// token on every request, or no token at all if it's `None`.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if .
+    /// + Connecting to the daemon fails.
+    /// + The token isn't a valid header value.
+    /// + The base_url is a Unix socket on a platform without them.
+    pub async fn with_token(base_url: String, token: Option<String>) -> Result<Self, TarsError> {
+        Self::connect(
+            base_url,
+            ConnectOptions {
+                token,
+                ..Default::default()
+            },
+        )
+        .await
+    }
+
+    /// Creates a new TarsClient with the provided base_url, as configured by `options`.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if .
+    /// + Connecting to the daemon fails.
+    /// + The base_url isn't a valid url.
+    /// + The token isn't a valid header value.
+    /// + The fingerprint isn't a SHA-256 fingerprint.
+    /// + The base_url is a Unix socket on a platform without them.
+    pub async fn connect(base_url: String, options: ConnectOptions) -> Result<Self, TarsError> {
         let app_agent = concat!(env!("CARGO_PKG_NAME"), "/", env!("CARGO_PKG_VERSION"));
-        let client = ClientBuilder::new().user_agent(app_agent).build()?;
+
+        let mut headers = HeaderMap::new();
+        if let Some(token) = options.token {
+            let mut value = HeaderValue::from_str(&format!("Bearer {token}"))
+                .map_err(|_| TarsError::InvalidRequest("malformed token".to_owned()))?;
+            value.set_sensitive(true);
+            headers.insert(AUTHORIZATION, value);
+        }
+
+        let mut builder = ClientBuilder::new()
+            .user_agent(app_agent)
+            .default_headers(headers);
+        if let Some(fingerprint) = options.fingerprint {
+            builder = builder.use_preconfigured_tls(tls::pinned_config(&fingerprint)?);
+        }
+
+        let (builder, base_path) = match base_url.strip_prefix("unix://") {
+            #[cfg(unix)]
+            Some(socket) => (
+                builder.unix_socket(socket),
+                // never resolved, every request goes over the socket
+                Url::from_str("http://localhost").expect("should be a valid url"),
+            ),
+            #[cfg(not(unix))]
+            Some(_) => {
+                return Err(TarsError::InvalidRequest(
+                    "unix sockets aren't supported on this platform".to_owned(),
+                ));
+            }
+            None => (
+                builder,
+                Url::from_str(&base_url).map_err(|_| {
+                    ParseError::FailedToParse(format!("the daemon address {base_url}"))
+                })?,
+            ),
+        };
 
         Ok(Self {
-            conn: client,
-            base_path: Url::from_str(&base_url).expect("should be a valid url"),
+            conn: builder.build()?,
+            base_path,
         })
     }
 
+    /// Creates a new TarsClient for the daemon at `TARS_ADDR` if it's set, otherwise for the
+    /// local daemon, at the address it wrote to the discovery file when it started. Without
+    /// one, or if nothing answers there anymore, over its Unix socket if that exists, over
+    /// TCP otherwise.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if .
+    /// + Connecting to the daemon fails.
+    /// + `TARS_ADDR` isn't a valid url.
+    /// + The discovery file or the token exists but can't be read.
     pub async fn default() -> Result<Self, TarsError> {
-        TarsClient::new("http://127.0.0.1:42069".to_owned()).await
+        if let Ok(addr) = std::env::var(ADDR_VAR) {
+            return TarsClient::new(addr).await;
+        }
+
+        if let Some(addr) = discovery::read_address(&discovery::discovery_path())? {
+            // a daemon that crashed never got to remove its address
+            match TarsClient::new(addr.clone()).await {
+                Ok(client) if client.is_reachable().await => return Ok(client),
+                Ok(_) => warn!("no daemon at {addr} from the discovery file, using the default"),
+                Err(e) => warn!("unusable address {addr} in the discovery file: {e:?}"),
+            }
+        }
+
+        let socket = dirs::get_socket_path();
+        if cfg!(unix) && socket.exists() {
+            return TarsClient::new(format!("unix://{}", socket.display())).await;
+        }
+
+        TarsClient::new(format!("http://{DAEMON_ADDR}")).await
+    }
+
+    /// Returns whether anything answers at the base_url, whatever it answers with.
+    pub async fn is_reachable(&self) -> bool {
+        self.conn
+            .get(self.base_path.clone())
+            .timeout(Duration::from_secs(1))
+            .send()
+            .await
+            .is_ok()
+    }
+
+    /// Undoes the most recent change made through the `TarsDaemon`, returning the resulting `Diff`s.
+    ///
+    /// Returns an empty `Vec` if there was nothing to undo.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if
+    /// Something goes wrong with the requests to the Daemon.
+    pub async fn undo(&self) -> Result<Vec<Diff>, TarsError> {
+        let diffs: Vec<Diff> = self
+            .conn
+            .post(self.base_path.join("/undo")?)
+            .send()
+            .await
+            .inspect_err(|e| error!("Error undoing: {:?}", e))?
+            .error_for_daemon()
+            .await
+            .inspect_err(|e| error!("Error undoing: {:?}", e))?
+            .json()
+            .await
+            .inspect_err(|e| error!("Error parsing undo: {:?}", e))?;
+
+        Ok(diffs)
+    }
+
+    /// Redoes the most recently undone change, returning the resulting `Diff`s.
+    ///
+    /// Returns an empty `Vec` if there was nothing to redo.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if
+    /// Something goes wrong with the requests to the Daemon.
+    pub async fn redo(&self) -> Result<Vec<Diff>, TarsError> {
+        let diffs: Vec<Diff> = self
+            .conn
+            .post(self.base_path.join("/redo")?)
+            .send()
+            .await
+            .inspect_err(|e| error!("Error redoing: {:?}", e))?
+            .error_for_daemon()
+            .await
+            .inspect_err(|e| error!("Error redoing: {:?}", e))?
+            .json()
+            .await
+            .inspect_err(|e| error!("Error parsing redo: {:?}", e))?;
+
+        Ok(diffs)
+    }
+
+    /// Returns the strategy the `TarsDaemon` currently ranks tasks with.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if
+    /// Something goes wrong with the requests to the Daemon.
+    pub async fn scoring_strategy(&self) -> Result<ScoringStrategyKind, TarsError> {
+        let kind: ScoringStrategyKind = self
+            .conn
+            .post(self.base_path.join("/scoring/fetch")?)
+            .send()
+            .await
+            .inspect_err(|e| error!("Error fetching scoring strategy: {:?}", e))?
+            .error_for_daemon()
+            .await
+            .inspect_err(|e| error!("Error fetching scoring strategy: {:?}", e))?
+            .json()
+            .await
+            .inspect_err(|e| error!("Error parsing scoring strategy: {:?}", e))?;
+
+        Ok(kind)
+    }
+
+    /// Switches the strategy the `TarsDaemon` ranks tasks with, until it restarts.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if
+    /// Something goes wrong with the requests to the Daemon.
+    pub async fn set_scoring_strategy(&self, kind: ScoringStrategyKind) -> Result<(), TarsError> {
+        self.conn
+            .post(self.base_path.join("/scoring/set")?)
+            .json(&kind)
+            .send()
+            .await
+            .inspect_err(|e| error!("Error setting scoring strategy: {:?}", e))?
+            .error_for_daemon()
+            .await
+            .inspect_err(|e| error!("Error setting scoring strategy: {:?}", e))?;
+
+        Ok(())
+    }
+
+    /// Replaces the token of the `TarsDaemon` with a fresh one, returning it.
+    ///
+    /// The daemon writes the new token to the data directory, clients created afterwards
+    /// pick it up, while running ones (including this one) keep sending the old one.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if
+    /// Something goes wrong with the requests to the Daemon.
+    pub async fn rotate_token(&self) -> Result<String, TarsError> {
+        let token: String = self
+            .conn
+            .post(self.base_path.join("/auth/rotate")?)
+            .send()
+            .await
+            .inspect_err(|e| error!("Error rotating token: {:?}", e))?
+            .error_for_daemon()
+            .await
+            .inspect_err(|e| error!("Error rotating token: {:?}", e))?
+            .json()
+            .await
+            .inspect_err(|e| error!("Error parsing token: {:?}", e))?;
+
+        Ok(token)
     }
 }
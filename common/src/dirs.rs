@@ -36,6 +36,19 @@ pub fn get_data_dir() -> PathBuf {
     }
 }
 
+/// Returns the directory for runtime files like sockets, the data directory on platforms
+/// without one.
+pub fn get_runtime_dir() -> PathBuf {
+    project_directory()
+        .and_then(|proj_dirs| proj_dirs.runtime_dir().map(PathBuf::from))
+        .unwrap_or_else(get_data_dir)
+}
+
+/// Returns where the daemon's Unix socket lives when it's listening on one.
+pub fn get_socket_path() -> PathBuf {
+    get_runtime_dir().join(crate::DAEMON_SOCKET)
+}
+
 fn project_directory() -> Option<ProjectDirs> {
     ProjectDirs::from("com", "suri", env!("CARGO_PKG_NAME"))
 }
@@ -0,0 +1,40 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use crate::dirs::get_data_dir;
+
+/// Name of the file inside the data directory the running daemon writes its address to.
+pub const DISCOVERY_FILE: &str = "daemon.addr";
+
+/// Returns where the running daemon's address is kept.
+pub fn discovery_path() -> PathBuf {
+    get_data_dir().join(DISCOVERY_FILE)
+}
+
+/// Reads the address at `path`, `None` if no daemon has written one.
+///
+/// # Errors
+///
+/// This function will return an error if the file exists but can't be read.
+pub fn read_address(path: &Path) -> io::Result<Option<String>> {
+    match fs::read_to_string(path) {
+        Ok(addr) => Ok(Some(addr.trim().to_owned()).filter(|a| !a.is_empty())),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// Writes `addr`, a url `TarsClient::new` accepts, to `path`.
+///
+/// # Errors
+///
+/// This function will return an error if the file or its parent directory can't be written.
+pub fn write_address(path: &Path, addr: &str) -> io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    fs::write(path, addr)
+}
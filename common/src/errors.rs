@@ -1,14 +1,47 @@
-use axum::response::IntoResponse;
+use axum::{Json, response::IntoResponse};
 use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
 use tokio::sync::broadcast::error::SendError;
 
-use crate::Diff;
+use crate::{Diff, DiffInner};
 
 #[derive(Error, Debug)]
 pub enum ParseError {
-    #[error("Failed to Parse")]
-    FailedToParse,
+    #[error("Failed to parse {0}")]
+    FailedToParse(String),
+}
+
+/// Machine-readable kind of an `ApiError`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    /// A field of the request failed validation.
+    Invalid,
+    /// Something the request refers to doesn't exist.
+    NotFound,
+    /// The request couldn't be understood at all.
+    BadRequest,
+    Unauthorized,
+    Forbidden,
+    /// The row was changed since the copy the request is based on was fetched.
+    Conflict,
+    /// The daemon failed on its end, the request itself may be fine.
+    Internal,
+}
+
+/// Body of every error response of the `TarsDaemon`.
+#[derive(Error, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[error("{message}")]
+pub struct ApiError {
+    pub code: ErrorCode,
+    pub message: String,
+    /// The field of the request at fault, if it's down to a single one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub field: Option<String>,
+    /// The row as it is now, for a `Conflict`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub current: Option<Box<DiffInner>>,
 }
 
 #[derive(Error, Debug)]
@@ -26,26 +59,155 @@ pub enum TarsError {
     UrlError(#[from] url::ParseError),
 
     #[error("Send Error!")]
-    SendError(#[from] SendError<Diff>),
+    // boxed, a `Diff` carries a whole task which would bloat every result
+    SendError(#[from] Box<SendError<Diff>>),
+
+    #[error("Invalid Request: {0}")]
+    InvalidRequest(String),
+
+    #[error("Io Error!")]
+    Io(#[from] std::io::Error),
+
+    #[error("Unauthorized, missing or wrong bearer token")]
+    Unauthorized,
+
+    #[error("Forbidden: {0}")]
+    Forbidden(String),
+
+    #[error("Invalid {field}: {message}")]
+    Invalid { field: String, message: String },
+
+    #[error("Not found: {message}")]
+    NotFound {
+        field: Option<String>,
+        message: String,
+    },
+
+    #[error("Daemon Error: {0}")]
+    Internal(String),
+
+    #[error("Conflict, this was changed in the meantime")]
+    // boxed for the same reason as `SendError`
+    Conflict(Box<DiffInner>),
+}
+
+impl TarsError {
+    /// Shorthand for an `Invalid` error on `field`.
+    pub fn invalid(field: &str, message: impl Into<String>) -> Self {
+        TarsError::Invalid {
+            field: field.to_owned(),
+            message: message.into(),
+        }
+    }
+
+    /// Shorthand for a `NotFound` error on `field`.
+    pub fn not_found(field: &str, message: impl Into<String>) -> Self {
+        TarsError::NotFound {
+            field: Some(field.to_owned()),
+            message: message.into(),
+        }
+    }
+
+    /// Returns the status and body the daemon responds with for this error.
+    pub fn to_api_error(&self) -> (StatusCode, ApiError) {
+        let (status, code, field) = match self {
+            TarsError::Sqlx(sqlx::Error::InvalidArgument(_)) => {
+                (StatusCode::BAD_REQUEST, ErrorCode::BadRequest, None)
+            }
+            TarsError::Sqlx(sqlx::Error::RowNotFound) => {
+                (StatusCode::NOT_FOUND, ErrorCode::NotFound, None)
+            }
+            TarsError::InvalidRequest(_) => (StatusCode::BAD_REQUEST, ErrorCode::BadRequest, None),
+            TarsError::Unauthorized => (StatusCode::UNAUTHORIZED, ErrorCode::Unauthorized, None),
+            TarsError::Forbidden(_) => (StatusCode::FORBIDDEN, ErrorCode::Forbidden, None),
+            TarsError::Invalid { field, .. } => (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                ErrorCode::Invalid,
+                Some(field.clone()),
+            ),
+            TarsError::NotFound { field, .. } => {
+                (StatusCode::NOT_FOUND, ErrorCode::NotFound, field.clone())
+            }
+            TarsError::Conflict(_) => (
+                StatusCode::CONFLICT,
+                ErrorCode::Conflict,
+                Some("version".to_owned()),
+            ),
+            // this would never be hit for url errors
+            TarsError::Reqwest(_)
+            | TarsError::Sqlx(_)
+            | TarsError::Parse(_)
+            | TarsError::UrlError(_)
+            | TarsError::SendError(_)
+            | TarsError::Io(_)
+            | TarsError::Internal(_) => {
+                (StatusCode::INTERNAL_SERVER_ERROR, ErrorCode::Internal, None)
+            }
+        };
+
+        let message = match self {
+            TarsError::Sqlx(sqlx::Error::RowNotFound) => "no such row".to_owned(),
+            TarsError::InvalidRequest(message)
+            | TarsError::Forbidden(message)
+            | TarsError::Invalid { message, .. }
+            | TarsError::NotFound { message, .. }
+            | TarsError::Internal(message) => message.clone(),
+            // the details stay in the daemon's log
+            other => other.to_string(),
+        };
+
+        let current = match self {
+            TarsError::Conflict(current) => Some(current.clone()),
+            _ => None,
+        };
+
+        (
+            status,
+            ApiError {
+                code,
+                message,
+                field,
+                current,
+            },
+        )
+    }
+}
+
+impl From<ApiError> for TarsError {
+    fn from(value: ApiError) -> Self {
+        match value.code {
+            ErrorCode::Invalid => TarsError::Invalid {
+                field: value.field.unwrap_or_default(),
+                message: value.message,
+            },
+            ErrorCode::NotFound => TarsError::NotFound {
+                field: value.field,
+                message: value.message,
+            },
+            ErrorCode::BadRequest => TarsError::InvalidRequest(value.message),
+            ErrorCode::Unauthorized => TarsError::Unauthorized,
+            ErrorCode::Forbidden => TarsError::Forbidden(value.message),
+            ErrorCode::Conflict => match value.current {
+                Some(current) => TarsError::Conflict(current),
+                None => TarsError::Internal(value.message),
+            },
+            ErrorCode::Internal => TarsError::Internal(value.message),
+        }
+    }
+}
+
+impl From<SendError<Diff>> for TarsError {
+    fn from(value: SendError<Diff>) -> Self {
+        TarsError::SendError(Box::new(value))
+    }
 }
 
 impl IntoResponse for TarsError {
     fn into_response(self) -> axum::response::Response {
-        let status = match self {
-            TarsError::Reqwest(_) => StatusCode::INTERNAL_SERVER_ERROR,
-            TarsError::Sqlx(ref e) => match e {
-                sqlx::Error::InvalidArgument(_) => StatusCode::BAD_REQUEST,
-                sqlx::Error::RowNotFound => StatusCode::NOT_FOUND,
-                _ => StatusCode::INTERNAL_SERVER_ERROR,
-            },
-            TarsError::Parse(_) => StatusCode::INTERNAL_SERVER_ERROR,
-            // this would never be hit
-            TarsError::UrlError(_) => StatusCode::INTERNAL_SERVER_ERROR,
-            TarsError::SendError(_) => StatusCode::INTERNAL_SERVER_ERROR,
-        };
+        let (status, body) = self.to_api_error();
 
         tracing::error!("TarsError: {:?}, returning status code: {}", self, status);
 
-        status.into_response()
+        (status, Json(body)).into_response()
     }
 }
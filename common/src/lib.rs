@@ -1,8 +1,13 @@
+pub mod auth;
 pub mod dirs;
+pub mod discovery;
 mod errors;
+pub mod tls;
 pub mod types;
 pub use errors::*;
 mod client;
 pub use client::*;
 pub mod logging;
 pub const DAEMON_ADDR: &str = "127.0.0.1:42069";
+/// Name of the daemon's Unix socket inside the runtime directory.
+pub const DAEMON_SOCKET: &str = "tars.sock";
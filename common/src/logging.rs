@@ -8,6 +8,12 @@ use crate::dirs::get_data_dir;
 const LOG_ENV: &str = "TARS_LOG_LEVL";
 
 pub fn init(logfile_name: &str, term_out: bool) -> Result<()> {
+    init_with_level(logfile_name, term_out, None)
+}
+
+/// Same as `init`, except `level` (ex. `debug` or `tars_daemon=trace`) takes precedence over
+/// the environment variables when it's given.
+pub fn init_with_level(logfile_name: &str, term_out: bool, level: Option<&str>) -> Result<()> {
     let directory = get_data_dir();
     std::fs::create_dir_all(directory.clone())?;
     let log_path = directory.join(logfile_name);
@@ -17,9 +23,12 @@ pub fn init(logfile_name: &str, term_out: bool) -> Result<()> {
     // If the `RUST_LOG` environment variable is set, use that as the default, otherwise use the
     // value of the `LOG_ENV` environment variable. If the `LOG_ENV` environment variable contains
     // errors, then this will return an error.
-    let env_filter = env_filter
-        .try_from_env()
-        .or_else(|_| env_filter.with_env_var(LOG_ENV.to_owned()).from_env())?;
+    let env_filter = match level {
+        Some(level) => env_filter.parse(level)?,
+        None => env_filter
+            .try_from_env()
+            .or_else(|_| env_filter.with_env_var(LOG_ENV.to_owned()).from_env())?,
+    };
     let file_subscriber = fmt::layer()
         .with_file(true)
         .with_writer(log_file)
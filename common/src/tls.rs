@@ -0,0 +1,153 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use rustls::{
+    ClientConfig, DigitallySignedStruct, SignatureScheme,
+    client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier},
+    crypto::{self, WebPkiSupportedAlgorithms},
+    pki_types::{CertificateDer, ServerName, UnixTime},
+};
+use sha2::{Digest, Sha256};
+
+use crate::{TarsError, dirs::get_data_dir};
+
+/// Name of the file inside the data directory the daemon writes its certificate's
+/// fingerprint to while serving over TLS.
+pub const FINGERPRINT_FILE: &str = "daemon.fingerprint";
+
+/// Returns where the daemon's certificate fingerprint is kept.
+pub fn fingerprint_path() -> PathBuf {
+    get_data_dir().join(FINGERPRINT_FILE)
+}
+
+/// Returns the SHA-256 fingerprint of a DER encoded certificate, written as colon separated
+/// hex like `openssl x509 -fingerprint -sha256` does.
+pub fn fingerprint(der: &[u8]) -> String {
+    Sha256::digest(der)
+        .iter()
+        .map(|b| format!("{b:02X}"))
+        .collect::<Vec<_>>()
+        .join(":")
+}
+
+/// Reads the fingerprint at `path`, `None` if there is none.
+///
+/// # Errors
+///
+/// This function will return an error if the file exists but can't be read.
+pub fn load_fingerprint(path: &Path) -> io::Result<Option<String>> {
+    match fs::read_to_string(path) {
+        Ok(fp) => Ok(Some(fp.trim().to_owned()).filter(|f| !f.is_empty())),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// Writes `fingerprint` to `path`.
+///
+/// # Errors
+///
+/// This function will return an error if the file or its parent directory can't be written.
+pub fn write_fingerprint(path: &Path, fingerprint: &str) -> io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    fs::write(path, fingerprint)
+}
+
+/// Parses a SHA-256 fingerprint, hex with or without colons and an optional `sha256:` prefix.
+fn parse_fingerprint(fingerprint: &str) -> Option<Vec<u8>> {
+    let hex: String = fingerprint
+        .trim()
+        .trim_start_matches("sha256:")
+        .chars()
+        .filter(|c| *c != ':')
+        .collect();
+    if hex.len() != 64 || !hex.is_ascii() {
+        return None;
+    }
+
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).ok())
+        .collect()
+}
+
+/// Returns a rustls config that only trusts the certificate with the given fingerprint,
+/// whatever its issuer or the names on it.
+///
+/// # Errors
+///
+/// This function will return an error if the fingerprint isn't a SHA-256 fingerprint.
+pub(crate) fn pinned_config(fingerprint: &str) -> Result<ClientConfig, TarsError> {
+    let expected = parse_fingerprint(fingerprint)
+        .ok_or_else(|| TarsError::InvalidRequest("malformed certificate fingerprint".to_owned()))?;
+
+    let provider = Arc::new(crypto::aws_lc_rs::default_provider());
+    let verifier = PinnedCert {
+        expected,
+        algorithms: provider.signature_verification_algorithms,
+    };
+
+    let config = ClientConfig::builder_with_provider(provider)
+        .with_safe_default_protocol_versions()
+        .map_err(|e| TarsError::InvalidRequest(e.to_string()))?
+        .dangerous()
+        .with_custom_certificate_verifier(Arc::new(verifier))
+        .with_no_client_auth();
+
+    Ok(config)
+}
+
+/// Accepts exactly one certificate, the one whose SHA-256 digest is `expected`.
+#[derive(Debug)]
+struct PinnedCert {
+    expected: Vec<u8>,
+    algorithms: WebPkiSupportedAlgorithms,
+}
+
+impl ServerCertVerifier for PinnedCert {
+    fn verify_server_cert(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        if Sha256::digest(end_entity).as_slice() == self.expected.as_slice() {
+            Ok(ServerCertVerified::assertion())
+        } else {
+            Err(rustls::Error::General(
+                "certificate doesn't match the pinned fingerprint".to_owned(),
+            ))
+        }
+    }
+
+    // the daemon still has to prove it holds the key of the pinned certificate
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        crypto::verify_tls12_signature(message, cert, dss, &self.algorithms)
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        crypto::verify_tls13_signature(message, cert, dss, &self.algorithms)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.algorithms.supported_schemes()
+    }
+}
@@ -0,0 +1,18 @@
+use serde::{Deserialize, Serialize};
+
+/// A single step of a `Task`'s checklist, for procedures too small to deserve their own `Group`.
+#[derive(PartialEq, Eq, Debug, Serialize, Deserialize, Clone, PartialOrd, Ord, Hash)]
+pub struct ChecklistItem {
+    pub name: String,
+    pub done: bool,
+}
+
+impl ChecklistItem {
+    /// Initializes an unchecked `ChecklistItem`.
+    pub fn new(name: impl Into<String>) -> Self {
+        ChecklistItem {
+            name: name.into(),
+            done: false,
+        }
+    }
+}
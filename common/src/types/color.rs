@@ -11,7 +11,9 @@ pub struct Color(pub String);
 impl TryFrom<String> for Color {
     type Error = ParseError;
     fn try_from(value: String) -> Result<Self, Self::Error> {
-        let _col: RatColor = value.parse().map_err(|_| ParseError::FailedToParse)?;
+        let _col: RatColor = value
+            .parse()
+            .map_err(|_| ParseError::FailedToParse(format!("{value:?} as a color")))?;
 
         Ok(Self(value))
     }
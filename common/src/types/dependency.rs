@@ -0,0 +1,19 @@
+use serde::{Deserialize, Serialize};
+
+use super::{Id, Task};
+
+/// An edge in the dependency graph, `task_id` can't be worked on until `blocked_by` is finished.
+#[derive(PartialEq, Eq, Debug, Serialize, Deserialize, Clone)]
+pub struct TaskDependency {
+    pub task_id: Id,
+    pub blocked_by: Id,
+}
+
+/// The direct neighbours of a `Task` in the dependency graph.
+#[derive(PartialEq, Eq, Debug, Serialize, Deserialize, Clone, Default)]
+pub struct TaskDependencies {
+    /// Tasks that have to be finished first.
+    pub blockers: Vec<Task>,
+    /// Tasks waiting on this one.
+    pub dependents: Vec<Task>,
+}
@@ -0,0 +1,117 @@
+use std::{error::Error, fmt::Display};
+
+use serde::{Deserialize, Serialize};
+use sqlx::{Database, Decode, Encode, Sqlite, Type};
+
+use crate::ParseError;
+
+/// How long a `Task` is expected to take, to the minute.
+#[derive(
+    PartialEq, Eq, Debug, Serialize, Deserialize, Clone, Copy, PartialOrd, Ord, Hash, Default,
+)]
+#[serde(transparent)]
+pub struct Estimate {
+    minutes: u32,
+}
+
+impl Estimate {
+    pub fn from_minutes(minutes: u32) -> Self {
+        Estimate { minutes }
+    }
+
+    pub fn minutes(&self) -> u32 {
+        self.minutes
+    }
+
+    /// Parser for clap / the inspector, accepts `45m`, `2h`, `1h30m`, `1.5h` or a bare
+    /// number of minutes.
+    pub fn parse_str(str: &str) -> Result<Self, ParseError> {
+        str.try_into()
+    }
+}
+
+impl TryFrom<&str> for Estimate {
+    type Error = ParseError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        let value = value.trim().to_ascii_lowercase().replace(' ', "");
+        if value.is_empty() {
+            return Err(ParseError::FailedToParse(format!(
+                "{value:?} as an estimate"
+            )));
+        }
+
+        let (hours, minutes) = match value.split_once('h') {
+            Some((hours, minutes)) => (hours, minutes.strip_suffix('m').unwrap_or(minutes)),
+            None => ("", value.strip_suffix('m').unwrap_or(&value)),
+        };
+
+        let hours: f64 = if hours.is_empty() {
+            0.0
+        } else {
+            hours
+                .parse()
+                .map_err(|_| ParseError::FailedToParse(format!("{value:?} as an estimate")))?
+        };
+        let minutes: u32 = if minutes.is_empty() {
+            0
+        } else {
+            minutes
+                .parse()
+                .map_err(|_| ParseError::FailedToParse(format!("{value:?} as an estimate")))?
+        };
+
+        if !hours.is_finite() || hours < 0.0 {
+            return Err(ParseError::FailedToParse(format!(
+                "{value:?} as an estimate"
+            )));
+        }
+
+        Ok(Estimate::from_minutes(
+            (hours * 60.0).round() as u32 + minutes,
+        ))
+    }
+}
+
+impl Display for Estimate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (self.minutes / 60, self.minutes % 60) {
+            (0, minutes) => write!(f, "{minutes}m"),
+            (hours, 0) => write!(f, "{hours}h"),
+            (hours, minutes) => write!(f, "{hours}h{minutes:02}m"),
+        }
+    }
+}
+
+// stored as the number of minutes
+impl<'r, DB: Database> Decode<'r, DB> for Estimate
+where
+    i64: Decode<'r, DB>,
+{
+    fn decode(
+        value: <DB as Database>::ValueRef<'r>,
+    ) -> Result<Estimate, Box<dyn Error + 'static + Send + Sync>> {
+        let minutes = <i64 as Decode<DB>>::decode(value)?;
+
+        Ok(Estimate::from_minutes(u32::try_from(minutes)?))
+    }
+}
+
+impl<'q> Encode<'q, Sqlite> for Estimate {
+    fn encode_by_ref(
+        &self,
+        buf: &mut <Sqlite as Database>::ArgumentBuffer<'q>,
+    ) -> Result<sqlx::encode::IsNull, sqlx::error::BoxDynError> {
+        <i64 as Encode<'_, Sqlite>>::encode(self.minutes as i64, buf)
+    }
+}
+
+impl Type<Sqlite> for Estimate {
+    fn type_info() -> <Sqlite as Database>::TypeInfo {
+        <i64 as Type<Sqlite>>::type_info()
+    }
+
+    fn compatible(ty: &<Sqlite as Database>::TypeInfo) -> bool {
+        <i64 as Type<Sqlite>>::compatible(ty)
+    }
+}
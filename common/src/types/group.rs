@@ -10,7 +10,7 @@ use tracing::error;
 use std::error::Error;
 
 use crate::types::{Color, Priority};
-use crate::{TarsClient, TarsError};
+use crate::{DaemonResponse, TarsClient, TarsError};
 
 use super::{Id, Name};
 
@@ -23,6 +23,19 @@ pub struct Group {
     pub parent_id: Option<Id>,
     pub created_at: NaiveDateTime,
     pub color: Color,
+    /// Bumped by the daemon on every update, an update has to carry the current one.
+    #[serde(default)]
+    pub version: i64,
+}
+
+/// Request to move a `Group` under a new parent, or to the top level if `parent_id` is `None`.
+#[derive(PartialEq, Eq, Debug, Serialize, Deserialize, Clone)]
+pub struct GroupMove {
+    pub id: Id,
+    pub parent_id: Option<Id>,
+    /// The version of the group being moved, the move is refused if it was updated since.
+    #[serde(default)]
+    pub version: i64,
 }
 
 impl Group {
@@ -41,6 +54,7 @@ impl Group {
             priority,
             created_at,
             color,
+            version: 0,
         }
     }
 
@@ -69,6 +83,9 @@ impl Group {
             .send()
             .await
             .inspect_err(|e| error!("Error creating Group: {:?}", e))?
+            .error_for_daemon()
+            .await
+            .inspect_err(|e| error!("Error creating Group: {:?}", e))?
             .json()
             .await
             .inspect_err(|e| error!("Error creating Group: {:?}", e))?;
@@ -92,6 +109,9 @@ impl Group {
             .send()
             .await
             .inspect_err(|e| error!("Error creating Group: {:?}", e))?
+            .error_for_daemon()
+            .await
+            .inspect_err(|e| error!("Error creating Group: {:?}", e))?
             .json()
             .await
             .inspect_err(|e| error!("Error creating Group: {:?}", e))?;
@@ -112,6 +132,9 @@ impl Group {
             .send()
             .await
             .inspect_err(|e| error!("Error Fetching Group: {:?}", e))?
+            .error_for_daemon()
+            .await
+            .inspect_err(|e| error!("Error Fetching Group: {:?}", e))?
             .json()
             .await
             .inspect_err(|e| error!("Error Fetching Group: {:?}", e))?;
@@ -119,14 +142,16 @@ impl Group {
         Ok(res)
     }
 
-    /// Sync's this `Group` with its representation in database, via the `TarsDaemon`.
+    /// Sync's this `Group` with its representation in database, via the `TarsDaemon`,
+    /// taking on the version the daemon assigned.
     ///
     /// # Errors
     ///
     /// This function will return an error if
     /// + Something goes wrong with the requests to the Daemon.
-    /// + Will panic at runtime if the sync'd `Group` doesnt match with `self`
-    pub async fn sync(&self, client: &TarsClient) -> Result<(), TarsError> {
+    /// + The `Group` was changed since this copy was fetched, `TarsError::Conflict` carries
+    ///   the current one.
+    pub async fn sync(&mut self, client: &TarsClient) -> Result<(), TarsError> {
         let res: Group = client
             .conn
             .post(client.base_path.join("/group/update")?)
@@ -134,22 +159,61 @@ impl Group {
             .send()
             .await
             .inspect_err(|e| error!("Error Sync'ing Group: {:?}", e))?
+            .error_for_daemon()
+            .await
+            .inspect_err(|e| error!("Error Sync'ing Group: {:?}", e))?
             .json()
             .await
             .inspect_err(|e| error!("Error Sync'ing Group: {:?}", e))?;
 
-        assert_eq!(res, *self);
+        *self = res;
 
         Ok(())
     }
 
+    /// Moves this `Group` (along with everything inside it) under the group with `parent_id`,
+    /// or to the top level if `None`, returning the moved `Group`.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if
+    /// + Something goes wrong with the requests to the Daemon.
+    /// + The daemon refuses the move, ex. moving a group into one of its own descendants.
+    /// + The group was updated since this copy was fetched, the conflict holds the current one.
+    pub async fn move_to(
+        &self,
+        client: &TarsClient,
+        parent_id: Option<Id>,
+    ) -> Result<Group, TarsError> {
+        let moved: Group = client
+            .conn
+            .post(client.base_path.join("/group/move")?)
+            .json(&GroupMove {
+                id: self.id.clone(),
+                parent_id,
+                version: self.version,
+            })
+            .send()
+            .await
+            .inspect_err(|e| error!("Error Moving Group: {:?}", e))?
+            .error_for_daemon()
+            .await
+            .inspect_err(|e| error!("Error Moving Group: {:?}", e))?
+            .json()
+            .await
+            .inspect_err(|e| error!("Error Moving Group: {:?}", e))?;
+
+        Ok(moved)
+    }
+
     /// Deletes this `Group` via the `TarsDaemon`.
     ///
     /// # Errors
     ///
     /// This function will return an error if
     /// + Something goes wrong with the requests to the Daemon.
-    /// + Will panic at runtime if deleted `Group` doesnt match the `Group` we wanted to delete.
+    /// + The `Group` was changed since this copy, `TarsError::Conflict` carries the current one.
+    /// + The Daemon deleted some other `Group` than this one.
     pub async fn delete(&self, client: &TarsClient) -> Result<(), TarsError> {
         let deleted: Group = client
             .conn
@@ -158,11 +222,19 @@ impl Group {
             .send()
             .await
             .inspect_err(|e| error!("Error Deleting Group: {:?}", e))?
+            .error_for_daemon()
+            .await
+            .inspect_err(|e| error!("Error Deleting Group: {:?}", e))?
             .json()
             .await
             .inspect_err(|e| error!("Error Deleting Group: {:?}", e))?;
 
-        assert_eq!(deleted, *self);
+        if deleted.id != self.id {
+            return Err(TarsError::Internal(format!(
+                "deleted group {} instead of {}",
+                *deleted.id, *self.id
+            )));
+        }
 
         Ok(())
     }
@@ -176,6 +248,9 @@ impl Group {
             .send()
             .await
             .inspect_err(|e| error!("Error fetching score for Group: {:?}", e))?
+            .error_for_daemon()
+            .await
+            .inspect_err(|e| error!("Error fetching score for Group: {:?}", e))?
             .json()
             .await
             .inspect_err(|e| error!("Error parsing score for Group: {:?}", e))?;
@@ -1,16 +1,44 @@
+mod checklist;
 mod color;
+mod dependency;
+mod estimate;
 mod group;
 mod id;
 mod name;
+mod patch;
+mod plan;
 mod priority;
+mod recurrence;
+mod reminder;
+mod schedule;
+mod scoring;
+mod search;
+mod snapshot;
+mod tag;
 mod task;
+mod time_entry;
+mod token;
 
+pub use checklist::*;
 pub use color::*;
+pub use dependency::*;
+pub use estimate::*;
 pub use group::*;
 pub use id::*;
 pub use name::*;
+pub use patch::*;
+pub use plan::*;
 pub use priority::*;
+pub use recurrence::*;
+pub use reminder::*;
+pub use schedule::*;
+pub use scoring::*;
+pub use search::*;
+pub use snapshot::*;
+pub use tag::*;
 pub use task::*;
+pub use time_entry::*;
+pub use token::*;
 
 use crate::ParseError;
 use chrono::{Datelike, NaiveDate, NaiveDateTime, Utc};
@@ -28,7 +56,9 @@ pub fn parse_date_time(possible_date: &str) -> Result<NaiveDateTime, ParseError>
     ) {
         date = parsed.and_hms_opt(23, 59, 59).unwrap()
     } else {
-        return Err(ParseError::FailedToParse);
+        return Err(ParseError::FailedToParse(format!(
+            "{possible_date:?} as a date"
+        )));
     }
     Ok(date)
 }
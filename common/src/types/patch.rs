@@ -0,0 +1,339 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use tracing::error;
+
+use crate::{DaemonResponse, TarsClient, TarsError};
+
+use super::{ChecklistItem, Color, Estimate, Group, Id, Name, Priority, Recurrence, Tag, Task};
+
+/// Changes to some of the fields of a `Task`, the ones left out stay as they are.
+///
+/// Moving a task to another group, and what blocks it, go through `Task::sync`,
+/// `Task::block_on` and `Task::unblock` instead.
+#[derive(PartialEq, Eq, Debug, Serialize, Deserialize, Clone)]
+pub struct TaskPatch {
+    pub id: Id,
+    /// When sent, the version the patch was made against, only checked if present. In a
+    /// diff, the version the task has after it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<Name>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub priority: Option<Priority>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// `Some(None)` marks the task as unfinished.
+    #[serde(default, skip_serializing_if = "Option::is_none", with = "nullable")]
+    pub finished_at: Option<Option<NaiveDateTime>>,
+    #[serde(default, skip_serializing_if = "Option::is_none", with = "nullable")]
+    pub due: Option<Option<NaiveDateTime>>,
+    #[serde(default, skip_serializing_if = "Option::is_none", with = "nullable")]
+    pub recurrence: Option<Option<Recurrence>>,
+    #[serde(default, skip_serializing_if = "Option::is_none", with = "nullable")]
+    pub estimate: Option<Option<Estimate>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tags: Option<Vec<Tag>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub checklist: Option<Vec<ChecklistItem>>,
+}
+
+/// Changes to some of the fields of a `Group`, the ones left out stay as they are.
+///
+/// Moving a group under another one goes through `Group::move_to` instead.
+#[derive(PartialEq, Eq, Debug, Serialize, Deserialize, Clone)]
+pub struct GroupPatch {
+    pub id: Id,
+    /// When sent, the version the patch was made against, only checked if present. In a
+    /// diff, the version the group has after it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<Name>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub priority: Option<Priority>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<Color>,
+}
+
+/// Tells a field that's left out apart from one that's set to `null`, for the fields of a
+/// patch that are optional themselves.
+mod nullable {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<T: Serialize, S: Serializer>(
+        value: &Option<Option<T>>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        // left out fields are skipped before getting here
+        value
+            .as_ref()
+            .and_then(Option::as_ref)
+            .serialize(serializer)
+    }
+
+    pub fn deserialize<'de, T: Deserialize<'de>, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<Option<T>>, D::Error> {
+        Option::<T>::deserialize(deserializer).map(Some)
+    }
+}
+
+/// Returns `new` if it differs from `old`, for the fields of `between`.
+fn changed<T: PartialEq + Clone>(old: &T, new: &T) -> Option<T> {
+    (old != new).then(|| new.clone())
+}
+
+impl TaskPatch {
+    /// Starts an empty patch of the task with this `Id`.
+    pub fn new(id: Id) -> Self {
+        Self {
+            id,
+            version: None,
+            name: None,
+            priority: None,
+            description: None,
+            finished_at: None,
+            due: None,
+            recurrence: None,
+            estimate: None,
+            tags: None,
+            checklist: None,
+        }
+    }
+
+    /// Returns the patch that turns `old` into `new`, holding only the fields that differ
+    /// along with the version of `new`.
+    pub fn between(old: &Task, new: &Task) -> Self {
+        Self {
+            id: new.id.clone(),
+            version: Some(new.version),
+            name: changed(&old.name, &new.name),
+            priority: changed(&old.priority, &new.priority),
+            description: changed(&old.description, &new.description),
+            finished_at: changed(&old.finished_at, &new.finished_at),
+            due: changed(&old.due, &new.due),
+            recurrence: changed(&old.recurrence, &new.recurrence),
+            estimate: changed(&old.estimate, &new.estimate),
+            tags: changed(&old.tags, &new.tags),
+            checklist: changed(&old.checklist, &new.checklist),
+        }
+    }
+
+    /// Only applies the patch if the task is still at `version`, otherwise the daemon
+    /// answers with a conflict.
+    pub fn if_version(mut self, version: i64) -> Self {
+        self.version = Some(version);
+        self
+    }
+
+    pub fn name(mut self, name: impl Into<Name>) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+
+    pub fn priority(mut self, priority: Priority) -> Self {
+        self.priority = Some(priority);
+        self
+    }
+
+    pub fn description(mut self, description: impl Into<String>) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+
+    pub fn finished_at(mut self, finished_at: Option<NaiveDateTime>) -> Self {
+        self.finished_at = Some(finished_at);
+        self
+    }
+
+    pub fn due(mut self, due: Option<NaiveDateTime>) -> Self {
+        self.due = Some(due);
+        self
+    }
+
+    pub fn recurrence(mut self, recurrence: Option<Recurrence>) -> Self {
+        self.recurrence = Some(recurrence);
+        self
+    }
+
+    pub fn estimate(mut self, estimate: Option<Estimate>) -> Self {
+        self.estimate = Some(estimate);
+        self
+    }
+
+    pub fn tags(mut self, tags: Vec<Tag>) -> Self {
+        self.tags = Some(tags);
+        self
+    }
+
+    pub fn checklist(mut self, checklist: Vec<ChecklistItem>) -> Self {
+        self.checklist = Some(checklist);
+        self
+    }
+
+    /// Writes every field present in the patch onto `task`, the version included.
+    pub fn apply_to(&self, task: &mut Task) {
+        if let Some(version) = self.version {
+            task.version = version;
+        }
+        if let Some(ref name) = self.name {
+            task.name = name.clone();
+        }
+        if let Some(priority) = self.priority {
+            task.priority = priority;
+        }
+        if let Some(ref description) = self.description {
+            task.description = description.clone();
+        }
+        if let Some(finished_at) = self.finished_at {
+            task.finished_at = finished_at;
+        }
+        if let Some(due) = self.due {
+            task.due = due;
+        }
+        if let Some(ref recurrence) = self.recurrence {
+            task.recurrence = recurrence.clone();
+        }
+        if let Some(estimate) = self.estimate {
+            task.estimate = estimate;
+        }
+        if let Some(ref tags) = self.tags {
+            task.set_tags(tags.clone());
+        }
+        if let Some(ref checklist) = self.checklist {
+            task.checklist = checklist.clone();
+        }
+    }
+
+    /// Applies the patch through the `TarsDaemon`, returning the whole `Task` after it.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if
+    /// + Something goes wrong with the requests to the Daemon.
+    /// + The patch carries a version and the `Task` was changed since, `TarsError::Conflict`
+    ///   carries the current one.
+    pub async fn send(&self, client: &TarsClient) -> Result<Task, TarsError> {
+        let task: Task = client
+            .conn
+            .post(client.base_path.join("/task/patch")?)
+            .json(self)
+            .send()
+            .await
+            .inspect_err(|e| error!("Error patching Task: {:?}", e))?
+            .error_for_daemon()
+            .await
+            .inspect_err(|e| error!("Error patching Task: {:?}", e))?
+            .json()
+            .await
+            .inspect_err(|e| error!("Error patching Task: {:?}", e))?;
+
+        Ok(task)
+    }
+}
+
+impl GroupPatch {
+    /// Starts an empty patch of the group with this `Id`.
+    pub fn new(id: Id) -> Self {
+        Self {
+            id,
+            version: None,
+            name: None,
+            priority: None,
+            color: None,
+        }
+    }
+
+    /// Returns the patch that turns `old` into `new`, holding only the fields that differ
+    /// along with the version of `new`.
+    pub fn between(old: &Group, new: &Group) -> Self {
+        Self {
+            id: new.id.clone(),
+            version: Some(new.version),
+            name: changed(&old.name, &new.name),
+            priority: changed(&old.priority, &new.priority),
+            color: changed(&old.color, &new.color),
+        }
+    }
+
+    /// Only applies the patch if the group is still at `version`, otherwise the daemon
+    /// answers with a conflict.
+    pub fn if_version(mut self, version: i64) -> Self {
+        self.version = Some(version);
+        self
+    }
+
+    pub fn name(mut self, name: impl Into<Name>) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+
+    pub fn priority(mut self, priority: Priority) -> Self {
+        self.priority = Some(priority);
+        self
+    }
+
+    pub fn color(mut self, color: Color) -> Self {
+        self.color = Some(color);
+        self
+    }
+
+    /// Writes every field present in the patch onto `group`, the version included.
+    pub fn apply_to(&self, group: &mut Group) {
+        if let Some(version) = self.version {
+            group.version = version;
+        }
+        if let Some(ref name) = self.name {
+            group.name = name.clone();
+        }
+        if let Some(priority) = self.priority {
+            group.priority = priority;
+        }
+        if let Some(ref color) = self.color {
+            group.color = color.clone();
+        }
+    }
+
+    /// Applies the patch through the `TarsDaemon`, returning the whole `Group` after it.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if
+    /// + Something goes wrong with the requests to the Daemon.
+    /// + The patch carries a version and the `Group` was changed since, `TarsError::Conflict`
+    ///   carries the current one.
+    pub async fn send(&self, client: &TarsClient) -> Result<Group, TarsError> {
+        let group: Group = client
+            .conn
+            .post(client.base_path.join("/group/patch")?)
+            .json(self)
+            .send()
+            .await
+            .inspect_err(|e| error!("Error patching Group: {:?}", e))?
+            .error_for_daemon()
+            .await
+            .inspect_err(|e| error!("Error patching Group: {:?}", e))?
+            .json()
+            .await
+            .inspect_err(|e| error!("Error patching Group: {:?}", e))?;
+
+        Ok(group)
+    }
+}
+
+impl Task {
+    /// Starts a `TaskPatch` of this task, ex.
+    /// `task.patch().priority(Priority::High).send(&client)`.
+    pub fn patch(&self) -> TaskPatch {
+        TaskPatch::new(self.id.clone())
+    }
+}
+
+impl Group {
+    /// Starts a `GroupPatch` of this group, ex.
+    /// `group.patch().name("work").send(&client)`.
+    pub fn patch(&self) -> GroupPatch {
+        GroupPatch::new(self.id.clone())
+    }
+}
@@ -0,0 +1,77 @@
+use serde::{Deserialize, Serialize};
+use tracing::error;
+
+use crate::{DaemonResponse, TarsClient, TarsError};
+
+use super::{Estimate, Id, Name, Task};
+
+/// Asks for the work that fits into `available`, picked from the tasks in `scope`
+/// (recursively) or from every task when there is no scope.
+#[derive(PartialEq, Eq, Debug, Serialize, Deserialize, Clone)]
+pub struct PlanRequest {
+    pub available: Estimate,
+    pub scope: Option<Id>,
+}
+
+/// The highest scoring unfinished tasks that fit into the available time, picked greedily.
+#[derive(PartialEq, Debug, Serialize, Deserialize, Clone)]
+pub struct Plan {
+    pub available: Estimate,
+    /// The estimates of the picked tasks added up, never more than `available`.
+    pub planned: Estimate,
+    /// The picked tasks, highest score first.
+    pub tasks: Vec<PlannedTask>,
+    /// Estimated work per group, most open work first.
+    pub groups: Vec<GroupWorkload>,
+    /// Open tasks in scope without an estimate, these are never picked.
+    pub unestimated: usize,
+}
+
+/// A task picked by the planner, along with the p_score it was picked by.
+#[derive(PartialEq, Debug, Serialize, Deserialize, Clone)]
+pub struct PlannedTask {
+    pub task: Task,
+    pub score: f64,
+}
+
+/// Estimated work in a single group, only counting the tasks directly inside it.
+#[derive(PartialEq, Eq, Debug, Serialize, Deserialize, Clone)]
+pub struct GroupWorkload {
+    pub group_id: Id,
+    pub name: Name,
+    /// The estimates of the picked tasks in this group.
+    pub planned: Estimate,
+    /// The estimates of every open, unblocked task in this group.
+    pub open: Estimate,
+}
+
+impl Plan {
+    /// Fills `available` with the highest scoring tasks that fit, leaving out tasks that are
+    /// finished, blocked or don't have an estimate.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if
+    /// Something goes wrong with the requests to the Daemon.
+    pub async fn fetch(
+        client: &TarsClient,
+        available: Estimate,
+        scope: Option<Id>,
+    ) -> Result<Self, TarsError> {
+        let plan: Plan = client
+            .conn
+            .post(client.base_path.join("/task/plan")?)
+            .json(&PlanRequest { available, scope })
+            .send()
+            .await
+            .inspect_err(|e| error!("Error fetching plan: {:?}", e))?
+            .error_for_daemon()
+            .await
+            .inspect_err(|e| error!("Error fetching plan: {:?}", e))?
+            .json()
+            .await
+            .inspect_err(|e| error!("Error parsing plan: {:?}", e))?;
+
+        Ok(plan)
+    }
+}
@@ -0,0 +1,349 @@
+use std::{error::Error, fmt::Display};
+
+use chrono::{Datelike, Days, Months, NaiveDateTime, Weekday};
+use serde::{Deserialize, Serialize};
+use sqlx::{Database, Decode, Encode, Sqlite, Type};
+
+use crate::ParseError;
+
+/// How a `Task` repeats once it has been finished.
+#[derive(PartialEq, Eq, Debug, Serialize, Deserialize, Clone, PartialOrd, Ord, Hash)]
+#[serde(try_from = "String", into = "String")]
+pub enum Recurrence {
+    /// Due again the next day.
+    Daily,
+    /// Due again on the next of the chosen weekdays, or a week later if none are chosen.
+    Weekly { weekdays: Vec<Day> },
+    /// Due again next month on this day, clamped to the length of the month.
+    Monthly { day: u32 },
+    /// Due again this many days after it was finished.
+    AfterCompletion { days: u64 },
+}
+
+/// A day of the week, wrapped so it can be ordered alongside the rest of a `Task`.
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Hash)]
+pub struct Day(pub Weekday);
+
+impl PartialOrd for Day {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Day {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.0
+            .num_days_from_monday()
+            .cmp(&other.0.num_days_from_monday())
+    }
+}
+
+impl Recurrence {
+    /// Parser for clap / the inspector, accepts `daily`, `weekly`, `weekly:mon,wed`,
+    /// `monthly:15` or `every:3d`.
+    pub fn parse_str(str: &str) -> Result<Self, ParseError> {
+        str.try_into()
+    }
+
+    /// Returns when the next instance of a task is due, given when the finished
+    /// instance was due and when it was finished.
+    ///
+    /// Fixed schedules are stepped forward until they land after `finished_at`, so
+    /// finishing late doesn't leave the next instance already overdue.
+    pub fn next_due(
+        &self,
+        due: Option<NaiveDateTime>,
+        finished_at: NaiveDateTime,
+    ) -> NaiveDateTime {
+        let base = due.unwrap_or(finished_at);
+
+        if let Recurrence::AfterCompletion { days } = self {
+            // keep the time of day the task used to be due at
+            return finished_at.date().and_time(base.time()) + Days::new(*days);
+        }
+
+        let mut next = self.step(base);
+        while next <= finished_at {
+            next = self.step(next);
+        }
+
+        next
+    }
+
+    /// Moves `from` forward by a single occurrence.
+    fn step(&self, from: NaiveDateTime) -> NaiveDateTime {
+        match self {
+            Recurrence::Daily => from + Days::new(1),
+            Recurrence::Weekly { weekdays } => {
+                if weekdays.is_empty() {
+                    return from + Days::new(7);
+                }
+
+                (1..=7)
+                    .map(|d| from + Days::new(d))
+                    .find(|d| weekdays.contains(&Day(d.weekday())))
+                    .expect("a week contains every weekday")
+            }
+            Recurrence::Monthly { day } => {
+                let next_month = from
+                    .date()
+                    .with_day(1)
+                    .expect("every month has a first day")
+                    + Months::new(1);
+                let days_in_month = (next_month + Months::new(1) - Days::new(1)).day();
+
+                next_month
+                    .with_day((*day).min(days_in_month))
+                    .expect("day is clamped to the month")
+                    .and_time(from.time())
+            }
+            Recurrence::AfterCompletion { days } => from + Days::new(*days),
+        }
+    }
+}
+
+impl TryFrom<&str> for Recurrence {
+    type Error = ParseError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        let value = value.trim().to_ascii_lowercase();
+        let (kind, arg) = match value.split_once(':') {
+            Some((kind, arg)) => (kind, Some(arg.trim())),
+            None => (value.as_str(), None),
+        };
+
+        match (kind, arg) {
+            ("daily", None) => Ok(Recurrence::Daily),
+            ("weekly", None) => Ok(Recurrence::Weekly { weekdays: vec![] }),
+            ("weekly", Some(days)) => {
+                let mut weekdays = days
+                    .split(',')
+                    .map(|d| {
+                        d.trim().parse::<Weekday>().map(Day).map_err(|_| {
+                            ParseError::FailedToParse(format!("{value:?} as a recurrence"))
+                        })
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                weekdays.sort();
+                weekdays.dedup();
+
+                Ok(Recurrence::Weekly { weekdays })
+            }
+            ("monthly", Some(day)) => {
+                let day: u32 = day
+                    .parse()
+                    .map_err(|_| ParseError::FailedToParse(format!("{value:?} as a recurrence")))?;
+                if !(1..=31).contains(&day) {
+                    return Err(ParseError::FailedToParse(format!(
+                        "{value:?} as a recurrence"
+                    )));
+                }
+
+                Ok(Recurrence::Monthly { day })
+            }
+            ("every", Some(days)) => {
+                let days: u64 = days
+                    .strip_suffix('d')
+                    .unwrap_or(days)
+                    .parse()
+                    .map_err(|_| ParseError::FailedToParse(format!("{value:?} as a recurrence")))?;
+                if days == 0 {
+                    return Err(ParseError::FailedToParse(format!(
+                        "{value:?} as a recurrence"
+                    )));
+                }
+
+                Ok(Recurrence::AfterCompletion { days })
+            }
+            _ => Err(ParseError::FailedToParse(format!(
+                "{value:?} as a recurrence"
+            ))),
+        }
+    }
+}
+
+impl TryFrom<String> for Recurrence {
+    type Error = ParseError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.as_str().try_into()
+    }
+}
+
+impl From<Recurrence> for String {
+    fn from(value: Recurrence) -> Self {
+        value.to_string()
+    }
+}
+
+impl Display for Recurrence {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Recurrence::Daily => write!(f, "daily"),
+            Recurrence::Weekly { weekdays } if weekdays.is_empty() => write!(f, "weekly"),
+            Recurrence::Weekly { weekdays } => {
+                let days: Vec<String> = weekdays
+                    .iter()
+                    .map(|d| d.0.to_string().to_ascii_lowercase())
+                    .collect();
+                write!(f, "weekly:{}", days.join(","))
+            }
+            Recurrence::Monthly { day } => write!(f, "monthly:{day}"),
+            Recurrence::AfterCompletion { days } => write!(f, "every:{days}d"),
+        }
+    }
+}
+
+// stored as its textual form, same as what the CLI takes in
+impl<'r, DB: Database> Decode<'r, DB> for Recurrence
+where
+    &'r str: Decode<'r, DB>,
+{
+    fn decode(
+        value: <DB as Database>::ValueRef<'r>,
+    ) -> Result<Recurrence, Box<dyn Error + 'static + Send + Sync>> {
+        let value = <&str as Decode<DB>>::decode(value)?;
+
+        Ok(Recurrence::try_from(value)?)
+    }
+}
+
+impl<'q> Encode<'q, Sqlite> for Recurrence {
+    fn encode_by_ref(
+        &self,
+        buf: &mut <Sqlite as Database>::ArgumentBuffer<'q>,
+    ) -> Result<sqlx::encode::IsNull, sqlx::error::BoxDynError> {
+        <String as Encode<'_, Sqlite>>::encode(self.to_string(), buf)
+    }
+}
+
+impl Type<Sqlite> for Recurrence {
+    fn type_info() -> <Sqlite as Database>::TypeInfo {
+        <String as Type<Sqlite>>::type_info()
+    }
+
+    fn compatible(ty: &<Sqlite as Database>::TypeInfo) -> bool {
+        <String as Type<Sqlite>>::compatible(ty)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::*;
+
+    fn at(year: i32, month: u32, day: u32, hour: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(year, month, day)
+            .unwrap()
+            .and_hms_opt(hour, 0, 0)
+            .unwrap()
+    }
+
+    fn parse(rule: &str) -> Recurrence {
+        Recurrence::parse_str(rule).unwrap()
+    }
+
+    #[test]
+    fn monthly_clamps_to_short_months() {
+        let monthly = parse("monthly:31");
+
+        assert_eq!(
+            monthly.next_due(Some(at(2026, 3, 31, 9)), at(2026, 3, 31, 12)),
+            at(2026, 4, 30, 9)
+        );
+        assert_eq!(
+            monthly.next_due(Some(at(2026, 1, 31, 9)), at(2026, 1, 31, 12)),
+            at(2026, 2, 28, 9)
+        );
+        assert_eq!(
+            monthly.next_due(Some(at(2028, 1, 31, 9)), at(2028, 1, 31, 12)),
+            at(2028, 2, 29, 9)
+        );
+
+        // and back to the 31st once the month has one
+        assert_eq!(
+            monthly.next_due(Some(at(2026, 2, 28, 9)), at(2026, 2, 28, 12)),
+            at(2026, 3, 31, 9)
+        );
+    }
+
+    #[test]
+    fn weekly_wraps_past_sunday() {
+        let weekly = parse("weekly:wed,mon");
+        assert_eq!(weekly.to_string(), "weekly:mon,wed");
+
+        // due on a friday, the next one is the monday after
+        assert_eq!(
+            weekly.next_due(Some(at(2026, 3, 6, 9)), at(2026, 3, 6, 12)),
+            at(2026, 3, 9, 9)
+        );
+        // and from a sunday too
+        assert_eq!(
+            weekly.next_due(Some(at(2026, 3, 8, 9)), at(2026, 3, 8, 12)),
+            at(2026, 3, 9, 9)
+        );
+
+        // no weekdays is the same day next week
+        assert_eq!(
+            parse("weekly").next_due(Some(at(2026, 3, 8, 9)), at(2026, 3, 8, 12)),
+            at(2026, 3, 15, 9)
+        );
+    }
+
+    #[test]
+    fn finishing_late() {
+        let due = Some(at(2026, 3, 2, 9));
+        let finished_at = at(2026, 3, 5, 18);
+
+        // counted from when it was finished, at the time of day it was due
+        assert_eq!(
+            parse("every:3d").next_due(due, finished_at),
+            at(2026, 3, 8, 9)
+        );
+        // without a due date, from when it was finished
+        assert_eq!(
+            parse("every:3d").next_due(None, finished_at),
+            at(2026, 3, 8, 18)
+        );
+
+        // fixed schedules skip what already went by
+        assert_eq!(parse("daily").next_due(due, finished_at), at(2026, 3, 6, 9));
+        assert_eq!(
+            parse("weekly:mon").next_due(due, finished_at),
+            at(2026, 3, 9, 9)
+        );
+    }
+
+    #[test]
+    fn malformed_rules() {
+        for rule in [
+            "",
+            "yearly",
+            "daily:2",
+            "weekly:",
+            "weekly:mon,funday",
+            "monthly",
+            "monthly:0",
+            "monthly:32",
+            "monthly:last",
+            "every",
+            "every:0d",
+            "every:-1d",
+            "every:3w",
+        ] {
+            assert!(Recurrence::parse_str(rule).is_err(), "{rule:?} parsed");
+        }
+
+        for rule in [
+            "daily",
+            "weekly",
+            "weekly:tue,sat",
+            "monthly:15",
+            "every:3d",
+        ] {
+            assert_eq!(parse(rule).to_string(), rule);
+        }
+        assert_eq!(parse(" Every:3 "), Recurrence::AfterCompletion { days: 3 });
+    }
+}
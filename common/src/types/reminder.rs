@@ -0,0 +1,142 @@
+use std::fmt::Display;
+
+use chrono::{Duration, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+
+use crate::ParseError;
+
+use super::Task;
+
+/// When a reminder should fire relative to a `Task`'s due date.
+#[derive(PartialEq, Eq, Debug, Serialize, Deserialize, Clone, Copy, PartialOrd, Ord, Hash)]
+#[serde(try_from = "String", into = "String")]
+pub enum Reminder {
+    /// Fires this many minutes before the task is due.
+    Before(i64),
+    /// Fires once the task is past its due date.
+    Overdue,
+}
+
+impl Reminder {
+    /// Parser for clap / config files, accepts `overdue` or a number followed by
+    /// one of `m`, `h`, `d` or `w` (ex. `90m`, `1h`, `2d`).
+    pub fn parse_str(str: &str) -> Result<Self, ParseError> {
+        str.try_into()
+    }
+
+    /// Returns the point in time at which this reminder should fire for the given due date.
+    pub fn fires_at(&self, due: NaiveDateTime) -> NaiveDateTime {
+        match self {
+            Reminder::Before(minutes) => due - Duration::minutes(*minutes),
+            Reminder::Overdue => due,
+        }
+    }
+}
+
+impl TryFrom<&str> for Reminder {
+    type Error = ParseError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        let value = value.trim();
+
+        if value.eq_ignore_ascii_case("overdue") {
+            return Ok(Reminder::Overdue);
+        }
+
+        let Some(unit) = value.chars().last() else {
+            return Err(ParseError::FailedToParse(format!(
+                "{value:?} as a reminder"
+            )));
+        };
+
+        let amount: i64 = value[..value.len() - unit.len_utf8()]
+            .parse()
+            .map_err(|_| ParseError::FailedToParse(format!("{value:?} as a reminder")))?;
+
+        let minutes = match unit {
+            'm' => amount,
+            'h' => amount * 60,
+            'd' => amount * 60 * 24,
+            'w' => amount * 60 * 24 * 7,
+            _ => {
+                return Err(ParseError::FailedToParse(format!(
+                    "{value:?} as a reminder"
+                )));
+            }
+        };
+
+        if minutes <= 0 {
+            return Err(ParseError::FailedToParse(format!(
+                "{value:?} as a reminder"
+            )));
+        }
+
+        Ok(Reminder::Before(minutes))
+    }
+}
+
+impl TryFrom<String> for Reminder {
+    type Error = ParseError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.as_str().try_into()
+    }
+}
+
+impl From<Reminder> for String {
+    fn from(value: Reminder) -> Self {
+        value.to_string()
+    }
+}
+
+impl Display for Reminder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Reminder::Overdue => write!(f, "overdue"),
+            Reminder::Before(minutes) => {
+                if minutes % (60 * 24 * 7) == 0 {
+                    write!(f, "{}w", minutes / (60 * 24 * 7))
+                } else if minutes % (60 * 24) == 0 {
+                    write!(f, "{}d", minutes / (60 * 24))
+                } else if minutes % 60 == 0 {
+                    write!(f, "{}h", minutes / 60)
+                } else {
+                    write!(f, "{minutes}m")
+                }
+            }
+        }
+    }
+}
+
+/// A fired `Reminder` for a specific `Task`, this is what gets handed to every notification sink.
+#[derive(PartialEq, Eq, Debug, Serialize, Deserialize, Clone)]
+pub struct Notification {
+    pub task: Task,
+    pub reminder: Reminder,
+    pub message: String,
+    pub fired_at: NaiveDateTime,
+}
+
+impl Notification {
+    /// Creates a new `Notification`, generating a human readable message for it.
+    pub fn new(task: Task, reminder: Reminder, fired_at: NaiveDateTime) -> Self {
+        let due = task
+            .due
+            .map(|d| d.format("%m/%d/%Y %I:%M %p").to_string())
+            .unwrap_or_else(|| "None".to_owned());
+
+        let message = match reminder {
+            Reminder::Before(_) => {
+                format!("\"{}\" is due in {reminder} ({due})", *task.name)
+            }
+            Reminder::Overdue => format!("\"{}\" is overdue! (was due {due})", *task.name),
+        };
+
+        Self {
+            task,
+            reminder,
+            message,
+            fired_at,
+        }
+    }
+}
@@ -0,0 +1,120 @@
+use chrono::{NaiveDate, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+use tracing::error;
+
+use crate::{DaemonResponse, TarsClient, TarsError};
+
+use super::{Estimate, Id, Name};
+
+/// Asks for a schedule of the open tasks in `scope` (recursively), or of every task when there
+/// is no scope.
+#[derive(PartialEq, Eq, Debug, Serialize, Deserialize, Clone, Default)]
+pub struct ScheduleRequest {
+    pub scope: Option<Id>,
+    /// When the schedule starts, now if left out.
+    pub from: Option<NaiveDateTime>,
+    /// How many days to schedule, the daemon's configured horizon if left out.
+    pub days: Option<u32>,
+}
+
+/// Open tasks laid out over the coming working days, highest scoring first.
+#[derive(PartialEq, Eq, Debug, Serialize, Deserialize, Clone)]
+pub struct Schedule {
+    /// Every working day within the horizon, in order, including the empty ones.
+    pub days: Vec<ScheduleDay>,
+    /// Tasks that can't be finished before they're due, the rest of their work is booked
+    /// onto their due day regardless of capacity.
+    pub late: Vec<LateTask>,
+    /// Tasks that didn't fit within the horizon at all, or only partly.
+    pub unplaced: Vec<UnplacedTask>,
+    /// Open, unblocked tasks without an estimate, these are never scheduled.
+    pub unestimated: usize,
+    /// Open tasks still waiting on an unfinished task, these are never scheduled.
+    pub blocked: usize,
+}
+
+/// A single working day of a `Schedule`.
+#[derive(PartialEq, Eq, Debug, Serialize, Deserialize, Clone)]
+pub struct ScheduleDay {
+    pub date: NaiveDate,
+    /// The working time of the day, what's left of it when the schedule starts midday.
+    pub capacity: Estimate,
+    /// The work booked onto the day, more than `capacity` when it's over capacity.
+    pub booked: Estimate,
+    pub blocks: Vec<TimeBlock>,
+}
+
+/// A stretch of a day set aside for a task.
+#[derive(PartialEq, Eq, Debug, Serialize, Deserialize, Clone)]
+pub struct TimeBlock {
+    pub task_id: Id,
+    pub name: Name,
+    pub start: NaiveDateTime,
+    pub end: NaiveDateTime,
+    /// Whether this block is work on a late task, booked regardless of capacity.
+    pub late: bool,
+}
+
+/// A task that won't be finished before it's due.
+#[derive(PartialEq, Eq, Debug, Serialize, Deserialize, Clone)]
+pub struct LateTask {
+    pub task_id: Id,
+    pub name: Name,
+    pub due: NaiveDateTime,
+    /// The part of its estimate that doesn't fit before `due`.
+    pub missing: Estimate,
+}
+
+/// A task whose work didn't all fit within the horizon.
+#[derive(PartialEq, Eq, Debug, Serialize, Deserialize, Clone)]
+pub struct UnplacedTask {
+    pub task_id: Id,
+    pub name: Name,
+    /// The part of its estimate left over.
+    pub remaining: Estimate,
+}
+
+impl ScheduleDay {
+    /// Whether more work is booked onto this day than fits into it.
+    pub fn is_over_capacity(&self) -> bool {
+        self.booked > self.capacity
+    }
+}
+
+impl TimeBlock {
+    pub fn duration(&self) -> Estimate {
+        Estimate::from_minutes((self.end - self.start).num_minutes().max(0) as u32)
+    }
+}
+
+impl Schedule {
+    /// Returns the days with more work booked onto them than fits.
+    pub fn over_capacity(&self) -> impl Iterator<Item = &ScheduleDay> {
+        self.days.iter().filter(|day| day.is_over_capacity())
+    }
+
+    /// Lays out the open tasks with an estimate over the coming working days, as configured
+    /// in the daemon.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if
+    /// Something goes wrong with the requests to the Daemon.
+    pub async fn fetch(client: &TarsClient, request: ScheduleRequest) -> Result<Self, TarsError> {
+        let schedule: Schedule = client
+            .conn
+            .post(client.base_path.join("/schedule")?)
+            .json(&request)
+            .send()
+            .await
+            .inspect_err(|e| error!("Error fetching schedule: {:?}", e))?
+            .error_for_daemon()
+            .await
+            .inspect_err(|e| error!("Error fetching schedule: {:?}", e))?
+            .json()
+            .await
+            .inspect_err(|e| error!("Error parsing schedule: {:?}", e))?;
+
+        Ok(schedule)
+    }
+}
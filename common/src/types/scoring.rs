@@ -0,0 +1,94 @@
+use std::fmt::Display;
+
+use serde::{Deserialize, Serialize};
+
+use crate::ParseError;
+
+use super::{Id, Name, Priority};
+
+/// The built-in ways the daemon can rank tasks, the active one can be switched at runtime.
+#[derive(PartialEq, Eq, Debug, Serialize, Deserialize, Clone, Copy, Default, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum ScoringStrategyKind {
+    /// Priorities of the task and its groups, plus an exponential bump as the due date nears.
+    #[default]
+    Classic,
+    /// Anything due soon outranks anything that isn't, priority only breaks ties.
+    DeadlineFirst,
+    /// Sorts tasks into the four urgent / important quadrants.
+    Eisenhower,
+    /// Weighted shortest job first, cost of delay divided by how much work is left.
+    Wsjf,
+}
+
+impl ScoringStrategyKind {
+    /// Every strategy, in the order they're listed to users.
+    pub const ALL: [ScoringStrategyKind; 4] = [
+        ScoringStrategyKind::Classic,
+        ScoringStrategyKind::DeadlineFirst,
+        ScoringStrategyKind::Eisenhower,
+        ScoringStrategyKind::Wsjf,
+    ];
+
+    pub fn parse_clap(str: &str) -> Result<Self, ParseError> {
+        str.try_into()
+    }
+}
+
+impl TryFrom<&str> for ScoringStrategyKind {
+    type Error = ParseError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        Self::ALL
+            .into_iter()
+            .find(|kind| kind.to_string() == value.trim().to_ascii_lowercase().replace('-', "_"))
+            .ok_or_else(|| ParseError::FailedToParse(format!("{value:?} as a scoring strategy")))
+    }
+}
+
+impl Display for ScoringStrategyKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            ScoringStrategyKind::Classic => "classic",
+            ScoringStrategyKind::DeadlineFirst => "deadline_first",
+            ScoringStrategyKind::Eisenhower => "eisenhower",
+            ScoringStrategyKind::Wsjf => "wsjf",
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// One group's share of a task's score, `1 / priority` of the group.
+#[derive(PartialEq, Debug, Serialize, Deserialize, Clone)]
+pub struct GroupMultiplier {
+    pub id: Id,
+    pub name: Name,
+    pub priority: Priority,
+    pub multiplier: f64,
+}
+
+/// A named value the active strategy folded into a task's score, ex. the due-date term.
+#[derive(PartialEq, Debug, Serialize, Deserialize, Clone)]
+pub struct ScoreTerm {
+    pub name: String,
+    pub value: f64,
+}
+
+/// Why a task scored what it did.
+#[derive(PartialEq, Debug, Serialize, Deserialize, Clone)]
+pub struct ScoreExplanation {
+    /// The strategy that was active when the score was computed.
+    pub strategy: ScoringStrategyKind,
+    pub priority: Priority,
+    /// `1 / priority` of the task itself.
+    pub priority_multiplier: f64,
+    /// The task's group first, then every group above it up to the root.
+    pub groups: Vec<GroupMultiplier>,
+    /// Days until the task is due, negative once it's overdue.
+    pub days_left: Option<f64>,
+    /// How many unfinished tasks are (transitively) waiting on this one.
+    pub blocked_dependents: i64,
+    /// The terms the strategy combined into `score`, in the order it uses them.
+    pub terms: Vec<ScoreTerm>,
+    pub score: f64,
+}
@@ -0,0 +1,37 @@
+use serde::{Deserialize, Serialize};
+
+use super::{Group, Task};
+
+/// What the daemon should look for, `query` is split on whitespace and every word has to match,
+/// either fully or as the start of a word.
+#[derive(PartialEq, Eq, Debug, Serialize, Deserialize, Clone)]
+pub struct SearchQuery {
+    pub query: String,
+    /// At most this many matches are returned.
+    pub limit: Option<u32>,
+}
+
+/// The row a `SearchMatch` was found in.
+// same as `DiffInner`, tasks are most of what gets matched
+#[allow(clippy::large_enum_variant)]
+#[derive(PartialEq, Eq, Debug, Serialize, Deserialize, Clone)]
+pub enum SearchItem {
+    Task(Task),
+    Group(Group),
+}
+
+/// A single hit for a `SearchQuery`.
+#[derive(PartialEq, Debug, Serialize, Deserialize, Clone)]
+pub struct SearchMatch {
+    pub item: SearchItem,
+    /// The part of the name / description that matched, with the matching words wrapped in
+    /// `SNIPPET_OPEN` and `SNIPPET_CLOSE`.
+    pub snippet: String,
+    /// Higher is a better match.
+    pub rank: f64,
+}
+
+/// Marks the start of a matching word in a `SearchMatch::snippet`.
+pub const SNIPPET_OPEN: &str = "[";
+/// Marks the end of a matching word in a `SearchMatch::snippet`.
+pub const SNIPPET_CLOSE: &str = "]";
@@ -0,0 +1,42 @@
+use serde::{Deserialize, Serialize};
+use tracing::error;
+
+use crate::{DaemonResponse, TarsClient, TarsError};
+
+use super::{Group, Task};
+
+/// Every group and task as of a single point in the diff stream, read all at once so clients
+/// can build their state from it without racing the diffs.
+#[derive(PartialEq, Eq, Debug, Serialize, Deserialize, Clone)]
+pub struct Snapshot {
+    /// Number of the newest diff already included, subscribe with it as the `Last-Event-ID`
+    /// to get every change after it.
+    pub seq: i64,
+    pub groups: Vec<Group>,
+    pub tasks: Vec<Task>,
+}
+
+impl Snapshot {
+    /// Fetches every `Group` and `Task` the client may see.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if
+    /// Something goes wrong with the requests to the Daemon.
+    pub async fn fetch(client: &TarsClient) -> Result<Self, TarsError> {
+        let snapshot: Snapshot = client
+            .conn
+            .get(client.base_path.join("/snapshot")?)
+            .send()
+            .await
+            .inspect_err(|e| error!("Error fetching snapshot: {:?}", e))?
+            .error_for_daemon()
+            .await
+            .inspect_err(|e| error!("Error fetching snapshot: {:?}", e))?
+            .json()
+            .await
+            .inspect_err(|e| error!("Error parsing snapshot: {:?}", e))?;
+
+        Ok(snapshot)
+    }
+}
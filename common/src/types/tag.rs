@@ -0,0 +1,185 @@
+use std::fmt::Display;
+
+use chrono::{Local, NaiveDateTime};
+use color_eyre::owo_colors::OwoColorize;
+use serde::{Deserialize, Serialize};
+use tracing::error;
+
+use crate::{DaemonResponse, TarsClient, TarsError};
+
+use super::{Color, Id, Name};
+
+/// A label that can be attached to any number of `Task`s, regardless of which `Group` they live in.
+#[derive(PartialEq, Eq, Debug, Serialize, Deserialize, Clone, PartialOrd, Ord)]
+pub struct Tag {
+    pub id: Id,
+    pub name: Name,
+    pub color: Color,
+    pub created_at: NaiveDateTime,
+}
+
+impl Tag {
+    /// Initializes a `Tag` with all fields.
+    pub fn with_all_fields(
+        id: impl Into<Id>,
+        name: impl Into<Name>,
+        color: Color,
+        created_at: NaiveDateTime,
+    ) -> Self {
+        Tag {
+            id: id.into(),
+            name: name.into(),
+            color,
+            created_at,
+        }
+    }
+
+    /// Creates a new `Tag` through the `TarsDaemon`.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if
+    /// + Something goes wrong with the requests to the Daemon.
+    /// + A `Tag` with this name already exists.
+    pub async fn new(
+        client: &TarsClient,
+        name: impl Into<Name>,
+        color: Color,
+    ) -> Result<Self, TarsError> {
+        let created_at = Local::now().naive_local();
+
+        let tag = Tag::with_all_fields(Id::default(), name, color, created_at);
+
+        let res: Tag = client
+            .conn
+            .post(client.base_path.join("/tag/create")?)
+            .json(&tag)
+            .send()
+            .await
+            .inspect_err(|e| error!("Error creating Tag: {:?}", e))?
+            .error_for_daemon()
+            .await
+            .inspect_err(|e| error!("Error creating Tag: {:?}", e))?
+            .json()
+            .await
+            .inspect_err(|e| error!("Error creating Tag: {:?}", e))?;
+
+        Ok(res)
+    }
+
+    /// Forcefully creates this `Tag`...
+    /// WARN: If youre just trying to make a new tag / don't know
+    /// what youre doing, use `Tag::new()` instead.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if
+    /// Something goes wrong with the requests to the Daemon.
+    pub async fn raw_create(&self, client: &TarsClient) -> Result<(), TarsError> {
+        let _: Tag = client
+            .conn
+            .post(client.base_path.join("/tag/create")?)
+            .json(&self)
+            .send()
+            .await
+            .inspect_err(|e| error!("Error creating Tag: {:?}", e))?
+            .error_for_daemon()
+            .await
+            .inspect_err(|e| error!("Error creating Tag: {:?}", e))?
+            .json()
+            .await
+            .inspect_err(|e| error!("Error creating Tag: {:?}", e))?;
+
+        Ok(())
+    }
+
+    /// Fetches all `Tag`s.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if
+    /// Something goes wrong with the requests to the Daemon.
+    pub async fn fetch_all(client: &TarsClient) -> Result<Vec<Tag>, TarsError> {
+        let res: Vec<Tag> = client
+            .conn
+            .get(client.base_path.join("/tag")?)
+            .send()
+            .await
+            .inspect_err(|e| error!("Error Fetching Tags: {:?}", e))?
+            .error_for_daemon()
+            .await
+            .inspect_err(|e| error!("Error Fetching Tags: {:?}", e))?
+            .json()
+            .await
+            .inspect_err(|e| error!("Error Fetching Tags: {:?}", e))?;
+
+        Ok(res)
+    }
+
+    /// Sync's this `Tag` with its representation in database, via the `TarsDaemon`, taking
+    /// on what the daemon stored.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if
+    /// + Something goes wrong with the requests to the Daemon.
+    /// + The tag fails validation, ex. another tag already has its name.
+    pub async fn sync(&mut self, client: &TarsClient) -> Result<(), TarsError> {
+        let res: Tag = client
+            .conn
+            .post(client.base_path.join("/tag/update")?)
+            .json(self)
+            .send()
+            .await
+            .inspect_err(|e| error!("Error Sync'ing Tag: {:?}", e))?
+            .error_for_daemon()
+            .await
+            .inspect_err(|e| error!("Error Sync'ing Tag: {:?}", e))?
+            .json()
+            .await
+            .inspect_err(|e| error!("Error Sync'ing Tag: {:?}", e))?;
+
+        *self = res;
+
+        Ok(())
+    }
+
+    /// Deletes this `Tag` via the `TarsDaemon`, removing it from every `Task` it was attached to.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if
+    /// + Something goes wrong with the requests to the Daemon.
+    /// + The Daemon deleted some other `Tag` than this one.
+    pub async fn delete(&self, client: &TarsClient) -> Result<(), TarsError> {
+        let deleted: Tag = client
+            .conn
+            .post(client.base_path.join("/tag/delete")?)
+            .json(&self.id)
+            .send()
+            .await
+            .inspect_err(|e| error!("Error Deleting Tag: {:?}", e))?
+            .error_for_daemon()
+            .await
+            .inspect_err(|e| error!("Error Deleting Tag: {:?}", e))?
+            .json()
+            .await
+            .inspect_err(|e| error!("Error Deleting Tag: {:?}", e))?;
+
+        if deleted.id != self.id {
+            return Err(TarsError::Internal(format!(
+                "deleted tag {} instead of {}",
+                *deleted.id, *self.id
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+impl Display for Tag {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Name: {}", (*self.name).green())?;
+        write!(f, "Id: {}", *self.id)
+    }
+}
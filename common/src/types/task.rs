@@ -1,13 +1,16 @@
-use std::{f64, fmt::Display};
+use std::{collections::HashMap, f64, fmt::Display};
 
 use chrono::{Local, NaiveDateTime};
 use color_eyre::owo_colors::OwoColorize;
 use serde::{Deserialize, Serialize};
 use tracing::error;
 
-use crate::{TarsClient, TarsError};
+use crate::{DaemonResponse, TarsClient, TarsError};
 
-use super::{Group, Id, Name, Priority};
+use super::{
+    ChecklistItem, Estimate, Group, Id, Name, Priority, Recurrence, ScoreExplanation, SearchMatch,
+    SearchQuery, Tag, TaskDependencies, TaskDependency,
+};
 
 /// Task type that holds all information relavant to a task.
 #[derive(PartialEq, Eq, Debug, Serialize, Deserialize, Clone, PartialOrd, Ord)]
@@ -20,10 +23,28 @@ pub struct Task {
     pub finished_at: Option<NaiveDateTime>,
     pub created_at: NaiveDateTime,
     pub due: Option<NaiveDateTime>,
+    /// Sorted, so that two tasks with the same tags compare equal.
+    #[serde(default)]
+    pub tags: Vec<Tag>,
+    /// Finishing a recurring task creates its next instance.
+    #[serde(default)]
+    pub recurrence: Option<Recurrence>,
+    /// Sorted `Id`s of the tasks that have to be finished before this one.
+    #[serde(default)]
+    pub blocked_by: Vec<Id>,
+    /// Ordered steps of this task, each with its own done state.
+    #[serde(default)]
+    pub checklist: Vec<ChecklistItem>,
+    /// How long this task is expected to take.
+    #[serde(default)]
+    pub estimate: Option<Estimate>,
+    /// Bumped by the daemon on every update, an update has to carry the current one.
+    #[serde(default)]
+    pub version: i64,
 }
 
 impl Task {
-    /// Initializes a `Task` with all fields.
+    /// Initializes a `Task` with all fields, besides its tags, recurrence, blockers, checklist and estimate which start out empty.
     #[allow(clippy::too_many_arguments)]
     pub fn with_all_fields(
         id: impl Into<Id>,
@@ -44,9 +65,33 @@ impl Task {
             finished_at,
             created_at,
             due,
+            tags: Vec::new(),
+            recurrence: None,
+            blocked_by: Vec::new(),
+            checklist: Vec::new(),
+            estimate: None,
+            version: 0,
         }
     }
 
+    /// Replaces the tags of this `Task`, keeping them sorted and free of duplicates.
+    pub fn set_tags(&mut self, mut tags: Vec<Tag>) {
+        tags.sort();
+        tags.dedup_by(|a, b| a.id == b.id);
+        self.tags = tags;
+    }
+
+    /// Returns how many checklist items are done out of how many there are,
+    /// `None` if this task has no checklist.
+    pub fn checklist_progress(&self) -> Option<(usize, usize)> {
+        if self.checklist.is_empty() {
+            return None;
+        }
+
+        let done = self.checklist.iter().filter(|i| i.done).count();
+        Some((done, self.checklist.len()))
+    }
+
     /// Creates a new `Task` through the `TarsDaemon`.
     ///
     /// # Errors
@@ -72,6 +117,12 @@ impl Task {
             description: description.into(),
             created_at,
             due,
+            tags: Vec::new(),
+            recurrence: None,
+            blocked_by: Vec::new(),
+            checklist: Vec::new(),
+            estimate: None,
+            version: 0,
         };
 
         let res: Task = client
@@ -81,6 +132,9 @@ impl Task {
             .send()
             .await
             .inspect_err(|e| error!("Error creating Task: {:?}", e))?
+            .error_for_daemon()
+            .await
+            .inspect_err(|e| error!("Error creating Task: {:?}", e))?
             .json()
             .await
             .inspect_err(|e| error!("Error creating Task: {:?}", e))?;
@@ -103,6 +157,9 @@ impl Task {
             .send()
             .await
             .inspect_err(|e| error!("Error creating Task: {:?}", e))?
+            .error_for_daemon()
+            .await
+            .inspect_err(|e| error!("Error creating Task: {:?}", e))?
             .json()
             .await
             .inspect_err(|e| error!("Error creating Task: {:?}", e))?;
@@ -127,6 +184,9 @@ impl Task {
             .send()
             .await
             .inspect_err(|e| error!("Error creating Task: {:?}", e))?
+            .error_for_daemon()
+            .await
+            .inspect_err(|e| error!("Error creating Task: {:?}", e))?
             .json()
             .await
             .inspect_err(|e| error!("Error creating Task: {:?}", e))?;
@@ -134,14 +194,16 @@ impl Task {
         Ok(res)
     }
 
-    /// Sync's this `Task` with its representation in database, via the `TarsDaemon`.
+    /// Sync's this `Task` with its representation in database, via the `TarsDaemon`,
+    /// taking on the version the daemon assigned.
     ///
     /// # Errors
     ///
     /// This function will return an error if
     /// + Something goes wrong with the requests to the Daemon.
-    /// + Will panic at runtime if the sync'd task doesnt match with `self`
-    pub async fn sync(&self, client: &TarsClient) -> Result<(), TarsError> {
+    /// + The `Task` was changed since this copy was fetched, `TarsError::Conflict` carries
+    ///   the current one.
+    pub async fn sync(&mut self, client: &TarsClient) -> Result<(), TarsError> {
         let task: Task = client
             .conn
             .post(client.base_path.join("/task/update")?)
@@ -149,12 +211,14 @@ impl Task {
             .send()
             .await
             .inspect_err(|e| error!("Error creating Task: {:?}", e))?
+            .error_for_daemon()
+            .await
+            .inspect_err(|e| error!("Error creating Task: {:?}", e))?
             .json()
             .await
             .inspect_err(|e| error!("Error creating Task: {:?}", e))?;
 
-        // make sure that its actually sync'd
-        assert_eq!(*self, task);
+        *self = task;
         Ok(())
     }
 
@@ -164,7 +228,7 @@ impl Task {
     ///
     /// This function will return an error if
     /// + Something goes wrong with the requests to the Daemon.
-    /// + Will panic at runtime if deleted task doesnt match the task we wanted to delete.
+    /// + The Daemon deleted some other task than this one.
     pub async fn delete(&self, client: &TarsClient) -> Result<(), TarsError> {
         let deleted_task: Task = client
             .conn
@@ -173,15 +237,134 @@ impl Task {
             .send()
             .await
             .inspect_err(|e| error!("Error creating Task: {:?}", e))?
+            .error_for_daemon()
+            .await
+            .inspect_err(|e| error!("Error creating Task: {:?}", e))?
             .json()
             .await
             .inspect_err(|e| error!("Error creating Task: {:?}", e))?;
 
-        assert_eq!(deleted_task, *self);
+        if deleted_task.id != self.id {
+            return Err(TarsError::Internal(format!(
+                "deleted task {} instead of {}",
+                *deleted_task.id, *self.id
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Marks this `Task` as blocked by `blocker`, updating `self` with the result.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if
+    /// + Something goes wrong with the requests to the Daemon.
+    /// + The edge would create a cycle in the dependency graph.
+    pub async fn block_on(&mut self, client: &TarsClient, blocker: &Id) -> Result<(), TarsError> {
+        self.edit_dependency(client, "/task/block", blocker).await
+    }
+
+    /// Removes `blocker` from the tasks blocking this `Task`, updating `self` with the result.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if
+    /// Something goes wrong with the requests to the Daemon.
+    pub async fn unblock(&mut self, client: &TarsClient, blocker: &Id) -> Result<(), TarsError> {
+        self.edit_dependency(client, "/task/unblock", blocker).await
+    }
+
+    async fn edit_dependency(
+        &mut self,
+        client: &TarsClient,
+        path: &str,
+        blocker: &Id,
+    ) -> Result<(), TarsError> {
+        let dependency = TaskDependency {
+            task_id: self.id.clone(),
+            blocked_by: blocker.clone(),
+        };
 
+        let task: Task = client
+            .conn
+            .post(client.base_path.join(path)?)
+            .json(&dependency)
+            .send()
+            .await
+            .inspect_err(|e| error!("Error editing Task dependency: {:?}", e))?
+            .error_for_daemon()
+            .await
+            .inspect_err(|e| error!("Error editing Task dependency: {:?}", e))?
+            .json()
+            .await
+            .inspect_err(|e| error!("Error editing Task dependency: {:?}", e))?;
+
+        *self = task;
         Ok(())
     }
 
+    /// Fetches the tasks blocking this `Task`, and the ones it blocks.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if
+    /// Something goes wrong with the requests to the Daemon.
+    pub async fn fetch_dependencies(
+        &self,
+        client: &TarsClient,
+    ) -> Result<TaskDependencies, TarsError> {
+        let res: TaskDependencies = client
+            .conn
+            .post(client.base_path.join("/task/dependencies")?)
+            .json(&self.id)
+            .send()
+            .await
+            .inspect_err(|e| error!("Error fetching Task dependencies: {:?}", e))?
+            .error_for_daemon()
+            .await
+            .inspect_err(|e| error!("Error fetching Task dependencies: {:?}", e))?
+            .json()
+            .await
+            .inspect_err(|e| error!("Error fetching Task dependencies: {:?}", e))?;
+
+        Ok(res)
+    }
+
+    /// Searches the names and descriptions of all `Task`s, as well as the names of all `Group`s,
+    /// returning the best matches first.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if
+    /// Something goes wrong with the requests to the Daemon.
+    pub async fn search(
+        client: &TarsClient,
+        query: impl Into<String>,
+        limit: Option<u32>,
+    ) -> Result<Vec<SearchMatch>, TarsError> {
+        let query = SearchQuery {
+            query: query.into(),
+            limit,
+        };
+
+        let matches: Vec<SearchMatch> = client
+            .conn
+            .post(client.base_path.join("/search")?)
+            .json(&query)
+            .send()
+            .await
+            .inspect_err(|e| error!("Error searching: {:?}", e))?
+            .error_for_daemon()
+            .await
+            .inspect_err(|e| error!("Error searching: {:?}", e))?
+            .json()
+            .await
+            .inspect_err(|e| error!("Error parsing search results: {:?}", e))?;
+
+        Ok(matches)
+    }
+
     /// Returns the p score of this [`Task`].
     pub async fn p_score(&self, client: &TarsClient) -> Result<f64, TarsError> {
         let score: f64 = client
@@ -191,12 +374,64 @@ impl Task {
             .send()
             .await
             .inspect_err(|e| error!("Error fetching score for Task: {:?}", e))?
+            .error_for_daemon()
+            .await
+            .inspect_err(|e| error!("Error fetching score for Task: {:?}", e))?
             .json()
             .await
             .inspect_err(|e| error!("Error parsing score for Task: {:?}", e))?;
 
         Ok(score)
     }
+
+    /// Returns the factors behind the p score of this [`Task`], under the active scoring strategy.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if
+    /// Something goes wrong with the requests to the Daemon.
+    pub async fn explain_score(&self, client: &TarsClient) -> Result<ScoreExplanation, TarsError> {
+        let explanation: ScoreExplanation = client
+            .conn
+            .post(client.base_path.join("/task/score/explain")?)
+            .json(&self.id)
+            .send()
+            .await
+            .inspect_err(|e| error!("Error explaining score for Task: {:?}", e))?
+            .error_for_daemon()
+            .await
+            .inspect_err(|e| error!("Error explaining score for Task: {:?}", e))?
+            .json()
+            .await
+            .inspect_err(|e| error!("Error parsing score explanation for Task: {:?}", e))?;
+
+        Ok(explanation)
+    }
+
+    /// Returns the p scores of many `Task`s at once, keyed by their `Id`, in a single request.
+    /// Ids that don't belong to a task are left out.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if
+    /// Something goes wrong with the requests to the Daemon.
+    pub async fn p_scores(client: &TarsClient, ids: &[Id]) -> Result<HashMap<Id, f64>, TarsError> {
+        let scores: HashMap<Id, f64> = client
+            .conn
+            .post(client.base_path.join("/task/scores")?)
+            .json(ids)
+            .send()
+            .await
+            .inspect_err(|e| error!("Error fetching scores for Tasks: {:?}", e))?
+            .error_for_daemon()
+            .await
+            .inspect_err(|e| error!("Error fetching scores for Tasks: {:?}", e))?
+            .json()
+            .await
+            .inspect_err(|e| error!("Error parsing scores for Tasks: {:?}", e))?;
+
+        Ok(scores)
+    }
 }
 
 impl Display for Task {
@@ -204,6 +439,20 @@ impl Display for Task {
         writeln!(f, "Name: {}", (*self.name).green())?;
         writeln!(f, "Id: {}", *self.id)?;
         writeln!(f, "Group: {}", (*self.group.name).green())?;
+        if !self.tags.is_empty() {
+            let tags: Vec<&str> = self.tags.iter().map(|t| t.name.as_str()).collect();
+            writeln!(f, "Tags: {}", tags.join(", ").cyan())?;
+        }
+        if !self.blocked_by.is_empty() {
+            let blockers: Vec<&str> = self.blocked_by.iter().map(|id| id.as_str()).collect();
+            writeln!(f, "Blocked by: {}", blockers.join(", ").red())?;
+        }
+        if let Some(ref recurrence) = self.recurrence {
+            writeln!(f, "Repeats: {}", recurrence.to_string().cyan())?;
+        }
+        if let Some(estimate) = self.estimate {
+            writeln!(f, "Estimate: {}", estimate.to_string().cyan())?;
+        }
         match self.priority {
             Priority::Far => writeln!(f, "Priority: {}", "Far".blue()),
             Priority::Low => writeln!(f, "Priority: {}", "Low".green()),
@@ -212,6 +461,17 @@ impl Display for Task {
             Priority::Asap => writeln!(f, "Priority: {}", "ASAP".red()),
         }?;
         writeln!(f, "Description:\n {}", self.description)?;
+        if let Some((done, total)) = self.checklist_progress() {
+            writeln!(f, "Checklist: {done}/{total}")?;
+            for item in self.checklist.iter() {
+                let mark = if item.done {
+                    "x".green().to_string()
+                } else {
+                    " ".to_string()
+                };
+                writeln!(f, " [{mark}] {}", item.name)?;
+            }
+        }
         if let Some(time) = self.finished_at {
             let str = format!("Completed at {} ", time.format("%m/%d/%Y %I:%M:%S %p"));
             let str = str.green();
@@ -232,5 +492,6 @@ pub enum TaskFetchOptions {
     // NOTE: only add those we deem necessary, dont have to add shit just to add them
     // ById { id: Id },
     ByGroup { group_id: Id, recursive: bool },
+    ByTag { tag_id: Id },
     All,
 }
@@ -0,0 +1,213 @@
+use chrono::{NaiveDateTime, TimeDelta};
+use serde::{Deserialize, Serialize};
+use tracing::error;
+
+use crate::{DaemonResponse, TarsClient, TarsError};
+
+use super::{Id, Name};
+
+/// A stretch of time spent on a `Task`, the one without an end is the running timer.
+#[derive(PartialEq, Eq, Debug, Serialize, Deserialize, Clone, PartialOrd, Ord)]
+pub struct TimeEntry {
+    pub id: Id,
+    pub task_id: Id,
+    pub started_at: NaiveDateTime,
+    pub ended_at: Option<NaiveDateTime>,
+    pub note: String,
+}
+
+/// Starts the timer on a task, stopping whichever timer was running before.
+#[derive(PartialEq, Eq, Debug, Serialize, Deserialize, Clone)]
+pub struct TimerStart {
+    pub task_id: Id,
+    pub note: Option<String>,
+}
+
+/// Stops the running timer, the note replaces the one it was started with.
+#[derive(PartialEq, Eq, Debug, Serialize, Deserialize, Clone, Default)]
+pub struct TimerStop {
+    pub note: Option<String>,
+}
+
+/// A span of time, `from` inclusive and `to` exclusive.
+#[derive(PartialEq, Eq, Debug, Serialize, Deserialize, Clone, Copy)]
+pub struct TimeRange {
+    pub from: NaiveDateTime,
+    pub to: NaiveDateTime,
+}
+
+/// Time spent within a `TimeRange`, per task and per group.
+#[derive(PartialEq, Eq, Debug, Serialize, Deserialize, Clone)]
+pub struct Timesheet {
+    pub range: TimeRange,
+    /// Every task with time in the range, most time first.
+    pub tasks: Vec<TaskTime>,
+    /// Every group with time in the range, itself or below it, most time first.
+    pub groups: Vec<GroupTime>,
+}
+
+/// Time spent on a single task.
+#[derive(PartialEq, Eq, Debug, Serialize, Deserialize, Clone)]
+pub struct TaskTime {
+    pub task_id: Id,
+    pub name: Name,
+    pub group_id: Id,
+    pub seconds: i64,
+}
+
+/// Time spent on the tasks of a group.
+#[derive(PartialEq, Eq, Debug, Serialize, Deserialize, Clone)]
+pub struct GroupTime {
+    pub group_id: Id,
+    pub name: Name,
+    pub parent_id: Option<Id>,
+    /// Time spent on tasks directly inside this group.
+    pub own_seconds: i64,
+    /// Time spent on tasks in this group or any group below it.
+    pub total_seconds: i64,
+}
+
+impl TimeEntry {
+    /// Returns whether this is the running timer.
+    pub fn is_running(&self) -> bool {
+        self.ended_at.is_none()
+    }
+
+    /// Returns how long this entry lasted, or has lasted up to `now` if it's still running.
+    pub fn duration(&self, now: NaiveDateTime) -> TimeDelta {
+        self.ended_at.unwrap_or(now) - self.started_at
+    }
+
+    /// Starts the timer on a task through the `TarsDaemon`, stopping whichever timer was running.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if
+    /// + Something goes wrong with the requests to the Daemon.
+    /// + There is no task with this `Id`.
+    pub async fn start(
+        client: &TarsClient,
+        task_id: &Id,
+        note: Option<String>,
+    ) -> Result<Self, TarsError> {
+        let entry: TimeEntry = client
+            .conn
+            .post(client.base_path.join("/time/start")?)
+            .json(&TimerStart {
+                task_id: task_id.clone(),
+                note,
+            })
+            .send()
+            .await
+            .inspect_err(|e| error!("Error starting timer: {:?}", e))?
+            .error_for_daemon()
+            .await
+            .inspect_err(|e| error!("Error starting timer: {:?}", e))?
+            .json()
+            .await
+            .inspect_err(|e| error!("Error parsing started timer: {:?}", e))?;
+
+        Ok(entry)
+    }
+
+    /// Stops the running timer through the `TarsDaemon`, returning it if one was running.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if
+    /// Something goes wrong with the requests to the Daemon.
+    pub async fn stop(
+        client: &TarsClient,
+        note: Option<String>,
+    ) -> Result<Option<Self>, TarsError> {
+        let entry: Option<TimeEntry> = client
+            .conn
+            .post(client.base_path.join("/time/stop")?)
+            .json(&TimerStop { note })
+            .send()
+            .await
+            .inspect_err(|e| error!("Error stopping timer: {:?}", e))?
+            .error_for_daemon()
+            .await
+            .inspect_err(|e| error!("Error stopping timer: {:?}", e))?
+            .json()
+            .await
+            .inspect_err(|e| error!("Error parsing stopped timer: {:?}", e))?;
+
+        Ok(entry)
+    }
+
+    /// Returns the running timer, if there is one.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if
+    /// Something goes wrong with the requests to the Daemon.
+    pub async fn running(client: &TarsClient) -> Result<Option<Self>, TarsError> {
+        let entry: Option<TimeEntry> = client
+            .conn
+            .post(client.base_path.join("/time/running")?)
+            .send()
+            .await
+            .inspect_err(|e| error!("Error fetching running timer: {:?}", e))?
+            .error_for_daemon()
+            .await
+            .inspect_err(|e| error!("Error fetching running timer: {:?}", e))?
+            .json()
+            .await
+            .inspect_err(|e| error!("Error parsing running timer: {:?}", e))?;
+
+        Ok(entry)
+    }
+
+    /// Returns every entry overlapping `range`, oldest first.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if
+    /// Something goes wrong with the requests to the Daemon.
+    pub async fn fetch(client: &TarsClient, range: TimeRange) -> Result<Vec<Self>, TarsError> {
+        let entries: Vec<TimeEntry> = client
+            .conn
+            .post(client.base_path.join("/time/entries")?)
+            .json(&range)
+            .send()
+            .await
+            .inspect_err(|e| error!("Error fetching time entries: {:?}", e))?
+            .error_for_daemon()
+            .await
+            .inspect_err(|e| error!("Error fetching time entries: {:?}", e))?
+            .json()
+            .await
+            .inspect_err(|e| error!("Error parsing time entries: {:?}", e))?;
+
+        Ok(entries)
+    }
+}
+
+impl Timesheet {
+    /// Returns how much time was spent on each task and group within `range`, entries that
+    /// only partly overlap it count for the overlapping part.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if
+    /// Something goes wrong with the requests to the Daemon.
+    pub async fn fetch(client: &TarsClient, range: TimeRange) -> Result<Self, TarsError> {
+        let timesheet: Timesheet = client
+            .conn
+            .post(client.base_path.join("/time/timesheet")?)
+            .json(&range)
+            .send()
+            .await
+            .inspect_err(|e| error!("Error fetching timesheet: {:?}", e))?
+            .error_for_daemon()
+            .await
+            .inspect_err(|e| error!("Error fetching timesheet: {:?}", e))?
+            .json()
+            .await
+            .inspect_err(|e| error!("Error parsing timesheet: {:?}", e))?;
+
+        Ok(timesheet)
+    }
+}
@@ -0,0 +1,160 @@
+use std::fmt::Display;
+
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use tracing::error;
+
+use crate::{DaemonResponse, ParseError, TarsClient, TarsError};
+
+use super::{Id, Name};
+
+/// What an `ApiToken` may do, every level includes the ones below it.
+#[derive(
+    sqlx::Type, Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize, PartialOrd, Ord, Default,
+)]
+#[repr(i32)]
+pub enum Access {
+    /// Fetching tasks and groups, scores, plans and the diff stream.
+    #[default]
+    Read = 1,
+    /// Everything `Read` may, plus creating, changing and deleting data.
+    Write = 2,
+    /// Everything, including managing tokens and the scoring strategy.
+    Admin = 3,
+}
+
+impl Access {
+    pub const ALL: [Access; 3] = [Access::Read, Access::Write, Access::Admin];
+
+    pub fn parse_clap(str: &str) -> Result<Self, ParseError> {
+        str.try_into()
+    }
+}
+
+impl TryFrom<&str> for Access {
+    type Error = ParseError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        Self::ALL
+            .into_iter()
+            .find(|access| access.to_string() == value.trim().to_ascii_lowercase())
+            .ok_or_else(|| ParseError::FailedToParse(format!("{value:?} as an access level")))
+    }
+}
+
+impl Display for Access {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Access::Read => "read",
+            Access::Write => "write",
+            Access::Admin => "admin",
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// A token handed out to a script or dashboard, on top of the daemon's own token. Its secret
+/// is only ever shown once, when it's created.
+#[derive(PartialEq, Eq, Debug, Serialize, Deserialize, Clone)]
+pub struct ApiToken {
+    pub id: Id,
+    pub name: Name,
+    pub access: Access,
+    /// The group the token is limited to, along with everything below it, `None` for all of
+    /// them.
+    pub group_id: Option<Id>,
+    pub created_at: NaiveDateTime,
+}
+
+/// Asks the daemon for a new `ApiToken`.
+#[derive(PartialEq, Eq, Debug, Serialize, Deserialize, Clone)]
+pub struct TokenRequest {
+    pub name: Name,
+    pub access: Access,
+    pub group_id: Option<Id>,
+}
+
+/// A freshly created `ApiToken` along with its secret.
+#[derive(PartialEq, Eq, Debug, Serialize, Deserialize, Clone)]
+pub struct CreatedToken {
+    pub token: ApiToken,
+    pub secret: String,
+}
+
+impl ApiToken {
+    /// Creates a new token through the `TarsDaemon`, returning it along with its secret.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if
+    /// + Something goes wrong with the requests to the Daemon.
+    /// + The client's own token isn't an admin token.
+    /// + The group doesn't exist, or an admin token is limited to a group.
+    pub async fn create(
+        client: &TarsClient,
+        request: TokenRequest,
+    ) -> Result<CreatedToken, TarsError> {
+        let created: CreatedToken = client
+            .conn
+            .post(client.base_path.join("/auth/tokens/create")?)
+            .json(&request)
+            .send()
+            .await
+            .inspect_err(|e| error!("Error creating token: {:?}", e))?
+            .error_for_daemon()
+            .await
+            .inspect_err(|e| error!("Error creating token: {:?}", e))?
+            .json()
+            .await
+            .inspect_err(|e| error!("Error parsing created token: {:?}", e))?;
+
+        Ok(created)
+    }
+
+    /// Returns every token, oldest first.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if
+    /// + Something goes wrong with the requests to the Daemon.
+    /// + The client's own token isn't an admin token.
+    pub async fn fetch_all(client: &TarsClient) -> Result<Vec<Self>, TarsError> {
+        let tokens: Vec<ApiToken> = client
+            .conn
+            .get(client.base_path.join("/auth/tokens")?)
+            .send()
+            .await
+            .inspect_err(|e| error!("Error fetching tokens: {:?}", e))?
+            .error_for_daemon()
+            .await
+            .inspect_err(|e| error!("Error fetching tokens: {:?}", e))?
+            .json()
+            .await
+            .inspect_err(|e| error!("Error parsing tokens: {:?}", e))?;
+
+        Ok(tokens)
+    }
+
+    /// Revokes the token with this `Id`, it stops working right away.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if
+    /// + Something goes wrong with the requests to the Daemon.
+    /// + The client's own token isn't an admin token.
+    /// + There is no token with this `Id`.
+    pub async fn revoke(client: &TarsClient, id: &Id) -> Result<(), TarsError> {
+        client
+            .conn
+            .post(client.base_path.join("/auth/tokens/revoke")?)
+            .json(id)
+            .send()
+            .await
+            .inspect_err(|e| error!("Error revoking token: {:?}", e))?
+            .error_for_daemon()
+            .await
+            .inspect_err(|e| error!("Error revoking token: {:?}", e))?;
+
+        Ok(())
+    }
+}
@@ -4,7 +4,10 @@ use clap::{Args, Parser, Subcommand, ValueEnum};
 use color_eyre::owo_colors::OwoColorize;
 use common::{
     ParseError,
-    types::{Color, Id, Name, Priority, parse_date_time},
+    types::{
+        Access, Color, Estimate, Id, Name, Priority, Recurrence, ScoringStrategyKind,
+        parse_date_time,
+    },
 };
 use sqlx::types::chrono::NaiveDateTime;
 
@@ -33,6 +36,72 @@ pub enum Commands {
     // default values if they arent present / aren't able to be
     // parsed properly
     Import(ImportArgs),
+
+    /// Undoes the most recent change made to TARS.
+    Undo,
+
+    /// Redoes the most recently undone change.
+    Redo,
+
+    /// Searches the names and descriptions of tasks and groups.
+    Search(SearchArgs),
+
+    /// Shows how tasks are being ranked, or switches to another scoring strategy.
+    Scoring(ScoringArgs),
+
+    /// Start or stop tracking time on a task.
+    #[command(subcommand)]
+    Clock(ClockSubcommand),
+
+    /// Report where the time went, today by default.
+    Timesheet(TimesheetArgs),
+
+    /// Pick the highest scoring tasks that fit into the next few hours.
+    Plan(PlanArgs),
+
+    /// Lay the open tasks out over the coming working days.
+    Schedule(ScheduleArgs),
+
+    /// Manage the token clients authenticate to the daemon with.
+    #[command(subcommand)]
+    Token(TokenSubcommand),
+}
+
+#[derive(Subcommand, Debug)]
+/// Subcommand to manage the daemon's token.
+pub enum TokenSubcommand {
+    /// Replace the token with a fresh one, running clients have to be restarted.
+    Rotate,
+    /// Hand out an extra token, limited in what it may do.
+    Create(TokenCreateArgs),
+    /// List the extra tokens.
+    List,
+    /// Revoke an extra token, it stops working right away.
+    Revoke(TokenRevokeArgs),
+}
+
+#[derive(Debug, Args)]
+/// Arguments for creating a token.
+pub struct TokenCreateArgs {
+    #[arg(short, long, value_parser=Name::parse_clap)]
+    /// What the token is for, ex. `statusbar`.
+    pub name: Name,
+
+    #[arg(short, long, value_parser=Access::parse_clap, default_value = "read")]
+    /// What the token may do, one of `read`, `write` or `admin`.
+    pub access: Access,
+
+    #[arg(short, long, value_parser=Name::parse_clap)]
+    /// Limit the token to this group, along with the groups below it.
+    pub group: Option<Name>,
+}
+
+#[derive(Debug, Args)]
+/// Arguments for revoking a token.
+pub struct TokenRevokeArgs {
+    #[arg(short, long, value_parser=Id::parse_clap)]
+    /// The id of the token.
+    pub id: Id,
 }
 
 #[derive(Subcommand, Debug)]
@@ -42,6 +111,8 @@ pub enum GroupSubcommand {
     Add(GroupAddArgs),
     /// List groups.
     List(GroupListArgs),
+    /// Move a group, along with everything inside it, under another group.
+    Move(GroupMoveArgs),
 }
 
 #[derive(Debug, Args)]
@@ -63,6 +134,88 @@ pub struct ImportArgs {
     pub strict: bool,
 }
 
+#[derive(Debug, Args)]
+/// Arguments for searching tasks and groups.
+pub struct SearchArgs {
+    /// The words to look for, every word has to match the start of a word.
+    #[arg(required = true)]
+    pub query: Vec<String>,
+
+    #[arg(short, long)]
+    /// Show at most this many matches.
+    pub limit: Option<u32>,
+}
+
+#[derive(Subcommand, Debug)]
+/// Subcommand to track time.
+pub enum ClockSubcommand {
+    /// Start the timer on a task, stopping whichever timer was running.
+    In(ClockInArgs),
+    /// Stop the running timer.
+    Out(ClockOutArgs),
+}
+
+#[derive(Debug, Args)]
+/// Arguments for starting a timer.
+pub struct ClockInArgs {
+    #[arg(short, long, value_parser=Id::parse_clap)]
+    /// The id of the task.
+    pub id: Id,
+
+    #[arg(short, long)]
+    /// What the time is being spent on.
+    pub note: Option<String>,
+}
+
+#[derive(Debug, Args)]
+/// Arguments for stopping the timer.
+pub struct ClockOutArgs {
+    #[arg(short, long)]
+    /// What the time was spent on, replaces the note given when clocking in.
+    pub note: Option<String>,
+}
+
+#[derive(Debug, Args)]
+/// Arguments for reporting tracked time.
+pub struct TimesheetArgs {
+    #[arg(short, long)]
+    /// Report the current week, starting on monday, instead of today.
+    pub week: bool,
+}
+
+#[derive(Debug, Args)]
+/// Arguments for planning the next few hours.
+pub struct PlanArgs {
+    #[arg(short = 'H', long)]
+    /// How many hours there are to fill, ex. `3` or `1.5`.
+    pub hours: f64,
+
+    #[arg(short, long, value_parser=Name::parse_clap)]
+    /// Only plan tasks within this group, or the groups below it.
+    pub group: Option<Name>,
+}
+
+#[derive(Debug, Args)]
+/// Arguments for scheduling the coming days.
+pub struct ScheduleArgs {
+    #[arg(short, long)]
+    /// How many days ahead to schedule, the daemon's horizon by default.
+    pub days: Option<u32>,
+
+    #[arg(short, long, value_parser=Name::parse_clap)]
+    /// Only schedule tasks within this group, or the groups below it.
+    pub group: Option<Name>,
+}
+
+#[derive(Debug, Args)]
+/// Arguments for picking a scoring strategy.
+pub struct ScoringArgs {
+    #[arg(value_parser=ScoringStrategyKind::parse_clap)]
+    /// The strategy to switch to, one of classic, deadline-first, eisenhower or wsjf.
+    /// Leave it out to see the active one.
+    pub strategy: Option<ScoringStrategyKind>,
+}
+
 #[derive(Debug, Args)]
 /// Arguments for adding a group.
 pub struct GroupAddArgs {
@@ -92,6 +245,19 @@ pub struct GroupListArgs {
     pub name: Option<Name>,
 }
 
+#[derive(Debug, Args)]
+/// Arguments for moving a group.
+pub struct GroupMoveArgs {
+    #[arg(short, long, value_parser=Name::parse_clap)]
+    /// Name of the group to move.
+    pub name: Name,
+
+    #[arg(short, long, value_parser=Name::parse_clap)]
+    /// Name of the new parent group.
+    /// NOTE: Will be moved to the top level if argument not provided.
+    pub parent: Option<Name>,
+}
+
 #[derive(Subcommand, Debug)]
 /// Subcommand to mange tars tasks.
 pub enum TaskSubcommand {
@@ -99,6 +265,18 @@ pub enum TaskSubcommand {
     Add(TaskAddArgs),
     /// List tasks.
     List(TaskListArgs),
+    /// Move a task into another group.
+    Move(TaskMoveArgs),
+    /// Mark a task as blocked by another one.
+    Block(TaskBlockArgs),
+    /// Remove a task from the ones blocking another.
+    Unblock(TaskBlockArgs),
+    /// Show the tasks blocking a task, and the ones it blocks.
+    Deps(TaskDepsArgs),
+    /// Show the factors behind a task's score.
+    Explain(TaskExplainArgs),
+    /// Set how long a task is expected to take.
+    Estimate(TaskEstimateArgs),
 }
 
 #[derive(Debug, Args)]
@@ -123,6 +301,24 @@ pub struct TaskAddArgs {
     #[arg(short, long)]
     /// A description of the task at hand.
     pub description: String,
+
+    #[arg(short, long = "tag", value_parser=Name::parse_clap)]
+    /// Tags to attach to the task, can be repeated.
+    /// NOTE: Tags that don't exist yet will be created.
+    pub tags: Vec<Name>,
+
+    #[arg(short, long, value_parser=Recurrence::parse_str)]
+    /// Makes the task repeat once finished, one of
+    /// `daily`, `weekly`, `weekly:mon,fri`, `monthly:15` or `every:3d`.
+    pub repeat: Option<Recurrence>,
+
+    #[arg(short, long = "check")]
+    /// Checklist items of the task, in order, can be repeated.
+    pub checklist: Vec<String>,
+
+    #[arg(short, long, value_parser=Estimate::parse_str)]
+    /// How long the task is expected to take, ex. `45m`, `2h` or `1h30m`.
+    pub estimate: Option<Estimate>,
 }
 
 #[derive(Debug, Args)]
@@ -138,6 +334,63 @@ pub struct TaskListArgs {
 
     #[arg(short, long)]
     unfinished: Option<bool>,
+
+    #[arg(short, long, value_parser=Name::parse_clap)]
+    /// Only list tasks carrying this tag.
+    pub tag: Option<Name>,
+}
+
+#[derive(Debug, Args)]
+/// Arguments for moving a task.
+pub struct TaskMoveArgs {
+    #[arg(short, long, value_parser=Id::parse_clap)]
+    /// The id of the task to move.
+    pub id: Id,
+
+    #[arg(short, long, value_parser=Name::parse_clap)]
+    /// The group the task should be moved into.
+    pub group: Name,
+}
+
+#[derive(Debug, Args)]
+/// Arguments for editing the dependencies of a task.
+pub struct TaskBlockArgs {
+    #[arg(short, long, value_parser=Id::parse_clap)]
+    /// The id of the blocked task.
+    pub id: Id,
+
+    #[arg(short, long, value_parser=Id::parse_clap)]
+    /// The id of the task blocking it.
+    pub by: Id,
+}
+
+#[derive(Debug, Args)]
+/// Arguments for showing the dependencies of a task.
+pub struct TaskDepsArgs {
+    #[arg(short, long, value_parser=Id::parse_clap)]
+    /// The id of the task.
+    pub id: Id,
+}
+
+#[derive(Debug, Args)]
+/// Arguments for explaining the score of a task.
+pub struct TaskExplainArgs {
+    #[arg(value_parser=Id::parse_clap)]
+    /// The id of the task.
+    pub id: Id,
+}
+
+#[derive(Debug, Args)]
+/// Arguments for estimating a task.
+pub struct TaskEstimateArgs {
+    #[arg(value_parser=Id::parse_clap)]
+    /// The id of the task.
+    pub id: Id,
+
+    #[arg(value_parser=Estimate::parse_str)]
+    /// How long the task is expected to take, ex. `45m`, `2h` or `1h30m`.
+    /// Leave it out to clear the estimate.
+    pub estimate: Option<Estimate>,
 }
 
 #[derive(Debug, Copy, Clone, ValueEnum)]
@@ -179,7 +432,7 @@ pub fn clap_parse_date(arg: &str) -> Result<Option<NaiveDateTime>, ParseError> {
                     .magenta()
             );
 
-            Err(ParseError::FailedToParse)
+            Err(ParseError::FailedToParse(format!("{arg:?} as a date")))
         }
     }
 }
@@ -1,10 +1,22 @@
-use color_eyre::eyre::Result;
+use color_eyre::{
+    eyre::{OptionExt, Result, eyre},
+    owo_colors::OwoColorize,
+};
 use common::{
-    TarsClient,
-    types::{Group, Task, TaskFetchOptions},
+    Diff, DiffInner, Patch, TarsClient, auth,
+    types::{
+        ApiToken, ChecklistItem, Color, Estimate, Group, GroupTime, Id, Name, Plan, SNIPPET_CLOSE,
+        SNIPPET_OPEN, Schedule, ScheduleRequest, ScoreExplanation, ScoringStrategyKind, SearchItem,
+        Tag, Task, TaskFetchOptions, TaskPatch, TimeEntry, TimeRange, Timesheet, TokenRequest,
+    },
 };
 
-use crate::args::{GroupSubcommand, TaskSubcommand};
+use chrono::{Local, NaiveTime, TimeDelta, Weekday};
+
+use crate::args::{
+    ClockSubcommand, GroupSubcommand, PlanArgs, ScheduleArgs, ScoringArgs, SearchArgs,
+    TaskSubcommand, TimesheetArgs, TokenSubcommand,
+};
 
 pub async fn task_handler(client: &TarsClient, t_sub: TaskSubcommand) -> Result<()> {
     match t_sub {
@@ -28,7 +40,7 @@ pub async fn task_handler(client: &TarsClient, t_sub: TaskSubcommand) -> Result<
                 }
             };
 
-            let task = Task::new(
+            let mut task = Task::new(
                 client,
                 &g,
                 args.name,
@@ -38,18 +50,115 @@ pub async fn task_handler(client: &TarsClient, t_sub: TaskSubcommand) -> Result<
             )
             .await?;
 
+            if !args.tags.is_empty() {
+                let all_tags = Tag::fetch_all(client).await?;
+                let mut tags = Vec::new();
+
+                for name in args.tags {
+                    let tag = match all_tags.iter().find(|t| t.name == name) {
+                        Some(t) => t.to_owned(),
+                        None => {
+                            let t = Tag::new(client, name, Color::random()).await?;
+                            eprintln!("Created new tag:\n{t}");
+                            t
+                        }
+                    };
+                    tags.push(tag);
+                }
+
+                task.set_tags(tags);
+            }
+
+            task.recurrence = args.repeat;
+            task.checklist = args.checklist.into_iter().map(ChecklistItem::new).collect();
+            task.estimate = args.estimate;
+
+            if !task.tags.is_empty()
+                || task.recurrence.is_some()
+                || !task.checklist.is_empty()
+                || task.estimate.is_some()
+            {
+                task.sync(client).await?;
+            }
+
             println!("Added Task:\n{task}");
         }
-        TaskSubcommand::List(_args) => {
+        TaskSubcommand::List(args) => {
             // TODO: we need to filter on the arguments / debate whether we
             // want to do this filtering on the serverf side through taskfetchoptions.
-            let all_tasks = Task::fetch(client, TaskFetchOptions::All).await?;
+            let fetch_opts = match args.tag {
+                Some(name) => {
+                    let tag = Tag::fetch_all(client)
+                        .await?
+                        .into_iter()
+                        .find(|t| t.name == name)
+                        .ok_or_eyre("No tag with that name exists")?;
+
+                    TaskFetchOptions::ByTag { tag_id: tag.id }
+                }
+                None => TaskFetchOptions::All,
+            };
+
+            let all_tasks = Task::fetch(client, fetch_opts).await?;
 
             for t in all_tasks.iter() {
                 println!("{t}");
                 println!("====================================================")
             }
         }
+        TaskSubcommand::Move(args) => {
+            let mut task = fetch_task(client, &args.id).await?;
+
+            let all_groups = Group::fetch_all(client).await?;
+            task.group = all_groups
+                .into_iter()
+                .find(|g| g.name == args.group)
+                .ok_or_eyre("No group with that name exists")?;
+
+            task.sync(client).await?;
+
+            println!("Moved Task:\n{task}");
+        }
+        TaskSubcommand::Block(args) => {
+            let mut task = fetch_task(client, &args.id).await?;
+            task.block_on(client, &args.by).await?;
+
+            println!("Blocked Task:\n{task}");
+        }
+        TaskSubcommand::Unblock(args) => {
+            let mut task = fetch_task(client, &args.id).await?;
+            task.unblock(client, &args.by).await?;
+
+            println!("Unblocked Task:\n{task}");
+        }
+        TaskSubcommand::Deps(args) => {
+            let task = fetch_task(client, &args.id).await?;
+            let deps = task.fetch_dependencies(client).await?;
+
+            println!("Blocked by:");
+            for blocker in deps.blockers {
+                println!("{blocker}\n");
+            }
+
+            println!("Blocks:");
+            for dependent in deps.dependents {
+                println!("{dependent}\n");
+            }
+        }
+        TaskSubcommand::Explain(args) => {
+            let task = fetch_task(client, &args.id).await?;
+            let explanation = task.explain_score(client).await?;
+
+            print_score_explanation(&task, &explanation);
+        }
+        TaskSubcommand::Estimate(args) => {
+            let task = TaskPatch::new(args.id)
+                .estimate(args.estimate)
+                .send(client)
+                .await?;
+
+            println!("Estimated Task:\n{task}");
+        }
     }
     Ok(())
 }
@@ -105,6 +214,500 @@ pub async fn group_handler(client: &TarsClient, g_sub: GroupSubcommand) -> Resul
                 println!("====================================================")
             }
         }
+        GroupSubcommand::Move(args) => {
+            let all = Group::fetch_all(client).await?;
+            let group = all
+                .iter()
+                .find(|g| g.name == args.name)
+                .ok_or_eyre("No group with that name exists")?;
+
+            let parent_id = match args.parent {
+                Some(parent_name) => Some(
+                    all.iter()
+                        .find(|g| g.name == parent_name)
+                        .map(|g| g.id.clone())
+                        .ok_or_eyre("No parent group with that name exists")?,
+                ),
+                None => None,
+            };
+
+            let moved = group.move_to(client, parent_id).await?;
+            println!("Moved Group: {moved}");
+        }
+    }
+    Ok(())
+}
+
+pub async fn undo_handler(client: &TarsClient) -> Result<()> {
+    let diffs = client.undo().await?;
+
+    if diffs.is_empty() {
+        println!("{}", "Nothing to undo!".yellow());
+    }
+
+    print_diffs(&diffs);
+    Ok(())
+}
+
+pub async fn redo_handler(client: &TarsClient) -> Result<()> {
+    let diffs = client.redo().await?;
+
+    if diffs.is_empty() {
+        println!("{}", "Nothing to redo!".yellow());
+    }
+
+    print_diffs(&diffs);
+    Ok(())
+}
+
+/// Finds the task with this id.
+async fn fetch_task(client: &TarsClient, id: &Id) -> Result<Task> {
+    Task::fetch(client, TaskFetchOptions::All)
+        .await?
+        .into_iter()
+        .find(|t| t.id == *id)
+        .ok_or_eyre("No task with that id exists")
+}
+
+pub async fn search_handler(client: &TarsClient, args: SearchArgs) -> Result<()> {
+    let matches = Task::search(client, args.query.join(" "), args.limit).await?;
+
+    if matches.is_empty() {
+        println!("{}", "No matches!".yellow());
+    }
+
+    for m in matches {
+        let (kind, name, id) = match m.item {
+            SearchItem::Task(ref t) => ("Task", t.name.as_str(), t.id.as_str()),
+            SearchItem::Group(ref g) => ("Group", g.name.as_str(), g.id.as_str()),
+        };
+        println!("{kind}: {} ({id})", name.green());
+        println!("  {}", highlight_snippet(&m.snippet));
+    }
+
+    Ok(())
+}
+
+pub async fn scoring_handler(client: &TarsClient, args: ScoringArgs) -> Result<()> {
+    let active = match args.strategy {
+        Some(strategy) => {
+            client.set_scoring_strategy(strategy).await?;
+            strategy
+        }
+        None => client.scoring_strategy().await?,
+    };
+
+    for kind in ScoringStrategyKind::ALL {
+        if kind == active {
+            println!("{} {}", "*".green(), kind.green().bold());
+        } else {
+            println!("  {kind}");
+        }
+    }
+
+    Ok(())
+}
+
+/// Prints the factors behind a task's score as a small table.
+fn print_score_explanation(task: &Task, explanation: &ScoreExplanation) {
+    println!(
+        "{} scored by {}",
+        task.name.as_str().green(),
+        explanation.strategy.bold()
+    );
+
+    println!(
+        "  {:<24} {:>10.4}",
+        format!("priority ({})", explanation.priority),
+        explanation.priority_multiplier
+    );
+    for group in &explanation.groups {
+        println!(
+            "  {:<24} {:>10.4}",
+            format!("× {} ({})", *group.name, group.priority),
+            group.multiplier
+        );
+    }
+    if let Some(days_left) = explanation.days_left {
+        println!("  {:<24} {:>10.2}", "days left", days_left);
+    }
+    println!(
+        "  {:<24} {:>10}",
+        "blocked dependents", explanation.blocked_dependents
+    );
+
+    println!();
+    for term in &explanation.terms {
+        println!("  {:<24} {:>10.4}", term.name, term.value);
+    }
+    println!(
+        "  {:<24} {:>10.4}",
+        "= score".bold(),
+        explanation.score.bold()
+    );
+}
+
+pub async fn clock_handler(client: &TarsClient, c_sub: ClockSubcommand) -> Result<()> {
+    match c_sub {
+        ClockSubcommand::In(args) => {
+            let task = fetch_task(client, &args.id).await?;
+            let previous = TimeEntry::running(client).await?;
+            let entry = TimeEntry::start(client, &task.id, args.note).await?;
+
+            if let Some(previous) = previous {
+                println!(
+                    "Stopped the timer on {} after {}",
+                    *previous.task_id,
+                    format_duration(previous.duration(entry.started_at).num_seconds())
+                );
+            }
+            println!("Clocked in on {}", task.name.as_str().green());
+        }
+        ClockSubcommand::Out(args) => match TimeEntry::stop(client, args.note).await? {
+            Some(entry) => {
+                let now = Local::now().naive_local();
+                println!(
+                    "Clocked out after {}",
+                    format_duration(entry.duration(now).num_seconds()).green()
+                );
+            }
+            None => println!("{}", "No timer is running!".yellow()),
+        },
+    }
+    Ok(())
+}
+
+pub async fn timesheet_handler(client: &TarsClient, args: TimesheetArgs) -> Result<()> {
+    let today = Local::now().date_naive();
+    let (from, days) = if args.week {
+        (today.week(Weekday::Mon).first_day(), 7)
+    } else {
+        (today, 1)
+    };
+    let from = from.and_time(NaiveTime::MIN);
+    let range = TimeRange {
+        from,
+        to: from + TimeDelta::days(days),
+    };
+
+    let timesheet = Timesheet::fetch(client, range).await?;
+    println!(
+        "Timesheet {} - {}",
+        range.from.format("%m/%d/%Y"),
+        (range.to - TimeDelta::days(1)).format("%m/%d/%Y")
+    );
+
+    if timesheet.groups.is_empty() {
+        println!("{}", "No time tracked!".yellow());
+        return Ok(());
+    }
+
+    let mut total = 0;
+    for root in timesheet.groups.iter().filter(|g| g.parent_id.is_none()) {
+        print_group(&timesheet, root, 0);
+        total += root.total_seconds;
+    }
+    println!(
+        "{:<32} {:>10}",
+        "Total".green(),
+        format_duration(total).green()
+    );
+
+    Ok(())
+}
+
+pub async fn plan_handler(client: &TarsClient, args: PlanArgs) -> Result<()> {
+    if !args.hours.is_finite() || args.hours <= 0.0 {
+        return Err(eyre!("The number of hours must be positive"));
+    }
+    let available = Estimate::from_minutes((args.hours * 60.0).round() as u32);
+
+    let scope = match args.group {
+        Some(name) => Some(find_group_id(client, &name).await?),
+        None => None,
+    };
+
+    let plan = Plan::fetch(client, available, scope).await?;
+    println!(
+        "Plan for the next {} ({} planned)",
+        plan.available.bold(),
+        plan.planned.green()
+    );
+
+    if plan.tasks.is_empty() {
+        println!("{}", "Nothing fits!".yellow());
+    }
+    for planned in &plan.tasks {
+        let task = &planned.task;
+        println!(
+            "{:>7.3} {:>6} {} {} {}",
+            planned.score,
+            task.estimate.unwrap_or_default().to_string(),
+            task.name.as_str().bold(),
+            format!("[{}]", task.group.name.as_str()).dimmed(),
+            task.id.as_str().dimmed()
+        );
+    }
+
+    if !plan.groups.is_empty() {
+        println!("\n{:<32} {:>8} {:>8}", "Group".bold(), "Planned", "Open");
+    }
+    for group in &plan.groups {
+        println!(
+            "{:<32} {:>8} {:>8}",
+            group.name.as_str(),
+            group.planned.to_string(),
+            group.open.to_string()
+        );
+    }
+
+    if plan.unestimated > 0 {
+        println!(
+            "{}",
+            format!(
+                "\n{} open task(s) have no estimate and were left out.",
+                plan.unestimated
+            )
+            .yellow()
+        );
+    }
+
+    Ok(())
+}
+
+pub async fn schedule_handler(client: &TarsClient, args: ScheduleArgs) -> Result<()> {
+    let scope = match args.group {
+        Some(name) => Some(find_group_id(client, &name).await?),
+        None => None,
+    };
+
+    let schedule = Schedule::fetch(
+        client,
+        ScheduleRequest {
+            scope,
+            from: None,
+            days: args.days,
+        },
+    )
+    .await?;
+
+    for day in &schedule.days {
+        let header = format!(
+            "{} ({} / {})",
+            day.date.format("%a %m/%d/%Y"),
+            day.booked,
+            day.capacity
+        );
+        if day.is_over_capacity() {
+            println!("{} {}", header.red().bold(), "over capacity".red());
+        } else {
+            println!("{}", header.bold());
+        }
+
+        for block in &day.blocks {
+            let line = format!(
+                "  {}-{} {:>6} {}",
+                block.start.format("%H:%M"),
+                block.end.format("%H:%M"),
+                block.duration().to_string(),
+                block.name.as_str()
+            );
+            if block.late {
+                println!("{}", line.red());
+            } else {
+                println!("{line}");
+            }
+        }
+    }
+
+    if !schedule.late.is_empty() {
+        println!("\n{}", "Late:".red().bold());
+    }
+    for late in &schedule.late {
+        println!(
+            "  {} due {}, {} short",
+            late.name.as_str(),
+            late.due.format("%m/%d/%Y %H:%M"),
+            late.missing
+        );
+    }
+
+    if !schedule.unplaced.is_empty() {
+        println!("\n{}", "Doesn't fit:".yellow().bold());
+    }
+    for unplaced in &schedule.unplaced {
+        println!(
+            "  {}, {} left over",
+            unplaced.name.as_str(),
+            unplaced.remaining
+        );
     }
+
+    if schedule.unestimated > 0 || schedule.blocked > 0 {
+        println!(
+            "{}",
+            format!(
+                "\nLeft out {} task(s) without an estimate and {} blocked task(s).",
+                schedule.unestimated, schedule.blocked
+            )
+            .yellow()
+        );
+    }
+
     Ok(())
 }
+
+pub async fn token_handler(client: &TarsClient, t_sub: TokenSubcommand) -> Result<()> {
+    match t_sub {
+        TokenSubcommand::Rotate => {
+            client.rotate_token().await?;
+
+            println!(
+                "Rotated the daemon token, it's stored in {}",
+                auth::token_path().display()
+            );
+            println!(
+                "{}",
+                "Clients that are already running have to be restarted.".yellow()
+            );
+        }
+        TokenSubcommand::Create(args) => {
+            let group_id = match args.group {
+                Some(ref name) => Some(find_group_id(client, name).await?),
+                None => None,
+            };
+            let created = ApiToken::create(
+                client,
+                TokenRequest {
+                    name: args.name,
+                    access: args.access,
+                    group_id,
+                },
+            )
+            .await?;
+
+            println!(
+                "Created {} token {} ({})",
+                created.token.access,
+                created.token.name.as_str().green(),
+                *created.token.id
+            );
+            println!("{}", created.secret.bold());
+            println!(
+                "{}",
+                "This is the only time the token is shown, store it somewhere safe.".yellow()
+            );
+        }
+        TokenSubcommand::List => {
+            let tokens = ApiToken::fetch_all(client).await?;
+            if tokens.is_empty() {
+                println!("{}", "No tokens have been created.".yellow());
+                return Ok(());
+            }
+
+            let groups = Group::fetch_all(client).await?;
+            for token in tokens {
+                let scope = match token.group_id {
+                    Some(ref id) => groups
+                        .iter()
+                        .find(|g| g.id == *id)
+                        .map_or_else(|| id.to_string(), |g| g.name.to_string()),
+                    None => "everything".to_owned(),
+                };
+                println!(
+                    "{}  {:<20} {:<6} {}",
+                    *token.id,
+                    token.name.as_str().bold(),
+                    token.access.to_string(),
+                    scope
+                );
+            }
+        }
+        TokenSubcommand::Revoke(args) => {
+            ApiToken::revoke(client, &args.id).await?;
+            println!("Revoked token {}", *args.id);
+        }
+    }
+    Ok(())
+}
+
+/// Looks up the id of the group with this name.
+async fn find_group_id(client: &TarsClient, name: &Name) -> Result<Id> {
+    Group::fetch_all(client)
+        .await?
+        .into_iter()
+        .find(|g| g.name == *name)
+        .map(|g| g.id)
+        .ok_or_eyre("No group with that name exists")
+}
+
+/// Prints a group of a timesheet, followed by its tasks and then the groups below it.
+fn print_group(timesheet: &Timesheet, group: &GroupTime, depth: usize) {
+    let indent = "  ".repeat(depth);
+    // keeps the durations lined up however deep the group is
+    let width = 32usize.saturating_sub(indent.len());
+    println!(
+        "{indent}{:<width$} {:>10}",
+        group.name.as_str().bold(),
+        format_duration(group.total_seconds).bold()
+    );
+
+    for task in timesheet
+        .tasks
+        .iter()
+        .filter(|t| t.group_id == group.group_id)
+    {
+        println!(
+            "{indent}  {:<w$} {:>10}",
+            task.name.as_str(),
+            format_duration(task.seconds),
+            w = width.saturating_sub(2)
+        );
+    }
+    for child in timesheet
+        .groups
+        .iter()
+        .filter(|g| g.parent_id.as_ref() == Some(&group.group_id))
+    {
+        print_group(timesheet, child, depth + 1);
+    }
+}
+
+/// Formats a number of seconds as hours and minutes, ex. `3h 05m`.
+fn format_duration(seconds: i64) -> String {
+    format!("{}h {:02}m", seconds / 3600, seconds % 3600 / 60)
+}
+
+/// Colors the matching words of a search snippet.
+fn highlight_snippet(snippet: &str) -> String {
+    let mut highlighted = String::new();
+    let mut rest = snippet;
+
+    while let Some((before, after)) = rest.split_once(SNIPPET_OPEN) {
+        let Some((word, after)) = after.split_once(SNIPPET_CLOSE) else {
+            break;
+        };
+        highlighted.push_str(before);
+        highlighted.push_str(&word.bold().yellow().to_string());
+        rest = after;
+    }
+    highlighted.push_str(rest);
+
+    highlighted
+}
+
+fn print_diffs(diffs: &[Diff]) {
+    for diff in diffs {
+        match diff {
+            Diff::Added(DiffInner::Task(t)) => println!("Added Task:\n{t}"),
+            Diff::Added(DiffInner::Group(g)) => println!("Added Group:\n{g}"),
+            Diff::Updated(DiffInner::Task(t)) => println!("Updated Task:\n{t}"),
+            Diff::Updated(DiffInner::Group(g)) => println!("Updated Group:\n{g}"),
+            Diff::Patched(Patch::Task(p)) => println!("Patched Task: {}", *p.id),
+            Diff::Patched(Patch::Group(p)) => println!("Patched Group: {}", *p.id),
+            Diff::Deleted(id) => println!("Deleted: {}", **id),
+            Diff::Notification(_) => continue,
+        }
+        println!("====================================================")
+    }
+}
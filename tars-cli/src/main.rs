@@ -5,7 +5,10 @@ use crate::{
 use clap::Parser;
 use color_eyre::{eyre::Result, owo_colors::OwoColorize};
 use common::TarsClient;
-use handlers::{group_handler, task_handler};
+use handlers::{
+    clock_handler, group_handler, plan_handler, redo_handler, schedule_handler, scoring_handler,
+    search_handler, task_handler, timesheet_handler, token_handler, undo_handler,
+};
 use rustyline::{Config, Editor, history::FileHistory};
 mod args;
 mod handlers;
@@ -16,7 +19,7 @@ async fn main() -> Result<()> {
     color_eyre::install()?;
     let args = CliArgs::parse();
 
-    let client = TarsClient::default().await.unwrap();
+    let client = TarsClient::default().await?;
 
     match args.command {
         Commands::Group(g_sub) => group_handler(&client, g_sub).await,
@@ -25,6 +28,21 @@ async fn main() -> Result<()> {
 
         Commands::Export(ex_args) => export(ex_args).await,
         Commands::Import(im_args) => import(im_args).await,
+
+        Commands::Undo => undo_handler(&client).await,
+        Commands::Redo => redo_handler(&client).await,
+
+        Commands::Search(args) => search_handler(&client, args).await,
+
+        Commands::Scoring(args) => scoring_handler(&client, args).await,
+
+        Commands::Clock(c_sub) => clock_handler(&client, c_sub).await,
+        Commands::Timesheet(args) => timesheet_handler(&client, args).await,
+
+        Commands::Plan(args) => plan_handler(&client, args).await,
+        Commands::Schedule(args) => schedule_handler(&client, args).await,
+
+        Commands::Token(t_sub) => token_handler(&client, t_sub).await,
     }
 }
 
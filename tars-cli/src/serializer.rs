@@ -3,7 +3,7 @@ use std::fs;
 use color_eyre::eyre::{Result, eyre};
 use common::{
     TarsClient,
-    types::{Color, Group, Task, TaskFetchOptions, parse_date_time},
+    types::{Color, Group, Tag, Task, TaskFetchOptions, parse_date_time},
 };
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
@@ -15,14 +15,21 @@ use crate::args::{ExportArgs, ImportArgs};
 struct SerializedInfo {
     groups: Vec<Group>,
     tasks: Vec<Task>,
+    #[serde(default)]
+    tags: Vec<Tag>,
 }
 
 pub async fn export(args: ExportArgs) -> Result<()> {
     let client = TarsClient::default().await?;
     let tasks = Task::fetch(&client, TaskFetchOptions::All).await?;
     let groups = Group::fetch_all(&client).await?;
+    let tags = Tag::fetch_all(&client).await?;
 
-    let export_info = SerializedInfo { groups, tasks };
+    let export_info = SerializedInfo {
+        groups,
+        tasks,
+        tags,
+    };
 
     let export_info_str = serde_json::to_string_pretty(&export_info)?;
 
@@ -69,7 +76,18 @@ pub async fn import(args: ImportArgs) -> Result<()> {
             task_i += 1;
         }
 
-        SerializedInfo { groups, tasks }
+        // tags were added later on, so older exports won't have them
+        let tags = intermediate
+            .get("tags")
+            .map(|t| serde_json::from_value(t.clone()))
+            .transpose()?
+            .unwrap_or_default();
+
+        SerializedInfo {
+            groups,
+            tasks,
+            tags,
+        }
     };
 
     let client = TarsClient::default().await?;
@@ -78,6 +96,10 @@ pub async fn import(args: ImportArgs) -> Result<()> {
         group.raw_create(&client).await?;
     }
 
+    for tag in import_info.tags {
+        tag.raw_create(&client).await?;
+    }
+
     for task in import_info.tasks {
         task.raw_create(&client).await?;
     }
@@ -166,7 +188,12 @@ pub fn extract_task(task_json: &Value) -> Task {
         }
     });
 
-    Task::with_all_fields(
+    let tags = task_json
+        .get("tags")
+        .and_then(|t| serde_json::from_value(t.clone()).ok())
+        .unwrap_or_default();
+
+    let mut task = Task::with_all_fields(
         id,
         group,
         name,
@@ -175,7 +202,22 @@ pub fn extract_task(task_json: &Value) -> Task {
         finished_at,
         created_at,
         due,
-    )
+    );
+    task.set_tags(tags);
+    task.recurrence = task_json
+        .get("recurrence")
+        .and_then(|r| serde_json::from_value(r.clone()).ok())
+        .flatten();
+    task.checklist = task_json
+        .get("checklist")
+        .and_then(|c| serde_json::from_value(c.clone()).ok())
+        .unwrap_or_default();
+    task.estimate = task_json
+        .get("estimate")
+        .and_then(|e| serde_json::from_value(e.clone()).ok())
+        .flatten();
+
+    task
 }
 
 pub fn extract_group(group_json: &Value) -> Group {
@@ -3,7 +3,7 @@ use std::{thread, time::Duration};
 use common::{TarsClient, types::Group};
 use criterion::{Criterion, criterion_group, criterion_main};
 use tars_daemon::utils::new_test_daemon;
-use tokio::{runtime::Runtime, time::timeout};
+use tokio::{runtime::Runtime, sync::Mutex, time::timeout};
 
 async fn group_creation(client: &TarsClient) {
     let _g = Group::new(client, "lol", None, Default::default(), Default::default())
@@ -11,8 +11,9 @@ async fn group_creation(client: &TarsClient) {
         .unwrap();
 }
 
-async fn group_sync(client: &TarsClient, group: Group) {
-    group.sync(client).await.unwrap();
+// every sync bumps the version, so the same copy has to be carried over between runs
+async fn group_sync(client: &TarsClient, group: &Mutex<Group>) {
+    group.lock().await.sync(client).await.unwrap();
 }
 
 async fn group_delete(client: &TarsClient, group: Group) {
@@ -35,19 +36,20 @@ fn bench_groups(c: &mut Criterion) {
         b.to_async(rt).iter(|| group_creation(&client));
     });
 
-    let g = b_rt
-        .block_on(Group::new(
+    let g = Mutex::new(
+        b_rt.block_on(Group::new(
             &client,
             "sync",
             None,
             Default::default(),
             Default::default(),
         ))
-        .unwrap();
+        .unwrap(),
+    );
 
     c.bench_function("group sync", |b| {
         let rt = Runtime::new().unwrap();
-        b.to_async(rt).iter(|| group_sync(&client, g.clone()));
+        b.to_async(rt).iter(|| group_sync(&client, &g));
     });
 
     c.bench_function("group creation + delete", |b| {
@@ -0,0 +1,14 @@
+-- Keeps track of which due-date reminders have already been sent, so the
+-- notifier doesn't send them again after a restart.
+CREATE TABLE FiredReminders (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    task_id VARCHAR(255) NOT NULL,
+    reminder VARCHAR(255) NOT NULL,
+    -- the due date the reminder was fired for, changing the due date re-arms reminders
+    due DATETIME NOT NULL,
+    fired_at DATETIME DEFAULT CURRENT_TIMESTAMP NOT NULL,
+    UNIQUE (task_id, reminder, due),
+    FOREIGN KEY (task_id) REFERENCES Tasks (pub_id) ON UPDATE CASCADE ON DELETE CASCADE
+);
+
+CREATE INDEX idx_fired_reminders_task_id ON FiredReminders (task_id);
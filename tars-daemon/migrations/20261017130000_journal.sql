@@ -0,0 +1,12 @@
+-- Every mutation made through the daemon, along with the operations needed to
+-- revert it. Entries that have been undone are kept until a new mutation is
+-- made, so they can be redone.
+CREATE TABLE Journal (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    redo TEXT NOT NULL, -- JSON encoded operations that apply the mutation
+    undo TEXT NOT NULL, -- JSON encoded operations that revert the mutation
+    undone BOOLEAN NOT NULL DEFAULT FALSE,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP NOT NULL
+);
+
+CREATE INDEX idx_journal_undone ON Journal (undone);
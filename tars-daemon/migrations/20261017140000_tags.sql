@@ -0,0 +1,20 @@
+CREATE TABLE Tags (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    pub_id VARCHAR(255) NOT NULL UNIQUE,
+    name VARCHAR(255) NOT NULL UNIQUE,
+    color VARCHAR(255) NOT NULL DEFAULT '',
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP NOT NULL,
+    updated_at DATETIME DEFAULT CURRENT_TIMESTAMP NOT NULL
+);
+
+-- Join table between Tasks and Tags, a task can have any number of tags and vice versa.
+CREATE TABLE TaskTags (
+    task_id VARCHAR(255) NOT NULL,
+    tag_id VARCHAR(255) NOT NULL,
+    PRIMARY KEY (task_id, tag_id),
+    FOREIGN KEY (task_id) REFERENCES Tasks (pub_id) ON UPDATE CASCADE ON DELETE CASCADE,
+    FOREIGN KEY (tag_id) REFERENCES Tags (pub_id) ON UPDATE CASCADE ON DELETE CASCADE
+);
+
+CREATE INDEX idx_tags_pub_id ON Tags (pub_id);
+CREATE INDEX idx_task_tags_tag_id ON TaskTags (tag_id);
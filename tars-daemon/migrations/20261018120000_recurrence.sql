@@ -0,0 +1,2 @@
+-- NULL if the task doesn't repeat, otherwise its rule (ex. `daily`, `weekly:mon,fri`, `monthly:15`, `every:3d`)
+ALTER TABLE Tasks ADD COLUMN recurrence VARCHAR(255);
@@ -0,0 +1,13 @@
+-- Edges of the dependency graph, `task_id` can't be worked on until `blocked_by` is finished.
+-- The daemon refuses any edge that would create a cycle.
+CREATE TABLE TaskDependencies (
+    task_id VARCHAR(255) NOT NULL,
+    blocked_by VARCHAR(255) NOT NULL,
+    PRIMARY KEY (task_id, blocked_by),
+    CHECK (task_id != blocked_by),
+    -- deferred so restoring a batch of tasks (ex. undoing a group delete) can happen in any order
+    FOREIGN KEY (task_id) REFERENCES Tasks (pub_id) ON UPDATE CASCADE ON DELETE CASCADE DEFERRABLE INITIALLY DEFERRED,
+    FOREIGN KEY (blocked_by) REFERENCES Tasks (pub_id) ON UPDATE CASCADE ON DELETE CASCADE DEFERRABLE INITIALLY DEFERRED
+);
+
+CREATE INDEX idx_task_dependencies_blocked_by ON TaskDependencies (blocked_by);
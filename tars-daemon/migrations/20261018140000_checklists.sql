@@ -0,0 +1,9 @@
+-- Ordered checklist items of a task, position is the index of the item in the checklist.
+CREATE TABLE TaskChecklistItems (
+    task_id VARCHAR(255) NOT NULL,
+    position INTEGER NOT NULL,
+    name TEXT NOT NULL,
+    done BOOLEAN NOT NULL DEFAULT FALSE,
+    PRIMARY KEY (task_id, position),
+    FOREIGN KEY (task_id) REFERENCES Tasks (pub_id) ON UPDATE CASCADE ON DELETE CASCADE
+);
@@ -0,0 +1,45 @@
+-- Full-text index over task names / descriptions and group names, kept in sync by the triggers below.
+-- kind is either 'task' or 'group', pub_id points at the row in the matching table.
+CREATE VIRTUAL TABLE SearchIndex USING fts5(
+    kind UNINDEXED,
+    pub_id UNINDEXED,
+    name,
+    description,
+    tokenize = 'unicode61 remove_diacritics 2'
+);
+
+INSERT INTO SearchIndex (kind, pub_id, name, description)
+SELECT 'task', pub_id, name, description FROM Tasks;
+
+INSERT INTO SearchIndex (kind, pub_id, name, description)
+SELECT 'group', pub_id, name, '' FROM Groups;
+
+CREATE TRIGGER tasks_search_insert AFTER INSERT ON Tasks BEGIN
+    INSERT INTO SearchIndex (kind, pub_id, name, description)
+    VALUES ('task', new.pub_id, new.name, new.description);
+END;
+
+CREATE TRIGGER tasks_search_update AFTER UPDATE OF pub_id, name, description ON Tasks BEGIN
+    UPDATE SearchIndex
+    SET pub_id = new.pub_id, name = new.name, description = new.description
+    WHERE kind = 'task' AND pub_id = old.pub_id;
+END;
+
+CREATE TRIGGER tasks_search_delete AFTER DELETE ON Tasks BEGIN
+    DELETE FROM SearchIndex WHERE kind = 'task' AND pub_id = old.pub_id;
+END;
+
+CREATE TRIGGER groups_search_insert AFTER INSERT ON Groups BEGIN
+    INSERT INTO SearchIndex (kind, pub_id, name, description)
+    VALUES ('group', new.pub_id, new.name, '');
+END;
+
+CREATE TRIGGER groups_search_update AFTER UPDATE OF pub_id, name ON Groups BEGIN
+    UPDATE SearchIndex
+    SET pub_id = new.pub_id, name = new.name
+    WHERE kind = 'group' AND pub_id = old.pub_id;
+END;
+
+CREATE TRIGGER groups_search_delete AFTER DELETE ON Groups BEGIN
+    DELETE FROM SearchIndex WHERE kind = 'group' AND pub_id = old.pub_id;
+END;
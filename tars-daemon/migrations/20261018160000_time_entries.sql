@@ -0,0 +1,16 @@
+-- Time spent on a task, an entry without an end is the running timer.
+CREATE TABLE TimeEntries (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    pub_id VARCHAR(255) NOT NULL UNIQUE,
+    task_id VARCHAR(255) NOT NULL,
+    started_at DATETIME NOT NULL,
+    ended_at DATETIME,
+    note TEXT NOT NULL DEFAULT '',
+    FOREIGN KEY (task_id) REFERENCES Tasks (pub_id) ON UPDATE CASCADE ON DELETE CASCADE
+);
+
+CREATE INDEX idx_time_entries_started_at ON TimeEntries (started_at);
+
+-- at most one timer runs at a time
+CREATE UNIQUE INDEX idx_time_entries_one_running ON TimeEntries ((ended_at IS NULL))
+WHERE ended_at IS NULL;
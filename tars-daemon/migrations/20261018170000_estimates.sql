@@ -0,0 +1,2 @@
+-- NULL if the task hasn't been estimated, otherwise how many minutes it's expected to take
+ALTER TABLE Tasks ADD COLUMN estimate INTEGER;
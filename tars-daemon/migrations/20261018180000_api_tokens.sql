@@ -0,0 +1,12 @@
+-- Tokens handed out on top of the daemon's own, only the hash of the secret is kept.
+CREATE TABLE ApiTokens (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    pub_id VARCHAR(255) NOT NULL UNIQUE,
+    name VARCHAR(255) NOT NULL,
+    secret_hash VARCHAR(255) NOT NULL UNIQUE,
+    access INTEGER NOT NULL,
+    -- the group the token is limited to, along with everything below it
+    group_id VARCHAR(255),
+    created_at DATETIME NOT NULL,
+    FOREIGN KEY (group_id) REFERENCES Groups (pub_id) ON UPDATE CASCADE ON DELETE CASCADE
+);
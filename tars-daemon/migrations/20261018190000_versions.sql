@@ -0,0 +1,3 @@
+-- bumped on every update, so edits made against an outdated copy can be refused
+ALTER TABLE Groups ADD COLUMN version INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE Tasks ADD COLUMN version INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1,7 @@
+-- Every diff sent to subscribers, numbered so clients that reconnect can catch up on what
+-- they missed. Only the most recent ones are kept.
+CREATE TABLE Diffs (
+    seq INTEGER PRIMARY KEY AUTOINCREMENT,
+    diff TEXT NOT NULL,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP NOT NULL
+);
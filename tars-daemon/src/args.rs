@@ -0,0 +1,45 @@
+use std::path::PathBuf;
+
+use clap::{Parser, Subcommand};
+
+#[derive(Parser, Debug)]
+#[command(version, about, long_about = None)]
+pub struct DaemonArgs {
+    #[command(subcommand)]
+    /// What to do instead of running the daemon.
+    pub command: Option<DaemonCommand>,
+
+    #[arg(short, long)]
+    /// Read the config from here instead of daemon.toml in the config directory.
+    pub config: Option<PathBuf>,
+
+    #[arg(short, long)]
+    /// Where to listen instead of the address in daemon.toml, `host:port`, `unix://<path>` or
+    /// `unix` for the default socket.
+    pub listen: Option<String>,
+
+    #[arg(short, long)]
+    /// The database to use instead of the one in daemon.toml.
+    pub database: Option<PathBuf>,
+
+    #[arg(long)]
+    /// What to log instead of the level in daemon.toml, ex. `debug` or `tars_daemon=trace`.
+    pub log_level: Option<String>,
+
+    #[arg(short, long)]
+    /// The pidfile to lock instead of the one in daemon.toml.
+    pub pidfile: Option<PathBuf>,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum DaemonCommand {
+    /// Applies pending migrations to the database and exits, backing it up first.
+    Migrate {
+        #[arg(short, long)]
+        /// Only show which migrations have been applied, without applying any.
+        status: bool,
+    },
+    /// Prints the fingerprint of the certificate the daemon serves HTTPS with, for clients
+    /// to pin, generating the certificate first if it's self-signed and doesn't exist yet.
+    Fingerprint,
+}
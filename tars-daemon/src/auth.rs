@@ -0,0 +1,601 @@
+use std::{
+    collections::{HashMap, HashSet},
+    path::PathBuf,
+    slice,
+    sync::RwLock,
+};
+
+use axum::{
+    Extension, Json,
+    body::{Body, Bytes, to_bytes},
+    extract::{Request, State},
+    http::{HeaderMap, header::AUTHORIZATION},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use common::{
+    Diff, DiffInner, Patch, TarsError, auth,
+    types::{
+        Access, Group, GroupMove, GroupPatch, Id, PlanRequest, Task, TaskDependencies,
+        TaskDependency, TaskPatch,
+    },
+};
+use serde::{Serialize, de::DeserializeOwned};
+use sha2::{Digest, Sha256};
+use sqlx::{Pool, Sqlite};
+use tracing::{error, info, warn};
+
+use crate::DaemonState;
+
+/// Largest request body the scope checks will buffer, the same limit axum's `Json` has.
+const BODY_LIMIT: usize = 2 * 1024 * 1024;
+
+/// Routes that only read data, anything not listed here or in `ADMIN_ROUTES` needs `Write`.
+const READ_ROUTES: &[&str] = &[
+    "",
+    "/subscribe",
+    "/snapshot",
+    "/group",
+    "/group/score",
+    "/tag",
+    "/task/fetch",
+    "/task/score",
+    "/task/scores",
+    "/task/score/explain",
+    "/task/plan",
+    "/task/dependencies",
+    "/scoring/fetch",
+    "/time/running",
+    "/time/entries",
+    "/time/timesheet",
+    "/search",
+    "/schedule",
+];
+
+/// Routes, along with everything below them, that need `Admin`.
+const ADMIN_ROUTES: &[&str] = &["/auth", "/scoring/set"];
+
+/// Routes, along with everything below them, a token limited to a group may use. These are
+/// the ones `scope_tasks`, `scope_groups`, the diff stream and the snapshot know how to limit.
+const GROUP_ROUTES: &[&str] = &["/task", "/group", "/subscribe", "/snapshot"];
+
+/// The bearer token every request has to carry, along with the file it's kept in.
+#[derive(Debug)]
+pub struct Auth {
+    path: PathBuf,
+    token: RwLock<String>,
+}
+
+impl Auth {
+    /// Loads the token at `path`, generating one first if there isn't any yet.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the token can't be read or written.
+    pub fn load(path: PathBuf) -> Result<Self, TarsError> {
+        let token = auth::load_or_create_token(&path)?;
+        info!("Loaded the daemon token from {:?}", path);
+
+        Ok(Self {
+            path,
+            token: RwLock::new(token),
+        })
+    }
+
+    /// Loads the token from the default location in the data directory.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the token can't be read or written.
+    pub fn load_default() -> Result<Self, TarsError> {
+        Self::load(auth::token_path())
+    }
+
+    /// Whether `presented` is the current token.
+    pub fn verify(&self, presented: &str) -> bool {
+        let token = self.token.read().expect("token lock poisoned");
+
+        constant_time_eq(token.as_bytes(), presented.as_bytes())
+    }
+
+    /// Replaces the token with a fresh one, on disk too, and returns it.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the new token can't be written, in which case
+    /// the old one stays valid.
+    pub fn rotate(&self) -> Result<String, TarsError> {
+        let fresh = auth::generate_token();
+        auth::write_token(&self.path, &fresh)?;
+
+        *self.token.write().expect("token lock poisoned") = fresh.clone();
+        info!("Rotated the daemon token");
+
+        Ok(fresh)
+    }
+}
+
+/// What the token a request was made with may do, `require_token` adds it to the extensions
+/// of every request it lets through.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Grant {
+    pub access: Access,
+    /// The group the token is limited to, along with everything below it.
+    pub group_id: Option<Id>,
+}
+
+impl Grant {
+    /// The grant of the daemon's own token, everything goes.
+    pub fn full() -> Self {
+        Self {
+            access: Access::Admin,
+            group_id: None,
+        }
+    }
+
+    /// Checks whether this grant may use the route at `path` at all, `scope_tasks` and
+    /// `scope_groups` look at what it's used on.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the grant isn't enough for the route.
+    pub fn permits(&self, path: &str) -> Result<(), TarsError> {
+        let path = path.trim_end_matches('/');
+
+        let needed = if ADMIN_ROUTES.iter().any(|route| is_below(path, route)) {
+            Access::Admin
+        } else if READ_ROUTES.contains(&path) {
+            Access::Read
+        } else {
+            Access::Write
+        };
+        if self.access < needed {
+            return Err(TarsError::Forbidden(format!(
+                "a {} token can't use {path}",
+                self.access
+            )));
+        }
+
+        if self.group_id.is_some()
+            && !path.is_empty()
+            && !GROUP_ROUTES.iter().any(|route| is_below(path, route))
+        {
+            return Err(TarsError::Forbidden(format!(
+                "a token limited to a group can't use {path}"
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+/// Hashes the secret of an `ApiToken`, only the hash is ever stored.
+pub fn hash_secret(secret: &str) -> String {
+    Sha256::digest(secret.as_bytes())
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect()
+}
+
+/// Middleware rejecting every request that doesn't carry the daemon's token, or an
+/// `ApiToken` allowed to use the route, as `Authorization: Bearer <token>`.
+pub async fn require_token(
+    State(state): State<DaemonState>,
+    mut req: Request,
+    next: Next,
+) -> Result<Response, TarsError> {
+    let grant = authenticate(&state, req.headers())
+        .await
+        .inspect_err(|_| warn!("Rejected unauthorized request to {}", req.uri()))?;
+    grant
+        .permits(req.uri().path())
+        .inspect_err(|e| warn!("Rejected request to {}: {e}", req.uri()))?;
+
+    req.extensions_mut().insert(grant);
+    Ok(next.run(req).await)
+}
+
+/// Works out the `Grant` of the token in `headers`.
+async fn authenticate(state: &DaemonState, headers: &HeaderMap) -> Result<Grant, TarsError> {
+    let Some(ref auth) = state.auth else {
+        return Ok(Grant::full());
+    };
+
+    let presented = headers
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .ok_or(TarsError::Unauthorized)?;
+    if auth.verify(presented) {
+        return Ok(Grant::full());
+    }
+
+    // the lookup only ever sees the hash, so its timing can't leak anything about the secret
+    let hash = hash_secret(presented);
+    let token = sqlx::query!(
+        r#"
+        SELECT access as "access!: Access", group_id as "group_id: Id"
+        FROM ApiTokens WHERE secret_hash = ?
+        "#,
+        hash
+    )
+    .fetch_optional(&state.pool)
+    .await?
+    .ok_or(TarsError::Unauthorized)?;
+
+    Ok(Grant {
+        access: token.access,
+        group_id: token.group_id,
+    })
+}
+
+/// Middleware in front of `task_router`, keeping a token limited to a group to the tasks
+/// inside it.
+pub async fn scope_tasks(
+    State(state): State<DaemonState>,
+    Extension(grant): Extension<Grant>,
+    req: Request,
+    next: Next,
+) -> Result<Response, TarsError> {
+    let Some(ref root) = grant.group_id else {
+        return Ok(next.run(req).await);
+    };
+
+    let groups = fetch_visible_groups(root, &state.pool).await?;
+    let pool = &state.pool;
+
+    let (parts, body) = req.into_parts();
+    let bytes = read_body(body).await?;
+
+    let allowed = match parts.uri.path() {
+        "/create" => match parse::<Task>(&bytes) {
+            Some(task) => {
+                groups.contains(&task.group.id)
+                    && tasks_visible(&task.blocked_by, &groups, pool).await?
+            }
+            None => true,
+        },
+        "/update" => match parse::<Task>(&bytes) {
+            Some(task) => {
+                tasks_visible(slice::from_ref(&task.id), &groups, pool).await?
+                    && groups.contains(&task.group.id)
+                    && tasks_visible(&task.blocked_by, &groups, pool).await?
+            }
+            None => true,
+        },
+        "/patch" => match parse::<TaskPatch>(&bytes) {
+            Some(patch) => tasks_visible(&[patch.id], &groups, pool).await?,
+            None => true,
+        },
+        "/delete" | "/score" | "/score/explain" | "/dependencies" => match parse::<Id>(&bytes) {
+            Some(id) => tasks_visible(&[id], &groups, pool).await?,
+            None => true,
+        },
+        "/scores" => match parse::<Vec<Id>>(&bytes) {
+            Some(ids) => tasks_visible(&ids, &groups, pool).await?,
+            None => true,
+        },
+        "/block" | "/unblock" => match parse::<TaskDependency>(&bytes) {
+            Some(dep) => tasks_visible(&[dep.task_id, dep.blocked_by], &groups, pool).await?,
+            None => true,
+        },
+        "/plan" => match parse::<PlanRequest>(&bytes) {
+            Some(request) => request.scope.is_some_and(|scope| groups.contains(&scope)),
+            None => true,
+        },
+        // the response is filtered instead
+        "/fetch" => true,
+        _ => false,
+    };
+    if !allowed {
+        return Err(outside_scope(parts.uri.path()));
+    }
+
+    let path = parts.uri.path().to_owned();
+    let res = next
+        .run(Request::from_parts(parts, Body::from(bytes)))
+        .await;
+
+    match path.as_str() {
+        "/fetch" => {
+            filter_response(res, |tasks: &mut Vec<Task>| {
+                tasks.retain(|task| groups.contains(&task.group.id))
+            })
+            .await
+        }
+        "/dependencies" => {
+            filter_response(res, |deps: &mut TaskDependencies| {
+                deps.blockers.retain(|task| groups.contains(&task.group.id));
+                deps.dependents
+                    .retain(|task| groups.contains(&task.group.id));
+            })
+            .await
+        }
+        _ => Ok(res),
+    }
+}
+
+/// Middleware in front of `group_router`, keeping a token limited to a group to that group
+/// and the ones below it. The group itself can't be moved or deleted with such a token.
+pub async fn scope_groups(
+    State(state): State<DaemonState>,
+    Extension(grant): Extension<Grant>,
+    req: Request,
+    next: Next,
+) -> Result<Response, TarsError> {
+    let Some(ref root) = grant.group_id else {
+        return Ok(next.run(req).await);
+    };
+
+    let groups = fetch_visible_groups(root, &state.pool).await?;
+
+    let (parts, body) = req.into_parts();
+    let bytes = read_body(body).await?;
+
+    let path = parts.uri.path().trim_end_matches('/').to_owned();
+    let allowed = match path.as_str() {
+        // the response is filtered instead
+        "" => true,
+        "/create" => match parse::<Group>(&bytes) {
+            Some(group) => group.parent_id.is_some_and(|p| groups.contains(&p)),
+            None => true,
+        },
+        "/update" => match parse::<Group>(&bytes) {
+            Some(group) => groups.contains(&group.id),
+            None => true,
+        },
+        "/patch" => match parse::<GroupPatch>(&bytes) {
+            Some(patch) => groups.contains(&patch.id),
+            None => true,
+        },
+        "/delete" => match parse::<Group>(&bytes) {
+            Some(group) => groups.contains(&group.id) && group.id != *root,
+            None => true,
+        },
+        "/move" => match parse::<GroupMove>(&bytes) {
+            Some(group_move) => {
+                groups.contains(&group_move.id)
+                    && group_move.id != *root
+                    && group_move.parent_id.is_some_and(|p| groups.contains(&p))
+            }
+            None => true,
+        },
+        "/score" => match parse::<Id>(&bytes) {
+            Some(id) => groups.contains(&id),
+            None => true,
+        },
+        _ => false,
+    };
+    if !allowed {
+        return Err(outside_scope(&path));
+    }
+
+    let res = next
+        .run(Request::from_parts(parts, Body::from(bytes)))
+        .await;
+
+    if path.is_empty() {
+        filter_response(res, |all: &mut Vec<Group>| {
+            all.retain(|group| groups.contains(&group.id))
+        })
+        .await
+    } else {
+        Ok(res)
+    }
+}
+
+/// Keeps track of what a token limited to a group may see of the diff stream. Groups can be
+/// moved in and out of the scope, so the visible groups are reloaded whenever one changes.
+#[derive(Debug)]
+pub struct ScopeFilter {
+    root: Id,
+    groups: HashSet<Id>,
+    /// Every visible task along with the group it's in.
+    tasks: HashMap<Id, Id>,
+}
+
+impl ScopeFilter {
+    /// Loads what's currently visible below `root`.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if something goes wrong with sqlx.
+    pub async fn load(root: Id, pool: &Pool<Sqlite>) -> Result<Self, TarsError> {
+        let mut filter = Self {
+            root,
+            groups: HashSet::new(),
+            tasks: HashMap::new(),
+        };
+        filter.reload(pool).await?;
+
+        Ok(filter)
+    }
+
+    async fn reload(&mut self, pool: &Pool<Sqlite>) -> Result<(), TarsError> {
+        self.groups = fetch_visible_groups(&self.root, pool).await?;
+
+        let root = &*self.root;
+        self.tasks = sqlx::query!(
+            r#"
+            WITH RECURSIVE subtree(pub_id) AS (
+                SELECT pub_id FROM Groups WHERE pub_id = ?
+                UNION ALL
+                SELECT g.pub_id FROM Groups g JOIN subtree s ON g.parent_id = s.pub_id
+            )
+            SELECT t.pub_id as "id!: String", t.group_id as "group_id!: String"
+            FROM Tasks t JOIN subtree s ON t.group_id = s.pub_id
+            "#,
+            root
+        )
+        .fetch_all(pool)
+        .await?
+        .into_iter()
+        .map(|row| (Id::from(row.id), Id::from(row.group_id)))
+        .collect();
+
+        Ok(())
+    }
+
+    /// Returns the part of `diff` the token may see, if any. Something moving out of the
+    /// scope shows up as deleted.
+    pub async fn admit(&mut self, diff: Diff, pool: &Pool<Sqlite>) -> Option<Diff> {
+        match diff {
+            Diff::Added(DiffInner::Task(ref task)) | Diff::Updated(DiffInner::Task(ref task)) => {
+                if self.groups.contains(&task.group.id) {
+                    self.tasks.insert(task.id.clone(), task.group.id.clone());
+                    Some(diff)
+                } else {
+                    self.tasks
+                        .remove(&task.id)
+                        .map(|_| Diff::Deleted(task.id.clone()))
+                }
+            }
+            Diff::Added(DiffInner::Group(ref group))
+            | Diff::Updated(DiffInner::Group(ref group)) => {
+                let was_visible = self.groups.contains(&group.id);
+                self.reload_or_log(pool).await;
+
+                if self.groups.contains(&group.id) {
+                    Some(diff)
+                } else {
+                    was_visible.then(|| Diff::Deleted(group.id.clone()))
+                }
+            }
+            // patches can't move anything, so they're visible wherever their target is
+            Diff::Patched(Patch::Task(ref patch)) => {
+                self.tasks.contains_key(&patch.id).then_some(diff)
+            }
+            Diff::Patched(Patch::Group(ref patch)) => {
+                self.groups.contains(&patch.id).then_some(diff)
+            }
+            Diff::Deleted(ref id) => {
+                if self.tasks.remove(id).is_some() {
+                    Some(diff)
+                } else if self.groups.contains(id) {
+                    self.reload_or_log(pool).await;
+                    Some(diff)
+                } else {
+                    None
+                }
+            }
+            Diff::Notification(ref notification) => self
+                .groups
+                .contains(&notification.task.group.id)
+                .then_some(diff),
+        }
+    }
+
+    /// Reloads, keeping what was visible before if that fails, the stream shouldn't end over it.
+    async fn reload_or_log(&mut self, pool: &Pool<Sqlite>) {
+        if let Err(e) = self.reload(pool).await {
+            error!("Error reloading the scope of a subscriber: {:?}", e);
+        }
+    }
+}
+
+/// Returns `root` along with every group below it.
+async fn fetch_visible_groups(root: &Id, pool: &Pool<Sqlite>) -> Result<HashSet<Id>, TarsError> {
+    let root = &**root;
+    let groups = sqlx::query!(
+        r#"
+        WITH RECURSIVE subtree(pub_id) AS (
+            SELECT pub_id FROM Groups WHERE pub_id = ?
+            UNION ALL
+            SELECT g.pub_id FROM Groups g JOIN subtree s ON g.parent_id = s.pub_id
+        )
+        SELECT pub_id as "id!: String" FROM subtree
+        "#,
+        root
+    )
+    .fetch_all(pool)
+    .await?
+    .into_iter()
+    .map(|row| Id::from(row.id))
+    .collect();
+
+    Ok(groups)
+}
+
+/// Returns the group of the task with this `Id`, `None` if there is no such task.
+async fn task_group(id: &Id, pool: &Pool<Sqlite>) -> Result<Option<Id>, TarsError> {
+    let id = &**id;
+    let group = sqlx::query_scalar!(
+        r#"SELECT group_id as "group_id!: String" FROM Tasks WHERE pub_id = ?"#,
+        id
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(group.map(Id::from))
+}
+
+/// Whether every task with these `Ids` exists and is in one of `groups`.
+async fn tasks_visible(
+    ids: &[Id],
+    groups: &HashSet<Id>,
+    pool: &Pool<Sqlite>,
+) -> Result<bool, TarsError> {
+    for id in ids {
+        if !task_group(id, pool)
+            .await?
+            .is_some_and(|group| groups.contains(&group))
+        {
+            return Ok(false);
+        }
+    }
+
+    Ok(true)
+}
+
+async fn read_body(body: Body) -> Result<Bytes, TarsError> {
+    to_bytes(body, BODY_LIMIT)
+        .await
+        .map_err(|_| TarsError::InvalidRequest("request body too large".to_owned()))
+}
+
+/// Parses a request body the way the handler behind the middleware will. A body that doesn't
+/// parse is let through, the handler rejects it anyway.
+fn parse<T: DeserializeOwned>(bytes: &Bytes) -> Option<T> {
+    serde_json::from_slice(bytes).ok()
+}
+
+/// Applies `filter` to a successful JSON response, leaving anything else as is.
+async fn filter_response<T: Serialize + DeserializeOwned>(
+    res: Response,
+    filter: impl FnOnce(&mut T),
+) -> Result<Response, TarsError> {
+    if !res.status().is_success() {
+        return Ok(res);
+    }
+
+    let (parts, body) = res.into_parts();
+    let bytes = to_bytes(body, usize::MAX)
+        .await
+        .map_err(|e| TarsError::Io(std::io::Error::other(e)))?;
+    let Some(mut value) = parse::<T>(&bytes) else {
+        return Ok(Response::from_parts(parts, Body::from(bytes)));
+    };
+
+    filter(&mut value);
+    Ok(Json(value).into_response())
+}
+
+fn outside_scope(path: &str) -> TarsError {
+    TarsError::Forbidden(format!("{path} reaches outside of the token's group"))
+}
+
+/// Whether `path` is `route` or somewhere below it.
+fn is_below(path: &str, route: &str) -> bool {
+    path.strip_prefix(route)
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+}
+
+/// Compares without bailing out on the first differing byte, so response times don't leak
+/// how much of a guessed token was right.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+
+    a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
@@ -0,0 +1,88 @@
+use std::path::PathBuf;
+
+use color_eyre::eyre::{Result, eyre};
+use common::{
+    DAEMON_ADDR,
+    dirs::{get_config_dir, get_socket_path},
+};
+use serde::Deserialize;
+use tracing::info;
+
+use crate::{
+    Db, notifier::NotifierConfig, pidfile::PidFile, scheduler::ScheduleConfig,
+    scoring::ScoringConfig, tls::TlsConfig,
+};
+
+const CONFIG_FILE: &str = "daemon.toml";
+
+/// Configuration for the `TarsDaemon`, read from `daemon.toml` inside the config directory.
+///
+/// Every field has a sane default, so the file (or any section of it) can be omitted.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct DaemonConfig {
+    /// Where to listen, `host:port`, `unix://<path>` for a Unix socket, or just `unix` for
+    /// the socket in the runtime directory. `127.0.0.1:42069` if left out.
+    pub listen: Option<String>,
+    /// The database to use, `tars.db` in the data directory if left out.
+    pub database: Option<PathBuf>,
+    /// What to log, ex. `debug` or `tars_daemon=trace`, takes precedence over `RUST_LOG`.
+    pub log_level: Option<String>,
+    /// The file locked while the daemon runs, so that only one daemon uses the database at a
+    /// time. Next to the database if left out.
+    pub pidfile: Option<PathBuf>,
+    pub notifier: NotifierConfig,
+    pub scoring: ScoringConfig,
+    pub schedule: ScheduleConfig,
+    pub tls: TlsConfig,
+}
+
+impl DaemonConfig {
+    /// Loads the config from the default location, falling back to defaults if it doesn't exist.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the file exists but can't be read or parsed.
+    pub fn load() -> Result<Self> {
+        Self::load_from(get_config_dir().join(CONFIG_FILE))
+    }
+
+    /// Returns the address to listen on, as `TarsDaemon::run` expects it.
+    pub fn listen_addr(&self) -> String {
+        match self.listen.as_deref() {
+            Some("unix") => format!("unix://{}", get_socket_path().display()),
+            Some(addr) => addr.to_owned(),
+            None => DAEMON_ADDR.to_owned(),
+        }
+    }
+
+    /// Returns the database to use.
+    pub fn db_path(&self) -> PathBuf {
+        self.database.clone().unwrap_or_else(Db::default_path)
+    }
+
+    /// Returns the pidfile to lock.
+    pub fn pidfile_path(&self) -> PathBuf {
+        self.pidfile
+            .clone()
+            .unwrap_or_else(|| PidFile::default_path(&self.db_path()))
+    }
+
+    /// Loads the config from the provided path, falling back to defaults if it doesn't exist.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the file exists but can't be read or parsed.
+    pub fn load_from(path: PathBuf) -> Result<Self> {
+        if !path.exists() {
+            info!("No daemon config found at {:?}, using defaults", path);
+            return Ok(Self::default());
+        }
+
+        let raw = std::fs::read_to_string(&path)?;
+        let config = toml::from_str(&raw).map_err(|e| eyre!("Failed to parse {path:?}: {e}"))?;
+
+        info!("Loaded daemon config from {:?}", path);
+        Ok(config)
+    }
+}
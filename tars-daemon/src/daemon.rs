@@ -1,16 +1,30 @@
-use axum::{Router, routing::get};
+use std::{
+    fmt::Debug,
+    path::{Path, PathBuf},
+    sync::{Arc, RwLock},
+};
+
+use axum::{Router, middleware, routing::get, serve::Listener};
 use color_eyre::eyre::{Result, eyre};
-use common::Diff;
+use common::discovery;
 use sqlx::{Pool, Sqlite};
-use tokio::{
-    net::TcpListener,
-    sync::broadcast::{self, Sender},
-};
+use tokio::net::TcpListener;
+#[cfg(unix)]
+use tokio::net::{UnixListener, UnixStream};
 use tracing::{error, info};
 
 use crate::{
+    auth::{Auth, require_token, scope_groups, scope_tasks},
     db::Db,
-    handlers::{group_router, subscribe_router, task_router},
+    diff_log::DiffLog,
+    handlers::{
+        auth_router, group_router, journal_router, schedule_router, scoring_router, search_router,
+        snapshot_router, subscribe_router, tag_router, task_router, time_router,
+    },
+    scheduler::ScheduleConfig,
+    scoring::{Scorer, ScoringConfig},
+    tls::{ServerTls, TlsListener},
+    validation::json_errors,
 };
 
 /// Daemon that exposes access to the database, as well as being responsible
@@ -18,6 +32,10 @@ use crate::{
 pub struct TarsDaemon {
     app: Router,
     state: DaemonState,
+    /// Where to write the address the daemon listens on, if anywhere.
+    discovery: Option<PathBuf>,
+    /// The certificate to serve HTTPS with, plain HTTP if `None`.
+    tls: Option<ServerTls>,
 }
 
 // State required for the `TarsDaemon` to function properly.
@@ -25,18 +43,50 @@ pub struct TarsDaemon {
 pub struct DaemonState {
     pub pool: Pool<Sqlite>,
     addr: String,
-    pub diff_tx: Sender<Diff>,
+    /// Every `Diff` sent to subscribers, kept so they can catch up after reconnecting.
+    pub diffs: DiffLog,
+    /// Ranks tasks, shared so the strategy can be switched at runtime.
+    pub scorer: Arc<RwLock<Scorer>>,
+    /// Working hours and horizon the scheduler lays tasks out over.
+    pub schedule: Arc<ScheduleConfig>,
+    /// The token every request has to carry, `None` lets every request through.
+    pub auth: Option<Arc<Auth>>,
 }
 
 impl DaemonState {
     /// Returns a new instance of DaemonState
     pub fn new(db: Db, addr: &str) -> Self {
-        let (tx, _) = broadcast::channel::<Diff>(50);
-
         DaemonState {
+            diffs: DiffLog::new(db.pool.clone()),
             pool: db.pool,
             addr: addr.to_owned(),
-            diff_tx: tx,
+            scorer: Arc::new(RwLock::new(Scorer::default())),
+            schedule: Arc::new(ScheduleConfig::default()),
+            auth: None,
+        }
+    }
+
+    /// Scores tasks according to `config` instead of the defaults.
+    pub fn with_scoring(self, config: ScoringConfig) -> Self {
+        Self {
+            scorer: Arc::new(RwLock::new(Scorer::new(config))),
+            ..self
+        }
+    }
+
+    /// Requires every request to carry the token of `auth`.
+    pub fn with_auth(self, auth: Auth) -> Self {
+        Self {
+            auth: Some(Arc::new(auth)),
+            ..self
+        }
+    }
+
+    /// Schedules tasks according to `config` instead of the defaults.
+    pub fn with_schedule(self, config: ScheduleConfig) -> Self {
+        Self {
+            schedule: Arc::new(config),
+            ..self
         }
     }
 }
@@ -46,25 +96,176 @@ impl TarsDaemon {
     pub async fn init(state: DaemonState) -> Self {
         let app = Router::new()
             .route("/", get(root))
-            .nest("/task", task_router())
-            .nest("/group", group_router())
+            .nest(
+                "/task",
+                task_router()
+                    .route_layer(middleware::from_fn_with_state(state.clone(), scope_tasks)),
+            )
+            .nest(
+                "/group",
+                group_router()
+                    .route_layer(middleware::from_fn_with_state(state.clone(), scope_groups)),
+            )
+            .nest("/tag", tag_router())
+            .nest("/scoring", scoring_router())
+            .nest("/time", time_router())
             .nest("/subscribe", subscribe_router())
+            .merge(journal_router())
+            .merge(search_router())
+            .merge(schedule_router())
+            .merge(snapshot_router())
+            .nest("/auth", auth_router())
+            .layer(middleware::from_fn_with_state(state.clone(), require_token))
+            .layer(middleware::from_fn(json_errors))
             .with_state(state.clone());
 
-        Self { app, state }
+        Self {
+            app,
+            state,
+            discovery: None,
+            tls: None,
+        }
+    }
+
+    /// Returns the state shared between all the handlers of this daemon.
+    pub fn state(&self) -> &DaemonState {
+        &self.state
+    }
+
+    /// Writes the address the daemon ends up listening on to `path` once it's listening, for
+    /// clients to find it. The file is removed again when the daemon shuts down.
+    pub fn with_discovery(self, path: PathBuf) -> Self {
+        Self {
+            discovery: Some(path),
+            ..self
+        }
     }
 
-    /// Runs the daemon, will panic if something goes wrong.
+    /// Serves HTTPS with `tls` instead of plain HTTP when listening on TCP.
+    pub fn with_tls(self, tls: ServerTls) -> Self {
+        Self {
+            tls: Some(tls),
+            ..self
+        }
+    }
+
+    /// Runs the daemon until it's interrupted or terminated, will panic if something goes wrong.
+    ///
+    /// Listens on a Unix socket when the address is `unix://<path>`, on TCP otherwise.
     pub async fn run(self) -> Result<()> {
+        if self.state.addr.starts_with("unix://") && self.tls.is_some() {
+            return Err(eyre!("TLS only applies to TCP, not to Unix sockets"));
+        }
+
+        #[cfg(unix)]
+        if let Some(path) = self.state.addr.strip_prefix("unix://") {
+            let path = PathBuf::from(path);
+            let listener = bind_socket(&path).await?;
+
+            let res = self
+                .serve(listener, format!("unix://{}", path.display()))
+                .await;
+            let _ = std::fs::remove_file(&path);
+            return res;
+        }
+        #[cfg(not(unix))]
+        if self.state.addr.starts_with("unix://") {
+            return Err(eyre!("Unix sockets aren't supported on this platform"));
+        }
+
         let listener = TcpListener::bind(&self.state.addr).await?;
+        // the port might have been picked by the OS
+        let local_addr = listener.local_addr()?;
 
-        info!("App lisening on {}", self.state.addr);
+        match self.tls.clone() {
+            Some(tls) => {
+                info!("Serving certificate {}", tls.fingerprint);
+                let listener = TlsListener::new(listener, &tls)?;
+                self.serve(listener, format!("https://{local_addr}")).await
+            }
+            None => self.serve(listener, format!("http://{local_addr}")).await,
+        }
+    }
+
+    async fn serve<L>(self, listener: L, addr: String) -> Result<()>
+    where
+        L: Listener,
+        L::Addr: Debug,
+    {
+        info!("App lisening on {addr}");
+        if let Some(ref path) = self.discovery {
+            discovery::write_address(path, &addr)?;
+        }
+
+        let res = tokio::select! {
+            res = axum::serve(listener, self.app) => res.map_err(|e| {
+                error!("{:?}", e);
+                eyre!(e)
+            }),
+            // open event streams never end on their own, so there's no waiting for them
+            () = shutdown_signal() => Ok(()),
+        };
+
+        if let Some(ref path) = self.discovery {
+            // unless another daemon has taken it over since
+            if discovery::read_address(path).ok().flatten().as_deref() == Some(addr.as_str()) {
+                let _ = std::fs::remove_file(path);
+            }
+        }
+
+        res
+    }
+}
+
+/// Binds a Unix socket at `path`, which only the current user may connect to.
+#[cfg(unix)]
+async fn bind_socket(path: &Path) -> Result<UnixListener> {
+    use std::os::unix::fs::{DirBuilderExt, PermissionsExt};
+
+    if let Some(parent) = path.parent() {
+        std::fs::DirBuilder::new()
+            .recursive(true)
+            .mode(0o700)
+            .create(parent)?;
+    }
+
+    if path.exists() {
+        // a daemon that's still running answers, one that crashed leaves its socket behind
+        if UnixStream::connect(path).await.is_ok() {
+            return Err(eyre!("Another daemon is already listening on {path:?}"));
+        }
+        std::fs::remove_file(path)?;
+    }
+
+    let listener = UnixListener::bind(path)?;
+    std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))?;
+
+    Ok(listener)
+}
+
+/// Resolves once the daemon is asked to stop, by ctrl-c or SIGTERM.
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        let _ = tokio::signal::ctrl_c().await;
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(_) => std::future::pending().await,
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
 
-        axum::serve(listener, self.app).await.map_err(|e| {
-            error!("{:?}", e);
-            eyre!(e)
-        })
+    tokio::select! {
+        () = ctrl_c => {},
+        () = terminate => {},
     }
+    info!("Shutting down");
 }
 
 async fn root() -> &'static str {
@@ -1,89 +1,230 @@
-use std::{path::PathBuf, str::FromStr};
+use std::{collections::HashSet, path::PathBuf};
 
-use color_eyre::Result;
+use chrono::Local;
+use color_eyre::{Result, eyre::eyre};
 use common::{dirs::get_data_dir, types::Id};
 use sqlx::{
     Pool, Sqlite, SqlitePool,
+    migrate::{Migrate, Migrator},
     sqlite::{SqliteConnectOptions, SqliteJournalMode},
 };
 use tokio::fs::create_dir_all;
 use tracing::{error, info};
 
+/// Every migration this daemon knows about, embedded at compile time.
+static MIGRATOR: Migrator = sqlx::migrate!("./migrations");
+
 /// Holds the pool to a database.
 /// Mostly constructed as this type because it has logic to create a new db w migrations
 /// or test databases.
-///
-/// NOTE: Will panic if anything goes wrong.
 pub struct Db {
     pub pool: Pool<Sqlite>,
+    /// Where the database file lives, backups are put next to it.
+    pub path: PathBuf,
+}
+
+/// Where a single migration stands against a database.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MigrationState {
+    Applied,
+    Pending,
+    /// Applied to the database, but not known to this daemon, ie. the database was
+    /// migrated by a newer version.
+    Unknown,
+}
+
+/// A single migration, and whether it has been applied.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MigrationStatus {
+    pub version: i64,
+    pub description: String,
+    pub state: MigrationState,
 }
 
 impl Db {
     /// Creates a new Db
     ///
-    /// If database already exists, will just return a pool connected to that
-    /// If not, creates a new one with migrations
+    /// Opens the database, creating it if it doesn't exist yet, and applies any pending
+    /// migrations to it.
     ///
     /// Can also create test databases.
     ///
+    /// # Errors
+    ///
+    /// This function will return an error if the database can't be opened or migrated, see `Db::migrate`.
     pub async fn new(is_test: bool) -> Result<Self> {
-        let path = {
-            let mut dir = if is_test {
-                PathBuf::from(format!("/tmp/tars/test-db/{}/", *Id::default()))
-            } else {
-                get_data_dir()
-            };
-
-            let _ = create_dir_all(&dir).await;
-            dir.push("tars.db");
-
-            format!(
-                "sqlite://{}",
-                dir.to_str()
-                    .expect("Database Path should be a valid string.")
-            )
+        let path = if is_test {
+            PathBuf::from(format!("/tmp/tars/test-db/{}/tars.db", *Id::default()))
+        } else {
+            Self::default_path()
         };
 
-        info!("Db Path: {}", path);
+        Self::load(path).await
+    }
 
-        let sqlite_opts = SqliteConnectOptions::from_str(&path)
-            .inspect_err(|e| {
-                error!(
-                    "Failed to process path to sqlite db: {}. error: {}",
-                    path, e
-                )
-            })
-            .unwrap()
+    /// Opens the database at `path`, creating it if it doesn't exist yet, and applies any
+    /// pending migrations to it.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the database can't be opened or migrated, see `Db::migrate`.
+    pub async fn load(path: PathBuf) -> Result<Self> {
+        let db = Self::open(path).await?;
+        db.migrate().await?;
+
+        Ok(db)
+    }
+
+    /// Returns where the database lives outside of tests.
+    pub fn default_path() -> PathBuf {
+        get_data_dir().join("tars.db")
+    }
+
+    /// Opens the database at `path`, creating it if it doesn't exist yet,
+    /// without touching its schema.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the database can't be opened.
+    pub async fn open(path: PathBuf) -> Result<Self> {
+        if let Some(dir) = path.parent() {
+            let _ = create_dir_all(dir).await;
+        }
+        info!("Db Path: {}", path.display());
+
+        let sqlite_opts = SqliteConnectOptions::new()
+            .filename(&path)
+            .create_if_missing(true)
             .journal_mode(SqliteJournalMode::Wal);
 
-        let pool = match SqlitePool::connect_with(sqlite_opts.clone()).await {
-            Ok(p) => Ok(p),
-            Err(sqlx::Error::Database(_)) => {
-                // database doesnt exist yet, create it
-                let sqlite_opts = sqlite_opts.create_if_missing(true);
-
-                // now connect to it
-                let pool = SqlitePool::connect_with(sqlite_opts.clone())
-                    .await
-                    .inspect_err(|e| error!("{e}"))
-                    .unwrap();
-
-                // apply the migrations to it
-                sqlx::migrate!("./migrations")
-                    .run(&pool)
-                    .await
-                    .inspect_err(|e| error!("{e}"))
-                    .unwrap();
-                info!("Applied Migrations to db!");
-
-                Ok(pool)
-            }
-            Err(e) => {
-                error!("{e}");
-                Err(e)
-            }
-        }?;
-
-        Ok(Self { pool })
+        let pool = SqlitePool::connect_with(sqlite_opts)
+            .await
+            .inspect_err(|e| error!("Failed to open db at {}: {e}", path.display()))?;
+
+        Ok(Self { pool, path })
+    }
+
+    /// Returns every migration this daemon knows about, along with any applied to the
+    /// database that it doesn't know about, ordered by version.
+    ///
+    /// Only reads from the database, one that was never migrated has everything pending.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the applied migrations can't be read.
+    pub async fn migration_status(&self) -> Result<Vec<MigrationStatus>> {
+        let mut conn = self.pool.acquire().await?;
+
+        let migrated = sqlx::query_scalar!(
+            r#"
+            SELECT EXISTS(
+                SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = '_sqlx_migrations'
+            ) as "migrated!: bool"
+            "#
+        )
+        .fetch_one(&mut *conn)
+        .await?;
+
+        let mut applied: HashSet<i64> = if migrated {
+            conn.list_applied_migrations()
+                .await?
+                .into_iter()
+                .map(|m| m.version)
+                .collect()
+        } else {
+            HashSet::new()
+        };
+
+        let mut statuses: Vec<MigrationStatus> = MIGRATOR
+            .iter()
+            .filter(|m| !m.migration_type.is_down_migration())
+            .map(|m| MigrationStatus {
+                version: m.version,
+                description: m.description.to_string(),
+                state: if applied.remove(&m.version) {
+                    MigrationState::Applied
+                } else {
+                    MigrationState::Pending
+                },
+            })
+            .collect();
+
+        statuses.extend(applied.into_iter().map(|version| MigrationStatus {
+            version,
+            description: String::new(),
+            state: MigrationState::Unknown,
+        }));
+        statuses.sort_by_key(|s| s.version);
+
+        Ok(statuses)
+    }
+
+    /// Applies any pending migrations, backing up the database first if it already holds
+    /// a schema. Returns the path of the backup, if one was made.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if
+    /// + The database was migrated by a newer daemon, it is left untouched.
+    /// + The backup or one of the migrations fails.
+    pub async fn migrate(&self) -> Result<Option<PathBuf>> {
+        let statuses = self.migration_status().await?;
+
+        if let Some(unknown) = statuses.iter().find(|s| s.state == MigrationState::Unknown) {
+            return Err(eyre!(
+                "the database at {} has migration {} applied, which this daemon doesn't know about. \
+                 It was likely created by a newer version, refusing to start",
+                self.path.display(),
+                unknown.version
+            ));
+        }
+
+        let pending = statuses
+            .iter()
+            .filter(|s| s.state == MigrationState::Pending)
+            .count();
+        if pending == 0 {
+            return Ok(None);
+        }
+
+        // a fresh database has nothing worth backing up
+        let backup = if statuses.iter().any(|s| s.state == MigrationState::Applied) {
+            Some(self.backup().await?)
+        } else {
+            None
+        };
+
+        MIGRATOR
+            .run(&self.pool)
+            .await
+            .inspect_err(|e| error!("{e}"))?;
+        info!("Applied {pending} migration(s) to db!");
+
+        Ok(backup)
+    }
+
+    /// Writes a consistent copy of the database next to it, named after the current time.
+    async fn backup(&self) -> Result<PathBuf> {
+        let file_name = self
+            .path
+            .file_name()
+            .and_then(|f| f.to_str())
+            .unwrap_or("tars.db");
+        let backup_path = self.path.with_file_name(format!(
+            "{file_name}.{}.bak",
+            Local::now().format("%Y%m%d%H%M%S")
+        ));
+        let backup_str = backup_path
+            .to_str()
+            .expect("Database Path should be a valid string.");
+
+        // unlike copying the file, this also picks up whatever is still in the WAL
+        sqlx::query("VACUUM INTO ?")
+            .bind(backup_str)
+            .execute(&self.pool)
+            .await?;
+        info!("Backed up db to {}", backup_path.display());
+
+        Ok(backup_path)
     }
 }
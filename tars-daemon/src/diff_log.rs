@@ -0,0 +1,223 @@
+use std::sync::Arc;
+
+use common::{Diff, TarsError};
+use sqlx::{Pool, Sqlite, SqliteConnection};
+use tokio::sync::{
+    Mutex,
+    broadcast::{self, Receiver, Sender},
+};
+use tracing::error;
+
+/// How many diffs are kept for subscribers to catch up on, anyone further behind has to
+/// fetch everything again.
+pub const RETAINED_DIFFS: i64 = 10_000;
+
+/// How many diffs a subscriber may fall behind on before it's cut off, it then resumes
+/// from the log like any other reconnecting subscriber.
+const CHANNEL_CAPACITY: usize = 256;
+
+/// A `Diff` along with its place in the log.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SequencedDiff {
+    pub seq: i64,
+    pub diff: Diff,
+}
+
+/// What a subscriber that resumes gets sent before the live diffs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Replay {
+    /// Every diff that was missed, in order.
+    Diffs(Vec<SequencedDiff>),
+    /// What was missed isn't kept anymore, or was never handed out by this log.
+    ResyncRequired,
+}
+
+/// Every `Diff` the daemon sends, numbered and written to the database so subscribers
+/// that reconnect can pick up where they left off.
+///
+/// Diffs are recorded in the transaction of the change they describe, so their numbers
+/// follow the order changes are committed in, and published once it's committed.
+#[derive(Debug, Clone)]
+pub struct DiffLog {
+    pool: Pool<Sqlite>,
+    tx: Sender<SequencedDiff>,
+    /// The newest diff published, `None` until this daemon records its first one.
+    published: Arc<Mutex<Option<i64>>>,
+}
+
+impl DiffLog {
+    /// Returns a log kept in the database behind `pool`.
+    pub fn new(pool: Pool<Sqlite>) -> Self {
+        let (tx, _) = broadcast::channel(CHANNEL_CAPACITY);
+
+        Self {
+            pool,
+            tx,
+            published: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// Numbers and logs `diff` as part of the transaction `conn` is in, it goes out on the
+    /// next `publish` after that's committed.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if
+    /// + Something goes wrong with sqlx.
+    /// + The diff can't be serialized.
+    pub async fn record(&self, diff: &Diff, conn: &mut SqliteConnection) -> Result<(), TarsError> {
+        let json = serde_json::to_string(diff)
+            .map_err(|e| TarsError::Internal(format!("unserializable diff: {e}")))?;
+
+        let seq = sqlx::query_scalar!(
+            r#"INSERT INTO Diffs (diff) VALUES (?) RETURNING seq as "seq!: i64""#,
+            json
+        )
+        .fetch_one(&mut *conn)
+        .await?;
+
+        let oldest_kept = seq - RETAINED_DIFFS;
+        sqlx::query!("DELETE FROM Diffs WHERE seq <= ?", oldest_kept)
+            .execute(&mut *conn)
+            .await?;
+
+        // whatever came before this daemon's first diff was published by an earlier run
+        self.published.lock().await.get_or_insert(seq - 1);
+
+        Ok(())
+    }
+
+    /// Sends every committed diff that hasn't been sent yet to every subscriber, in order.
+    ///
+    /// The changes have already happened by the time they're published, so failing to is
+    /// only reported here, the diffs go out with the next publish instead.
+    pub async fn publish(&self) {
+        let mut published = self.published.lock().await;
+        let Some(last) = *published else {
+            return;
+        };
+
+        let rows = match sqlx::query!(
+            r#"SELECT seq as "seq!: i64", diff FROM Diffs WHERE seq > ? ORDER BY seq"#,
+            last
+        )
+        .fetch_all(&self.pool)
+        .await
+        {
+            Ok(rows) => rows,
+            Err(e) => {
+                error!("Failed to read diffs to publish: {e:?}");
+                return;
+            }
+        };
+
+        for row in rows {
+            *published = Some(row.seq);
+
+            match serde_json::from_str(&row.diff) {
+                // no subscribers isn't an error, nobody has the TUI open
+                Ok(diff) => {
+                    let _ = self.tx.send(SequencedDiff { seq: row.seq, diff });
+                }
+                Err(e) => error!("Skipping unreadable diff {}: {e}", row.seq),
+            }
+        }
+    }
+
+    /// Records and publishes `diff` on its own, for diffs that aren't part of a change to
+    /// the data, like notifications.
+    pub async fn send(&self, diff: Diff) {
+        let recorded = match self.pool.acquire().await {
+            Ok(mut conn) => self.record(&diff, &mut conn).await,
+            Err(e) => Err(e.into()),
+        };
+        if let Err(e) = recorded {
+            error!("Failed to log diff {diff:?}: {e:?}");
+            return;
+        }
+
+        self.publish().await;
+    }
+
+    /// Subscribes to the diffs sent from now on.
+    pub fn subscribe(&self) -> Receiver<SequencedDiff> {
+        self.tx.subscribe()
+    }
+
+    /// Subscribes to the diffs sent from now on, along with the ones after `last_seq` that
+    /// the subscriber missed.
+    ///
+    /// Diffs up to `last_seq` that were committed but not published yet still arrive on the
+    /// receiver, subscribers have to skip those.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if
+    /// + Something goes wrong with sqlx.
+    /// + A logged diff can't be parsed.
+    pub async fn resume(
+        &self,
+        last_seq: i64,
+    ) -> Result<(Replay, Receiver<SequencedDiff>), TarsError> {
+        // nothing is published between subscribing and looking up what was, so every diff
+        // ends up either in the replay or on the receiver
+        let (rx, published, committed) = {
+            let published = self.published.lock().await;
+            let rx = self.tx.subscribe();
+            let committed = sqlx::query_scalar!(r#"SELECT MAX(seq) as "seq: i64" FROM Diffs"#)
+                .fetch_one(&self.pool)
+                .await?
+                .unwrap_or(0);
+
+            (rx, published.unwrap_or(committed), committed)
+        };
+
+        if last_seq > committed {
+            return Ok((Replay::ResyncRequired, rx));
+        }
+        if last_seq >= published {
+            return Ok((Replay::Diffs(vec![]), rx));
+        }
+
+        let rows = sqlx::query!(
+            r#"SELECT seq as "seq!: i64", diff FROM Diffs WHERE seq > ? AND seq <= ? ORDER BY seq"#,
+            last_seq,
+            published
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        // pruned diffs leave a gap that can't be replayed
+        let complete = rows.len() as i64 == published - last_seq
+            && rows.first().is_none_or(|row| row.seq == last_seq + 1);
+        if !complete {
+            return Ok((Replay::ResyncRequired, rx));
+        }
+
+        let diffs = rows
+            .into_iter()
+            .map(|row| {
+                let diff = serde_json::from_str(&row.diff).map_err(|e| {
+                    TarsError::Internal(format!("unreadable diff {}: {e}", row.seq))
+                })?;
+                Ok(SequencedDiff { seq: row.seq, diff })
+            })
+            .collect::<Result<Vec<_>, TarsError>>()?;
+
+        Ok((Replay::Diffs(diffs), rx))
+    }
+
+    /// Returns the number of the newest diff committed, as seen by `conn`. Reading it in the
+    /// same transaction as the data makes it line up with exactly what was read.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if something goes wrong with sqlx.
+    pub async fn newest(&self, conn: &mut SqliteConnection) -> Result<i64, TarsError> {
+        let newest = sqlx::query_scalar!(r#"SELECT MAX(seq) as "seq: i64" FROM Diffs"#)
+            .fetch_one(&mut *conn)
+            .await?;
+
+        Ok(newest.unwrap_or(0))
+    }
+}
@@ -0,0 +1,166 @@
+use axum::{
+    Json, Router, debug_handler,
+    extract::State,
+    routing::{get, post},
+};
+use chrono::Local;
+use common::{
+    TarsError, auth,
+    types::{Access, ApiToken, CreatedToken, Id, Name, TokenRequest},
+};
+use tracing::{info, instrument};
+
+use crate::{DaemonState, auth::hash_secret};
+
+/// Returns a router with the endpoints for managing the daemon's token and the `ApiToken`s
+/// handed out on top of it.
+pub fn auth_router() -> Router<DaemonState> {
+    Router::new()
+        .route("/rotate", post(rotate_token))
+        .route("/tokens", get(fetch_tokens))
+        .route("/tokens/create", post(create_token))
+        .route("/tokens/revoke", post(revoke_token))
+}
+
+/// Replaces the daemon's token with a fresh one and returns it, the old token stops working
+/// right away.
+///
+/// # Errors
+/// TarsError
+///
+/// This function will return an error if
+/// + The daemon isn't using a token at all.
+/// + The new token can't be written to disk.
+#[instrument(skip(state))]
+#[debug_handler]
+async fn rotate_token(State(state): State<DaemonState>) -> Result<Json<String>, TarsError> {
+    let Some(ref auth) = state.auth else {
+        return Err(TarsError::InvalidRequest(
+            "the daemon isn't using a token".to_owned(),
+        ));
+    };
+
+    Ok(Json(auth.rotate()?))
+}
+
+/// Returns every `ApiToken`, oldest first.
+///
+/// # Errors
+/// TarsError
+///
+/// This function will return an error if something goes wrong with sqlx.
+#[instrument(skip(state))]
+#[debug_handler]
+async fn fetch_tokens(State(state): State<DaemonState>) -> Result<Json<Vec<ApiToken>>, TarsError> {
+    let tokens = sqlx::query!(
+        r#"
+        SELECT
+        pub_id as "id!: String",
+        name as "name!: String",
+        access as "access!: Access",
+        group_id as "group_id: String",
+        created_at
+        FROM ApiTokens
+        ORDER BY created_at, id
+        "#
+    )
+    .fetch_all(&state.pool)
+    .await?
+    .into_iter()
+    .map(|row| ApiToken {
+        id: Id::from(row.id),
+        name: Name::from(row.name),
+        access: row.access,
+        group_id: row.group_id.map(Id::from),
+        created_at: row.created_at,
+    })
+    .collect();
+
+    Ok(Json(tokens))
+}
+
+/// Creates a new `ApiToken` with a fresh secret, only the hash of which is stored.
+///
+/// # Errors
+/// TarsError
+///
+/// This function will return an error if
+/// + An admin token is limited to a group.
+/// + The group doesn't exist.
+/// + Something goes wrong with sqlx.
+#[instrument(skip(state))]
+#[debug_handler]
+async fn create_token(
+    State(state): State<DaemonState>,
+    Json(request): Json<TokenRequest>,
+) -> Result<Json<CreatedToken>, TarsError> {
+    if request.access == Access::Admin && request.group_id.is_some() {
+        return Err(TarsError::invalid(
+            "group_id",
+            "an admin token can't be limited to a group",
+        ));
+    }
+
+    if let Some(ref group_id) = request.group_id {
+        let group_id = &**group_id;
+        sqlx::query_scalar!("SELECT pub_id FROM Groups WHERE pub_id = ?", group_id)
+            .fetch_optional(&state.pool)
+            .await?
+            .ok_or_else(|| {
+                TarsError::not_found("group_id", format!("there is no group {group_id}"))
+            })?;
+    }
+
+    let token = ApiToken {
+        id: Id::default(),
+        name: request.name,
+        access: request.access,
+        group_id: request.group_id,
+        created_at: Local::now().naive_local(),
+    };
+    let secret = auth::generate_token();
+    let hash = hash_secret(&secret);
+
+    sqlx::query!(
+        r#"
+        INSERT INTO ApiTokens (pub_id, name, secret_hash, access, group_id, created_at)
+        VALUES (?, ?, ?, ?, ?, ?)
+        "#,
+        *token.id,
+        *token.name,
+        hash,
+        token.access,
+        token.group_id,
+        token.created_at,
+    )
+    .execute(&state.pool)
+    .await?;
+    info!("Created token: {:?}", token);
+
+    Ok(Json(CreatedToken { token, secret }))
+}
+
+/// Revokes the `ApiToken` with this `Id`, it stops working right away.
+///
+/// # Errors
+/// TarsError
+///
+/// This function will return an error if
+/// + There is no token with this `Id`.
+/// + Something goes wrong with sqlx.
+#[instrument(skip(state))]
+#[debug_handler]
+async fn revoke_token(
+    State(state): State<DaemonState>,
+    Json(id): Json<Id>,
+) -> Result<(), TarsError> {
+    let revoked = sqlx::query!("DELETE FROM ApiTokens WHERE pub_id = ?", *id)
+        .execute(&state.pool)
+        .await?;
+    if revoked.rows_affected() == 0 {
+        return Err(sqlx::Error::RowNotFound.into());
+    }
+    info!("Revoked token: {}", *id);
+
+    Ok(())
+}
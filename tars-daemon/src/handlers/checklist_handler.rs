@@ -0,0 +1,87 @@
+use std::collections::HashMap;
+
+use common::{
+    TarsError,
+    types::{ChecklistItem, Id, Task},
+};
+use sqlx::SqliteConnection;
+
+/// Replaces the checklist of `task_id` with `checklist`, keeping the order it was given in.
+pub(crate) async fn write_task_checklist(
+    task_id: &Id,
+    checklist: &[ChecklistItem],
+    conn: &mut SqliteConnection,
+) -> Result<(), TarsError> {
+    sqlx::query!(
+        "DELETE FROM TaskChecklistItems WHERE task_id = ?",
+        **task_id
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    for (position, item) in checklist.iter().enumerate() {
+        let position = position as i64;
+        sqlx::query!(
+            "INSERT INTO TaskChecklistItems (task_id, position, name, done) VALUES (?, ?, ?, ?)",
+            **task_id,
+            position,
+            item.name,
+            item.done
+        )
+        .execute(&mut *conn)
+        .await?;
+    }
+
+    Ok(())
+}
+
+/// Returns the checklist of a single task, in order.
+pub(crate) async fn fetch_task_checklist(
+    task_id: &Id,
+    conn: &mut SqliteConnection,
+) -> Result<Vec<ChecklistItem>, TarsError> {
+    let checklist = sqlx::query_as!(
+        ChecklistItem,
+        r#"
+        SELECT name, done as "done: bool"
+        FROM TaskChecklistItems
+        WHERE task_id = ?
+        ORDER BY position
+        "#,
+        **task_id
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    Ok(checklist)
+}
+
+/// Fills in the checklist of every task, in one pass over the checklist table.
+pub(crate) async fn attach_checklists(
+    tasks: &mut [Task],
+    conn: &mut SqliteConnection,
+) -> Result<(), TarsError> {
+    let rows = sqlx::query!(
+        r#"
+        SELECT task_id, name, done as "done: bool"
+        FROM TaskChecklistItems
+        ORDER BY task_id, position
+        "#
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    let mut map: HashMap<String, Vec<ChecklistItem>> = HashMap::new();
+    for row in rows {
+        map.entry(row.task_id).or_default().push(ChecklistItem {
+            name: row.name,
+            done: row.done,
+        });
+    }
+
+    for task in tasks.iter_mut() {
+        task.checklist = map.remove(&*task.id).unwrap_or_default();
+    }
+
+    Ok(())
+}
@@ -0,0 +1,306 @@
+use std::collections::HashMap;
+
+use axum::{Json, Router, debug_handler, extract::State, routing::post};
+use common::{
+    Diff, DiffInner, TarsError,
+    types::{Id, Task, TaskDependencies, TaskDependency},
+};
+use sqlx::SqliteConnection;
+use tracing::{info, instrument};
+
+use crate::{
+    DaemonState,
+    handlers::{bump_task_version, fetch_task_by_id},
+    journal::{JournalEntry, JournalOp},
+};
+
+/// Returns a router with the endpoints that edit the dependency graph between tasks,
+/// merged into the task router.
+pub fn dependency_router() -> Router<DaemonState> {
+    Router::new()
+        .route("/block", post(block_task))
+        .route("/unblock", post(unblock_task))
+        .route("/dependencies", post(fetch_dependencies))
+}
+
+/// Takes in a `TaskDependency` and adds the edge, returning the now blocked task.
+///
+/// # Errors
+/// TarsError
+///
+/// This function will return an error if
+/// + Either of the tasks doesn't exist.
+/// + The edge would create a cycle.
+/// + Something goes wrong with sqlx.
+#[instrument(skip(state))]
+#[debug_handler]
+async fn block_task(
+    State(state): State<DaemonState>,
+    Json(dependency): Json<TaskDependency>,
+) -> Result<Json<Task>, TarsError> {
+    let mut tx = state.pool.begin().await?;
+
+    let old_task = fetch_task_by_id(&dependency.task_id, &mut tx).await?;
+    // make sure the blocker exists, the foreign key is only checked on commit
+    fetch_task_by_id(&dependency.blocked_by, &mut tx).await?;
+
+    insert_dependency(&dependency.task_id, &dependency.blocked_by, &mut tx).await?;
+    bump_task_version(&dependency.task_id, &mut tx).await?;
+    let updated_task = fetch_task_by_id(&dependency.task_id, &mut tx).await?;
+
+    JournalEntry {
+        redo: vec![JournalOp::UpdateTask(updated_task.clone())],
+        undo: vec![JournalOp::UpdateTask(old_task)],
+    }
+    .record(&mut tx)
+    .await?;
+
+    state
+        .diffs
+        .record(
+            &Diff::Updated(DiffInner::Task(updated_task.clone())),
+            &mut tx,
+        )
+        .await?;
+
+    tx.commit().await?;
+    info!("Added dependency: {:#?}", dependency);
+
+    state.diffs.publish().await;
+
+    Ok(Json::from(updated_task))
+}
+
+/// Takes in a `TaskDependency` and removes the edge, returning the task that was blocked.
+///
+/// # Errors
+/// TarsError
+///
+/// This function will return an error if
+/// + The task doesn't exist.
+/// + Something goes wrong with sqlx.
+#[instrument(skip(state))]
+#[debug_handler]
+async fn unblock_task(
+    State(state): State<DaemonState>,
+    Json(dependency): Json<TaskDependency>,
+) -> Result<Json<Task>, TarsError> {
+    let mut tx = state.pool.begin().await?;
+
+    let old_task = fetch_task_by_id(&dependency.task_id, &mut tx).await?;
+
+    sqlx::query!(
+        "DELETE FROM TaskDependencies WHERE task_id = ? AND blocked_by = ?",
+        *dependency.task_id,
+        *dependency.blocked_by
+    )
+    .execute(&mut *tx)
+    .await?;
+    bump_task_version(&dependency.task_id, &mut tx).await?;
+
+    let updated_task = fetch_task_by_id(&dependency.task_id, &mut tx).await?;
+
+    JournalEntry {
+        redo: vec![JournalOp::UpdateTask(updated_task.clone())],
+        undo: vec![JournalOp::UpdateTask(old_task)],
+    }
+    .record(&mut tx)
+    .await?;
+
+    state
+        .diffs
+        .record(
+            &Diff::Updated(DiffInner::Task(updated_task.clone())),
+            &mut tx,
+        )
+        .await?;
+
+    tx.commit().await?;
+    info!("Removed dependency: {:#?}", dependency);
+
+    state.diffs.publish().await;
+
+    Ok(Json::from(updated_task))
+}
+
+/// Takes in a task `Id` and returns the tasks directly blocking it and the ones it directly blocks.
+///
+/// # Errors
+/// TarsError
+///
+/// This function will return an error if
+/// + The task doesn't exist.
+/// + Something goes wrong with sqlx.
+#[instrument(skip(state))]
+#[debug_handler]
+async fn fetch_dependencies(
+    State(state): State<DaemonState>,
+    Json(id): Json<Id>,
+) -> Result<Json<TaskDependencies>, TarsError> {
+    let mut conn = state.pool.acquire().await?;
+    let task = fetch_task_by_id(&id, &mut conn).await?;
+
+    let mut blockers = Vec::new();
+    for blocker_id in task.blocked_by.iter() {
+        blockers.push(fetch_task_by_id(blocker_id, &mut conn).await?);
+    }
+
+    let mut dependents = Vec::new();
+    for dependent_id in fetch_dependent_ids(&id, &mut conn).await? {
+        dependents.push(fetch_task_by_id(&dependent_id, &mut conn).await?);
+    }
+
+    let dependencies = TaskDependencies {
+        blockers,
+        dependents,
+    };
+    info!("Fetched dependencies: {:#?}", dependencies);
+
+    Ok(Json::from(dependencies))
+}
+
+/// Adds a single edge, refusing it if `blocked_by` already (transitively) waits on `task_id`.
+async fn insert_dependency(
+    task_id: &Id,
+    blocked_by: &Id,
+    conn: &mut SqliteConnection,
+) -> Result<(), TarsError> {
+    let creates_cycle = sqlx::query_scalar!(
+        r#"
+        WITH RECURSIVE chain(id) AS (
+            SELECT ?
+            UNION
+            SELECT d.blocked_by FROM TaskDependencies d JOIN chain c ON d.task_id = c.id
+        )
+        SELECT EXISTS(SELECT 1 FROM chain WHERE id = ?) as "creates_cycle!: bool"
+        "#,
+        **blocked_by,
+        **task_id
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+
+    if creates_cycle {
+        return Err(TarsError::invalid(
+            "blocked_by",
+            format!(
+                "{} being blocked by {} would create a cycle",
+                **task_id, **blocked_by
+            ),
+        ));
+    }
+
+    sqlx::query!(
+        "INSERT OR IGNORE INTO TaskDependencies (task_id, blocked_by) VALUES (?, ?)",
+        **task_id,
+        **blocked_by
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(())
+}
+
+/// Replaces the tasks blocking `task_id` with `blocked_by`.
+///
+/// # Errors
+///
+/// This function will return an error if one of the edges would create a cycle.
+pub(crate) async fn write_task_blockers(
+    task_id: &Id,
+    blocked_by: &[Id],
+    conn: &mut SqliteConnection,
+) -> Result<(), TarsError> {
+    sqlx::query!("DELETE FROM TaskDependencies WHERE task_id = ?", **task_id)
+        .execute(&mut *conn)
+        .await?;
+
+    for blocker in blocked_by {
+        insert_dependency(task_id, blocker, conn).await?;
+    }
+
+    Ok(())
+}
+
+/// Returns the sorted `Id`s of the tasks blocking a single task.
+pub(crate) async fn fetch_task_blockers(
+    task_id: &Id,
+    conn: &mut SqliteConnection,
+) -> Result<Vec<Id>, TarsError> {
+    let ids = sqlx::query_scalar!(
+        "SELECT blocked_by FROM TaskDependencies WHERE task_id = ? ORDER BY blocked_by",
+        **task_id
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    Ok(ids.into_iter().map(Id::from).collect())
+}
+
+/// Returns the `Id`s of the tasks directly blocked by a single task.
+pub(crate) async fn fetch_dependent_ids(
+    task_id: &Id,
+    conn: &mut SqliteConnection,
+) -> Result<Vec<Id>, TarsError> {
+    let ids = sqlx::query_scalar!(
+        "SELECT task_id FROM TaskDependencies WHERE blocked_by = ? ORDER BY task_id",
+        **task_id
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    Ok(ids.into_iter().map(Id::from).collect())
+}
+
+/// Returns the `Id`s of tasks outside of the group's subtree that are blocked by a task inside it,
+/// these lose an edge when the group gets deleted.
+pub(crate) async fn fetch_group_outside_dependent_ids(
+    group_id: &Id,
+    conn: &mut SqliteConnection,
+) -> Result<Vec<Id>, TarsError> {
+    let ids = sqlx::query_scalar!(
+        r#"
+        WITH RECURSIVE subtree(pub_id) AS (
+            SELECT pub_id FROM Groups WHERE pub_id = ?
+            UNION ALL
+            SELECT g.pub_id FROM Groups g JOIN subtree s ON g.parent_id = s.pub_id
+        ),
+        subtree_tasks(pub_id) AS (
+            SELECT pub_id FROM Tasks WHERE group_id IN (SELECT pub_id FROM subtree)
+        )
+        SELECT DISTINCT d.task_id
+        FROM TaskDependencies d
+        WHERE d.blocked_by IN (SELECT pub_id FROM subtree_tasks)
+        AND d.task_id NOT IN (SELECT pub_id FROM subtree_tasks)
+        ORDER BY d.task_id
+        "#,
+        **group_id
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    Ok(ids.into_iter().map(Id::from).collect())
+}
+
+/// Fills in the blockers of every task, in one pass over the edge table.
+pub(crate) async fn attach_blockers(
+    tasks: &mut [Task],
+    conn: &mut SqliteConnection,
+) -> Result<(), TarsError> {
+    let rows = sqlx::query!("SELECT task_id, blocked_by FROM TaskDependencies ORDER BY blocked_by")
+        .fetch_all(&mut *conn)
+        .await?;
+
+    let mut map: HashMap<String, Vec<Id>> = HashMap::new();
+    for row in rows {
+        map.entry(row.task_id)
+            .or_default()
+            .push(Id::from(row.blocked_by));
+    }
+
+    for task in tasks.iter_mut() {
+        task.blocked_by = map.remove(&*task.id).unwrap_or_default();
+    }
+
+    Ok(())
+}
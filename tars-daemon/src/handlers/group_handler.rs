@@ -1,14 +1,19 @@
-use crate::DaemonState;
+use crate::{
+    DaemonState,
+    handlers::{fetch_group_outside_dependent_ids, fetch_task_by_id},
+    journal::{JournalEntry, JournalOp, SavedTask},
+    validation::{check_group, check_group_deletable, check_group_exists},
+};
 use async_recursion::async_recursion;
 use axum::{
     Json, Router, debug_handler,
     extract::State,
     routing::{get, post},
 };
-use common::{Diff, DiffInner, TarsError, types::*};
+use common::{Diff, DiffInner, Patch, TarsError, types::*};
 
-use sqlx::{Pool, Sqlite};
-use tracing::{info, instrument};
+use sqlx::{Pool, Sqlite, SqliteConnection};
+use tracing::{error, info, instrument};
 
 /// Returns a router with all the group specific endpoints
 pub fn group_router() -> Router<DaemonState> {
@@ -16,6 +21,8 @@ pub fn group_router() -> Router<DaemonState> {
         .route("/", get(fetch_groups))
         .route("/create", post(create_group))
         .route("/update", post(update_group))
+        .route("/patch", post(patch_group))
+        .route("/move", post(move_group))
         .route("/delete", post(delete_group))
         .route("/score", post(calculate_group_score))
 }
@@ -26,6 +33,7 @@ pub fn group_router() -> Router<DaemonState> {
 /// TarsError
 ///
 /// This function will return an error if
+/// + The group fails validation, ex. an empty name or a parent that doesn't exist.
 /// + Something goes wrong with sqlx.
 /// + Something goes wrong turning what sqlx returns into our wrapper types.
 #[instrument(skip(state))]
@@ -34,39 +42,58 @@ async fn create_group(
     State(state): State<DaemonState>,
     Json(group): Json<Group>,
 ) -> Result<Json<Group>, TarsError> {
-    // we want to recursively attach all of the parent groups
-    // let new_id = Id::default();
+    let mut tx = state.pool.begin().await?;
+    check_group(&group, &mut tx).await?;
     let inserted = sqlx::query_as!(
         Group,
         r#"
-            INSERT INTO Groups (pub_id, name, parent_id, color, created_at,priority)
+            INSERT INTO Groups (pub_id, name, parent_id, color, created_at,priority, version)
             VALUES (
                 ?,
                 ?,
                 ?,
                 ?,
                 ?,
+                ?,
                 ?
             )
-            RETURNING Groups.name as "name: Name", Groups.pub_id as "id: Id", Groups.parent_id as "parent_id: Id", Groups.color as "color: Color",Groups.created_at, Groups.priority as "priority: Priority"
+            RETURNING Groups.name as "name: Name", Groups.pub_id as "id: Id", Groups.parent_id as "parent_id: Id", Groups.color as "color: Color",Groups.created_at, Groups.priority as "priority: Priority", Groups.version
         "#,
         *group.id,
         *group.name,
         group.parent_id,
         group.color,
         group.created_at,
-        group.priority
+        group.priority,
+        group.version
     )
-    .fetch_one(&state.pool)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    if group != inserted {
+        error!("Created group doesn't match the request: {:#?}", inserted);
+        return Err(TarsError::Internal(
+            "the created group doesn't match the request".to_owned(),
+        ));
+    }
+
+    JournalEntry {
+        redo: vec![JournalOp::CreateGroup(inserted.clone())],
+        undo: vec![JournalOp::DeleteGroup(inserted.id.clone())],
+    }
+    .record(&mut tx)
     .await?;
 
-    assert_eq!(group, inserted);
+    state
+        .diffs
+        .record(&Diff::Added(DiffInner::Group(inserted.clone())), &mut tx)
+        .await?;
+
+    tx.commit().await?;
 
     info!("Created group: {:#?}", inserted);
 
-    let _ = state
-        .diff_tx
-        .send(Diff::Added(DiffInner::Group(inserted.clone())));
+    state.diffs.publish().await;
 
     Ok(Json(inserted))
 }
@@ -83,6 +110,19 @@ async fn create_group(
 #[instrument(skip(state))]
 #[debug_handler]
 async fn fetch_groups(State(state): State<DaemonState>) -> Result<Json<Vec<Group>>, TarsError> {
+    let groups = fetch_all_groups(&mut *state.pool.acquire().await?).await?;
+
+    info!("Fetched groups: {:#?}", groups);
+
+    Ok(Json::from(groups))
+}
+
+/// Fetches every group.
+///
+/// # Errors
+///
+/// This function will return an error if something goes wrong with sqlx.
+pub(crate) async fn fetch_all_groups(conn: &mut SqliteConnection) -> Result<Vec<Group>, TarsError> {
     let groups = sqlx::query_as!(
         Group,
         r#"
@@ -92,16 +132,15 @@ async fn fetch_groups(State(state): State<DaemonState>) -> Result<Json<Vec<Group
         parent_id as "parent_id: Id",
         color as "color: Color",
         created_at,
-        priority as "priority: Priority"
+        priority as "priority: Priority",
+        version
         FROM Groups
         "#
     )
-    .fetch_all(&state.pool)
+    .fetch_all(&mut *conn)
     .await?;
 
-    info!("Fetched groups: {:#?}", groups);
-
-    Ok(Json::from(groups))
+    Ok(groups)
 }
 
 /// Takes in a `Group` and then updates that group to the database.
@@ -110,6 +149,8 @@ async fn fetch_groups(State(state): State<DaemonState>) -> Result<Json<Vec<Group
 /// TarsError
 ///
 /// This function will return an error if
+/// + The group fails validation, ex. an empty name or an invalid color.
+/// + The group was updated since the version it carries, the conflict holds the current one.
 /// + Something goes wrong with sqlx.
 /// + Something goes wrong turning what sqlx returns into our wrapper types.
 #[instrument(skip(state))]
@@ -118,6 +159,88 @@ async fn update_group(
     State(state): State<DaemonState>,
     Json(group): Json<Group>,
 ) -> Result<Json<Group>, TarsError> {
+    let mut tx = state.pool.begin().await?;
+    check_group(&group, &mut tx).await?;
+    let old_group = fetch_group_by_id(&group.id, &mut tx).await?;
+
+    let updated = write_group_update(&group, old_group, &mut tx).await?;
+
+    state
+        .diffs
+        .record(&Diff::Updated(DiffInner::Group(updated.clone())), &mut tx)
+        .await?;
+
+    tx.commit().await?;
+    info!("Updated group: {:#?}", updated);
+
+    state.diffs.publish().await;
+
+    Ok(Json::from(updated))
+}
+
+/// Takes in a patch of some of the fields of a group and writes only those, leaving the rest
+/// as they are in the database.
+///
+/// # Errors
+/// TarsError
+///
+/// This function will return an error if
+/// + There is no group with the patch's `Id`.
+/// + The patched group fails validation, ex. an empty name or an invalid color.
+/// + The patch carries a version and the group was updated since, the conflict holds the
+///   current one.
+/// + Something goes wrong with sqlx.
+/// + Something goes wrong turning what sqlx returns into our wrapper types.
+#[instrument(skip(state))]
+#[debug_handler]
+async fn patch_group(
+    State(state): State<DaemonState>,
+    Json(patch): Json<GroupPatch>,
+) -> Result<Json<Group>, TarsError> {
+    let mut tx = state.pool.begin().await?;
+    let old_group = fetch_group_by_id(&patch.id, &mut tx).await?;
+
+    if patch.version.is_some_and(|v| v != old_group.version) {
+        return Err(TarsError::Conflict(Box::new(DiffInner::Group(old_group))));
+    }
+
+    let mut group = old_group.clone();
+    patch.apply_to(&mut group);
+    group.version = old_group.version;
+    check_group(&group, &mut tx).await?;
+
+    let updated = write_group_update(&group, old_group.clone(), &mut tx).await?;
+
+    // subscribers already have the rest, only send them what changed
+    state
+        .diffs
+        .record(
+            &Diff::Patched(Patch::Group(GroupPatch::between(&old_group, &updated))),
+            &mut tx,
+        )
+        .await?;
+
+    tx.commit().await?;
+    info!("Patched group: {:#?}", updated);
+
+    state.diffs.publish().await;
+
+    Ok(Json::from(updated))
+}
+
+/// Overwrites the name, color and priority of `old_group` with those of `group` as long as
+/// it's still at the version `group` carries, and journals the change.
+///
+/// # Errors
+///
+/// This function will return an error if
+/// + The group was updated since the version it carries, the conflict holds the current one.
+/// + Something goes wrong with sqlx.
+async fn write_group_update(
+    group: &Group,
+    old_group: Group,
+    conn: &mut SqliteConnection,
+) -> Result<Group, TarsError> {
     let col = group.color.as_str();
 
     let updated = sqlx::query_as!(
@@ -127,40 +250,152 @@ async fn update_group(
             SET
             name = ?,
             color = ?,
-            priority = ?
-            WHERE pub_id = ?
+            priority = ?,
+            version = version + 1,
+            updated_at = CURRENT_TIMESTAMP
+            WHERE pub_id = ? AND version = ?
             RETURNING
                 name as "name: Name",
                 pub_id as "id: Id",
                 parent_id as "parent_id: Id",
                 color as "color: Color",
                 created_at,
-                priority as "priority: Priority"
-
+                priority as "priority: Priority",
+                version
         "#,
         *group.name,
         col,
         group.priority,
-        *group.id
+        *group.id,
+        group.version
     )
-    .fetch_one(&state.pool)
+    .fetch_optional(&mut *conn)
     .await?;
 
-    assert_eq!(group, updated);
-    info!("Updated group: {:#?}", updated);
+    // someone else got there first, let the client decide what to do with their copy
+    let Some(updated) = updated else {
+        return Err(TarsError::Conflict(Box::new(DiffInner::Group(old_group))));
+    };
 
-    let _ = state
-        .diff_tx
-        .send(Diff::Updated(DiffInner::Group(updated.clone())));
-    Ok(Json::from(updated))
+    JournalEntry {
+        redo: vec![JournalOp::UpdateGroup(updated.clone())],
+        undo: vec![JournalOp::UpdateGroup(old_group)],
+    }
+    .record(conn)
+    .await?;
+
+    Ok(updated)
+}
+
+/// Takes in a `GroupMove` and reparents the group, moving everything inside it along.
+///
+/// # Errors
+/// TarsError
+///
+/// This function will return an error if
+/// + The group was updated since the version the move carries, the conflict holds the
+///   current one.
+/// + The group or the new parent doesn't exist.
+/// + The new parent is the group itself or one of its descendants.
+/// + Something goes wrong with sqlx.
+#[instrument(skip(state))]
+#[debug_handler]
+async fn move_group(
+    State(state): State<DaemonState>,
+    Json(group_move): Json<GroupMove>,
+) -> Result<Json<Group>, TarsError> {
+    let mut tx = state.pool.begin().await?;
+    let old_group = fetch_group_by_id(&group_move.id, &mut tx).await?;
+
+    if let Some(ref parent_id) = group_move.parent_id {
+        check_group_exists("parent_id", parent_id, &mut tx).await?;
+
+        // walk up from the new parent, if we run into the group we're moving it would
+        // end up as its own ancestor
+        let creates_cycle = sqlx::query_scalar!(
+            r#"
+            WITH RECURSIVE ancestors(pub_id, parent_id) AS (
+                SELECT pub_id, parent_id FROM Groups WHERE pub_id = ?
+                UNION ALL
+                SELECT g.pub_id, g.parent_id FROM Groups g JOIN ancestors a ON g.pub_id = a.parent_id
+            )
+            SELECT EXISTS(SELECT 1 FROM ancestors WHERE pub_id = ?) as "creates_cycle!: bool"
+            "#,
+            **parent_id,
+            *group_move.id
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+
+        if creates_cycle {
+            return Err(TarsError::invalid(
+                "parent_id",
+                format!(
+                    "can't move group {} into itself or one of its descendants",
+                    *group_move.id
+                ),
+            ));
+        }
+    }
+
+    let moved = sqlx::query_as!(
+        Group,
+        r#"
+            UPDATE Groups
+            SET parent_id = ?, version = version + 1, updated_at = CURRENT_TIMESTAMP
+            WHERE pub_id = ? AND version = ?
+            RETURNING
+                name as "name: Name",
+                pub_id as "id: Id",
+                parent_id as "parent_id: Id",
+                color as "color: Color",
+                created_at,
+                priority as "priority: Priority",
+                version
+        "#,
+        group_move.parent_id,
+        *group_move.id,
+        group_move.version
+    )
+    .fetch_optional(&mut *tx)
+    .await?;
+
+    // moved or updated since the caller fetched it, same as an update
+    let Some(moved) = moved else {
+        return Err(TarsError::Conflict(Box::new(DiffInner::Group(old_group))));
+    };
+
+    JournalEntry {
+        redo: vec![JournalOp::UpdateGroup(moved.clone())],
+        undo: vec![JournalOp::UpdateGroup(old_group)],
+    }
+    .record(&mut tx)
+    .await?;
+
+    state
+        .diffs
+        .record(&Diff::Updated(DiffInner::Group(moved.clone())), &mut tx)
+        .await?;
+
+    tx.commit().await?;
+    info!("Moved group: {:#?}", moved);
+
+    state.diffs.publish().await;
+
+    Ok(Json::from(moved))
 }
 
 /// Takes in a `Group` and then deletes that group in the database.
 ///
+/// Every descendant group and task gets deleted along with it, all of which
+/// are recorded in the journal so that undoing the deletion restores them.
+///
 /// # Errors
 /// TarsError
 ///
 /// This function will return an error if
+/// + The group was updated since the version it carries, the conflict holds the current one.
+/// + An API token is limited to the group or one below it.
 /// + Something goes wrong with sqlx.
 /// + Something goes wrong turning what sqlx returns into our wrapper types.
 #[instrument(skip(state))]
@@ -169,32 +404,164 @@ async fn delete_group(
     State(state): State<DaemonState>,
     Json(group): Json<Group>,
 ) -> Result<Json<Group>, TarsError> {
+    let mut tx = state.pool.begin().await?;
+    check_group_deletable(&group.id, &mut tx).await?;
+
+    // snapshot everything the cascade is about to take out, parents before children
+    let subtree = fetch_group_subtree(&group.id, &mut tx).await?;
+    let task_ids = sqlx::query_scalar!(
+        r#"
+            WITH RECURSIVE subtree(pub_id) AS (
+                SELECT pub_id FROM Groups WHERE pub_id = ?
+                UNION ALL
+                SELECT g.pub_id FROM Groups g JOIN subtree s ON g.parent_id = s.pub_id
+            )
+            SELECT t.pub_id FROM Tasks t WHERE t.group_id IN (SELECT pub_id FROM subtree)
+        "#,
+        *group.id
+    )
+    .fetch_all(&mut *tx)
+    .await?;
+
+    let mut undo: Vec<JournalOp> = subtree.into_iter().map(JournalOp::CreateGroup).collect();
+    for id in task_ids {
+        undo.push(JournalOp::CreateTask(
+            SavedTask::load(&Id::from(id), &mut tx).await?,
+        ));
+    }
+
+    // tasks elsewhere that were waiting on the subtree lose those edges
+    let outside_dependents = fetch_group_outside_dependent_ids(&group.id, &mut tx).await?;
+    for id in outside_dependents.iter() {
+        undo.push(JournalOp::UpdateTask(fetch_task_by_id(id, &mut tx).await?));
+    }
+
     let deleted = sqlx::query_as!(
         Group,
         r#"
             DELETE FROM Groups
-            WHERE pub_id = ?
+            WHERE pub_id = ? AND version = ?
             RETURNING
                 pub_id as "id: Id",
                 name as "name: Name",
                 parent_id as "parent_id: Id",
                 color as "color: Color",
                 created_at,
-                priority as "priority: Priority"
-           
+                priority as "priority: Priority",
+                version
         "#,
         *group.id,
+        group.version
     )
-    .fetch_one(&state.pool)
+    .fetch_optional(&mut *tx)
     .await?;
 
+    // someone else changed the group since, let the client decide if it still wants it gone
+    let Some(deleted) = deleted else {
+        let current = fetch_group_by_id(&group.id, &mut tx).await?;
+        return Err(TarsError::Conflict(Box::new(DiffInner::Group(current))));
+    };
+
+    JournalEntry {
+        redo: vec![JournalOp::DeleteGroup(deleted.id.clone())],
+        undo,
+    }
+    .record(&mut tx)
+    .await?;
+
+    let mut unblocked = Vec::new();
+    for id in outside_dependents.iter() {
+        unblocked.push(fetch_task_by_id(id, &mut tx).await?);
+    }
+
+    state
+        .diffs
+        .record(&Diff::Deleted(deleted.id.clone()), &mut tx)
+        .await?;
+    for task in unblocked {
+        state
+            .diffs
+            .record(&Diff::Updated(DiffInner::Task(task)), &mut tx)
+            .await?;
+    }
+
+    tx.commit().await?;
     info!("Deleted group: {:#?}", deleted);
-    assert_eq!(group, deleted);
 
-    let _ = state.diff_tx.send(Diff::Deleted(deleted.id.clone()));
+    state.diffs.publish().await;
+
     Ok(Json::from(deleted))
 }
 
+/// Fetches a single `Group` by its `Id`.
+///
+/// # Errors
+///
+/// This function will return an error if the group doesn't exist or something goes wrong with sqlx.
+pub(crate) async fn fetch_group_by_id(
+    id: &Id,
+    conn: &mut SqliteConnection,
+) -> Result<Group, TarsError> {
+    let group = sqlx::query_as!(
+        Group,
+        r#"
+        SELECT
+        pub_id as "id: Id",
+        name as "name: Name",
+        parent_id as "parent_id: Id",
+        color as "color: Color",
+        created_at,
+        priority as "priority: Priority",
+        version
+        FROM Groups
+        WHERE pub_id = ?
+        "#,
+        **id
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+
+    Ok(group)
+}
+
+/// Fetches the `Group` with this `Id` along with all of its descendants,
+/// ordered so that every parent comes before its children.
+///
+/// # Errors
+///
+/// This function will return an error if something goes wrong with sqlx.
+async fn fetch_group_subtree(
+    id: &Id,
+    conn: &mut SqliteConnection,
+) -> Result<Vec<Group>, TarsError> {
+    let groups = sqlx::query_as!(
+        Group,
+        r#"
+        WITH RECURSIVE subtree(pub_id, depth) AS (
+            SELECT pub_id, 0 FROM Groups WHERE pub_id = ?
+            UNION ALL
+            SELECT g.pub_id, s.depth + 1 FROM Groups g JOIN subtree s ON g.parent_id = s.pub_id
+        )
+        SELECT
+        g.pub_id as "id!: Id",
+        g.name as "name!: Name",
+        g.parent_id as "parent_id: Id",
+        g.color as "color!: Color",
+        g.created_at as "created_at!",
+        g.priority as "priority!: Priority",
+        g.version as "version!"
+        FROM Groups g
+        JOIN subtree s ON g.pub_id = s.pub_id
+        ORDER BY s.depth
+        "#,
+        **id
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    Ok(groups)
+}
+
 /// Returns the p_score for this group.
 ///
 /// # Errors
@@ -0,0 +1,104 @@
+use axum::{Json, Router, debug_handler, extract::State, routing::post};
+use common::{Diff, TarsError};
+use tracing::{info, instrument};
+
+use crate::{DaemonState, journal::decode_ops};
+
+/// Returns a router with the undo / redo endpoints.
+pub fn journal_router() -> Router<DaemonState> {
+    Router::new()
+        .route("/undo", post(undo))
+        .route("/redo", post(redo))
+}
+
+/// Reverts the most recent mutation that hasn't been undone yet, returning the resulting `Diff`s.
+///
+/// Returns no `Diff`s if there is nothing to undo.
+///
+/// # Errors
+/// TarsError
+///
+/// This function will return an error if
+/// + Something goes wrong with sqlx.
+/// + The journal entry can't be decoded.
+#[instrument(skip(state))]
+#[debug_handler]
+async fn undo(State(state): State<DaemonState>) -> Result<Json<Vec<Diff>>, TarsError> {
+    let mut tx = state.pool.begin().await?;
+
+    let Some(entry) = sqlx::query!(
+        r#"SELECT id as "id!", undo FROM Journal WHERE undone = FALSE ORDER BY id DESC LIMIT 1"#
+    )
+    .fetch_optional(&mut *tx)
+    .await?
+    else {
+        info!("Nothing to undo");
+        return Ok(Json(vec![]));
+    };
+
+    let mut diffs = Vec::new();
+    for op in decode_ops(&entry.undo)? {
+        diffs.extend(op.apply(&mut tx).await?);
+    }
+
+    sqlx::query!("UPDATE Journal SET undone = TRUE WHERE id = ?", entry.id)
+        .execute(&mut *tx)
+        .await?;
+
+    for diff in diffs.iter() {
+        state.diffs.record(diff, &mut tx).await?;
+    }
+
+    tx.commit().await?;
+    info!("Undid journal entry {}: {:#?}", entry.id, diffs);
+
+    state.diffs.publish().await;
+
+    Ok(Json(diffs))
+}
+
+/// Reapplies the oldest mutation that has been undone, returning the resulting `Diff`s.
+///
+/// Returns no `Diff`s if there is nothing to redo.
+///
+/// # Errors
+/// TarsError
+///
+/// This function will return an error if
+/// + Something goes wrong with sqlx.
+/// + The journal entry can't be decoded.
+#[instrument(skip(state))]
+#[debug_handler]
+async fn redo(State(state): State<DaemonState>) -> Result<Json<Vec<Diff>>, TarsError> {
+    let mut tx = state.pool.begin().await?;
+
+    let Some(entry) = sqlx::query!(
+        r#"SELECT id as "id!", redo FROM Journal WHERE undone = TRUE ORDER BY id ASC LIMIT 1"#
+    )
+    .fetch_optional(&mut *tx)
+    .await?
+    else {
+        info!("Nothing to redo");
+        return Ok(Json(vec![]));
+    };
+
+    let mut diffs = Vec::new();
+    for op in decode_ops(&entry.redo)? {
+        diffs.extend(op.apply(&mut tx).await?);
+    }
+
+    sqlx::query!("UPDATE Journal SET undone = FALSE WHERE id = ?", entry.id)
+        .execute(&mut *tx)
+        .await?;
+
+    for diff in diffs.iter() {
+        state.diffs.record(diff, &mut tx).await?;
+    }
+
+    tx.commit().await?;
+    info!("Redid journal entry {}: {:#?}", entry.id, diffs);
+
+    state.diffs.publish().await;
+
+    Ok(Json(diffs))
+}
@@ -1,7 +1,27 @@
+mod auth_handler;
+mod checklist_handler;
+mod dependency_handler;
 mod group_handler;
+mod journal_handler;
+mod schedule_handler;
+mod scoring_handler;
+mod search_handler;
+mod snapshot_handler;
 mod subscribe_handler;
+mod tag_handler;
 mod task_handler;
+mod time_handler;
 
+pub use auth_handler::*;
+pub(crate) use checklist_handler::*;
+pub use dependency_handler::*;
 pub use group_handler::*;
+pub use journal_handler::*;
+pub use schedule_handler::*;
+pub use scoring_handler::*;
+pub use search_handler::*;
+pub use snapshot_handler::*;
 pub use subscribe_handler::*;
+pub use tag_handler::*;
 pub use task_handler::*;
+pub use time_handler::*;
@@ -0,0 +1,43 @@
+use axum::{Json, Router, debug_handler, extract::State, routing::post};
+use common::{
+    TarsError,
+    types::{Schedule, ScheduleRequest},
+};
+use sqlx::types::chrono::Local;
+use tracing::instrument;
+
+use crate::{DaemonState, handlers::fetch_open_tasks};
+
+/// Returns a router with the scheduling endpoint.
+pub fn schedule_router() -> Router<DaemonState> {
+    Router::new().route("/schedule", post(fetch_schedule))
+}
+
+/// Lays the open tasks in scope out over the coming working days, highest scoring first,
+/// flagging the ones that can't be finished before they're due.
+///
+/// Tasks are scored as of the start of the schedule, so a due date weighs in the same way it
+/// will when the schedule starts.
+///
+/// # Errors
+/// TarsError
+///
+/// This function will return an error if
+/// + Something goes wrong with sqlx.
+#[instrument(skip(state))]
+#[debug_handler]
+async fn fetch_schedule(
+    State(state): State<DaemonState>,
+    Json(request): Json<ScheduleRequest>,
+) -> Result<Json<Schedule>, TarsError> {
+    let from = request.from.unwrap_or_else(|| Local::now().naive_local());
+    let days = request.days.unwrap_or(state.schedule.horizon_days);
+
+    let open = fetch_open_tasks(request.scope, from, &state).await?;
+
+    let mut schedule = state.schedule.schedule(&open.estimated, from, days);
+    schedule.unestimated = open.unestimated;
+    schedule.blocked = open.blocked;
+
+    Ok(Json(schedule))
+}
@@ -0,0 +1,50 @@
+use axum::{Json, Router, debug_handler, extract::State, routing::post};
+use common::{TarsError, types::ScoringStrategyKind};
+use tracing::instrument;
+
+use crate::DaemonState;
+
+/// Returns a router with the endpoints for picking how tasks get scored.
+pub fn scoring_router() -> Router<DaemonState> {
+    Router::new()
+        .route("/fetch", post(fetch_scoring_strategy))
+        .route("/set", post(set_scoring_strategy))
+}
+
+/// Returns the active scoring strategy.
+///
+/// # Errors
+/// TarsError
+///
+/// This function does not fail, the signature is kept in line with the other handlers.
+#[instrument(skip(state))]
+#[debug_handler]
+async fn fetch_scoring_strategy(
+    State(state): State<DaemonState>,
+) -> Result<Json<ScoringStrategyKind>, TarsError> {
+    let scorer = state.scorer.read().expect("scorer lock poisoned");
+
+    Ok(Json(scorer.kind()))
+}
+
+/// Takes in a `ScoringStrategyKind` and makes it the active scoring strategy, every score handed
+/// out afterwards uses it. Returns the now active strategy.
+///
+/// The switch only lasts until the daemon restarts, the strategy it starts with is set in
+/// `daemon.toml`.
+///
+/// # Errors
+/// TarsError
+///
+/// This function does not fail, the signature is kept in line with the other handlers.
+#[instrument(skip(state))]
+#[debug_handler]
+async fn set_scoring_strategy(
+    State(state): State<DaemonState>,
+    Json(kind): Json<ScoringStrategyKind>,
+) -> Result<Json<ScoringStrategyKind>, TarsError> {
+    let mut scorer = state.scorer.write().expect("scorer lock poisoned");
+    scorer.switch(kind);
+
+    Ok(Json(scorer.kind()))
+}
@@ -0,0 +1,99 @@
+use axum::{Json, Router, debug_handler, extract::State, routing::post};
+use common::{
+    TarsError,
+    types::{Id, SNIPPET_CLOSE, SNIPPET_OPEN, SearchItem, SearchMatch, SearchQuery},
+};
+use tracing::{info, instrument};
+
+use crate::{
+    DaemonState,
+    handlers::{fetch_group_by_id, fetch_task_by_id},
+};
+
+/// How many matches are returned when the query doesn't ask for a limit.
+const DEFAULT_SEARCH_LIMIT: u32 = 25;
+
+/// How many words a snippet holds at most.
+const SNIPPET_WORDS: i64 = 12;
+
+/// Returns a router with the full-text search endpoint.
+pub fn search_router() -> Router<DaemonState> {
+    Router::new().route("/search", post(search))
+}
+
+/// Takes in a `SearchQuery` and returns the matching tasks and groups, best match first.
+///
+/// Names weigh more than descriptions, so a task called "groceries" beats one that
+/// only mentions them.
+///
+/// # Errors
+/// TarsError
+///
+/// This function will return an error if
+/// + Something goes wrong with sqlx.
+#[instrument(skip(state))]
+#[debug_handler]
+async fn search(
+    State(state): State<DaemonState>,
+    Json(query): Json<SearchQuery>,
+) -> Result<Json<Vec<SearchMatch>>, TarsError> {
+    let Some(fts_query) = to_fts_query(&query.query) else {
+        return Ok(Json(vec![]));
+    };
+    let limit = query.limit.unwrap_or(DEFAULT_SEARCH_LIMIT);
+
+    let mut conn = state.pool.acquire().await?;
+
+    let rows = sqlx::query!(
+        r#"
+        SELECT
+            kind as "kind!: String",
+            pub_id as "pub_id!: String",
+            snippet(SearchIndex, -1, ?, ?, '...', ?) as "snippet!: String",
+            -bm25(SearchIndex, 0.0, 0.0, 10.0, 1.0) as "score!: f64"
+        FROM SearchIndex
+        WHERE SearchIndex MATCH ?
+        ORDER BY bm25(SearchIndex, 0.0, 0.0, 10.0, 1.0)
+        LIMIT ?
+        "#,
+        SNIPPET_OPEN,
+        SNIPPET_CLOSE,
+        SNIPPET_WORDS,
+        fts_query,
+        limit
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    let mut matches = Vec::new();
+    for row in rows {
+        let id = Id::from(row.pub_id);
+        let item = match row.kind.as_str() {
+            "task" => SearchItem::Task(fetch_task_by_id(&id, &mut conn).await?),
+            _ => SearchItem::Group(fetch_group_by_id(&id, &mut conn).await?),
+        };
+
+        matches.push(SearchMatch {
+            item,
+            snippet: row.snippet,
+            rank: row.score,
+        });
+    }
+    info!("Found {} matches for {:?}", matches.len(), query.query);
+
+    Ok(Json(matches))
+}
+
+/// Turns what the user typed into an FTS5 query, every word has to match as a prefix.
+///
+/// Each word is quoted, so characters FTS5 gives a meaning to (`-`, `:`, `*`, ...)
+/// are searched for as-is instead of failing to parse. Returns `None` if there is
+/// nothing to search for.
+fn to_fts_query(query: &str) -> Option<String> {
+    let words: Vec<String> = query
+        .split_whitespace()
+        .map(|w| format!("\"{}\"*", w.replace('"', "\"\"")))
+        .collect();
+
+    (!words.is_empty()).then(|| words.join(" "))
+}
@@ -0,0 +1,81 @@
+use std::collections::HashSet;
+
+use axum::{Extension, Json, Router, debug_handler, extract::State, routing::get};
+use common::{
+    TarsError,
+    types::{Group, Id, Snapshot, TaskFetchOptions},
+};
+use tracing::{info, instrument};
+
+use crate::{
+    DaemonState,
+    auth::Grant,
+    handlers::{fetch_all_groups, fetch_tasks},
+};
+
+/// Returns a router with the snapshot endpoint.
+pub fn snapshot_router() -> Router<DaemonState> {
+    Router::new().route("/snapshot", get(snapshot))
+}
+
+/// Returns every group and task, read in a single transaction along with the number of the
+/// newest diff they include. A token limited to a group only gets that group and what's
+/// below it.
+///
+/// # Errors
+/// TarsError
+///
+/// This function will return an error if something goes wrong with sqlx.
+#[instrument(skip(state))]
+#[debug_handler]
+async fn snapshot(
+    State(state): State<DaemonState>,
+    Extension(grant): Extension<Grant>,
+) -> Result<Json<Snapshot>, TarsError> {
+    let mut tx = state.pool.begin().await?;
+
+    // diffs are logged in the transaction of their change, so whatever this reads lines up
+    // with exactly the diffs up to `seq`
+    let seq = state.diffs.newest(&mut tx).await?;
+    let mut groups = fetch_all_groups(&mut tx).await?;
+    let mut tasks = fetch_tasks(TaskFetchOptions::All, &mut tx).await?;
+
+    tx.commit().await?;
+
+    if let Some(ref root) = grant.group_id {
+        let visible = subtree(root, &groups);
+        groups.retain(|group| visible.contains(&group.id));
+        tasks.retain(|task| visible.contains(&task.group.id));
+    }
+
+    info!(
+        "Sending snapshot at {seq} of {} groups and {} tasks",
+        groups.len(),
+        tasks.len()
+    );
+
+    Ok(Json(Snapshot { seq, groups, tasks }))
+}
+
+/// Returns `root` and every group below it.
+fn subtree(root: &Id, groups: &[Group]) -> HashSet<Id> {
+    let mut visible = HashSet::from([root.clone()]);
+
+    // each pass takes in the children of what's visible so far, until nothing new turns up
+    loop {
+        let before = visible.len();
+        for group in groups {
+            if group
+                .parent_id
+                .as_ref()
+                .is_some_and(|p| visible.contains(p))
+            {
+                visible.insert(group.id.clone());
+            }
+        }
+
+        if visible.len() == before {
+            return visible;
+        }
+    }
+}
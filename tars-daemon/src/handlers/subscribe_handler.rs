@@ -1,37 +1,115 @@
-use crate::DaemonState;
+use crate::{
+    DaemonState,
+    auth::{Grant, ScopeFilter},
+    diff_log::{Replay, SequencedDiff},
+};
 
 use axum::{
-    Router,
+    Extension, Router,
     extract::State,
+    http::HeaderMap,
     response::sse::{Event, KeepAlive, Sse},
     routing::get,
 };
-use futures_util::stream::Stream;
-use std::convert::Infallible;
-use tokio_stream::{StreamExt as _, wrappers::BroadcastStream};
-use tracing::info;
+use common::{RESYNC_EVENT, TarsError};
+use futures_util::stream::{self, Stream, StreamExt as _};
+use std::{convert::Infallible, future::ready, sync::Arc};
+use tokio::sync::Mutex;
+use tokio_stream::wrappers::BroadcastStream;
+use tracing::{info, warn};
 
 pub fn subscribe_router() -> Router<DaemonState> {
     Router::new().route("/", get(diff_handler))
 }
 
+/// What goes out on the stream, before being turned into an `Event`.
+// resyncs are rare, boxing every diff for them isn't worth it
+#[allow(clippy::large_enum_variant)]
+enum Message {
+    Diff(SequencedDiff),
+    Resync,
+}
+
+/// Streams every `Diff`, or only those about the group a token is limited to and what's
+/// below it.
+///
+/// Every diff carries its number in the log as the event id. A client reconnecting with a
+/// `Last-Event-ID` first gets what it missed, or a `resync` event if that's no longer
+/// kept. Clients that fall too far behind get disconnected, so they reconnect and catch up
+/// the same way.
 async fn diff_handler(
     State(state): State<DaemonState>,
-) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
-    let rx = state.diff_tx.subscribe();
+    Extension(grant): Extension<Grant>,
+    headers: HeaderMap,
+) -> Result<Sse<impl Stream<Item = Result<Event, Infallible>>>, TarsError> {
+    // an empty id is the same as none at all
+    let last_seq = headers
+        .get("last-event-id")
+        .filter(|id| !id.is_empty())
+        .map(|id| {
+            id.to_str()
+                .ok()
+                .and_then(|id| id.trim().parse::<i64>().ok())
+        });
+
+    // live diffs up to `seen` were already replayed, or were part of the client's snapshot
+    let (head, rx, seen) = match last_seq {
+        None => (vec![], state.diffs.subscribe(), 0),
+        Some(Some(last_seq)) => match state.diffs.resume(last_seq).await? {
+            (Replay::Diffs(diffs), rx) => {
+                (diffs.into_iter().map(Message::Diff).collect(), rx, last_seq)
+            }
+            (Replay::ResyncRequired, rx) => (vec![Message::Resync], rx, 0),
+        },
+        // not an id this daemon hands out
+        Some(None) => (vec![Message::Resync], state.diffs.subscribe(), 0),
+    };
+
+    let filter = match grant.group_id {
+        Some(root) => Some(Arc::new(Mutex::new(
+            ScopeFilter::load(root, &state.pool).await?,
+        ))),
+        None => None,
+    };
 
-    let stream = BroadcastStream::new(rx)
-        .map(|res| match res {
-            Ok(data) => {
-                let json = serde_json::to_string(&data).unwrap_or_default();
+    let live = BroadcastStream::new(rx)
+        .take_while(|res| {
+            if let Err(e) = res {
+                warn!("Subscriber fell behind, disconnecting it: {e}");
+            }
+            ready(res.is_ok())
+        })
+        .filter_map(move |res| ready(res.ok().filter(|diff| diff.seq > seen).map(Message::Diff)));
+
+    let stream = stream::iter(head)
+        .chain(live)
+        .filter_map(move |message| {
+            let filter = filter.clone();
+            let pool = state.pool.clone();
+
+            async move {
+                match (message, filter) {
+                    (Message::Diff(SequencedDiff { seq, diff }), Some(filter)) => filter
+                        .lock()
+                        .await
+                        .admit(diff, &pool)
+                        .await
+                        .map(|diff| Message::Diff(SequencedDiff { seq, diff })),
+                    (message, _) => Some(message),
+                }
+            }
+        })
+        .map(|message| match message {
+            Message::Diff(SequencedDiff { seq, diff }) => {
+                let json = serde_json::to_string(&diff).unwrap_or_default();
 
-                info!("sending: {json}");
+                info!("sending {seq}: {json}");
 
-                Event::default().data(json)
+                Event::default().id(seq.to_string()).data(json)
             }
-            Err(_) => Event::default().data("error"),
+            Message::Resync => Event::default().event(RESYNC_EVENT).data("resync required"),
         })
         .map(Ok);
 
-    Sse::new(stream).keep_alive(KeepAlive::default())
+    Ok(Sse::new(stream).keep_alive(KeepAlive::default()))
 }
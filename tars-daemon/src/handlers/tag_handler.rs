@@ -0,0 +1,409 @@
+use std::collections::HashMap;
+
+use axum::{
+    Json, Router, debug_handler,
+    extract::State,
+    routing::{get, post},
+};
+use common::{
+    Diff, DiffInner, TarsError,
+    types::{Color, Id, Name, Tag, Task},
+};
+use sqlx::SqliteConnection;
+use tracing::{error, info, instrument};
+
+use crate::{
+    DaemonState,
+    handlers::fetch_task_by_id,
+    journal::{JournalEntry, JournalOp},
+    validation::check_tag,
+};
+
+/// Returns a router with all the tag specific endpoints
+pub fn tag_router() -> Router<DaemonState> {
+    Router::new()
+        .route("/", get(fetch_tags))
+        .route("/create", post(create_tag))
+        .route("/update", post(update_tag))
+        .route("/delete", post(delete_tag))
+}
+
+/// Takes in a `Tag` and then writes that tag to the database.
+///
+/// # Errors
+/// TarsError
+///
+/// This function will return an error if
+/// + The tag fails validation, ex. an empty name or an invalid color.
+/// + A tag with the same name already exists.
+/// + Something goes wrong with sqlx.
+#[instrument(skip(state))]
+#[debug_handler]
+async fn create_tag(
+    State(state): State<DaemonState>,
+    Json(tag): Json<Tag>,
+) -> Result<Json<Tag>, TarsError> {
+    check_tag(&tag)?;
+
+    let mut tx = state.pool.begin().await?;
+
+    let inserted = sqlx::query_as!(
+        Tag,
+        r#"
+            INSERT INTO Tags (pub_id, name, color, created_at)
+            VALUES (?, ?, ?, ?)
+            RETURNING
+                pub_id as "id: Id",
+                name as "name: Name",
+                color as "color: Color",
+                created_at
+        "#,
+        *tag.id,
+        *tag.name,
+        tag.color,
+        tag.created_at
+    )
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| match e {
+        sqlx::Error::Database(ref db_err) if db_err.is_unique_violation() => {
+            TarsError::invalid("name", format!("a tag named {} already exists", *tag.name))
+        }
+        e => e.into(),
+    })?;
+
+    if tag != inserted {
+        error!("Created tag doesn't match the request: {:#?}", inserted);
+        return Err(TarsError::Internal(
+            "the created tag doesn't match the request".to_owned(),
+        ));
+    }
+
+    JournalEntry {
+        redo: vec![JournalOp::CreateTag(inserted.clone())],
+        undo: vec![JournalOp::DeleteTag(inserted.id.clone())],
+    }
+    .record(&mut tx)
+    .await?;
+
+    tx.commit().await?;
+    info!("Created tag: {:#?}", inserted);
+
+    Ok(Json(inserted))
+}
+
+/// Fetches all tags from the database.
+///
+/// # Errors
+/// TarsError
+///
+/// This function will return an error if
+/// + Something goes wrong with sqlx.
+/// + Something goes wrong turning what sqlx returns into our wrapper types.
+#[instrument(skip(state))]
+#[debug_handler]
+async fn fetch_tags(State(state): State<DaemonState>) -> Result<Json<Vec<Tag>>, TarsError> {
+    let tags = sqlx::query_as!(
+        Tag,
+        r#"
+        SELECT
+        pub_id as "id: Id",
+        name as "name: Name",
+        color as "color: Color",
+        created_at
+        FROM Tags
+        ORDER BY name
+        "#
+    )
+    .fetch_all(&state.pool)
+    .await?;
+
+    info!("Fetched tags: {:#?}", tags);
+
+    Ok(Json::from(tags))
+}
+
+/// Takes in a `Tag` and updates its name and color, every task carrying it gets
+/// broadcasted as updated.
+///
+/// # Errors
+/// TarsError
+///
+/// This function will return an error if
+/// + The tag fails validation, ex. an empty name, an invalid color or a name another tag has.
+/// + Something goes wrong with sqlx.
+/// + Something goes wrong turning what sqlx returns into our wrapper types.
+#[instrument(skip(state))]
+#[debug_handler]
+async fn update_tag(
+    State(state): State<DaemonState>,
+    Json(tag): Json<Tag>,
+) -> Result<Json<Tag>, TarsError> {
+    check_tag(&tag)?;
+
+    let mut tx = state.pool.begin().await?;
+    let old_tag = fetch_tag_by_id(&tag.id, &mut tx).await?;
+
+    let updated = sqlx::query_as!(
+        Tag,
+        r#"
+            UPDATE Tags
+            SET
+            name = ?,
+            color = ?
+            WHERE pub_id = ?
+            RETURNING
+                pub_id as "id: Id",
+                name as "name: Name",
+                color as "color: Color",
+                created_at
+        "#,
+        *tag.name,
+        tag.color,
+        *tag.id
+    )
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| match e {
+        sqlx::Error::Database(ref db_err) if db_err.is_unique_violation() => {
+            TarsError::invalid("name", format!("a tag named {} already exists", *tag.name))
+        }
+        e => e.into(),
+    })?;
+
+    JournalEntry {
+        redo: vec![JournalOp::UpdateTag(updated.clone())],
+        undo: vec![JournalOp::UpdateTag(old_tag)],
+    }
+    .record(&mut tx)
+    .await?;
+
+    let mut tagged = Vec::new();
+    for id in fetch_tagged_task_ids(&updated.id, &mut tx).await? {
+        tagged.push(fetch_task_by_id(&id, &mut tx).await?);
+    }
+
+    for task in tagged {
+        state
+            .diffs
+            .record(&Diff::Updated(DiffInner::Task(task)), &mut tx)
+            .await?;
+    }
+
+    tx.commit().await?;
+    info!("Updated tag: {:#?}", updated);
+
+    state.diffs.publish().await;
+
+    Ok(Json::from(updated))
+}
+
+/// Takes in a tag `Id` and deletes it, detaching it from every task that carried it.
+///
+/// # Errors
+/// TarsError
+///
+/// This function will return an error if
+/// + Something goes wrong with sqlx.
+/// + Something goes wrong turning what sqlx returns into our wrapper types.
+#[instrument(skip(state))]
+#[debug_handler]
+async fn delete_tag(
+    State(state): State<DaemonState>,
+    Json(id): Json<Id>,
+) -> Result<Json<Tag>, TarsError> {
+    let mut tx = state.pool.begin().await?;
+
+    // snapshot the tasks carrying this tag, so undoing brings the tag back on them
+    let tagged_ids = fetch_tagged_task_ids(&id, &mut tx).await?;
+    let mut undo = Vec::new();
+    for task_id in tagged_ids.iter() {
+        undo.push(JournalOp::UpdateTask(
+            fetch_task_by_id(task_id, &mut tx).await?,
+        ));
+    }
+
+    let deleted = sqlx::query_as!(
+        Tag,
+        r#"
+            DELETE FROM Tags
+            WHERE pub_id = ?
+            RETURNING
+                pub_id as "id: Id",
+                name as "name: Name",
+                color as "color: Color",
+                created_at
+        "#,
+        *id
+    )
+    .fetch_one(&mut *tx)
+    .await?;
+
+    undo.insert(0, JournalOp::CreateTag(deleted.clone()));
+
+    JournalEntry {
+        redo: vec![JournalOp::DeleteTag(deleted.id.clone())],
+        undo,
+    }
+    .record(&mut tx)
+    .await?;
+
+    let mut untagged = Vec::new();
+    for task_id in tagged_ids.iter() {
+        untagged.push(fetch_task_by_id(task_id, &mut tx).await?);
+    }
+
+    for task in untagged {
+        state
+            .diffs
+            .record(&Diff::Updated(DiffInner::Task(task)), &mut tx)
+            .await?;
+    }
+
+    tx.commit().await?;
+    info!("Deleted tag: {:#?}", deleted);
+
+    state.diffs.publish().await;
+
+    Ok(Json::from(deleted))
+}
+
+/// Fetches a single `Tag` by its `Id`.
+///
+/// # Errors
+///
+/// This function will return an error if the tag doesn't exist or something goes wrong with sqlx.
+pub(crate) async fn fetch_tag_by_id(
+    id: &Id,
+    conn: &mut SqliteConnection,
+) -> Result<Tag, TarsError> {
+    let tag = sqlx::query_as!(
+        Tag,
+        r#"
+        SELECT
+        pub_id as "id: Id",
+        name as "name: Name",
+        color as "color: Color",
+        created_at
+        FROM Tags
+        WHERE pub_id = ?
+        "#,
+        **id
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+
+    Ok(tag)
+}
+
+/// Returns the `Id`s of every task carrying the tag.
+pub(crate) async fn fetch_tagged_task_ids(
+    tag_id: &Id,
+    conn: &mut SqliteConnection,
+) -> Result<Vec<Id>, TarsError> {
+    let ids = sqlx::query_scalar!("SELECT task_id FROM TaskTags WHERE tag_id = ?", **tag_id)
+        .fetch_all(&mut *conn)
+        .await?;
+
+    Ok(ids.into_iter().map(Id::from).collect())
+}
+
+/// Returns the sorted tags of a single task.
+pub(crate) async fn fetch_task_tags(
+    task_id: &Id,
+    conn: &mut SqliteConnection,
+) -> Result<Vec<Tag>, TarsError> {
+    let mut tags = sqlx::query_as!(
+        Tag,
+        r#"
+        SELECT
+        t.pub_id as "id: Id",
+        t.name as "name: Name",
+        t.color as "color: Color",
+        t.created_at
+        FROM TaskTags tt
+        JOIN Tags t ON tt.tag_id = t.pub_id
+        WHERE tt.task_id = ?
+        "#,
+        **task_id
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    tags.sort();
+    Ok(tags)
+}
+
+/// Fills in the tags of every task, in one pass over the join table.
+pub(crate) async fn attach_tags(
+    tasks: &mut [Task],
+    conn: &mut SqliteConnection,
+) -> Result<(), TarsError> {
+    let rows = sqlx::query!(
+        r#"
+        SELECT
+        tt.task_id,
+        t.pub_id as "id: Id",
+        t.name as "name: Name",
+        t.color as "color: Color",
+        t.created_at
+        FROM TaskTags tt
+        JOIN Tags t ON tt.tag_id = t.pub_id
+        "#
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    let mut map: HashMap<String, Vec<Tag>> = HashMap::new();
+    for row in rows {
+        map.entry(row.task_id)
+            .or_default()
+            .push(Tag::with_all_fields(
+                row.id,
+                row.name,
+                row.color,
+                row.created_at,
+            ));
+    }
+
+    for task in tasks.iter_mut() {
+        let mut tags = map.remove(&*task.id).unwrap_or_default();
+        tags.sort();
+        task.tags = tags;
+    }
+
+    Ok(())
+}
+
+/// Replaces the tags of the task with `tags`.
+///
+/// # Errors
+///
+/// This function will return an error if one of the tags doesn't exist.
+pub(crate) async fn write_task_tags(
+    task_id: &Id,
+    tags: &[Tag],
+    conn: &mut SqliteConnection,
+) -> Result<(), TarsError> {
+    sqlx::query!("DELETE FROM TaskTags WHERE task_id = ?", **task_id)
+        .execute(&mut *conn)
+        .await?;
+
+    for tag in tags {
+        sqlx::query!(
+            "INSERT OR IGNORE INTO TaskTags (task_id, tag_id) VALUES (?, ?)",
+            **task_id,
+            *tag.id
+        )
+        .execute(&mut *conn)
+        .await
+        .map_err(|e| match e {
+            sqlx::Error::Database(ref db_err) if db_err.is_foreign_key_violation() => {
+                TarsError::not_found("tags", format!("tag {} doesn't exist", *tag.id))
+            }
+            e => e.into(),
+        })?;
+    }
+
+    Ok(())
+}
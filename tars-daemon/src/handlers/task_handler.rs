@@ -1,15 +1,33 @@
+use std::collections::{HashMap, HashSet};
+
 use async_recursion::async_recursion;
 use axum::{Json, Router, debug_handler, extract::State, routing::post};
 
 use color_eyre::eyre::Result;
 use common::{
-    Diff, DiffInner, TarsError,
-    types::{Color, Group, Id, Name, Priority, Task, TaskFetchOptions},
+    Diff, DiffInner, Patch, TarsError,
+    types::{
+        Color, Estimate, Group, GroupMultiplier, GroupWorkload, Id, Name, Plan, PlanRequest,
+        PlannedTask, Priority, Recurrence, ScoreExplanation, Task, TaskFetchOptions, TaskPatch,
+    },
+};
+use sqlx::{
+    Pool, Sqlite, SqliteConnection,
+    types::chrono::{Local, NaiveDateTime},
 };
-use sqlx::{Pool, Sqlite, types::chrono::Local};
 use tracing::{error, info, instrument};
 
-use crate::{DaemonState, handlers::calculate_group_p_score};
+use crate::{
+    DaemonState,
+    handlers::{
+        attach_blockers, attach_checklists, attach_tags, dependency_router, fetch_dependent_ids,
+        fetch_task_blockers, fetch_task_checklist, fetch_task_tags, write_task_blockers,
+        write_task_checklist, write_task_tags,
+    },
+    journal::{JournalEntry, JournalOp, SavedTask},
+    scoring::ScoreInput,
+    validation::check_task,
+};
 
 /// Returns a router with all the task specific endpoints
 pub fn task_router() -> Router<DaemonState> {
@@ -17,8 +35,13 @@ pub fn task_router() -> Router<DaemonState> {
         .route("/create", post(create_task))
         .route("/fetch", post(fetch_task))
         .route("/update", post(update_task))
+        .route("/patch", post(patch_task))
         .route("/delete", post(delete_task))
         .route("/score", post(calculate_task_score))
+        .route("/scores", post(calculate_task_scores))
+        .route("/score/explain", post(explain_task_score))
+        .route("/plan", post(plan_tasks))
+        .merge(dependency_router())
 }
 
 /// Takes in a task and then writes that task to the database.
@@ -27,17 +50,25 @@ pub fn task_router() -> Router<DaemonState> {
 /// TarsError
 ///
 /// This function will return an error if
+/// + The task fails validation, ex. an empty name or a group that doesn't exist.
 /// + Something goes wrong with sqlx.
 /// + Something goes wrong turning what sqlx returns into our wrapper types.
 #[instrument(skip(state))]
 #[debug_handler]
 pub async fn create_task(
     State(state): State<DaemonState>,
-    Json(task): Json<Task>,
+    Json(mut task): Json<Task>,
 ) -> Result<Json<Task>, TarsError> {
+    let mut tx = state.pool.begin().await?;
+    check_task(&task, &mut tx).await?;
+
+    let tags = std::mem::take(&mut task.tags);
+    let blocked_by = std::mem::take(&mut task.blocked_by);
+    let checklist = std::mem::take(&mut task.checklist);
+
     let inserted = sqlx::query!(
         r#"
-            INSERT INTO Tasks (pub_id, group_id, name, priority, description, due, created_at)
+            INSERT INTO Tasks (pub_id, group_id, name, priority, description, finished_at, due, created_at, recurrence, estimate, version)
             VALUES (
                 ?,
                 ?,
@@ -45,9 +76,13 @@ pub async fn create_task(
                 ?,
                 ?,
                 ?,
+                ?,
+                ?,
+                ?,
+                ?,
                 ?
             )
-            RETURNING Tasks.pub_id, Tasks.name, Tasks.priority as "priority: Priority", Tasks.description, Tasks.due, Tasks.group_id, Tasks.finished_at, Tasks.created_at
+            RETURNING Tasks.pub_id, Tasks.name, Tasks.priority as "priority: Priority", Tasks.description, Tasks.due, Tasks.group_id, Tasks.finished_at, Tasks.created_at, Tasks.recurrence as "recurrence: Recurrence", Tasks.estimate as "estimate: Estimate", Tasks.version
             
         "#,
         *task.id,
@@ -55,23 +90,27 @@ pub async fn create_task(
         *task.name,
         task.priority,
         task.description,
+        task.finished_at,
         task.due,
         task.created_at,
+        task.recurrence,
+        task.estimate,
+        task.version,
     )
-    .fetch_one(&state.pool)
+    .fetch_one(&mut *tx)
     .await.inspect_err(|e|error!("{:?}", e))?;
 
     let group = sqlx::query_as!(
         Group,
         r#"
-        SELECT name as "name: Name", pub_id as "id: Id", parent_id as "parent_id: Id", color as "color: Color",created_at, priority as "priority: Priority" FROM Groups WHERE pub_id = ?
+        SELECT name as "name: Name", pub_id as "id: Id", parent_id as "parent_id: Id", color as "color: Color",created_at, priority as "priority: Priority", version FROM Groups WHERE pub_id = ?
         "#,
         inserted.group_id
     )
-    .fetch_one(&state.pool)
+    .fetch_one(&mut *tx)
     .await.inspect_err(|e|error!("{:?}", e))?;
 
-    let created_task = Task::with_all_fields(
+    let mut created_task = Task::with_all_fields(
         inserted.pub_id,
         group,
         inserted.name,
@@ -81,13 +120,46 @@ pub async fn create_task(
         inserted.created_at,
         inserted.due,
     );
+    created_task.recurrence = inserted.recurrence;
+    created_task.estimate = inserted.estimate;
+    created_task.version = inserted.version;
+
+    // the client's copy of the group may be outdated, only the task itself has to match
+    task.group = created_task.group.clone();
+    if task != created_task {
+        error!(
+            "Created task doesn't match the request: {:#?}",
+            created_task
+        );
+        return Err(TarsError::Internal(
+            "the created task doesn't match the request".to_owned(),
+        ));
+    }
+
+    write_task_tags(&created_task.id, &tags, &mut tx).await?;
+    created_task.tags = fetch_task_tags(&created_task.id, &mut tx).await?;
+    write_task_blockers(&created_task.id, &blocked_by, &mut tx).await?;
+    created_task.blocked_by = fetch_task_blockers(&created_task.id, &mut tx).await?;
+    write_task_checklist(&created_task.id, &checklist, &mut tx).await?;
+    created_task.checklist = fetch_task_checklist(&created_task.id, &mut tx).await?;
+
+    JournalEntry {
+        redo: vec![JournalOp::CreateTask(created_task.clone().into())],
+        undo: vec![JournalOp::DeleteTask(created_task.id.clone())],
+    }
+    .record(&mut tx)
+    .await?;
+
+    state
+        .diffs
+        .record(&Diff::Added(DiffInner::Task(created_task.clone())), &mut tx)
+        .await?;
 
-    assert_eq!(task, created_task);
+    tx.commit().await?;
     info!("Created task: {:#?}", created_task);
 
-    let _ = state
-        .diff_tx
-        .send(Diff::Added(DiffInner::Task(created_task.clone())));
+    state.diffs.publish().await;
+
     Ok(Json::from(created_task))
 }
 
@@ -105,7 +177,22 @@ async fn fetch_task(
     State(state): State<DaemonState>,
     Json(task_fetch_opts): Json<TaskFetchOptions>,
 ) -> Result<Json<Vec<Task>>, TarsError> {
-    match task_fetch_opts {
+    let tasks = fetch_tasks(task_fetch_opts, &mut *state.pool.acquire().await?).await?;
+    info!("Fetched tasks: {:#?}", tasks);
+
+    Ok(Json::from(tasks))
+}
+
+/// Fetches the tasks matching the options, with their tags, blockers and checklists attached.
+///
+/// # Errors
+///
+/// This function will return an error if something goes wrong with sqlx.
+pub(crate) async fn fetch_tasks(
+    task_fetch_opts: TaskFetchOptions,
+    conn: &mut SqliteConnection,
+) -> Result<Vec<Task>, TarsError> {
+    let mut tasks = match task_fetch_opts {
         TaskFetchOptions::All => {
             let records = sqlx::query!(
                 r#"
@@ -122,19 +209,23 @@ async fn fetch_task(
                         t.description,
                         t.finished_at,
                         t.created_at,
-                        t.due
+                        t.due,
+                        t.recurrence as "recurrence: Recurrence",
+                        t.estimate as "estimate: Estimate",
+                        t.version,
+                        g.version as group_version
                     FROM Tasks t
                     JOIN Groups g ON t.group_id = g.pub_id
                         
                 "#,
             )
-            .fetch_all(&state.pool)
+            .fetch_all(&mut *conn)
             .await?;
 
             let tasks: Vec<Task> = records
                 .into_iter()
                 .map(|row| {
-                    Task::with_all_fields(
+                    let mut task = Task::with_all_fields(
                         row.task_pub_id,
                         Group::with_all_fields(
                             row.group_pub_id,
@@ -150,34 +241,99 @@ async fn fetch_task(
                         row.finished_at,
                         row.created_at,
                         row.due,
-                    )
+                    );
+                    task.recurrence = row.recurrence;
+                    task.estimate = row.estimate;
+                    task.version = row.version;
+                    task.group.version = row.group_version;
+                    task
                 })
                 .collect();
-            info!("Fetched tasks: {:#?}", &tasks);
 
-            Ok(Json::from(tasks))
+            tasks
         }
         TaskFetchOptions::ByGroup {
             group_id,
             recursive,
         } => {
-            let tasks = if recursive {
+            if recursive {
                 let mut tasks: Vec<Task> = Vec::new();
 
-                recurse_group_fetch(&mut tasks, group_id, &state.pool).await?;
+                recurse_group_fetch(&mut tasks, group_id, conn).await?;
                 tasks
             } else {
-                fetch_group(group_id, &state.pool).await?
-            };
-
-            info!("Fetched tasks: {:#?}", tasks);
+                fetch_group(group_id, conn).await?
+            }
+        }
+        TaskFetchOptions::ByTag { tag_id } => {
+            let records = sqlx::query!(
+                r#"
+                    SELECT
+                        t.pub_id as task_pub_id,
+                        t.name as task_name,
+                        g.name  as group_name,
+                        g.pub_id as group_pub_id,
+                        g.parent_id as "group_parent_id: Id",
+                        g.created_at as group_created_at,
+                        g.color as "group_color: Color",
+                        g.priority as "group_priority: Priority",
+                        t.priority as "priority: Priority",
+                        t.description,
+                        t.finished_at,
+                        t.created_at,
+                        t.due,
+                        t.recurrence as "recurrence: Recurrence",
+                        t.estimate as "estimate: Estimate",
+                        t.version,
+                        g.version as group_version
+                    FROM Tasks t
+                    JOIN Groups g ON t.group_id = g.pub_id
+                    JOIN TaskTags tt ON tt.task_id = t.pub_id
+                    WHERE tt.tag_id = ?
+                "#,
+                *tag_id
+            )
+            .fetch_all(&mut *conn)
+            .await?;
 
-            Ok(Json::from(tasks))
+            records
+                .into_iter()
+                .map(|row| {
+                    let mut task = Task::with_all_fields(
+                        row.task_pub_id,
+                        Group::with_all_fields(
+                            row.group_pub_id,
+                            row.group_name,
+                            row.group_parent_id,
+                            row.group_priority,
+                            row.group_created_at,
+                            row.group_color,
+                        ),
+                        row.task_name,
+                        row.priority,
+                        row.description,
+                        row.finished_at,
+                        row.created_at,
+                        row.due,
+                    );
+                    task.recurrence = row.recurrence;
+                    task.estimate = row.estimate;
+                    task.version = row.version;
+                    task.group.version = row.group_version;
+                    task
+                })
+                .collect()
         }
-    }
+    };
+
+    attach_tags(&mut tasks, conn).await?;
+    attach_blockers(&mut tasks, conn).await?;
+    attach_checklists(&mut tasks, conn).await?;
+
+    Ok(tasks)
 }
 
-async fn fetch_group(group_id: Id, pool: &Pool<Sqlite>) -> Result<Vec<Task>, TarsError> {
+async fn fetch_group(group_id: Id, conn: &mut SqliteConnection) -> Result<Vec<Task>, TarsError> {
     let records = sqlx::query!(
         r#"
                     SELECT
@@ -193,7 +349,11 @@ async fn fetch_group(group_id: Id, pool: &Pool<Sqlite>) -> Result<Vec<Task>, Tar
                         t.description,
                         t.finished_at,
                         t.created_at,
-                        t.due
+                        t.due,
+                        t.recurrence as "recurrence: Recurrence",
+                        t.estimate as "estimate: Estimate",
+                        t.version,
+                        g.version as group_version
                     FROM Tasks t
                     JOIN Groups g ON t.group_id = g.pub_id
                     WHERE g.pub_id = ?
@@ -201,13 +361,13 @@ async fn fetch_group(group_id: Id, pool: &Pool<Sqlite>) -> Result<Vec<Task>, Tar
                 "#,
         group_id
     )
-    .fetch_all(pool)
+    .fetch_all(&mut *conn)
     .await?;
 
     let mut tasks = vec![];
 
     for row in records {
-        let task = Task::with_all_fields(
+        let mut task = Task::with_all_fields(
             row.task_pub_id,
             Group::with_all_fields(
                 row.group_pub_id,
@@ -224,6 +384,10 @@ async fn fetch_group(group_id: Id, pool: &Pool<Sqlite>) -> Result<Vec<Task>, Tar
             row.created_at,
             row.due,
         );
+        task.recurrence = row.recurrence;
+        task.estimate = row.estimate;
+        task.version = row.version;
+        task.group.version = row.group_version;
 
         tasks.push(task)
     }
@@ -235,10 +399,10 @@ async fn fetch_group(group_id: Id, pool: &Pool<Sqlite>) -> Result<Vec<Task>, Tar
 async fn recurse_group_fetch(
     tasks: &mut Vec<Task>,
     group_id: Id,
-    pool: &Pool<Sqlite>,
+    conn: &mut SqliteConnection,
 ) -> Result<(), TarsError> {
     // first we add the tasks pertinent to the passed in group
-    let immediate_tasks = fetch_group(group_id.clone(), pool).await?;
+    let immediate_tasks = fetch_group(group_id.clone(), conn).await?;
     for task in immediate_tasks {
         tasks.push(task);
     }
@@ -247,15 +411,15 @@ async fn recurse_group_fetch(
     let children = sqlx::query_as!(
         Group,
         r#"
-        SELECT pub_id as "id: Id", name as "name: Name", color as "color: Color" , parent_id as "parent_id: Id", created_at,priority as "priority: Priority"
+        SELECT pub_id as "id: Id", name as "name: Name", color as "color: Color" , parent_id as "parent_id: Id", created_at,priority as "priority: Priority", version
         FROM Groups
         WHERE parent_id = ?
         "#,
         group_id
-    ).fetch_all(pool).await?;
+    ).fetch_all(&mut *conn).await?;
 
     for child in children {
-        recurse_group_fetch(tasks, child.id, pool).await?;
+        recurse_group_fetch(tasks, child.id, conn).await?;
     }
 
     Ok(())
@@ -266,6 +430,8 @@ async fn recurse_group_fetch(
 /// TarsError
 ///
 /// This function will return an error if
+/// + The task fails validation, ex. an empty name or a group that doesn't exist.
+/// + The task was updated since the version it carries, the conflict holds the current one.
 /// + Something goes wrong with sqlx.
 /// + Something goes wrong turning what sqlx returns into our wrapper types.
 #[instrument(skip(state))]
@@ -274,6 +440,95 @@ async fn update_task(
     State(state): State<DaemonState>,
     Json(task): Json<Task>,
 ) -> Result<Json<Task>, TarsError> {
+    let mut tx = state.pool.begin().await?;
+    check_task(&task, &mut tx).await?;
+
+    let old_task = fetch_task_by_id(&task.id, &mut tx).await?;
+    let (updated_task, follow_ups) = write_task_update(task, old_task, &mut tx).await?;
+
+    let mut diffs = vec![Diff::Updated(DiffInner::Task(updated_task.clone()))];
+    diffs.extend(follow_ups);
+    for diff in diffs.iter() {
+        state.diffs.record(diff, &mut tx).await?;
+    }
+
+    tx.commit().await?;
+    info!("Updated task: {:#?}", updated_task);
+
+    state.diffs.publish().await;
+
+    Ok(Json::from(updated_task))
+}
+
+/// Takes in a patch of some of the fields of a task and writes only those, leaving the rest as
+/// they are in the database.
+///
+/// # Errors
+/// TarsError
+///
+/// This function will return an error if
+/// + There is no task with the patch's `Id`.
+/// + The patched task fails validation, ex. an empty name.
+/// + The patch carries a version and the task was updated since, the conflict holds the
+///   current one.
+/// + Something goes wrong with sqlx.
+/// + Something goes wrong turning what sqlx returns into our wrapper types.
+#[instrument(skip(state))]
+#[debug_handler]
+async fn patch_task(
+    State(state): State<DaemonState>,
+    Json(patch): Json<TaskPatch>,
+) -> Result<Json<Task>, TarsError> {
+    let mut tx = state.pool.begin().await?;
+    let old_task = fetch_task_by_id(&patch.id, &mut tx).await?;
+
+    if patch.version.is_some_and(|v| v != old_task.version) {
+        return Err(TarsError::Conflict(Box::new(DiffInner::Task(old_task))));
+    }
+
+    let mut task = old_task.clone();
+    patch.apply_to(&mut task);
+    task.version = old_task.version;
+    check_task(&task, &mut tx).await?;
+
+    let (updated_task, follow_ups) = write_task_update(task, old_task.clone(), &mut tx).await?;
+
+    // subscribers already have the rest, only send them what changed
+    let mut diffs = vec![Diff::Patched(Patch::Task(TaskPatch::between(
+        &old_task,
+        &updated_task,
+    )))];
+    diffs.extend(follow_ups);
+    for diff in diffs.iter() {
+        state.diffs.record(diff, &mut tx).await?;
+    }
+
+    tx.commit().await?;
+    info!("Patched task: {:#?}", updated_task);
+
+    state.diffs.publish().await;
+
+    Ok(Json::from(updated_task))
+}
+
+/// Overwrites `old_task` with `task` as long as it's still at the version `task` carries,
+/// queueing up the next instance of a recurring task it finishes, and journals the change.
+/// Returns the updated task and the diffs of anything else it changed.
+///
+/// # Errors
+///
+/// This function will return an error if
+/// + The task was updated since the version it carries, the conflict holds the current one.
+/// + Something goes wrong with sqlx.
+async fn write_task_update(
+    mut task: Task,
+    old_task: Task,
+    conn: &mut SqliteConnection,
+) -> Result<(Task, Vec<Diff>), TarsError> {
+    let tags = std::mem::take(&mut task.tags);
+    let blocked_by = std::mem::take(&mut task.blocked_by);
+    let checklist = std::mem::take(&mut task.checklist);
+
     let row = sqlx::query!(
         r#"
         UPDATE Tasks
@@ -283,8 +538,12 @@ async fn update_task(
             description = ?,
             finished_at = ?,
             due = ?,
-            group_id = ?
-        WHERE pub_id = ?
+            group_id = ?,
+            recurrence = ?,
+            estimate = ?,
+            version = version + 1,
+            updated_at = CURRENT_TIMESTAMP
+        WHERE pub_id = ? AND version = ?
         RETURNING 
             pub_id as task_pub_id,
             name as task_name,
@@ -294,11 +553,15 @@ async fn update_task(
             (SELECT g.color FROM Groups g WHERE g.pub_id = Tasks.group_id) as "group_color: Color",
             (SELECT g.priority FROM Groups g WHERE g.pub_id = Tasks.group_id) as "group_priority: Priority",
             (SELECT g.created_at FROM Groups g WHERE g.pub_id = Tasks.group_id) as group_created_at,
+            (SELECT g.version FROM Groups g WHERE g.pub_id = Tasks.group_id) as "group_version!: i64",
             priority as "priority: Priority",
             description,
             finished_at,
             created_at,
-            due
+            due,
+            recurrence as "recurrence: Recurrence",
+            estimate as "estimate: Estimate",
+            version
         "#,
         *task.name,
         task.priority,
@@ -306,12 +569,20 @@ async fn update_task(
         task.finished_at,
         task.due,
         *task.group.id,
-        *task.id
+        task.recurrence,
+        task.estimate,
+        *task.id,
+        task.version
     )
-    .fetch_one(&state.pool)
+    .fetch_optional(&mut *conn)
     .await?;
 
-    let updated_task = Task::with_all_fields(
+    // someone else got there first, let the client decide what to do with their copy
+    let Some(row) = row else {
+        return Err(TarsError::Conflict(Box::new(DiffInner::Task(old_task))));
+    };
+
+    let mut updated_task = Task::with_all_fields(
         row.task_pub_id,
         Group::with_all_fields(
             row.group_id,
@@ -328,16 +599,50 @@ async fn update_task(
         row.created_at,
         row.due,
     );
+    updated_task.recurrence = row.recurrence;
+    updated_task.estimate = row.estimate;
+    updated_task.version = row.version;
+    updated_task.group.version = row.group_version;
 
-    // if they dont match, we have a problem!
-    assert_eq!(updated_task, task);
+    write_task_tags(&updated_task.id, &tags, conn).await?;
+    updated_task.tags = fetch_task_tags(&updated_task.id, conn).await?;
+    write_task_blockers(&updated_task.id, &blocked_by, conn).await?;
+    updated_task.blocked_by = fetch_task_blockers(&updated_task.id, conn).await?;
+    write_task_checklist(&updated_task.id, &checklist, conn).await?;
+    updated_task.checklist = fetch_task_checklist(&updated_task.id, conn).await?;
 
-    info!("Updated task: {:#?}", updated_task);
+    let mut redo = vec![JournalOp::UpdateTask(updated_task.clone())];
+    let mut undo = vec![JournalOp::UpdateTask(old_task.clone())];
+    let mut diffs = Vec::new();
 
-    let _ = state
-        .diff_tx
-        .send(Diff::Updated(DiffInner::Task(updated_task.clone())));
-    Ok(Json::from(updated_task))
+    // finishing a recurring task queues up its next instance
+    if let (None, Some(finished_at), Some(recurrence)) = (
+        old_task.finished_at,
+        updated_task.finished_at,
+        updated_task.recurrence.as_ref(),
+    ) {
+        let mut next = updated_task.clone();
+        next.id = Id::default();
+        next.finished_at = None;
+        // whatever blocked this instance has no say over the next one
+        next.blocked_by = Vec::new();
+        // the next instance starts over on its checklist
+        next.checklist.iter_mut().for_each(|i| i.done = false);
+        next.version = 0;
+        next.created_at = Local::now().naive_local();
+        next.due = Some(recurrence.next_due(updated_task.due, finished_at));
+
+        let create = JournalOp::CreateTask(next.clone().into());
+        diffs.extend(create.apply(conn).await?);
+
+        redo.push(create);
+        undo.insert(0, JournalOp::DeleteTask(next.id.clone()));
+        info!("Created next instance of recurring task: {:#?}", next);
+    }
+
+    JournalEntry { redo, undo }.record(conn).await?;
+
+    Ok((updated_task, diffs))
 }
 
 /// Takes in a task `ID`, deletes it, and returns the deleted task.
@@ -355,6 +660,82 @@ async fn delete_task(
     Json(deletion_id): Json<Id>,
 ) -> Result<Json<Task>, TarsError> {
     let mut tx = state.pool.begin().await?;
+    let deleted_task = fetch_task_by_id(&deletion_id, &mut tx).await?;
+
+    // the tasks waiting on this one lose an edge, snapshot them so undoing restores it
+    let dependent_ids = fetch_dependent_ids(&deletion_id, &mut tx).await?;
+    // time tracked on the task goes along with it, so it's put back too
+    let mut undo = vec![JournalOp::CreateTask(
+        SavedTask::load(&deletion_id, &mut tx).await?,
+    )];
+    for id in dependent_ids.iter() {
+        undo.push(JournalOp::UpdateTask(fetch_task_by_id(id, &mut tx).await?));
+    }
+
+    sqlx::query!("DELETE FROM Tasks WHERE pub_id = ?", *deletion_id)
+        .execute(&mut *tx)
+        .await?;
+
+    JournalEntry {
+        redo: vec![JournalOp::DeleteTask(deleted_task.id.clone())],
+        undo,
+    }
+    .record(&mut tx)
+    .await?;
+
+    let mut unblocked = Vec::new();
+    for id in dependent_ids.iter() {
+        unblocked.push(fetch_task_by_id(id, &mut tx).await?);
+    }
+
+    state
+        .diffs
+        .record(&Diff::Deleted(deleted_task.id.clone()), &mut tx)
+        .await?;
+    for task in unblocked {
+        state
+            .diffs
+            .record(&Diff::Updated(DiffInner::Task(task)), &mut tx)
+            .await?;
+    }
+
+    tx.commit().await?;
+    info!("Deleted task: {:#?}", deleted_task);
+
+    state.diffs.publish().await;
+
+    Ok(Json::from(deleted_task))
+}
+
+/// Bumps the version of the `Task` with this `Id`, for changes that don't go through
+/// `update_task`, so copies from before them are refused.
+///
+/// # Errors
+///
+/// This function will return an error if something goes wrong with sqlx.
+pub(crate) async fn bump_task_version(
+    id: &Id,
+    conn: &mut SqliteConnection,
+) -> Result<(), TarsError> {
+    sqlx::query!(
+        "UPDATE Tasks SET version = version + 1, updated_at = CURRENT_TIMESTAMP WHERE pub_id = ?",
+        **id
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(())
+}
+
+/// Fetches a single `Task` by its `Id`.
+///
+/// # Errors
+///
+/// This function will return an error if the task doesn't exist or something goes wrong with sqlx.
+pub(crate) async fn fetch_task_by_id(
+    id: &Id,
+    conn: &mut SqliteConnection,
+) -> Result<Task, TarsError> {
     let row = sqlx::query!(
         r#"
             SELECT
@@ -370,18 +751,22 @@ async fn delete_task(
                 t.description,
                 t.finished_at,
                 t.created_at,
-                t.due
+                t.due,
+                t.recurrence as "recurrence: Recurrence",
+                t.estimate as "estimate: Estimate",
+                t.version,
+                g.version as group_version
                 FROM Tasks t
                 JOIN Groups g ON t.group_id = g.pub_id
                 WHERE t.pub_id = ?
 
         "#,
-        *deletion_id
+        **id
     )
-    .fetch_one(&mut *tx)
+    .fetch_one(&mut *conn)
     .await?;
 
-    let deleted_task = Task::with_all_fields(
+    let mut task = Task::with_all_fields(
         row.task_id,
         Group::with_all_fields(
             row.group_id,
@@ -398,73 +783,332 @@ async fn delete_task(
         row.created_at,
         row.due,
     );
+    task.recurrence = row.recurrence;
+    task.estimate = row.estimate;
+    task.version = row.version;
+    task.group.version = row.group_version;
 
-    sqlx::query!("DELETE FROM Tasks WHERE pub_id = ?", *deletion_id)
-        .execute(&mut *tx)
-        .await?;
+    task.tags = fetch_task_tags(id, conn).await?;
+    task.blocked_by = fetch_task_blockers(id, conn).await?;
+    task.checklist = fetch_task_checklist(id, conn).await?;
+    Ok(task)
+}
 
-    assert_eq!(deletion_id, deleted_task.id);
+/// Returns the p_score for this task, according to the active scoring strategy.
+///
+/// # Errors
+///
+/// This function will return an error if
+/// + There is no task with this `Id`.
+/// + Something goes wrong with the sql query.
+#[instrument(skip(state))]
+#[debug_handler]
+pub async fn calculate_task_score(
+    State(state): State<DaemonState>,
+    Json(id): Json<Id>,
+) -> Result<Json<f64>, TarsError> {
+    let (_, input) = fetch_score_inputs(&[id], &state.pool)
+        .await?
+        .pop()
+        .ok_or(sqlx::Error::RowNotFound)?;
 
-    tx.commit().await?;
-    info!("Deleted task: {:#?}", deleted_task);
+    let scorer = state.scorer.read().expect("scorer lock poisoned");
 
-    let _ = state.diff_tx.send(Diff::Deleted(deleted_task.id.clone()));
-    Ok(Json::from(deleted_task))
+    Ok(Json::from(scorer.score(&input, Local::now().naive_local())))
 }
 
-/// Returns the p_score for this task.
+/// Takes in the `Id`s of many tasks and returns all of their p_scores, computed in a single query
+/// rather than one walk up the group tree per task.
+///
+/// Ids that don't belong to a task are left out of the result.
 ///
 /// # Errors
 ///
 /// This function will return an error if something goes wrong with the sql query.
+#[instrument(skip(state, ids))]
+#[debug_handler]
+pub async fn calculate_task_scores(
+    State(state): State<DaemonState>,
+    Json(ids): Json<Vec<Id>>,
+) -> Result<Json<HashMap<Id, f64>>, TarsError> {
+    let inputs = fetch_score_inputs(&ids, &state.pool).await?;
+
+    let now = Local::now().naive_local();
+    let scorer = state.scorer.read().expect("scorer lock poisoned");
+
+    let scores: HashMap<Id, f64> = inputs
+        .into_iter()
+        .map(|(id, input)| {
+            let score = scorer.score(&input, now);
+            (id, score)
+        })
+        .collect();
+
+    Ok(Json::from(scores))
+}
+
+/// Returns the factors behind the p_score of this task, under the active scoring strategy:
+/// its own priority, the multiplier of every group it's nested in, and the terms the strategy
+/// combined into the final score.
+///
+/// # Errors
+///
+/// This function will return an error if
+/// + There is no task with this `Id`.
+/// + Something goes wrong with the sql query.
 #[instrument(skip(state))]
 #[debug_handler]
-pub async fn calculate_task_score(
+pub async fn explain_task_score(
     State(state): State<DaemonState>,
     Json(id): Json<Id>,
-) -> Result<Json<f64>, TarsError> {
-    let task = sqlx::query!(
+) -> Result<Json<ScoreExplanation>, TarsError> {
+    let (_, input) = fetch_score_inputs(std::slice::from_ref(&id), &state.pool)
+        .await?
+        .pop()
+        .ok_or(sqlx::Error::RowNotFound)?;
+
+    // the task's group first, then up towards the root
+    let groups = sqlx::query!(
         r#"
+        WITH RECURSIVE ancestors(pub_id, name, priority, parent_id, depth) AS (
+            SELECT g.pub_id, g.name, g.priority, g.parent_id, 0
+            FROM Groups g
+            JOIN Tasks t ON t.group_id = g.pub_id
+            WHERE t.pub_id = ?
+            UNION ALL
+            SELECT g.pub_id, g.name, g.priority, g.parent_id, a.depth + 1
+            FROM Groups g
+            JOIN ancestors a ON g.pub_id = a.parent_id
+        )
         SELECT
-        group_id as "group_id: Id",
-        priority as "priority: Priority",
-        due 
-        FROM Tasks
-        WHERE pub_id = ?
-    "#,
+            pub_id as "id!: String",
+            name as "name!: String",
+            priority as "priority!: Priority"
+        FROM ancestors
+        ORDER BY depth
+        "#,
         *id
     )
-    .fetch_one(&state.pool)
-    .await?;
+    .fetch_all(&state.pool)
+    .await?
+    .into_iter()
+    .map(|row| GroupMultiplier {
+        id: Id::from(row.id),
+        name: Name::from(row.name),
+        priority: row.priority,
+        multiplier: 1.0 / row.priority as i32 as f64,
+    })
+    .collect();
+
+    let now = Local::now().naive_local();
+    let scorer = state.scorer.read().expect("scorer lock poisoned");
 
-    if task.priority == Priority::Asap {
-        return Ok(Json::from(1.0));
+    Ok(Json(ScoreExplanation {
+        strategy: scorer.kind(),
+        priority: input.priority,
+        priority_multiplier: 1.0 / input.priority as i32 as f64,
+        groups,
+        days_left: input.days_left(now),
+        blocked_dependents: input.blocked_dependents,
+        terms: scorer.explain(&input, now),
+        score: scorer.score(&input, now),
+    }))
+}
+
+/// Greedily fills the available time with the highest scoring open tasks in scope, skipping the
+/// ones that don't fit and moving on to smaller ones.
+///
+/// Finished tasks, tasks still blocked by an unfinished task and tasks without an estimate
+/// are never picked.
+///
+/// # Errors
+///
+/// This function will return an error if something goes wrong with the sql queries.
+#[instrument(skip(state))]
+#[debug_handler]
+pub async fn plan_tasks(
+    State(state): State<DaemonState>,
+    Json(request): Json<PlanRequest>,
+) -> Result<Json<Plan>, TarsError> {
+    let open = fetch_open_tasks(request.scope, Local::now().naive_local(), &state).await?;
+
+    let mut remaining = request.available.minutes();
+    let mut groups: HashMap<Id, GroupWorkload> = HashMap::new();
+    let mut planned = Vec::new();
+    for candidate in open.estimated {
+        let minutes = candidate.task.estimate.map_or(0, |e| e.minutes());
+        let group = &candidate.task.group;
+        let workload = groups
+            .entry(group.id.clone())
+            .or_insert_with(|| GroupWorkload {
+                group_id: group.id.clone(),
+                name: group.name.clone(),
+                planned: Estimate::default(),
+                open: Estimate::default(),
+            });
+        workload.open = Estimate::from_minutes(workload.open.minutes() + minutes);
+
+        if minutes <= remaining {
+            remaining -= minutes;
+            workload.planned = Estimate::from_minutes(workload.planned.minutes() + minutes);
+            planned.push(candidate);
+        }
     }
 
-    let task_p_score = 1.0 / task.priority as i32 as f64;
+    let mut groups: Vec<GroupWorkload> = groups.into_values().collect();
+    groups.sort_by(|a, b| b.open.cmp(&a.open).then_with(|| a.name.cmp(&b.name)));
 
-    let total_p_score = calculate_group_p_score(&task.group_id, &state.pool).await? * task_p_score;
+    Ok(Json(Plan {
+        available: request.available,
+        planned: Estimate::from_minutes(request.available.minutes() - remaining),
+        tasks: planned,
+        groups,
+        unestimated: open.unestimated,
+    }))
+}
 
-    let final_p_score = if task.due.is_none() {
-        total_p_score
-    } else {
-        let today = Local::now();
+/// The open tasks in a scope, split up by whether they can be planned.
+pub(crate) struct OpenTasks {
+    /// Unblocked tasks with an estimate, highest score first.
+    pub estimated: Vec<PlannedTask>,
+    /// Unblocked tasks without an estimate.
+    pub unestimated: usize,
+    /// Tasks still waiting on an unfinished task.
+    pub blocked: usize,
+}
 
-        let due = task
-            .due
-            .unwrap()
-            .and_local_timezone(*today.offset())
-            .unwrap();
+/// Fetches the unfinished tasks in `scope` (recursively), or every unfinished task, scoring
+/// the ones that can be planned as of `now`.
+pub(crate) async fn fetch_open_tasks(
+    scope: Option<Id>,
+    now: NaiveDateTime,
+    state: &DaemonState,
+) -> Result<OpenTasks, TarsError> {
+    let opts = match scope {
+        Some(group_id) => TaskFetchOptions::ByGroup {
+            group_id,
+            recursive: true,
+        },
+        None => TaskFetchOptions::All,
+    };
+    let tasks = fetch_tasks(opts, &mut *state.pool.acquire().await?).await?;
+
+    let blocked_ids: HashSet<Id> = sqlx::query!(
+        r#"
+        SELECT DISTINCT d.task_id as "id!: String"
+        FROM TaskDependencies d
+        JOIN Tasks b ON b.pub_id = d.blocked_by
+        WHERE b.finished_at IS NULL
+        "#
+    )
+    .fetch_all(&state.pool)
+    .await?
+    .into_iter()
+    .map(|row| Id::from(row.id))
+    .collect();
 
-        // (e/3)^(delta (in days)) + prio
-        let today = today.fixed_offset();
-        let difference = (due - today).num_minutes() as f64 / 1440.0;
+    let (blocked, open): (Vec<Task>, Vec<Task>) = tasks
+        .into_iter()
+        .filter(|task| task.finished_at.is_none())
+        .partition(|task| blocked_ids.contains(&task.id));
+    let (estimated, unestimated): (Vec<Task>, Vec<Task>) =
+        open.into_iter().partition(|task| task.estimate.is_some());
 
-        let e = std::f64::consts::E / 3.0;
+    let ids: Vec<Id> = estimated.iter().map(|task| task.id.clone()).collect();
+    let scores: HashMap<Id, f64> = {
+        let inputs = fetch_score_inputs(&ids, &state.pool).await?;
+        let scorer = state.scorer.read().expect("scorer lock poisoned");
 
-        let y = e.powf(difference);
-        y + total_p_score
+        inputs
+            .into_iter()
+            .map(|(id, input)| {
+                let score = scorer.score(&input, now);
+                (id, score)
+            })
+            .collect()
     };
 
-    Ok(Json::from(final_p_score))
+    let mut estimated: Vec<PlannedTask> = estimated
+        .into_iter()
+        .map(|task| PlannedTask {
+            score: scores.get(&task.id).copied().unwrap_or_default(),
+            task,
+        })
+        .collect();
+    estimated.sort_by(|a, b| b.score.total_cmp(&a.score));
+
+    Ok(OpenTasks {
+        estimated,
+        unestimated: unestimated.len(),
+        blocked: blocked.len(),
+    })
+}
+
+/// Gathers everything the scoring strategies look at for each of the tasks, in a single query.
+///
+/// Ids that don't belong to a task are left out of the result.
+pub(crate) async fn fetch_score_inputs(
+    ids: &[Id],
+    pool: &Pool<Sqlite>,
+) -> Result<Vec<(Id, ScoreInput)>, TarsError> {
+    // handed to sqlite as a json array, so any number of ids fit in one query
+    let ids = serde_json::to_string(ids).expect("a list of ids always serializes");
+
+    let rows = sqlx::query!(
+        r#"
+        WITH RECURSIVE group_scores(pub_id, score) AS (
+            SELECT pub_id, 1.0 / priority FROM Groups WHERE parent_id IS NULL
+            UNION ALL
+            SELECT g.pub_id, gs.score / g.priority
+            FROM Groups g
+            JOIN group_scores gs ON g.parent_id = gs.pub_id
+        ),
+        reachable(root, id) AS (
+            SELECT blocked_by, task_id FROM TaskDependencies
+            UNION
+            SELECT r.root, d.task_id FROM TaskDependencies d JOIN reachable r ON d.blocked_by = r.id
+        ),
+        blocked_counts(task_id, count) AS (
+            SELECT r.root, COUNT(*)
+            FROM reachable r
+            JOIN Tasks t ON t.pub_id = r.id
+            WHERE t.finished_at IS NULL
+            GROUP BY r.root
+        )
+        SELECT
+            t.pub_id as "id!: String",
+            t.priority as "priority: Priority",
+            t.due,
+            t.estimate as "estimate: Estimate",
+            gs.score as "group_score!: f64",
+            COALESCE(bc.count, 0) as "blocked_dependents!: i64",
+            (
+                SELECT COUNT(*) FROM TaskChecklistItems c
+                WHERE c.task_id = t.pub_id AND NOT c.done
+            ) as "open_checklist_items!: i64"
+        FROM Tasks t
+        JOIN group_scores gs ON gs.pub_id = t.group_id
+        LEFT JOIN blocked_counts bc ON bc.task_id = t.pub_id
+        WHERE t.pub_id IN (SELECT value FROM json_each(?))
+        "#,
+        ids
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| {
+            let input = ScoreInput {
+                priority: row.priority,
+                group_p_score: row.group_score,
+                due: row.due,
+                blocked_dependents: row.blocked_dependents,
+                estimate: row.estimate,
+                open_checklist_items: row.open_checklist_items,
+            };
+
+            (Id::from(row.id), input)
+        })
+        .collect())
 }
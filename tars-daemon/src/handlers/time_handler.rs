@@ -0,0 +1,360 @@
+use axum::{Json, Router, debug_handler, extract::State, routing::post};
+use common::{
+    TarsError,
+    types::{
+        GroupTime, Id, Name, TaskTime, TimeEntry, TimeRange, TimerStart, TimerStop, Timesheet,
+    },
+};
+use sqlx::{SqliteConnection, types::chrono::Local};
+use tracing::{info, instrument};
+
+use crate::DaemonState;
+
+/// Returns a router with all the time tracking endpoints
+pub fn time_router() -> Router<DaemonState> {
+    Router::new()
+        .route("/start", post(start_timer))
+        .route("/stop", post(stop_timer))
+        .route("/running", post(fetch_running_timer))
+        .route("/entries", post(fetch_time_entries))
+        .route("/timesheet", post(fetch_timesheet))
+}
+
+/// Returns every `TimeEntry` of a single task, oldest first.
+pub(crate) async fn fetch_task_time_entries(
+    task_id: &Id,
+    conn: &mut SqliteConnection,
+) -> Result<Vec<TimeEntry>, TarsError> {
+    let entries = sqlx::query_as!(
+        TimeEntry,
+        r#"
+            SELECT
+                pub_id as "id: Id",
+                task_id as "task_id: Id",
+                started_at,
+                ended_at,
+                note
+            FROM TimeEntries
+            WHERE task_id = ?
+            ORDER BY started_at
+        "#,
+        **task_id
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    Ok(entries)
+}
+
+/// Puts back time entries that were taken out along with their task.
+///
+/// A timer that was running when they went away is stopped now if another one started
+/// since, only one runs at a time.
+pub(crate) async fn restore_time_entries(
+    entries: &[TimeEntry],
+    conn: &mut SqliteConnection,
+) -> Result<(), TarsError> {
+    for entry in entries {
+        let mut ended_at = entry.ended_at;
+        if ended_at.is_none() {
+            let running =
+                sqlx::query_scalar!("SELECT pub_id FROM TimeEntries WHERE ended_at IS NULL")
+                    .fetch_optional(&mut *conn)
+                    .await?;
+            if running.is_some() {
+                ended_at = Some(Local::now().naive_local());
+            }
+        }
+
+        sqlx::query!(
+            "INSERT INTO TimeEntries (pub_id, task_id, started_at, ended_at, note) VALUES (?, ?, ?, ?, ?)",
+            *entry.id,
+            *entry.task_id,
+            entry.started_at,
+            ended_at,
+            entry.note
+        )
+        .execute(&mut *conn)
+        .await?;
+    }
+
+    Ok(())
+}
+
+/// Takes in a `TimerStart` and starts the timer on that task, returning the new `TimeEntry`.
+///
+/// Only one timer runs at a time, whichever was running before is stopped first.
+///
+/// # Errors
+/// TarsError
+///
+/// This function will return an error if
+/// + There is no task with this `Id`.
+/// + Something goes wrong with sqlx.
+#[instrument(skip(state))]
+#[debug_handler]
+async fn start_timer(
+    State(state): State<DaemonState>,
+    Json(start): Json<TimerStart>,
+) -> Result<Json<TimeEntry>, TarsError> {
+    let now = Local::now().naive_local();
+    let mut tx = state.pool.begin().await?;
+
+    sqlx::query_scalar!("SELECT pub_id FROM Tasks WHERE pub_id = ?", *start.task_id)
+        .fetch_one(&mut *tx)
+        .await?;
+
+    sqlx::query!(
+        "UPDATE TimeEntries SET ended_at = ? WHERE ended_at IS NULL",
+        now
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    let id = Id::default();
+    let note = start.note.unwrap_or_default();
+    let entry = sqlx::query_as!(
+        TimeEntry,
+        r#"
+            INSERT INTO TimeEntries (pub_id, task_id, started_at, note)
+            VALUES (?, ?, ?, ?)
+            RETURNING
+                pub_id as "id: Id",
+                task_id as "task_id: Id",
+                started_at,
+                ended_at,
+                note
+        "#,
+        *id,
+        *start.task_id,
+        now,
+        note
+    )
+    .fetch_one(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+    info!("Started timer on {}", *entry.task_id);
+
+    Ok(Json(entry))
+}
+
+/// Takes in a `TimerStop` and stops the running timer, returning it if there was one.
+///
+/// # Errors
+/// TarsError
+///
+/// This function will return an error if
+/// + Something goes wrong with sqlx.
+#[instrument(skip(state))]
+#[debug_handler]
+async fn stop_timer(
+    State(state): State<DaemonState>,
+    Json(stop): Json<TimerStop>,
+) -> Result<Json<Option<TimeEntry>>, TarsError> {
+    let now = Local::now().naive_local();
+
+    let entry = sqlx::query_as!(
+        TimeEntry,
+        r#"
+            UPDATE TimeEntries
+            SET ended_at = ?, note = COALESCE(?, note)
+            WHERE ended_at IS NULL
+            RETURNING
+                pub_id as "id: Id",
+                task_id as "task_id: Id",
+                started_at,
+                ended_at,
+                note
+        "#,
+        now,
+        stop.note
+    )
+    .fetch_optional(&state.pool)
+    .await?;
+
+    if let Some(ref entry) = entry {
+        info!("Stopped timer on {}", *entry.task_id);
+    }
+
+    Ok(Json(entry))
+}
+
+/// Returns the running timer, if there is one.
+///
+/// # Errors
+/// TarsError
+///
+/// This function will return an error if
+/// + Something goes wrong with sqlx.
+#[instrument(skip(state))]
+#[debug_handler]
+async fn fetch_running_timer(
+    State(state): State<DaemonState>,
+) -> Result<Json<Option<TimeEntry>>, TarsError> {
+    let entry = sqlx::query_as!(
+        TimeEntry,
+        r#"
+            SELECT
+                pub_id as "id: Id",
+                task_id as "task_id: Id",
+                started_at,
+                ended_at,
+                note
+            FROM TimeEntries
+            WHERE ended_at IS NULL
+        "#
+    )
+    .fetch_optional(&state.pool)
+    .await?;
+
+    Ok(Json(entry))
+}
+
+/// Takes in a `TimeRange` and returns every `TimeEntry` overlapping it, oldest first.
+///
+/// # Errors
+/// TarsError
+///
+/// This function will return an error if
+/// + Something goes wrong with sqlx.
+#[instrument(skip(state))]
+#[debug_handler]
+async fn fetch_time_entries(
+    State(state): State<DaemonState>,
+    Json(range): Json<TimeRange>,
+) -> Result<Json<Vec<TimeEntry>>, TarsError> {
+    let now = Local::now().naive_local();
+
+    let entries = sqlx::query_as!(
+        TimeEntry,
+        r#"
+            SELECT
+                pub_id as "id: Id",
+                task_id as "task_id: Id",
+                started_at,
+                ended_at,
+                note
+            FROM TimeEntries
+            WHERE started_at < ? AND COALESCE(ended_at, ?) > ?
+            ORDER BY started_at
+        "#,
+        range.to,
+        now,
+        range.from
+    )
+    .fetch_all(&state.pool)
+    .await?;
+
+    Ok(Json(entries))
+}
+
+/// Takes in a `TimeRange` and returns how much time was spent on each task and group within it.
+///
+/// Entries only count for the part that overlaps the range, the running timer counts up to now.
+/// Group totals roll up the hierarchy, a group's total includes every group below it.
+///
+/// # Errors
+/// TarsError
+///
+/// This function will return an error if
+/// + Something goes wrong with sqlx.
+#[instrument(skip(state))]
+#[debug_handler]
+async fn fetch_timesheet(
+    State(state): State<DaemonState>,
+    Json(range): Json<TimeRange>,
+) -> Result<Json<Timesheet>, TarsError> {
+    let now = Local::now().naive_local();
+    let mut conn = state.pool.acquire().await?;
+
+    // timestamps are all stored in the same format, so comparing them as text is fine
+    let tasks = sqlx::query!(
+        r#"
+        SELECT
+            t.pub_id as "task_id!: String",
+            t.name as "name!: String",
+            t.group_id as "group_id!: String",
+            SUM(
+                unixepoch(MIN(COALESCE(e.ended_at, ?), ?)) - unixepoch(MAX(e.started_at, ?))
+            ) as "seconds!: i64"
+        FROM TimeEntries e
+        JOIN Tasks t ON t.pub_id = e.task_id
+        WHERE e.started_at < ? AND COALESCE(e.ended_at, ?) > ?
+        GROUP BY t.pub_id
+        ORDER BY 4 DESC
+        "#,
+        now,
+        range.to,
+        range.from,
+        range.to,
+        now,
+        range.from
+    )
+    .fetch_all(&mut *conn)
+    .await?
+    .into_iter()
+    .map(|row| TaskTime {
+        task_id: Id::from(row.task_id),
+        name: Name::from(row.name),
+        group_id: Id::from(row.group_id),
+        seconds: row.seconds,
+    })
+    .collect();
+
+    let groups = sqlx::query!(
+        r#"
+        WITH RECURSIVE ancestors(group_id, ancestor_id) AS (
+            SELECT pub_id, pub_id FROM Groups
+            UNION ALL
+            SELECT a.group_id, g.parent_id
+            FROM ancestors a
+            JOIN Groups g ON g.pub_id = a.ancestor_id
+            WHERE g.parent_id IS NOT NULL
+        ),
+        entry_seconds(group_id, seconds) AS (
+            SELECT
+                t.group_id,
+                unixepoch(MIN(COALESCE(e.ended_at, ?), ?)) - unixepoch(MAX(e.started_at, ?))
+            FROM TimeEntries e
+            JOIN Tasks t ON t.pub_id = e.task_id
+            WHERE e.started_at < ? AND COALESCE(e.ended_at, ?) > ?
+        )
+        SELECT
+            g.pub_id as "group_id!: String",
+            g.name as "name!: String",
+            g.parent_id as "parent_id: String",
+            COALESCE(SUM(CASE WHEN es.group_id = g.pub_id THEN es.seconds END), 0)
+                as "own_seconds!: i64",
+            SUM(es.seconds) as "total_seconds!: i64"
+        FROM Groups g
+        JOIN ancestors a ON a.ancestor_id = g.pub_id
+        JOIN entry_seconds es ON es.group_id = a.group_id
+        GROUP BY g.pub_id
+        ORDER BY 5 DESC
+        "#,
+        now,
+        range.to,
+        range.from,
+        range.to,
+        now,
+        range.from
+    )
+    .fetch_all(&mut *conn)
+    .await?
+    .into_iter()
+    .map(|row| GroupTime {
+        group_id: Id::from(row.group_id),
+        name: Name::from(row.name),
+        parent_id: row.parent_id.map(Id::from),
+        own_seconds: row.own_seconds,
+        total_seconds: row.total_seconds,
+    })
+    .collect();
+
+    Ok(Json(Timesheet {
+        range,
+        tasks,
+        groups,
+    }))
+}
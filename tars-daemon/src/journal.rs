@@ -0,0 +1,361 @@
+use common::{
+    Diff, DiffInner, ParseError, TarsError,
+    types::{Group, Id, Tag, Task, TimeEntry},
+};
+use serde::{Deserialize, Serialize};
+use sqlx::SqliteConnection;
+use tracing::error;
+
+use crate::{
+    handlers::{
+        fetch_dependent_ids, fetch_group_by_id, fetch_group_outside_dependent_ids,
+        fetch_tagged_task_ids, fetch_task_by_id, fetch_task_time_entries, restore_time_entries,
+        write_task_blockers, write_task_checklist, write_task_tags,
+    },
+    validation::check_group_deletable,
+};
+
+/// How many entries are kept in the journal, older ones get dropped.
+const JOURNAL_LIMIT: i64 = 1000;
+
+/// A single operation that can be replayed against the database.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum JournalOp {
+    CreateTask(SavedTask),
+    UpdateTask(Task),
+    DeleteTask(Id),
+    CreateGroup(Group),
+    UpdateGroup(Group),
+    DeleteGroup(Id),
+    CreateTag(Tag),
+    UpdateTag(Tag),
+    DeleteTag(Id),
+}
+
+/// A task as `JournalOp::CreateTask` puts it back, along with the time tracked on it which
+/// goes away whenever the task does.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SavedTask {
+    #[serde(flatten)]
+    pub task: Task,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub time_entries: Vec<TimeEntry>,
+}
+
+impl SavedTask {
+    /// Loads the task with this `Id` along with its time entries, to put back after
+    /// deleting it.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the task doesn't exist or something goes wrong with sqlx.
+    pub async fn load(id: &Id, conn: &mut SqliteConnection) -> Result<Self, TarsError> {
+        Ok(Self {
+            task: fetch_task_by_id(id, conn).await?,
+            time_entries: fetch_task_time_entries(id, conn).await?,
+        })
+    }
+}
+
+/// A brand new task, with no time tracked on it yet.
+impl From<Task> for SavedTask {
+    fn from(task: Task) -> Self {
+        Self {
+            task,
+            time_entries: Vec::new(),
+        }
+    }
+}
+
+/// A mutation made through the daemon, described as the operations that apply it
+/// and the operations that revert it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JournalEntry {
+    pub redo: Vec<JournalOp>,
+    pub undo: Vec<JournalOp>,
+}
+
+impl JournalEntry {
+    /// Writes this entry to the journal, should be called within the same transaction
+    /// as the mutation itself.
+    ///
+    /// Recording a new entry discards everything that has been undone, as it can no longer be redone.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if something goes wrong with sqlx.
+    pub async fn record(&self, conn: &mut SqliteConnection) -> Result<(), TarsError> {
+        let redo = encode_ops(&self.redo)?;
+        let undo = encode_ops(&self.undo)?;
+
+        sqlx::query!("DELETE FROM Journal WHERE undone = TRUE")
+            .execute(&mut *conn)
+            .await?;
+
+        sqlx::query!("INSERT INTO Journal (redo, undo) VALUES (?, ?)", redo, undo)
+            .execute(&mut *conn)
+            .await?;
+
+        sqlx::query!(
+            r#"
+            DELETE FROM Journal
+            WHERE id NOT IN (SELECT id FROM Journal ORDER BY id DESC LIMIT ?)
+            "#,
+            JOURNAL_LIMIT
+        )
+        .execute(&mut *conn)
+        .await?;
+
+        Ok(())
+    }
+}
+
+impl JournalOp {
+    /// Applies this operation, returning the `Diff`s describing the change.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if something goes wrong with sqlx,
+    /// ex. the row this operation targets doesn't exist anymore.
+    pub async fn apply(&self, conn: &mut SqliteConnection) -> Result<Vec<Diff>, TarsError> {
+        let diffs = match self {
+            JournalOp::CreateTask(SavedTask { task, time_entries }) => {
+                sqlx::query!(
+                    r#"
+                    INSERT INTO Tasks (pub_id, group_id, name, priority, description, finished_at, due, created_at, recurrence, estimate, version)
+                    VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+                    "#,
+                    *task.id,
+                    *task.group.id,
+                    *task.name,
+                    task.priority,
+                    task.description,
+                    task.finished_at,
+                    task.due,
+                    task.created_at,
+                    task.recurrence,
+                    task.estimate,
+                    task.version
+                )
+                .execute(&mut *conn)
+                .await?;
+                write_task_tags(&task.id, &task.tags, conn).await?;
+                write_task_blockers(&task.id, &task.blocked_by, conn).await?;
+                write_task_checklist(&task.id, &task.checklist, conn).await?;
+                restore_time_entries(time_entries, conn).await?;
+
+                vec![Diff::Added(DiffInner::Task(
+                    fetch_task_by_id(&task.id, conn).await?,
+                ))]
+            }
+            JournalOp::UpdateTask(task) => {
+                let res = sqlx::query!(
+                    r#"
+                    UPDATE Tasks
+                    SET
+                        group_id = ?,
+                        name = ?,
+                        priority = ?,
+                        description = ?,
+                        finished_at = ?,
+                        due = ?,
+                        recurrence = ?,
+                        estimate = ?,
+                        version = version + 1,
+                        updated_at = CURRENT_TIMESTAMP
+                    WHERE pub_id = ?
+                    "#,
+                    *task.group.id,
+                    *task.name,
+                    task.priority,
+                    task.description,
+                    task.finished_at,
+                    task.due,
+                    task.recurrence,
+                    task.estimate,
+                    *task.id
+                )
+                .execute(&mut *conn)
+                .await?;
+                ensure_affected(res.rows_affected())?;
+                write_task_tags(&task.id, &task.tags, conn).await?;
+                write_task_blockers(&task.id, &task.blocked_by, conn).await?;
+                write_task_checklist(&task.id, &task.checklist, conn).await?;
+
+                vec![Diff::Updated(DiffInner::Task(
+                    fetch_task_by_id(&task.id, conn).await?,
+                ))]
+            }
+            JournalOp::DeleteTask(id) => {
+                let dependents = fetch_dependent_ids(id, conn).await?;
+
+                let res = sqlx::query!("DELETE FROM Tasks WHERE pub_id = ?", **id)
+                    .execute(&mut *conn)
+                    .await?;
+                ensure_affected(res.rows_affected())?;
+
+                let mut diffs = vec![Diff::Deleted(id.clone())];
+                for task_id in dependents {
+                    diffs.push(Diff::Updated(DiffInner::Task(
+                        fetch_task_by_id(&task_id, conn).await?,
+                    )));
+                }
+                diffs
+            }
+            JournalOp::CreateGroup(group) => {
+                sqlx::query!(
+                    r#"
+                    INSERT INTO Groups (pub_id, name, parent_id, color, created_at, priority, version)
+                    VALUES (?, ?, ?, ?, ?, ?, ?)
+                    "#,
+                    *group.id,
+                    *group.name,
+                    group.parent_id,
+                    group.color,
+                    group.created_at,
+                    group.priority,
+                    group.version
+                )
+                .execute(&mut *conn)
+                .await?;
+
+                vec![Diff::Added(DiffInner::Group(
+                    fetch_group_by_id(&group.id, conn).await?,
+                ))]
+            }
+            JournalOp::UpdateGroup(group) => {
+                let res = sqlx::query!(
+                    r#"
+                    UPDATE Groups
+                    SET
+                        name = ?,
+                        parent_id = ?,
+                        color = ?,
+                        priority = ?,
+                        version = version + 1,
+                        updated_at = CURRENT_TIMESTAMP
+                    WHERE pub_id = ?
+                    "#,
+                    *group.name,
+                    group.parent_id,
+                    group.color,
+                    group.priority,
+                    *group.id
+                )
+                .execute(&mut *conn)
+                .await?;
+                ensure_affected(res.rows_affected())?;
+
+                vec![Diff::Updated(DiffInner::Group(
+                    fetch_group_by_id(&group.id, conn).await?,
+                ))]
+            }
+            JournalOp::DeleteGroup(id) => {
+                // a token handed out since would be revoked for good
+                check_group_deletable(id, conn).await?;
+                let outside_dependents = fetch_group_outside_dependent_ids(id, conn).await?;
+
+                let res = sqlx::query!("DELETE FROM Groups WHERE pub_id = ?", **id)
+                    .execute(&mut *conn)
+                    .await?;
+                ensure_affected(res.rows_affected())?;
+
+                let mut diffs = vec![Diff::Deleted(id.clone())];
+                for task_id in outside_dependents {
+                    diffs.push(Diff::Updated(DiffInner::Task(
+                        fetch_task_by_id(&task_id, conn).await?,
+                    )));
+                }
+                diffs
+            }
+            JournalOp::CreateTag(tag) => {
+                sqlx::query!(
+                    r#"
+                    INSERT INTO Tags (pub_id, name, color, created_at)
+                    VALUES (?, ?, ?, ?)
+                    "#,
+                    *tag.id,
+                    *tag.name,
+                    tag.color,
+                    tag.created_at
+                )
+                .execute(&mut *conn)
+                .await?;
+
+                // nothing carries a brand new tag, so nothing visible has changed
+                vec![]
+            }
+            JournalOp::UpdateTag(tag) => {
+                let res = sqlx::query!(
+                    "UPDATE Tags SET name = ?, color = ? WHERE pub_id = ?",
+                    *tag.name,
+                    tag.color,
+                    *tag.id
+                )
+                .execute(&mut *conn)
+                .await?;
+                ensure_affected(res.rows_affected())?;
+
+                tagged_task_diffs(&tag.id, conn).await?
+            }
+            JournalOp::DeleteTag(id) => {
+                let tagged = fetch_tagged_task_ids(id, conn).await?;
+
+                let res = sqlx::query!("DELETE FROM Tags WHERE pub_id = ?", **id)
+                    .execute(&mut *conn)
+                    .await?;
+                ensure_affected(res.rows_affected())?;
+
+                let mut diffs = Vec::new();
+                for task_id in tagged {
+                    diffs.push(Diff::Updated(DiffInner::Task(
+                        fetch_task_by_id(&task_id, conn).await?,
+                    )));
+                }
+                diffs
+            }
+        };
+
+        Ok(diffs)
+    }
+}
+
+/// Returns an update for every task carrying the tag.
+async fn tagged_task_diffs(
+    tag_id: &Id,
+    conn: &mut SqliteConnection,
+) -> Result<Vec<Diff>, TarsError> {
+    let mut diffs = Vec::new();
+    for task_id in fetch_tagged_task_ids(tag_id, conn).await? {
+        diffs.push(Diff::Updated(DiffInner::Task(
+            fetch_task_by_id(&task_id, conn).await?,
+        )));
+    }
+
+    Ok(diffs)
+}
+
+/// Serializes operations for storage in the journal.
+fn encode_ops(ops: &[JournalOp]) -> Result<String, ParseError> {
+    serde_json::to_string(ops).map_err(|e| {
+        error!("Failed to encode journal operations: {:?}", e);
+        ParseError::FailedToParse(format!("journal operations: {e}"))
+    })
+}
+
+/// Deserializes operations stored in the journal.
+pub fn decode_ops(raw: &str) -> Result<Vec<JournalOp>, ParseError> {
+    serde_json::from_str(raw).map_err(|e| {
+        error!("Failed to decode journal operations: {:?}", e);
+        ParseError::FailedToParse(format!("journal operations: {e}"))
+    })
+}
+
+/// Replaying an operation against a row that no longer exists is an error.
+fn ensure_affected(rows_affected: u64) -> Result<(), sqlx::Error> {
+    if rows_affected == 0 {
+        return Err(sqlx::Error::RowNotFound);
+    }
+
+    Ok(())
+}
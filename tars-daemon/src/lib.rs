@@ -1,7 +1,18 @@
+pub mod auth;
+mod config;
 mod daemon;
 mod db;
+pub mod diff_log;
+mod journal;
+pub use config::*;
 pub use daemon::*;
 pub use db::*;
+pub mod notifier;
+pub mod pidfile;
+pub mod scheduler;
+pub mod scoring;
+pub mod tls;
 pub mod utils;
+mod validation;
 
 mod handlers;
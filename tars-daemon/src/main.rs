@@ -1,18 +1,97 @@
-use color_eyre::Result;
-use common::DAEMON_ADDR;
-use common::logging;
-use tars_daemon::{DaemonState, Db, TarsDaemon};
+use std::path::PathBuf;
+
+use clap::Parser;
+use color_eyre::{Result, owo_colors::OwoColorize};
+use common::{discovery, logging, tls};
+use tars_daemon::{
+    DaemonConfig, DaemonState, Db, MigrationState, TarsDaemon, auth::Auth, notifier::Notifier,
+    pidfile::PidFile, tls::ServerTls,
+};
+
+use crate::args::{DaemonArgs, DaemonCommand};
+
+mod args;
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    logging::init("tars-d.log", true)?;
+    let args = DaemonArgs::parse();
+
+    let mut config = match args.config {
+        Some(path) => DaemonConfig::load_from(path)?,
+        None => DaemonConfig::load()?,
+    };
+    // flags take precedence over the config file
+    config.listen = args.listen.or(config.listen);
+    config.database = args.database.or(config.database);
+    config.log_level = args.log_level.or(config.log_level);
+    config.pidfile = args.pidfile.or(config.pidfile);
+
+    logging::init_with_level("tars-d.log", true, config.log_level.as_deref())?;
+
+    match args.command {
+        Some(DaemonCommand::Migrate { status }) => {
+            return migrate(config.db_path(), config.pidfile_path(), status).await;
+        }
+        // only reads the certificate, which is exactly what's needed while the daemon runs
+        Some(DaemonCommand::Fingerprint) => {
+            println!("{}", ServerTls::load(&config.tls)?.fingerprint);
+            return Ok(());
+        }
+        None => {}
+    }
+
+    // held until the daemon exits
+    let _pidfile = PidFile::acquire(config.pidfile_path())?;
 
-    let db = Db::new(false).await?;
+    let db = Db::load(config.db_path()).await?;
 
-    let state = DaemonState::new(db, DAEMON_ADDR);
+    let state = DaemonState::new(db, &config.listen_addr())
+        .with_scoring(config.scoring)
+        .with_schedule(config.schedule)
+        .with_auth(Auth::load_default()?);
 
-    //TODO: also create a notifier thread later
-    let daemon = TarsDaemon::init(state).await;
+    if config.notifier.enabled {
+        let notifier = Notifier::new(state.clone(), config.notifier);
+        tokio::spawn(notifier.run());
+    }
+
+    let mut daemon = TarsDaemon::init(state)
+        .await
+        .with_discovery(discovery::discovery_path());
+    if config.tls.enabled {
+        let server_tls = ServerTls::load(&config.tls)?;
+        // local clients pin the certificate with it
+        tls::write_fingerprint(&tls::fingerprint_path(), &server_tls.fingerprint)?;
+        daemon = daemon.with_tls(server_tls);
+    }
 
     daemon.run().await
 }
+
+/// Either lists the state of every migration, or applies the pending ones.
+///
+/// Applying them takes the pidfile first, migrating under a running daemon is just as bad
+/// as running two of them. Listing them only reads, so it works while the daemon runs.
+async fn migrate(path: PathBuf, pidfile: PathBuf, status: bool) -> Result<()> {
+    let _pidfile = (!status).then(|| PidFile::acquire(pidfile)).transpose()?;
+    let db = Db::open(path).await?;
+
+    if !status && let Some(backup) = db.migrate().await? {
+        println!("Backed up the database to {}", backup.display());
+    }
+
+    println!("Database: {}", db.path.display());
+    for migration in db.migration_status().await? {
+        let state = match migration.state {
+            MigrationState::Applied => "applied".green().to_string(),
+            MigrationState::Pending => "pending".yellow().to_string(),
+            MigrationState::Unknown => "unknown (newer daemon?)".red().to_string(),
+        };
+        println!(
+            "{} {:<24} {state}",
+            migration.version, migration.description
+        );
+    }
+
+    Ok(())
+}
@@ -0,0 +1,215 @@
+use std::time::Duration;
+
+use common::{
+    TarsError,
+    types::{Id, Notification, Reminder, Task},
+};
+use serde::Deserialize;
+use sqlx::types::chrono::{Local, NaiveDateTime};
+use tokio::time::{MissedTickBehavior, interval};
+use tracing::{error, info, instrument};
+
+use crate::{DaemonState, handlers::fetch_task_by_id};
+
+mod sinks;
+pub use sinks::*;
+
+/// Configuration for the `Notifier`, read from the `[notifier]` section of `daemon.toml`.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct NotifierConfig {
+    pub enabled: bool,
+    /// How often, in seconds, the notifier checks for upcoming due dates.
+    pub poll_interval: u64,
+    /// When reminders should fire, ex. `["1d", "1h", "overdue"]`.
+    pub reminders: Vec<Reminder>,
+    pub sinks: Vec<SinkConfig>,
+}
+
+impl Default for NotifierConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            poll_interval: 60,
+            reminders: vec![
+                Reminder::Before(60 * 24),
+                Reminder::Before(60),
+                Reminder::Overdue,
+            ],
+            sinks: vec![SinkConfig::Broadcast],
+        }
+    }
+}
+
+/// Background task that watches the due dates of unfinished tasks and fires reminders
+/// to every configured `Sink`.
+pub struct Notifier {
+    state: DaemonState,
+    poll_interval: Duration,
+    reminders: Vec<Reminder>,
+    sinks: Vec<Box<dyn Sink>>,
+}
+
+impl Notifier {
+    /// Creates a new `Notifier` from the provided config.
+    pub fn new(state: DaemonState, config: NotifierConfig) -> Self {
+        let sinks = config
+            .sinks
+            .into_iter()
+            .map(|sink| sink.build(&state.diffs))
+            .collect();
+
+        Self {
+            state,
+            poll_interval: Duration::from_secs(config.poll_interval.max(1)),
+            reminders: config.reminders,
+            sinks,
+        }
+    }
+
+    /// Runs the notifier forever, checking for reminders every `poll_interval`.
+    pub async fn run(self) {
+        let mut ticker = interval(self.poll_interval);
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+        info!(
+            "Notifier running every {:?} with reminders: {:?}",
+            self.poll_interval, self.reminders
+        );
+
+        loop {
+            ticker.tick().await;
+
+            if let Err(e) = self.check_reminders().await {
+                error!("Failed checking reminders: {:?}", e);
+            }
+        }
+    }
+
+    /// Fires every reminder that is due and hasn't been fired yet, returning the sent notifications.
+    ///
+    /// If multiple reminders for the same task are pending (ex. the daemon was down for a while),
+    /// only the most recent one is sent, the older ones are recorded as fired.
+    ///
+    /// A reminder that none of the sinks could deliver isn't recorded, it's tried again on the
+    /// next check.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if something goes wrong with sqlx.
+    #[instrument(skip(self))]
+    pub async fn check_reminders(&self) -> Result<Vec<Notification>, TarsError> {
+        let now = Local::now().naive_local();
+        let mut fired = Vec::new();
+
+        for task in self.fetch_pending_tasks().await? {
+            let Some(due) = task.due else {
+                continue;
+            };
+
+            let mut pending: Vec<&Reminder> = self
+                .reminders
+                .iter()
+                .filter(|r| r.fires_at(due) <= now)
+                .collect();
+            pending.sort_by_key(|r| r.fires_at(due));
+
+            let mut latest = None;
+            for reminder in pending {
+                if self.record_reminder(&task.id, reminder, due, now).await? {
+                    latest = Some(*reminder);
+                }
+            }
+
+            let Some(reminder) = latest else {
+                continue;
+            };
+
+            let notification = Notification::new(task, reminder, now);
+            info!("Firing reminder: {}", notification.message);
+
+            let mut delivered = self.sinks.is_empty();
+            for sink in self.sinks.iter() {
+                match sink.send(&notification).await {
+                    Ok(()) => delivered = true,
+                    Err(e) => error!("Failed to deliver notification: {:?}", e),
+                }
+            }
+
+            if !delivered {
+                self.forget_reminder(&notification.task.id, &reminder, due)
+                    .await?;
+                continue;
+            }
+
+            fired.push(notification);
+        }
+
+        Ok(fired)
+    }
+
+    /// Records that the reminder has fired, returns `false` if it already had been.
+    async fn record_reminder(
+        &self,
+        task_id: &Id,
+        reminder: &Reminder,
+        due: NaiveDateTime,
+        now: NaiveDateTime,
+    ) -> Result<bool, TarsError> {
+        let reminder = reminder.to_string();
+
+        let res = sqlx::query!(
+            r#"
+            INSERT OR IGNORE INTO FiredReminders (task_id, reminder, due, fired_at)
+            VALUES (?, ?, ?, ?)
+            "#,
+            **task_id,
+            reminder,
+            due,
+            now
+        )
+        .execute(&self.state.pool)
+        .await?;
+
+        Ok(res.rows_affected() == 1)
+    }
+
+    /// Forgets that the reminder has fired, so it fires again.
+    async fn forget_reminder(
+        &self,
+        task_id: &Id,
+        reminder: &Reminder,
+        due: NaiveDateTime,
+    ) -> Result<(), TarsError> {
+        let reminder = reminder.to_string();
+
+        sqlx::query!(
+            "DELETE FROM FiredReminders WHERE task_id = ? AND reminder = ? AND due = ?",
+            **task_id,
+            reminder,
+            due
+        )
+        .execute(&self.state.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Returns every unfinished `Task` that has a due date.
+    async fn fetch_pending_tasks(&self) -> Result<Vec<Task>, TarsError> {
+        let mut conn = self.state.pool.acquire().await?;
+
+        let ids = sqlx::query_scalar!(
+            "SELECT pub_id FROM Tasks WHERE finished_at IS NULL AND due IS NOT NULL"
+        )
+        .fetch_all(&mut *conn)
+        .await?;
+
+        let mut tasks = Vec::with_capacity(ids.len());
+        for id in ids {
+            tasks.push(fetch_task_by_id(&Id::from(id), &mut conn).await?);
+        }
+
+        Ok(tasks)
+    }
+}
@@ -0,0 +1,151 @@
+use std::path::PathBuf;
+
+use async_trait::async_trait;
+use color_eyre::eyre::{Result, eyre};
+use common::{Diff, types::Notification};
+use serde::Deserialize;
+use tokio::{fs::OpenOptions, io::AsyncWriteExt, process::Command};
+
+use crate::diff_log::DiffLog;
+
+/// A destination that fired reminders get delivered to.
+#[async_trait]
+pub trait Sink: Send + Sync {
+    /// Delivers the notification.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the notification could not be delivered.
+    async fn send(&self, notification: &Notification) -> Result<()>;
+}
+
+/// Configuration for a single sink, as it appears in the `[[notifier.sinks]]` array.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum SinkConfig {
+    /// Runs a local program, ex. `notify-send`.
+    ///
+    /// The placeholders `{message}`, `{name}`, `{id}` and `{due}` get substituted in `args`.
+    Command {
+        program: String,
+        #[serde(default = "default_command_args")]
+        args: Vec<String>,
+    },
+    /// POSTs the notification as JSON to the url.
+    Webhook { url: String },
+    /// Appends a line for every notification to the file.
+    File { path: PathBuf },
+    /// Publishes the notification on the diff stream, so clients like the TUI can show them.
+    Broadcast,
+}
+
+fn default_command_args() -> Vec<String> {
+    vec!["{message}".to_owned()]
+}
+
+impl SinkConfig {
+    /// Builds the sink described by this config.
+    pub fn build(self, diffs: &DiffLog) -> Box<dyn Sink> {
+        match self {
+            SinkConfig::Command { program, args } => Box::new(CommandSink { program, args }),
+            SinkConfig::Webhook { url } => Box::new(WebhookSink {
+                url,
+                client: reqwest::Client::new(),
+            }),
+            SinkConfig::File { path } => Box::new(FileSink { path }),
+            SinkConfig::Broadcast => Box::new(BroadcastSink {
+                diffs: diffs.clone(),
+            }),
+        }
+    }
+}
+
+pub struct CommandSink {
+    program: String,
+    args: Vec<String>,
+}
+
+#[async_trait]
+impl Sink for CommandSink {
+    async fn send(&self, notification: &Notification) -> Result<()> {
+        let due = notification
+            .task
+            .due
+            .map(|d| d.to_string())
+            .unwrap_or_default();
+
+        let args = self.args.iter().map(|arg| {
+            arg.replace("{message}", &notification.message)
+                .replace("{name}", &notification.task.name)
+                .replace("{id}", &notification.task.id)
+                .replace("{due}", &due)
+        });
+
+        let status = Command::new(&self.program).args(args).status().await?;
+
+        if !status.success() {
+            return Err(eyre!("{} exited with {}", self.program, status));
+        }
+
+        Ok(())
+    }
+}
+
+pub struct WebhookSink {
+    url: String,
+    client: reqwest::Client,
+}
+
+#[async_trait]
+impl Sink for WebhookSink {
+    async fn send(&self, notification: &Notification) -> Result<()> {
+        self.client
+            .post(&self.url)
+            .json(notification)
+            .send()
+            .await?
+            .error_for_status()?;
+
+        Ok(())
+    }
+}
+
+pub struct FileSink {
+    path: PathBuf,
+}
+
+#[async_trait]
+impl Sink for FileSink {
+    async fn send(&self, notification: &Notification) -> Result<()> {
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .await?;
+
+        let line = format!(
+            "[{}] {}\n",
+            notification.fired_at.format("%m/%d/%Y %I:%M:%S %p"),
+            notification.message
+        );
+
+        file.write_all(line.as_bytes()).await?;
+
+        Ok(())
+    }
+}
+
+pub struct BroadcastSink {
+    diffs: DiffLog,
+}
+
+#[async_trait]
+impl Sink for BroadcastSink {
+    async fn send(&self, notification: &Notification) -> Result<()> {
+        self.diffs
+            .send(Diff::Notification(Box::new(notification.clone())))
+            .await;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,81 @@
+use std::{
+    fs::{File, OpenOptions, TryLockError},
+    io::{Read, Seek, Write},
+    path::{Path, PathBuf},
+};
+
+use color_eyre::eyre::{Result, eyre};
+use tracing::{info, warn};
+
+/// An exclusive lock on a file holding the daemon's pid, kept for as long as this lives. The
+/// OS lets go of it when the daemon exits, however it exits, so a leftover file is harmless.
+#[derive(Debug)]
+pub struct PidFile {
+    file: File,
+    path: PathBuf,
+}
+
+impl PidFile {
+    /// Returns where the pidfile of the database at `db_path` goes by default, next to it.
+    pub fn default_path(db_path: &Path) -> PathBuf {
+        let mut name = db_path.file_name().unwrap_or_default().to_owned();
+        name.push(".pid");
+        db_path.with_file_name(name)
+    }
+
+    /// Takes the lock on `path` and writes the current pid to it.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if
+    /// + Another process holds the lock, ie. another daemon is running on the same database.
+    /// + The file can't be opened or written.
+    pub fn acquire(path: PathBuf) -> Result<Self> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(&path)?;
+
+        match file.try_lock() {
+            Ok(()) => {}
+            Err(TryLockError::WouldBlock) => {
+                let mut pid = String::new();
+                let _ = file.read_to_string(&mut pid);
+                return Err(eyre!(
+                    "Another daemon (pid {}) is already running, it holds {}",
+                    pid.trim(),
+                    path.display()
+                ));
+            }
+            Err(TryLockError::Error(e)) => return Err(e.into()),
+        }
+
+        file.set_len(0)?;
+        file.rewind()?;
+        write!(file, "{}", std::process::id())?;
+        file.flush()?;
+        info!("Locked {}", path.display());
+
+        Ok(Self { file, path })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for PidFile {
+    fn drop(&mut self) {
+        // emptied rather than removed, removing it could let a daemon waiting to open it
+        // lock a file nobody else can see anymore
+        if let Err(e) = self.file.set_len(0) {
+            warn!("Failed to clear {}: {e}", self.path.display());
+        }
+    }
+}
@@ -0,0 +1,183 @@
+use chrono::{Datelike, NaiveDateTime, NaiveTime, TimeDelta, Weekday};
+use common::types::{
+    Estimate, LateTask, PlannedTask, Schedule, ScheduleDay, TimeBlock, UnplacedTask,
+};
+use serde::{Deserialize, Deserializer, de::Error};
+
+/// Configuration for the scheduler, read from the `[schedule]` section of `daemon.toml`.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct ScheduleConfig {
+    /// When the working day starts, ex. `"09:00"`.
+    #[serde(deserialize_with = "deserialize_time")]
+    pub day_start: NaiveTime,
+    /// When the working day ends, ex. `"17:30"`.
+    #[serde(deserialize_with = "deserialize_time")]
+    pub day_end: NaiveTime,
+    /// The days of the week that get work scheduled onto them, ex. `["mon", "tue"]`.
+    #[serde(deserialize_with = "deserialize_weekdays")]
+    pub work_days: Vec<Weekday>,
+    /// How many days ahead are scheduled by default.
+    pub horizon_days: u32,
+}
+
+impl Default for ScheduleConfig {
+    fn default() -> Self {
+        Self {
+            day_start: NaiveTime::from_hms_opt(9, 0, 0).expect("valid time"),
+            day_end: NaiveTime::from_hms_opt(17, 0, 0).expect("valid time"),
+            work_days: vec![
+                Weekday::Mon,
+                Weekday::Tue,
+                Weekday::Wed,
+                Weekday::Thu,
+                Weekday::Fri,
+            ],
+            horizon_days: 14,
+        }
+    }
+}
+
+/// A working day while it's being filled.
+struct Slot {
+    day: ScheduleDay,
+    /// Where the next block goes, past `end` once the day is over capacity.
+    cursor: NaiveDateTime,
+    end: NaiveDateTime,
+}
+
+impl Slot {
+    fn book(&mut self, task: &PlannedTask, minutes: u32, late: bool) {
+        let end = self.cursor + TimeDelta::minutes(minutes as i64);
+        self.day.blocks.push(TimeBlock {
+            task_id: task.task.id.clone(),
+            name: task.task.name.clone(),
+            start: self.cursor,
+            end,
+            late,
+        });
+        self.day.booked = Estimate::from_minutes(self.day.booked.minutes() + minutes);
+        self.cursor = end;
+    }
+}
+
+impl ScheduleConfig {
+    /// Lays `tasks` out over the working days of the `days` days starting at `from`, in the
+    /// order they're given.
+    ///
+    /// Each task takes the earliest free time left, split over as many days as it needs,
+    /// but never past its due date. Whatever doesn't fit before it's due gets booked onto
+    /// the last working day before the due date (today for overdue tasks) regardless of
+    /// capacity, and the task is reported as late. Tasks without a due date that don't fit
+    /// within the horizon are reported as unplaced.
+    pub fn schedule(&self, tasks: &[PlannedTask], from: NaiveDateTime, days: u32) -> Schedule {
+        let mut slots: Vec<Slot> = (0..days)
+            .filter_map(|offset| {
+                let date = from.date() + TimeDelta::days(offset as i64);
+                if !self.work_days.contains(&date.weekday()) {
+                    return None;
+                }
+
+                let end = date.and_time(self.day_end);
+                let start = date.and_time(self.day_start).max(from).min(end);
+
+                Some(Slot {
+                    day: ScheduleDay {
+                        date,
+                        capacity: Estimate::from_minutes((end - start).num_minutes() as u32),
+                        booked: Estimate::default(),
+                        blocks: Vec::new(),
+                    },
+                    cursor: start,
+                    end,
+                })
+            })
+            .collect();
+
+        let mut late = Vec::new();
+        let mut unplaced = Vec::new();
+
+        for planned in tasks {
+            let task = &planned.task;
+            let mut remaining = task.estimate.map_or(0, |e| e.minutes());
+
+            for slot in slots.iter_mut() {
+                if remaining == 0 {
+                    break;
+                }
+
+                let limit = match task.due {
+                    Some(due) => slot.end.min(due),
+                    None => slot.end,
+                };
+                if task.due.is_some_and(|due| slot.cursor >= due) {
+                    break;
+                }
+
+                let free = (limit - slot.cursor).num_minutes().max(0) as u32;
+                let minutes = free.min(remaining);
+                if minutes > 0 {
+                    slot.book(planned, minutes, false);
+                    remaining -= minutes;
+                }
+            }
+
+            if remaining == 0 {
+                continue;
+            }
+
+            let last_slot_end = slots.last().map(|slot| slot.end);
+            match task.due {
+                Some(due) if last_slot_end.is_some_and(|end| due <= end) => {
+                    let slot = match slots.iter().rposition(|s| s.day.date <= due.date()) {
+                        Some(i) => &mut slots[i],
+                        None => &mut slots[0],
+                    };
+                    slot.book(planned, remaining, true);
+
+                    late.push(LateTask {
+                        task_id: task.id.clone(),
+                        name: task.name.clone(),
+                        due,
+                        missing: Estimate::from_minutes(remaining),
+                    });
+                }
+                _ => unplaced.push(UnplacedTask {
+                    task_id: task.id.clone(),
+                    name: task.name.clone(),
+                    remaining: Estimate::from_minutes(remaining),
+                }),
+            }
+        }
+
+        Schedule {
+            days: slots.into_iter().map(|slot| slot.day).collect(),
+            late,
+            unplaced,
+            unestimated: 0,
+            blocked: 0,
+        }
+    }
+}
+
+/// Accepts `"09:00"` as well as `"09:00:00"`.
+fn deserialize_time<'de, D: Deserializer<'de>>(deserializer: D) -> Result<NaiveTime, D::Error> {
+    let raw = String::deserialize(deserializer)?;
+
+    NaiveTime::parse_from_str(&raw, "%H:%M")
+        .or_else(|_| NaiveTime::parse_from_str(&raw, "%H:%M:%S"))
+        .map_err(|e| D::Error::custom(format!("invalid time {raw:?}: {e}")))
+}
+
+/// Accepts short or long day names, ex. `"mon"` or `"Monday"`.
+fn deserialize_weekdays<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Vec<Weekday>, D::Error> {
+    Vec::<String>::deserialize(deserializer)?
+        .into_iter()
+        .map(|raw| {
+            raw.parse::<Weekday>()
+                .map_err(|_| D::Error::custom(format!("invalid day {raw:?}")))
+        })
+        .collect()
+}
@@ -0,0 +1,126 @@
+use std::fmt::Debug;
+
+use chrono::NaiveDateTime;
+use common::types::{Estimate, Priority, ScoreTerm, ScoringStrategyKind};
+use serde::Deserialize;
+use tracing::info;
+
+mod strategies;
+pub use strategies::*;
+
+/// Everything a `ScoringStrategy` gets to look at for a single task.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScoreInput {
+    pub priority: Priority,
+    /// Product of `1 / priority` over the task's group and every group above it.
+    pub group_p_score: f64,
+    pub due: Option<NaiveDateTime>,
+    /// How many unfinished tasks are (transitively) waiting on this one.
+    pub blocked_dependents: i64,
+    /// How long the task is expected to take, if anyone said.
+    pub estimate: Option<Estimate>,
+    /// Unchecked checklist items, the closest thing to a size a task has without an estimate.
+    pub open_checklist_items: i64,
+}
+
+impl ScoreInput {
+    /// `1 / priority` of the task itself, times that of its groups.
+    pub fn priority_score(&self) -> f64 {
+        self.group_p_score / self.priority as i32 as f64
+    }
+
+    /// Days until the task is due, negative once it's overdue.
+    pub fn days_left(&self, now: NaiveDateTime) -> Option<f64> {
+        self.due
+            .map(|due| (due - now).num_minutes() as f64 / 1440.0)
+    }
+}
+
+/// A way of ranking tasks, the higher the score the sooner a task should be worked on.
+pub trait ScoringStrategy: Send + Sync + Debug {
+    /// Scores a single task as of `now`.
+    fn score(&self, input: &ScoreInput, now: NaiveDateTime) -> f64;
+
+    /// Returns the terms that make up the score of a task as of `now`, in the order the
+    /// strategy combines them.
+    fn explain(&self, input: &ScoreInput, now: NaiveDateTime) -> Vec<ScoreTerm>;
+}
+
+/// Configuration for scoring, read from the `[scoring]` section of `daemon.toml`.
+///
+/// Every strategy has its own table of weights, ex. `[scoring.eisenhower]`, so switching
+/// strategies at runtime keeps whatever was tuned for each.
+#[derive(Debug, Clone, PartialEq, Default, Deserialize)]
+#[serde(default)]
+pub struct ScoringConfig {
+    /// The strategy the daemon starts out with.
+    pub strategy: ScoringStrategyKind,
+    pub classic: ClassicWeights,
+    pub deadline_first: DeadlineFirstWeights,
+    pub eisenhower: EisenhowerWeights,
+    pub wsjf: WsjfWeights,
+}
+
+impl ScoringConfig {
+    /// Builds the strategy of the given kind, with the weights configured for it.
+    pub fn build(&self, kind: ScoringStrategyKind) -> Box<dyn ScoringStrategy> {
+        match kind {
+            ScoringStrategyKind::Classic => Box::new(Classic(self.classic.clone())),
+            ScoringStrategyKind::DeadlineFirst => {
+                Box::new(DeadlineFirst(self.deadline_first.clone()))
+            }
+            ScoringStrategyKind::Eisenhower => Box::new(Eisenhower(self.eisenhower.clone())),
+            ScoringStrategyKind::Wsjf => Box::new(Wsjf(self.wsjf.clone())),
+        }
+    }
+}
+
+/// Holds the active `ScoringStrategy`, along with the config to build the others from.
+#[derive(Debug)]
+pub struct Scorer {
+    config: ScoringConfig,
+    kind: ScoringStrategyKind,
+    strategy: Box<dyn ScoringStrategy>,
+}
+
+impl Scorer {
+    /// Creates a new `Scorer`, starting out with the strategy picked in the config.
+    pub fn new(config: ScoringConfig) -> Self {
+        let kind = config.strategy;
+        let strategy = config.build(kind);
+
+        Self {
+            config,
+            kind,
+            strategy,
+        }
+    }
+
+    /// Returns which strategy is active.
+    pub fn kind(&self) -> ScoringStrategyKind {
+        self.kind
+    }
+
+    /// Makes `kind` the active strategy.
+    pub fn switch(&mut self, kind: ScoringStrategyKind) {
+        info!("Switching scoring strategy from {} to {}", self.kind, kind);
+        self.strategy = self.config.build(kind);
+        self.kind = kind;
+    }
+
+    /// Scores a single task with the active strategy.
+    pub fn score(&self, input: &ScoreInput, now: NaiveDateTime) -> f64 {
+        self.strategy.score(input, now)
+    }
+
+    /// Explains the score of a single task under the active strategy.
+    pub fn explain(&self, input: &ScoreInput, now: NaiveDateTime) -> Vec<ScoreTerm> {
+        self.strategy.explain(input, now)
+    }
+}
+
+impl Default for Scorer {
+    fn default() -> Self {
+        Self::new(ScoringConfig::default())
+    }
+}
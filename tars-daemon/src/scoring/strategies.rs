@@ -0,0 +1,274 @@
+use chrono::NaiveDateTime;
+use common::types::{Priority, ScoreTerm};
+use serde::Deserialize;
+
+use super::{ScoreInput, ScoringStrategy};
+
+/// Weights for the `Classic` strategy, the `[scoring.classic]` table.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct ClassicWeights {
+    /// Raised to the days left until the task is due, so it grows as the due date nears.
+    pub due_base: f64,
+    /// How much each task waiting on this one raises its score.
+    pub blocking_weight: f64,
+    /// What `Priority::Asap` tasks score, regardless of anything else.
+    pub asap_score: f64,
+}
+
+impl Default for ClassicWeights {
+    fn default() -> Self {
+        Self {
+            due_base: std::f64::consts::E / 3.0,
+            blocking_weight: 0.25,
+            asap_score: 1.0,
+        }
+    }
+}
+
+/// `due_base^days_left + priority score`, what TARS has always ranked by.
+#[derive(Debug)]
+pub struct Classic(pub ClassicWeights);
+
+impl Classic {
+    fn blocking_multiplier(&self, input: &ScoreInput) -> f64 {
+        1.0 + self.0.blocking_weight * input.blocked_dependents as f64
+    }
+
+    fn due_term(&self, input: &ScoreInput, now: NaiveDateTime) -> Option<f64> {
+        input
+            .days_left(now)
+            .map(|days_left| self.0.due_base.powf(days_left))
+    }
+}
+
+impl ScoringStrategy for Classic {
+    fn score(&self, input: &ScoreInput, now: NaiveDateTime) -> f64 {
+        if input.priority == Priority::Asap {
+            return self.0.asap_score;
+        }
+
+        input.priority_score() * self.blocking_multiplier(input)
+            + self.due_term(input, now).unwrap_or(0.0)
+    }
+
+    fn explain(&self, input: &ScoreInput, now: NaiveDateTime) -> Vec<ScoreTerm> {
+        if input.priority == Priority::Asap {
+            return vec![term("asap", self.0.asap_score)];
+        }
+
+        let mut terms = vec![
+            term("priority", input.priority_score()),
+            term("× blocking", self.blocking_multiplier(input)),
+        ];
+        if let Some(due) = self.due_term(input, now) {
+            terms.push(term("+ due", due));
+        }
+        terms
+    }
+}
+
+/// Weights for the `DeadlineFirst` strategy, the `[scoring.deadline_first]` table.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct DeadlineFirstWeights {
+    /// What a task due right now scores on top of its priority.
+    pub due_weight: f64,
+    /// Every this many days further out halves how much the due date counts.
+    pub half_life_days: f64,
+    pub priority_weight: f64,
+    pub blocking_weight: f64,
+}
+
+impl Default for DeadlineFirstWeights {
+    fn default() -> Self {
+        Self {
+            due_weight: 10.0,
+            half_life_days: 1.0,
+            priority_weight: 1.0,
+            blocking_weight: 0.25,
+        }
+    }
+}
+
+/// Ranks by how soon tasks are due, priority only decides between tasks due around the same time.
+#[derive(Debug)]
+pub struct DeadlineFirst(pub DeadlineFirstWeights);
+
+impl DeadlineFirst {
+    fn priority_term(&self, input: &ScoreInput) -> f64 {
+        self.0.priority_weight
+            * input.priority_score()
+            * (1.0 + self.0.blocking_weight * input.blocked_dependents as f64)
+    }
+
+    fn deadline_term(&self, input: &ScoreInput, now: NaiveDateTime) -> f64 {
+        input.days_left(now).map_or(0.0, |days_left| {
+            self.0.due_weight * 2f64.powf(-days_left / self.0.half_life_days.max(f64::EPSILON))
+        })
+    }
+}
+
+impl ScoringStrategy for DeadlineFirst {
+    fn score(&self, input: &ScoreInput, now: NaiveDateTime) -> f64 {
+        self.deadline_term(input, now) + self.priority_term(input)
+    }
+
+    fn explain(&self, input: &ScoreInput, now: NaiveDateTime) -> Vec<ScoreTerm> {
+        vec![
+            term("deadline", self.deadline_term(input, now)),
+            term("+ priority", self.priority_term(input)),
+        ]
+    }
+}
+
+/// Weights for the `Eisenhower` strategy, the `[scoring.eisenhower]` table.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct EisenhowerWeights {
+    /// Tasks due within this many days, or overdue, are urgent.
+    pub urgent_within_days: f64,
+    /// Tasks of this priority or higher are important.
+    pub important_from: Priority,
+    /// Urgent and important.
+    pub do_first: f64,
+    /// Important, but not urgent.
+    pub schedule: f64,
+    /// Urgent, but not important.
+    pub delegate: f64,
+    /// Neither.
+    pub eliminate: f64,
+}
+
+impl Default for EisenhowerWeights {
+    fn default() -> Self {
+        Self {
+            urgent_within_days: 2.0,
+            important_from: Priority::High,
+            do_first: 4.0,
+            schedule: 3.0,
+            delegate: 2.0,
+            eliminate: 1.0,
+        }
+    }
+}
+
+/// Puts every task into one of the four urgent / important quadrants, ranking within a
+/// quadrant by priority.
+#[derive(Debug)]
+pub struct Eisenhower(pub EisenhowerWeights);
+
+impl Eisenhower {
+    /// Returns the name and weight of the quadrant the task falls into.
+    fn quadrant(&self, input: &ScoreInput, now: NaiveDateTime) -> (&'static str, f64) {
+        let urgent = input
+            .days_left(now)
+            .is_some_and(|days_left| days_left <= self.0.urgent_within_days);
+        // lower discriminants are the more pressing priorities
+        let important = input.priority as i32 <= self.0.important_from as i32;
+
+        match (urgent, important) {
+            (true, true) => ("do first", self.0.do_first),
+            (false, true) => ("schedule", self.0.schedule),
+            (true, false) => ("delegate", self.0.delegate),
+            (false, false) => ("eliminate", self.0.eliminate),
+        }
+    }
+}
+
+impl ScoringStrategy for Eisenhower {
+    fn score(&self, input: &ScoreInput, now: NaiveDateTime) -> f64 {
+        self.quadrant(input, now).1 + input.priority_score()
+    }
+
+    fn explain(&self, input: &ScoreInput, now: NaiveDateTime) -> Vec<ScoreTerm> {
+        let (quadrant, weight) = self.quadrant(input, now);
+
+        vec![
+            term(&format!("quadrant: {quadrant}"), weight),
+            term("+ priority", input.priority_score()),
+        ]
+    }
+}
+
+/// Weights for the `Wsjf` strategy, the `[scoring.wsjf]` table.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct WsjfWeights {
+    /// How much the priority of the task and its groups counts towards the cost of delay.
+    pub value_weight: f64,
+    /// How much the nearing due date counts towards the cost of delay.
+    pub time_criticality_weight: f64,
+    /// How much each task waiting on this one counts towards the cost of delay.
+    pub risk_reduction_weight: f64,
+    /// Every this many days further out halves how time critical a task is.
+    pub time_horizon_days: f64,
+    /// Minutes of estimated work that make a job as big as one open checklist item does.
+    pub minutes_per_step: f64,
+}
+
+impl Default for WsjfWeights {
+    fn default() -> Self {
+        Self {
+            value_weight: 1.0,
+            time_criticality_weight: 1.0,
+            risk_reduction_weight: 0.25,
+            time_horizon_days: 7.0,
+            minutes_per_step: 60.0,
+        }
+    }
+}
+
+/// Weighted shortest job first, the cost of delaying a task divided by how much of it is left,
+/// so small tasks that unblock a lot float up.
+#[derive(Debug)]
+pub struct Wsjf(pub WsjfWeights);
+
+impl Wsjf {
+    /// Returns the value, time criticality and risk reduction parts of the cost of delay.
+    fn cost_of_delay(&self, input: &ScoreInput, now: NaiveDateTime) -> [f64; 3] {
+        let time_criticality = input.days_left(now).map_or(0.0, |days_left| {
+            2f64.powf(-days_left / self.0.time_horizon_days.max(f64::EPSILON))
+        });
+
+        [
+            self.0.value_weight * input.priority_score(),
+            self.0.time_criticality_weight * time_criticality,
+            self.0.risk_reduction_weight * input.blocked_dependents as f64,
+        ]
+    }
+
+    fn job_size(&self, input: &ScoreInput) -> f64 {
+        match input.estimate {
+            Some(estimate) => {
+                1.0 + estimate.minutes() as f64 / self.0.minutes_per_step.max(f64::EPSILON)
+            }
+            // without an estimate, every open checklist item is a step left to do
+            None => 1.0 + input.open_checklist_items as f64,
+        }
+    }
+}
+
+impl ScoringStrategy for Wsjf {
+    fn score(&self, input: &ScoreInput, now: NaiveDateTime) -> f64 {
+        self.cost_of_delay(input, now).iter().sum::<f64>() / self.job_size(input)
+    }
+
+    fn explain(&self, input: &ScoreInput, now: NaiveDateTime) -> Vec<ScoreTerm> {
+        let [value, time_criticality, risk_reduction] = self.cost_of_delay(input, now);
+
+        vec![
+            term("value", value),
+            term("+ time criticality", time_criticality),
+            term("+ risk reduction", risk_reduction),
+            term("÷ job size", self.job_size(input)),
+        ]
+    }
+}
+
+fn term(name: &str, value: f64) -> ScoreTerm {
+    ScoreTerm {
+        name: name.to_owned(),
+        value,
+    }
+}
@@ -0,0 +1,239 @@
+use std::{
+    io,
+    net::SocketAddr,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+
+use axum::serve::Listener;
+use color_eyre::eyre::{Result, eyre};
+use common::{dirs::get_data_dir, tls::fingerprint};
+use serde::Deserialize;
+use tokio::{
+    net::{TcpListener, TcpStream},
+    sync::mpsc,
+    task::JoinHandle,
+};
+use tokio_rustls::{
+    TlsAcceptor,
+    rustls::{
+        ServerConfig,
+        crypto::aws_lc_rs,
+        pki_types::{CertificateDer, PrivateKeyDer, pem::PemObject},
+    },
+    server::TlsStream,
+};
+use tracing::{info, warn};
+
+/// How long a client gets to finish its handshake before it's dropped.
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Configuration for serving over HTTPS, the `[tls]` section of `daemon.toml`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct TlsConfig {
+    /// Serve over HTTPS instead of plain HTTP, only applies to TCP.
+    pub enabled: bool,
+    /// PEM file holding the certificate chain, `tls/cert.pem` in the data directory if left out.
+    pub cert: Option<PathBuf>,
+    /// PEM file holding the private key, `tls/key.pem` in the data directory if left out.
+    pub key: Option<PathBuf>,
+    /// Generate a self-signed certificate and key when there are none yet.
+    pub self_sign: bool,
+    /// Names a self-signed certificate is issued for.
+    pub names: Vec<String>,
+}
+
+impl Default for TlsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            cert: None,
+            key: None,
+            self_sign: false,
+            names: vec!["localhost".to_owned(), "127.0.0.1".to_owned()],
+        }
+    }
+}
+
+impl TlsConfig {
+    /// Returns the certificate to serve.
+    pub fn cert_path(&self) -> PathBuf {
+        self.cert
+            .clone()
+            .unwrap_or_else(|| get_data_dir().join("tls").join("cert.pem"))
+    }
+
+    /// Returns the private key of the certificate.
+    pub fn key_path(&self) -> PathBuf {
+        self.key
+            .clone()
+            .unwrap_or_else(|| get_data_dir().join("tls").join("key.pem"))
+    }
+}
+
+/// The certificate the daemon serves HTTPS with.
+#[derive(Debug, Clone)]
+pub struct ServerTls {
+    config: Arc<ServerConfig>,
+    /// SHA-256 fingerprint of the certificate, for clients to pin.
+    pub fingerprint: String,
+}
+
+impl ServerTls {
+    /// Loads the certificate and key `config` points at, generating a self-signed pair first
+    /// if there is none and `config` allows it.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if
+    /// + The certificate or key is missing and may not be generated.
+    /// + The certificate or key can't be read, written or parsed.
+    pub fn load(config: &TlsConfig) -> Result<Self> {
+        let (cert, key) = (config.cert_path(), config.key_path());
+
+        if !cert.exists() || !key.exists() {
+            if !config.self_sign {
+                return Err(eyre!(
+                    "No certificate at {cert:?} or no key at {key:?}, provide both or set `self_sign`"
+                ));
+            }
+            self_sign(&cert, &key, &config.names)?;
+        }
+
+        let chain = CertificateDer::pem_file_iter(&cert)
+            .and_then(|certs| certs.collect::<Result<Vec<_>, _>>())
+            .map_err(|e| eyre!("Failed to read certificate {cert:?}: {e}"))?;
+        let private = PrivateKeyDer::from_pem_file(&key)
+            .map_err(|e| eyre!("Failed to read key {key:?}: {e}"))?;
+
+        Self::new(chain, private)
+    }
+
+    /// Serves `chain`, the first certificate of which belongs to `key`.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the chain is empty or doesn't match the key.
+    pub fn new(chain: Vec<CertificateDer<'static>>, key: PrivateKeyDer<'static>) -> Result<Self> {
+        let fingerprint = fingerprint(chain.first().ok_or_else(|| eyre!("Empty certificate"))?);
+
+        let mut config =
+            ServerConfig::builder_with_provider(Arc::new(aws_lc_rs::default_provider()))
+                .with_safe_default_protocol_versions()?
+                .with_no_client_auth()
+                .with_single_cert(chain, key)?;
+        config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+
+        Ok(Self {
+            config: Arc::new(config),
+            fingerprint,
+        })
+    }
+
+    /// Serves a freshly generated self-signed certificate for `names`, without writing it
+    /// anywhere.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the certificate can't be generated.
+    pub fn self_signed(names: &[String]) -> Result<Self> {
+        let certified = rcgen::generate_simple_self_signed(names.to_vec())?;
+        let key = PrivateKeyDer::Pkcs8(certified.key_pair.serialize_der().into());
+
+        Self::new(vec![certified.cert.der().clone()], key)
+    }
+}
+
+/// Generates a self-signed certificate for `names`, writing it to `cert` and its key to `key`.
+fn self_sign(cert: &Path, key: &Path, names: &[String]) -> Result<()> {
+    let certified = rcgen::generate_simple_self_signed(names.to_vec())?;
+
+    for path in [cert, key] {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+    }
+    std::fs::write(cert, certified.cert.pem())?;
+    // the key is as good as the token, so only the current user may read it
+    common::auth::write_token(key, &certified.key_pair.serialize_pem())?;
+
+    info!("Generated a self-signed certificate at {:?}", cert);
+    Ok(())
+}
+
+/// Accepts TCP connections and completes their TLS handshake in the background, so a slow
+/// client can't hold up everyone else.
+pub struct TlsListener {
+    accepted: mpsc::Receiver<(TlsStream<TcpStream>, SocketAddr)>,
+    local_addr: SocketAddr,
+    task: JoinHandle<()>,
+}
+
+impl TlsListener {
+    /// Starts accepting connections on `listener`, serving them with `tls`.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the address of `listener` can't be determined.
+    pub fn new(listener: TcpListener, tls: &ServerTls) -> io::Result<Self> {
+        let local_addr = listener.local_addr()?;
+        let acceptor = TlsAcceptor::from(tls.config.clone());
+        let (tx, accepted) = mpsc::channel(64);
+
+        let task = tokio::spawn(async move {
+            loop {
+                let (stream, addr) = match listener.accept().await {
+                    Ok(conn) => conn,
+                    Err(e) => {
+                        // likely out of file descriptors, give it a moment
+                        warn!("Failed to accept a connection: {e}");
+                        tokio::time::sleep(Duration::from_secs(1)).await;
+                        continue;
+                    }
+                };
+
+                let (acceptor, tx) = (acceptor.clone(), tx.clone());
+                tokio::spawn(async move {
+                    match tokio::time::timeout(HANDSHAKE_TIMEOUT, acceptor.accept(stream)).await {
+                        Ok(Ok(stream)) => {
+                            let _ = tx.send((stream, addr)).await;
+                        }
+                        Ok(Err(e)) => warn!("TLS handshake with {addr} failed: {e}"),
+                        Err(_) => warn!("TLS handshake with {addr} timed out"),
+                    }
+                });
+            }
+        });
+
+        Ok(Self {
+            accepted,
+            local_addr,
+            task,
+        })
+    }
+}
+
+impl Listener for TlsListener {
+    type Io = TlsStream<TcpStream>;
+    type Addr = SocketAddr;
+
+    async fn accept(&mut self) -> (Self::Io, Self::Addr) {
+        match self.accepted.recv().await {
+            Some(conn) => conn,
+            // the accepting task only stops once this is dropped
+            None => std::future::pending().await,
+        }
+    }
+
+    fn local_addr(&self) -> io::Result<Self::Addr> {
+        Ok(self.local_addr)
+    }
+}
+
+impl Drop for TlsListener {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
@@ -1,4 +1,8 @@
-use crate::{DaemonState, Db, TarsDaemon};
+use std::path::PathBuf;
+
+use common::types::Id;
+
+use crate::{DaemonState, Db, TarsDaemon, auth::Auth, tls::ServerTls};
 
 /// Returns a new `TarsDaemon`, with a temporary DB and a open port, perfect for testing.
 /// Ensure you use the returned String as the url to communicate with the daemon.
@@ -20,3 +24,46 @@ pub async fn new_test_daemon() -> (TarsDaemon, String) {
 
     (daemon, format!("http://127.0.0.1:{availible_port}"))
 }
+
+/// Same as `new_test_daemon`, except every request has to carry the token stored at the
+/// returned path.
+pub async fn new_authed_test_daemon() -> (TarsDaemon, String, PathBuf) {
+    let (daemon, addr) = new_test_daemon().await;
+
+    let token_path = PathBuf::from(format!(
+        "/tmp/tars/test-auth/{}/daemon.token",
+        *Id::default()
+    ));
+    let state = daemon
+        .state()
+        .clone()
+        .with_auth(Auth::load(token_path.clone()).unwrap());
+
+    (TarsDaemon::init(state).await, addr, token_path)
+}
+
+/// Same as `new_test_daemon`, except the daemon listens on a Unix socket, the returned
+/// `unix://` url points at it.
+pub async fn new_socket_test_daemon() -> (TarsDaemon, String) {
+    let db = Db::new(true).await.unwrap();
+
+    let addr = format!("unix:///tmp/tars/test-socket/{}/tars.sock", *Id::default());
+    let state = DaemonState::new(db, &addr);
+
+    (TarsDaemon::init(state).await, addr)
+}
+
+/// Same as `new_test_daemon`, except the daemon serves HTTPS with a fresh self-signed
+/// certificate, whose fingerprint is returned alongside the `https://` url.
+pub async fn new_tls_test_daemon() -> (TarsDaemon, String, String) {
+    let (daemon, addr) = new_test_daemon().await;
+
+    let tls = ServerTls::self_signed(&["localhost".to_owned()]).unwrap();
+    let fingerprint = tls.fingerprint.clone();
+
+    (
+        daemon.with_tls(tls),
+        addr.replace("http://", "https://"),
+        fingerprint,
+    )
+}
@@ -0,0 +1,203 @@
+use axum::{
+    Json,
+    body::to_bytes,
+    extract::Request,
+    http::{HeaderMap, StatusCode, header::CONTENT_TYPE},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use common::{
+    ApiError, ErrorCode, TarsError,
+    types::{Color, Group, Id, Name, Tag, Task},
+};
+use sqlx::SqliteConnection;
+
+/// Largest error body that gets carried over into the `ApiError`, rejections are short.
+const MESSAGE_LIMIT: usize = 64 * 1024;
+
+/// Rewrites error responses that don't come from a `TarsError`, like axum rejecting a body
+/// that isn't valid JSON or a route that doesn't exist, into an `ApiError` body too.
+pub async fn json_errors(req: Request, next: Next) -> Response {
+    let res = next.run(req).await;
+    let status = res.status();
+    if !(status.is_client_error() || status.is_server_error()) || is_json(res.headers()) {
+        return res;
+    }
+
+    let message = to_bytes(res.into_body(), MESSAGE_LIMIT)
+        .await
+        .ok()
+        .map(|body| String::from_utf8_lossy(&body).trim().to_owned())
+        .filter(|message| !message.is_empty())
+        .unwrap_or_else(|| status.canonical_reason().unwrap_or("error").to_owned());
+
+    let code = match status {
+        StatusCode::NOT_FOUND => ErrorCode::NotFound,
+        StatusCode::UNAUTHORIZED => ErrorCode::Unauthorized,
+        StatusCode::FORBIDDEN => ErrorCode::Forbidden,
+        s if s.is_server_error() => ErrorCode::Internal,
+        _ => ErrorCode::BadRequest,
+    };
+
+    let body = ApiError {
+        code,
+        message,
+        field: None,
+        current: None,
+    };
+    (status, Json(body)).into_response()
+}
+
+fn is_json(headers: &HeaderMap) -> bool {
+    headers
+        .get(CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.starts_with("application/json"))
+}
+
+/// Names can't be blank.
+///
+/// # Errors
+///
+/// This function will return an error if the name is empty or only whitespace.
+pub(crate) fn check_name(name: &Name) -> Result<(), TarsError> {
+    if name.trim().is_empty() {
+        return Err(TarsError::invalid("name", "can't be empty"));
+    }
+
+    Ok(())
+}
+
+/// Colors have to be something the TUI can draw.
+///
+/// # Errors
+///
+/// This function will return an error if the color can't be parsed.
+pub(crate) fn check_color(color: &Color) -> Result<(), TarsError> {
+    Color::parse_str(color.as_str())
+        .map(|_| ())
+        .map_err(|e| TarsError::invalid("color", e.to_string()))
+}
+
+/// Makes sure the group `field` refers to exists.
+///
+/// # Errors
+///
+/// This function will return an error if
+/// + There is no group with this `Id`.
+/// + Something goes wrong with sqlx.
+pub(crate) async fn check_group_exists(
+    field: &str,
+    id: &Id,
+    conn: &mut SqliteConnection,
+) -> Result<(), TarsError> {
+    let id = &**id;
+    sqlx::query_scalar!("SELECT pub_id FROM Groups WHERE pub_id = ?", id)
+        .fetch_optional(&mut *conn)
+        .await?
+        .ok_or_else(|| TarsError::not_found(field, format!("there is no group {id}")))?;
+
+    Ok(())
+}
+
+/// Checks a `Group` before it's written.
+///
+/// # Errors
+///
+/// This function will return an error if
+/// + The name is empty or the color invalid.
+/// + The group is its own parent, or the parent doesn't exist.
+/// + Something goes wrong with sqlx.
+pub(crate) async fn check_group(
+    group: &Group,
+    conn: &mut SqliteConnection,
+) -> Result<(), TarsError> {
+    check_name(&group.name)?;
+    check_color(&group.color)?;
+
+    if let Some(ref parent_id) = group.parent_id {
+        if *parent_id == group.id {
+            return Err(TarsError::invalid(
+                "parent_id",
+                "a group can't be its own parent",
+            ));
+        }
+        check_group_exists("parent_id", parent_id, conn).await?;
+    }
+
+    Ok(())
+}
+
+/// Checks that no API token is limited to the group with this `Id`, or one below it. Those
+/// tokens would be revoked along with the group, and undoing the deletion can't bring
+/// them back.
+///
+/// # Errors
+///
+/// This function will return an error if
+/// + A token is limited to the group or one of its descendants.
+/// + Something goes wrong with sqlx.
+pub(crate) async fn check_group_deletable(
+    id: &Id,
+    conn: &mut SqliteConnection,
+) -> Result<(), TarsError> {
+    let id = &**id;
+    let tokens = sqlx::query_scalar!(
+        r#"
+            WITH RECURSIVE subtree(pub_id) AS (
+                SELECT pub_id FROM Groups WHERE pub_id = ?
+                UNION ALL
+                SELECT g.pub_id FROM Groups g JOIN subtree s ON g.parent_id = s.pub_id
+            )
+            SELECT name FROM ApiTokens WHERE group_id IN (SELECT pub_id FROM subtree)
+        "#,
+        id
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    if !tokens.is_empty() {
+        return Err(TarsError::invalid(
+            "id",
+            format!(
+                "the group has tokens limited to it, revoke them first: {}",
+                tokens.join(", ")
+            ),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Checks a `Task` before it's written.
+///
+/// # Errors
+///
+/// This function will return an error if
+/// + The name is empty.
+/// + The group doesn't exist.
+/// + The task blocks itself.
+/// + Something goes wrong with sqlx.
+pub(crate) async fn check_task(task: &Task, conn: &mut SqliteConnection) -> Result<(), TarsError> {
+    check_name(&task.name)?;
+    check_group_exists("group", &task.group.id, conn).await?;
+
+    if task.blocked_by.contains(&task.id) {
+        return Err(TarsError::invalid(
+            "blocked_by",
+            "a task can't block itself",
+        ));
+    }
+
+    Ok(())
+}
+
+/// Checks a `Tag` before it's written.
+///
+/// # Errors
+///
+/// This function will return an error if the name is empty or the color invalid.
+pub(crate) fn check_tag(tag: &Tag) -> Result<(), TarsError> {
+    check_name(&tag.name)?;
+    check_color(&tag.color)
+}
@@ -0,0 +1,67 @@
+use std::{os::unix::fs::PermissionsExt, time::Duration};
+
+use common::{TarsClient, auth::load_token, types::Group};
+use reqwest::StatusCode;
+use tars_daemon::utils::new_authed_test_daemon;
+use tokio::time::{sleep, timeout};
+
+#[tokio::test]
+async fn requests_need_the_token() {
+    let (d, addr, token_path) = new_authed_test_daemon().await;
+
+    let x = tokio::spawn(async move {
+        timeout(Duration::from_secs(2), d.run())
+            .await
+            .unwrap_or_else(|_x| Ok(()))
+            .unwrap();
+    });
+    sleep(Duration::from_secs(1)).await;
+
+    // generated on startup, only readable by us
+    let token = load_token(&token_path)
+        .unwrap()
+        .expect("token should exist");
+    let mode = std::fs::metadata(&token_path).unwrap().permissions().mode();
+    assert_eq!(mode & 0o777, 0o600);
+
+    let raw = reqwest::Client::new();
+    for path in ["/", "/subscribe", "/task/fetch"] {
+        let res = raw.get(format!("{addr}{path}")).send().await.unwrap();
+        assert_eq!(res.status(), StatusCode::UNAUTHORIZED, "{path}");
+    }
+    let res = raw
+        .get(format!("{addr}/"))
+        .bearer_auth("not the token")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+
+    let anonymous = TarsClient::with_token(addr.clone(), None).await.unwrap();
+    assert!(Group::fetch_all(&anonymous).await.is_err());
+
+    let client = TarsClient::with_token(addr.clone(), Some(token.clone()))
+        .await
+        .unwrap();
+    Group::new(
+        &client,
+        "work",
+        None,
+        Default::default(),
+        Default::default(),
+    )
+    .await
+    .unwrap();
+    assert_eq!(Group::fetch_all(&client).await.unwrap().len(), 1);
+
+    // rotating swaps the token on disk, and the old one stops working right away
+    let fresh = client.rotate_token().await.unwrap();
+    assert_ne!(fresh, token);
+    assert_eq!(load_token(&token_path).unwrap(), Some(fresh.clone()));
+    assert!(Group::fetch_all(&client).await.is_err());
+
+    let client = TarsClient::with_token(addr, Some(fresh)).await.unwrap();
+    assert_eq!(Group::fetch_all(&client).await.unwrap().len(), 1);
+
+    x.await.unwrap();
+}
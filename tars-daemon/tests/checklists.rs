@@ -0,0 +1,73 @@
+use std::time::Duration;
+
+use common::{
+    TarsClient,
+    types::{ChecklistItem, Group, Priority, Task, TaskFetchOptions},
+};
+use tars_daemon::utils::new_test_daemon;
+use tokio::time::{sleep, timeout};
+
+#[tokio::test]
+async fn checklist_order_and_undo() {
+    let (d, addr) = new_test_daemon().await;
+
+    let x = tokio::spawn(async move {
+        timeout(Duration::from_secs(2), d.run())
+            .await
+            .unwrap_or_else(|_x| Ok(()))
+            .unwrap();
+    });
+    sleep(Duration::from_secs(1)).await;
+
+    let client = TarsClient::new(addr)
+        .await
+        .expect("failed to instantiate client");
+
+    let group = Group::new(
+        &client,
+        "testing",
+        None,
+        Default::default(),
+        Default::default(),
+    )
+    .await
+    .unwrap();
+
+    let mut task = Task::new(&client, &group, "release", Priority::Low, "", None)
+        .await
+        .unwrap();
+    assert_eq!(task.checklist_progress(), None);
+
+    // items keep the order they were given in, not an alphabetical one
+    task.checklist = vec![
+        ChecklistItem::new("bump version"),
+        ChecklistItem::new("tag"),
+        ChecklistItem::new("publish"),
+    ];
+    task.sync(&client).await.unwrap();
+
+    let fetched = Task::fetch(&client, TaskFetchOptions::All).await.unwrap();
+    assert_eq!(fetched, vec![task.clone()]);
+
+    let unchecked = task.clone();
+    task.checklist[0].done = true;
+    task.checklist.swap(1, 2);
+    task.sync(&client).await.unwrap();
+
+    let fetched = Task::fetch(&client, TaskFetchOptions::All).await.unwrap();
+    assert_eq!(fetched, vec![task.clone()]);
+    assert_eq!(fetched[0].checklist_progress(), Some((1, 3)));
+
+    // undoing brings back the old order and done states, as an update of its own
+    client.undo().await.unwrap();
+    let fetched = Task::fetch(&client, TaskFetchOptions::All).await.unwrap();
+    assert_eq!(
+        fetched,
+        vec![Task {
+            version: task.version + 1,
+            ..unchecked
+        }]
+    );
+
+    x.await.unwrap();
+}
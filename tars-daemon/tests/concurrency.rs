@@ -0,0 +1,226 @@
+use std::time::Duration;
+
+use chrono::Local;
+use common::{
+    DiffInner, TarsClient, TarsError,
+    types::{Group, Id, Name, Priority, Task, TaskFetchOptions},
+};
+use tars_daemon::utils::new_test_daemon;
+use tokio::time::{sleep, timeout};
+
+#[tokio::test]
+async fn stale_updates_conflict() {
+    let (d, addr) = new_test_daemon().await;
+
+    let x = tokio::spawn(async move {
+        timeout(Duration::from_secs(2), d.run())
+            .await
+            .unwrap_or_else(|_x| Ok(()))
+            .unwrap();
+    });
+    sleep(Duration::from_secs(1)).await;
+
+    let client = TarsClient::new(addr)
+        .await
+        .expect("failed to instantiate client");
+
+    let group = Group::new(
+        &client,
+        "testing",
+        None,
+        Default::default(),
+        Default::default(),
+    )
+    .await
+    .unwrap();
+
+    let task = Task::new(&client, &group, "test", Priority::Low, "", None)
+        .await
+        .unwrap();
+    assert_eq!(task.version, 0);
+
+    // two copies of the same task, like two TUIs would have
+    let (mut first, mut second) = (task.clone(), task.clone());
+
+    first.name = Name::from("renamed");
+    first.sync(&client).await.unwrap();
+    assert_eq!(first.version, 1);
+
+    second.priority = Priority::High;
+    let err = second.sync(&client).await.unwrap_err();
+    let TarsError::Conflict(current) = err else {
+        panic!("expected a conflict, got {err:?}");
+    };
+    assert_eq!(*current, DiffInner::Task(first.clone()));
+
+    // nothing of the stale edit made it in
+    let fetched = Task::fetch(&client, TaskFetchOptions::All).await.unwrap();
+    assert_eq!(fetched, vec![first.clone()]);
+
+    // overwriting means taking on the current version
+    second.version = first.version;
+    second.sync(&client).await.unwrap();
+    assert_eq!(second.version, 2);
+    assert_eq!(*second.name, "test");
+    assert_eq!(second.priority, Priority::High);
+
+    // syncing the same copy twice is fine, it keeps up with its own updates
+    second.sync(&client).await.unwrap();
+    assert_eq!(second.version, 3);
+
+    // and the same goes for groups
+    let (mut first, mut second) = (group.clone(), group.clone());
+
+    first.priority = Priority::Asap;
+    first.sync(&client).await.unwrap();
+
+    second.name = Name::from("renamed");
+    let err = second.sync(&client).await.unwrap_err();
+    let TarsError::Conflict(current) = err else {
+        panic!("expected a conflict, got {err:?}");
+    };
+    assert_eq!(*current, DiffInner::Group(first.clone()));
+    assert_eq!(Group::fetch_all(&client).await.unwrap(), vec![first]);
+
+    x.await.unwrap();
+}
+
+#[tokio::test]
+async fn stale_deletes_conflict() {
+    let (d, addr) = new_test_daemon().await;
+
+    let x = tokio::spawn(async move {
+        timeout(Duration::from_secs(2), d.run())
+            .await
+            .unwrap_or_else(|_x| Ok(()))
+            .unwrap();
+    });
+    sleep(Duration::from_secs(1)).await;
+
+    let client = TarsClient::new(addr)
+        .await
+        .expect("failed to instantiate client");
+
+    let group = Group::new(
+        &client,
+        "testing",
+        None,
+        Default::default(),
+        Default::default(),
+    )
+    .await
+    .unwrap();
+
+    // a task that starts out finished keeps that when it's created
+    let done = Task::with_all_fields(
+        Id::default(),
+        group.clone(),
+        "done",
+        Priority::Low,
+        String::new(),
+        Some(Local::now().naive_local()),
+        Local::now().naive_local(),
+        None,
+    );
+    done.raw_create(&client).await.unwrap();
+    assert!(
+        Task::fetch(&client, TaskFetchOptions::All).await.unwrap()[0]
+            .finished_at
+            .is_some()
+    );
+
+    // someone else renames the group, deleting the copy from before that is refused
+    let mut renamed = group.clone();
+    renamed.name = Name::from("renamed");
+    renamed.sync(&client).await.unwrap();
+
+    let err = group.delete(&client).await.unwrap_err();
+    let TarsError::Conflict(current) = err else {
+        panic!("expected a conflict, got {err:?}");
+    };
+    assert_eq!(*current, DiffInner::Group(renamed.clone()));
+    assert_eq!(
+        Group::fetch_all(&client).await.unwrap(),
+        vec![renamed.clone()]
+    );
+
+    // the current copy goes through
+    renamed.delete(&client).await.unwrap();
+    assert!(Group::fetch_all(&client).await.unwrap().is_empty());
+
+    x.await.unwrap();
+}
+
+#[tokio::test]
+async fn stale_moves_conflict() {
+    let (d, addr) = new_test_daemon().await;
+
+    let x = tokio::spawn(async move {
+        timeout(Duration::from_secs(2), d.run())
+            .await
+            .unwrap_or_else(|_x| Ok(()))
+            .unwrap();
+    });
+    sleep(Duration::from_secs(1)).await;
+
+    let client = TarsClient::new(addr)
+        .await
+        .expect("failed to instantiate client");
+
+    let parent = Group::new(
+        &client,
+        "parent",
+        None,
+        Default::default(),
+        Default::default(),
+    )
+    .await
+    .unwrap();
+
+    let child = Group::new(
+        &client,
+        "child",
+        Some(parent.id.clone()),
+        Default::default(),
+        Default::default(),
+    )
+    .await
+    .unwrap();
+
+    // an inspector still holding the child from before it was moved
+    let mut stale = child.clone();
+
+    let moved = child.move_to(&client, None).await.unwrap();
+    assert_eq!(moved.parent_id, None);
+    assert_eq!(moved.version, child.version + 1);
+
+    // syncing it can't put the group back where it was
+    stale.name = Name::from("renamed");
+    let err = stale.sync(&client).await.unwrap_err();
+    let TarsError::Conflict(current) = err else {
+        panic!("expected a conflict, got {err:?}");
+    };
+    assert_eq!(*current, DiffInner::Group(moved.clone()));
+
+    // and neither can moving it
+    let err = stale
+        .move_to(&client, Some(parent.id.clone()))
+        .await
+        .unwrap_err();
+    let TarsError::Conflict(current) = err else {
+        panic!("expected a conflict, got {err:?}");
+    };
+    assert_eq!(*current, DiffInner::Group(moved.clone()));
+
+    let fetched = Group::fetch_all(&client).await.unwrap();
+    assert!(fetched.contains(&moved));
+
+    // the current copy moves fine
+    let moved = moved
+        .move_to(&client, Some(parent.id.clone()))
+        .await
+        .unwrap();
+    assert_eq!(moved.parent_id, Some(parent.id.clone()));
+
+    x.await.unwrap();
+}
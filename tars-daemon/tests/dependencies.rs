@@ -0,0 +1,123 @@
+use std::time::Duration;
+
+use common::{
+    TarsClient,
+    types::{Group, Priority, Task, TaskFetchOptions},
+};
+use tars_daemon::utils::new_test_daemon;
+use tokio::time::{sleep, timeout};
+
+#[tokio::test]
+async fn dependency_cycles() {
+    let (d, addr) = new_test_daemon().await;
+
+    let x = tokio::spawn(async move {
+        timeout(Duration::from_secs(2), d.run())
+            .await
+            .unwrap_or_else(|_x| Ok(()))
+            .unwrap();
+    });
+    sleep(Duration::from_secs(1)).await;
+
+    let client = TarsClient::new(addr)
+        .await
+        .expect("failed to instantiate client");
+
+    let group = Group::new(
+        &client,
+        "testing",
+        None,
+        Default::default(),
+        Default::default(),
+    )
+    .await
+    .unwrap();
+
+    let migration = Task::new(&client, &group, "migration", Priority::Low, "", None)
+        .await
+        .unwrap();
+    let mut deploy = Task::new(&client, &group, "deploy", Priority::Low, "", None)
+        .await
+        .unwrap();
+    let mut announce = Task::new(&client, &group, "announce", Priority::Low, "", None)
+        .await
+        .unwrap();
+    let unrelated = Task::new(&client, &group, "unrelated", Priority::Low, "", None)
+        .await
+        .unwrap();
+
+    deploy.block_on(&client, &migration.id).await.unwrap();
+    announce.block_on(&client, &deploy.id).await.unwrap();
+    assert_eq!(deploy.blocked_by, vec![migration.id.clone()]);
+
+    // anything closing the loop gets refused
+    let mut looping = migration.clone();
+    assert!(looping.block_on(&client, &announce.id).await.is_err());
+    assert!(looping.block_on(&client, &migration.id).await.is_err());
+
+    let deps = deploy.fetch_dependencies(&client).await.unwrap();
+    assert_eq!(deps.blockers, vec![migration.clone()]);
+    assert_eq!(deps.dependents, vec![announce.clone()]);
+
+    // holding up two tasks makes the migration more pressing than a lone task
+    assert!(migration.p_score(&client).await.unwrap() > unrelated.p_score(&client).await.unwrap());
+
+    deploy.unblock(&client, &migration.id).await.unwrap();
+    assert!(deploy.blocked_by.is_empty());
+
+    x.await.unwrap();
+}
+
+#[tokio::test]
+async fn undo_blocker_delete() {
+    let (d, addr) = new_test_daemon().await;
+
+    let x = tokio::spawn(async move {
+        timeout(Duration::from_secs(2), d.run())
+            .await
+            .unwrap_or_else(|_x| Ok(()))
+            .unwrap();
+    });
+    sleep(Duration::from_secs(1)).await;
+
+    let client = TarsClient::new(addr)
+        .await
+        .expect("failed to instantiate client");
+
+    let group = Group::new(
+        &client,
+        "testing",
+        None,
+        Default::default(),
+        Default::default(),
+    )
+    .await
+    .unwrap();
+
+    let blocker = Task::new(&client, &group, "blocker", Priority::Low, "", None)
+        .await
+        .unwrap();
+    let mut blocked = Task::new(&client, &group, "blocked", Priority::Low, "", None)
+        .await
+        .unwrap();
+    blocked.block_on(&client, &blocker.id).await.unwrap();
+
+    // deleting the blocker frees up the blocked task
+    blocker.delete(&client).await.unwrap();
+    let fetched = Task::fetch(&client, TaskFetchOptions::All).await.unwrap();
+    assert!(fetched[0].blocked_by.is_empty());
+
+    // and undoing puts the edge back
+    client.undo().await.unwrap();
+    let fetched = Task::fetch(&client, TaskFetchOptions::All).await.unwrap();
+    let restored = fetched.iter().find(|t| t.id == blocked.id).unwrap();
+    assert_eq!(
+        *restored,
+        Task {
+            version: blocked.version + 1,
+            ..blocked
+        }
+    );
+
+    x.await.unwrap();
+}
@@ -116,3 +116,68 @@ async fn group_delete() {
 
     x.await.unwrap();
 }
+
+#[tokio::test]
+async fn group_move() {
+    let (d, addr) = new_test_daemon().await;
+
+    let x = tokio::spawn(async move {
+        timeout(Duration::from_secs(2), d.run())
+            .await
+            .unwrap_or_else(|_x| Ok(()))
+            .unwrap();
+    });
+    sleep(Duration::from_secs(1)).await;
+
+    let client = TarsClient::new(addr)
+        .await
+        .expect("failed to instantiate client");
+
+    let parent = Group::new(
+        &client,
+        "parent",
+        None,
+        Default::default(),
+        Default::default(),
+    )
+    .await
+    .unwrap();
+
+    let child = Group::new(
+        &client,
+        "child",
+        Some(parent.id.clone()),
+        Default::default(),
+        Default::default(),
+    )
+    .await
+    .unwrap();
+
+    // a group can't end up inside itself
+    assert!(
+        parent
+            .move_to(&client, Some(child.id.clone()))
+            .await
+            .is_err()
+    );
+    assert!(
+        parent
+            .move_to(&client, Some(parent.id.clone()))
+            .await
+            .is_err()
+    );
+
+    let moved = child.move_to(&client, None).await.unwrap();
+    assert_eq!(moved.parent_id, None);
+
+    let moved = parent
+        .move_to(&client, Some(child.id.clone()))
+        .await
+        .unwrap();
+    assert_eq!(moved.parent_id, Some(child.id.clone()));
+
+    let fetched = Group::fetch_all(&client).await.unwrap();
+    assert!(fetched.contains(&moved));
+
+    x.await.unwrap();
+}
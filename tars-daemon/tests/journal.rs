@@ -0,0 +1,205 @@
+use std::time::Duration;
+
+use chrono::{Local, TimeDelta};
+use common::{
+    TarsClient,
+    types::{Group, Name, Priority, Task, TaskFetchOptions, TimeEntry, TimeRange},
+};
+use tars_daemon::utils::new_test_daemon;
+use tokio::time::{sleep, timeout};
+
+#[tokio::test]
+async fn undo_cascaded_group_delete() {
+    let (d, addr) = new_test_daemon().await;
+
+    let x = tokio::spawn(async move {
+        timeout(Duration::from_secs(2), d.run())
+            .await
+            .unwrap_or_else(|_x| Ok(()))
+            .unwrap();
+    });
+    sleep(Duration::from_secs(1)).await;
+
+    let client = TarsClient::new(addr)
+        .await
+        .expect("failed to instantiate client");
+
+    let root = Group::new(
+        &client,
+        "root",
+        None,
+        Default::default(),
+        Default::default(),
+    )
+    .await
+    .unwrap();
+
+    let child = Group::new(
+        &client,
+        "child",
+        Some(root.id.clone()),
+        Default::default(),
+        Default::default(),
+    )
+    .await
+    .unwrap();
+
+    let root_task = Task::new(&client, &root, "root task", Priority::Low, "nothing", None)
+        .await
+        .unwrap();
+    let child_task = Task::new(
+        &client,
+        &child,
+        "child task",
+        Priority::High,
+        "nothing",
+        None,
+    )
+    .await
+    .unwrap();
+
+    // time tracked on both, the timer is still running on the child task
+    TimeEntry::start(&client, &root_task.id, Some("reading".to_owned()))
+        .await
+        .unwrap();
+    TimeEntry::start(&client, &child_task.id, None)
+        .await
+        .unwrap();
+
+    let now = Local::now().naive_local();
+    let range = TimeRange {
+        from: now - TimeDelta::days(1),
+        to: now + TimeDelta::days(1),
+    };
+    let entries = TimeEntry::fetch(&client, range).await.unwrap();
+    assert_eq!(entries.len(), 2);
+
+    let mut groups = Group::fetch_all(&client).await.unwrap();
+    let mut tasks = Task::fetch(&client, TaskFetchOptions::All).await.unwrap();
+    groups.sort();
+    tasks.sort();
+
+    root.delete(&client).await.unwrap();
+    assert!(Group::fetch_all(&client).await.unwrap().is_empty());
+    assert!(
+        Task::fetch(&client, TaskFetchOptions::All)
+            .await
+            .unwrap()
+            .is_empty()
+    );
+    assert!(TimeEntry::fetch(&client, range).await.unwrap().is_empty());
+
+    // everything comes back with the same ids
+    let diffs = client.undo().await.unwrap();
+    assert_eq!(diffs.len(), 4);
+
+    let mut restored_groups = Group::fetch_all(&client).await.unwrap();
+    let mut restored_tasks = Task::fetch(&client, TaskFetchOptions::All).await.unwrap();
+    restored_groups.sort();
+    restored_tasks.sort();
+
+    assert_eq!(groups, restored_groups);
+    assert_eq!(tasks, restored_tasks);
+
+    // along with the time tracked on them, timer included
+    assert_eq!(TimeEntry::fetch(&client, range).await.unwrap(), entries);
+    assert_eq!(
+        TimeEntry::running(&client).await.unwrap().as_ref(),
+        entries.last()
+    );
+
+    // and redoing takes it all out again
+    client.redo().await.unwrap();
+    assert!(Group::fetch_all(&client).await.unwrap().is_empty());
+
+    // nothing left to redo
+    assert!(client.redo().await.unwrap().is_empty());
+
+    x.await.unwrap();
+}
+
+#[tokio::test]
+async fn undo_update() {
+    let (d, addr) = new_test_daemon().await;
+
+    let x = tokio::spawn(async move {
+        timeout(Duration::from_secs(2), d.run())
+            .await
+            .unwrap_or_else(|_x| Ok(()))
+            .unwrap();
+    });
+    sleep(Duration::from_secs(1)).await;
+
+    let client = TarsClient::new(addr)
+        .await
+        .expect("failed to instantiate client");
+
+    let group = Group::new(
+        &client,
+        "testing",
+        None,
+        Default::default(),
+        Default::default(),
+    )
+    .await
+    .unwrap();
+
+    let original = Task::new(&client, &group, "test", Priority::Low, "nothing", None)
+        .await
+        .unwrap();
+
+    let mut renamed = original.clone();
+    renamed.name = Name::from("renamed");
+    renamed.sync(&client).await.unwrap();
+
+    // undoing and redoing are updates of their own, so the version keeps going up
+    client.undo().await.unwrap();
+    let fetched = Task::fetch(&client, TaskFetchOptions::All).await.unwrap();
+    assert_eq!(
+        fetched,
+        vec![Task {
+            version: renamed.version + 1,
+            ..original.clone()
+        }]
+    );
+
+    client.redo().await.unwrap();
+    let fetched = Task::fetch(&client, TaskFetchOptions::All).await.unwrap();
+    assert_eq!(
+        fetched,
+        vec![Task {
+            version: renamed.version + 2,
+            ..renamed.clone()
+        }]
+    );
+
+    // a new change after undoing throws away what could have been redone
+    client.undo().await.unwrap();
+    let mut reprioritized = Task {
+        version: renamed.version + 3,
+        ..original.clone()
+    };
+    reprioritized.priority = Priority::High;
+    reprioritized.sync(&client).await.unwrap();
+
+    assert!(client.redo().await.unwrap().is_empty());
+
+    // deleting the task takes its running timer along, undoing brings it back
+    TimeEntry::start(&client, &reprioritized.id, None)
+        .await
+        .unwrap();
+    let running = TimeEntry::running(&client).await.unwrap();
+    reprioritized.delete(&client).await.unwrap();
+    assert!(TimeEntry::running(&client).await.unwrap().is_none());
+    client.undo().await.unwrap();
+    assert_eq!(TimeEntry::running(&client).await.unwrap(), running);
+
+    // undoing everything removes the task and the group
+    client.undo().await.unwrap();
+    client.undo().await.unwrap();
+    client.undo().await.unwrap();
+    assert!(Group::fetch_all(&client).await.unwrap().is_empty());
+    assert!(client.undo().await.unwrap().is_empty());
+
+    x.await.unwrap();
+}
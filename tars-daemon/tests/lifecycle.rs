@@ -0,0 +1,106 @@
+use std::{path::PathBuf, time::Duration};
+
+use common::{TarsClient, TarsError, discovery, types::Group};
+use tars_daemon::{DaemonConfig, DaemonState, Db, TarsDaemon, pidfile::PidFile};
+use tokio::time::{sleep, timeout};
+
+fn test_dir(name: &str) -> PathBuf {
+    let dir = PathBuf::from(format!(
+        "/tmp/tars/test-lifecycle/{}/{name}",
+        *common::types::Id::default()
+    ));
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+#[test]
+fn config_file() {
+    let dir = test_dir("config");
+    let path = dir.join("daemon.toml");
+    std::fs::write(
+        &path,
+        r#"
+        listen = "127.0.0.1:4321"
+        database = "/srv/tars/tars.db"
+        log_level = "debug"
+        "#,
+    )
+    .unwrap();
+
+    let config = DaemonConfig::load_from(path).unwrap();
+    assert_eq!(config.listen_addr(), "127.0.0.1:4321");
+    assert_eq!(config.db_path(), PathBuf::from("/srv/tars/tars.db"));
+    assert_eq!(config.log_level.as_deref(), Some("debug"));
+    // the pidfile goes next to the database unless it's set
+    assert_eq!(
+        config.pidfile_path(),
+        PathBuf::from("/srv/tars/tars.db.pid")
+    );
+
+    let defaults = DaemonConfig::load_from(dir.join("missing.toml")).unwrap();
+    assert_eq!(defaults.listen_addr(), common::DAEMON_ADDR);
+    assert_eq!(defaults.db_path(), Db::default_path());
+}
+
+#[test]
+fn one_daemon_per_pidfile() {
+    let path = test_dir("pidfile").join("tars.db.pid");
+
+    let held = PidFile::acquire(path.clone()).unwrap();
+    assert_eq!(
+        std::fs::read_to_string(&path).unwrap(),
+        std::process::id().to_string()
+    );
+
+    let err = PidFile::acquire(path.clone()).unwrap_err();
+    assert!(err.to_string().contains(&std::process::id().to_string()));
+
+    drop(held);
+    PidFile::acquire(path).unwrap();
+}
+
+#[tokio::test]
+async fn discovery_file() {
+    let path = test_dir("discovery").join(discovery::DISCOVERY_FILE);
+
+    // the OS picks the port, only the discovery file knows which
+    let state = DaemonState::new(Db::new(true).await.unwrap(), "127.0.0.1:0");
+    let d = TarsDaemon::init(state).await.with_discovery(path.clone());
+
+    let x = tokio::spawn(async move {
+        timeout(Duration::from_secs(2), d.run())
+            .await
+            .unwrap_or_else(|_x| Ok(()))
+            .unwrap();
+    });
+    sleep(Duration::from_secs(1)).await;
+
+    let addr = discovery::read_address(&path).unwrap().unwrap();
+    assert!(addr.starts_with("http://127.0.0.1:"));
+    assert!(!addr.ends_with(":0"));
+
+    let client = TarsClient::with_token(addr, None).await.unwrap();
+    Group::new(
+        &client,
+        "work",
+        None,
+        Default::default(),
+        Default::default(),
+    )
+    .await
+    .unwrap();
+    assert_eq!(Group::fetch_all(&client).await.unwrap().len(), 1);
+    assert!(client.is_reachable().await);
+
+    x.await.unwrap();
+
+    // cut off without shutting down, so the address stays behind with nothing there
+    assert!(discovery::read_address(&path).unwrap().is_some());
+    assert!(!client.is_reachable().await);
+
+    // a mistyped address is an error rather than a panic
+    let err = TarsClient::with_token("127.0.0.1:42069:1".to_owned(), None)
+        .await
+        .unwrap_err();
+    assert!(matches!(err, TarsError::Parse(_)), "{err:?}");
+}
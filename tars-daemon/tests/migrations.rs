@@ -0,0 +1,101 @@
+use std::path::{Path, PathBuf};
+
+use common::types::Id;
+use sqlx::migrate::Migrator;
+use tars_daemon::{Db, MigrationState};
+
+fn test_db_path() -> PathBuf {
+    PathBuf::from(format!("/tmp/tars/test-db/{}/tars.db", *Id::default()))
+}
+
+#[tokio::test]
+async fn old_database_gets_backed_up_and_migrated() {
+    let path = test_db_path();
+
+    // an old database, that only ever saw the first migration
+    let baseline_dir = path.with_file_name("baseline");
+    std::fs::create_dir_all(&baseline_dir).unwrap();
+    std::fs::copy(
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("migrations/20250903013222_schema.sql"),
+        baseline_dir.join("20250903013222_schema.sql"),
+    )
+    .unwrap();
+
+    let db = Db::open(path.clone()).await.unwrap();
+    Migrator::new(baseline_dir.as_path())
+        .await
+        .unwrap()
+        .run(&db.pool)
+        .await
+        .unwrap();
+    sqlx::query("INSERT INTO Groups (pub_id, name, priority) VALUES ('old', 'groceries', 3)")
+        .execute(&db.pool)
+        .await
+        .unwrap();
+
+    let status = db.migration_status().await.unwrap();
+    assert_eq!(status[0].state, MigrationState::Applied);
+    assert!(
+        status[1..]
+            .iter()
+            .all(|m| m.state == MigrationState::Pending)
+    );
+
+    let backup = db.migrate().await.unwrap().expect("should have backed up");
+    assert!(
+        db.migration_status()
+            .await
+            .unwrap()
+            .iter()
+            .all(|m| m.state == MigrationState::Applied)
+    );
+
+    // the data made it through, and the backup still has it in the old schema
+    let (name,): (String,) = sqlx::query_as("SELECT name FROM Groups WHERE pub_id = 'old'")
+        .fetch_one(&db.pool)
+        .await
+        .unwrap();
+    assert_eq!(name, "groceries");
+
+    let backup_db = Db::open(backup).await.unwrap();
+    let backup_status = backup_db.migration_status().await.unwrap();
+    assert_eq!(backup_status[0].state, MigrationState::Applied);
+    assert_eq!(backup_status[1].state, MigrationState::Pending);
+
+    // running it again has nothing left to do
+    assert_eq!(db.migrate().await.unwrap(), None);
+}
+
+#[tokio::test]
+async fn newer_database_is_refused() {
+    let db = Db::open(test_db_path()).await.unwrap();
+    assert_eq!(db.migrate().await.unwrap(), None);
+
+    // pretend a newer daemon migrated this database
+    sqlx::query(
+        "INSERT INTO _sqlx_migrations (version, description, success, checksum, execution_time)
+        VALUES (99990101000000, 'from the future', TRUE, x'00', 0)",
+    )
+    .execute(&db.pool)
+    .await
+    .unwrap();
+
+    let status = db.migration_status().await.unwrap();
+    assert_eq!(status.last().unwrap().state, MigrationState::Unknown);
+    assert!(db.migrate().await.is_err());
+}
+
+#[tokio::test]
+async fn status_leaves_the_database_alone() {
+    let db = Db::open(test_db_path()).await.unwrap();
+
+    let status = db.migration_status().await.unwrap();
+    assert!(!status.is_empty());
+    assert!(status.iter().all(|m| m.state == MigrationState::Pending));
+
+    let tables: Vec<(String,)> = sqlx::query_as("SELECT name FROM sqlite_master")
+        .fetch_all(&db.pool)
+        .await
+        .unwrap();
+    assert_eq!(tables, vec![]);
+}
@@ -0,0 +1,171 @@
+use std::time::Duration;
+
+use chrono::Local;
+use common::{
+    Diff, TarsClient,
+    types::{Group, Priority, Reminder, Task},
+};
+use tars_daemon::{
+    notifier::{Notifier, NotifierConfig, SinkConfig},
+    utils::new_test_daemon,
+};
+use tokio::time::{sleep, timeout};
+
+#[tokio::test]
+async fn reminders_fire_once() {
+    let (d, addr) = new_test_daemon().await;
+    let state = d.state().clone();
+
+    let x = tokio::spawn(async move {
+        timeout(Duration::from_secs(2), d.run())
+            .await
+            .unwrap_or_else(|_x| Ok(()))
+            .unwrap();
+    });
+
+    sleep(Duration::from_secs(1)).await;
+
+    let client = TarsClient::new(addr)
+        .await
+        .expect("failed to instantiate client");
+
+    let group = Group::new(
+        &client,
+        "testing",
+        None,
+        Default::default(),
+        Default::default(),
+    )
+    .await
+    .unwrap();
+
+    let due = Local::now().naive_local() + chrono::TimeDelta::minutes(30);
+    let mut task = Task::new(&client, &group, "test", Priority::Low, "nothing", Some(due))
+        .await
+        .unwrap();
+
+    // not due anytime soon, so shouldn't fire
+    let later = Task::new(
+        &client,
+        &group,
+        "later",
+        Priority::Low,
+        "nothing",
+        Some(due + chrono::TimeDelta::days(7)),
+    )
+    .await
+    .unwrap();
+
+    // the sinks get the whole task, not just the columns the notifier looks at
+    task.block_on(&client, &later.id).await.unwrap();
+    assert_eq!(task.version, 1);
+
+    let log_path = std::env::temp_dir().join(format!("tars-reminders-{}.log", *task.id));
+
+    let config = NotifierConfig {
+        reminders: vec![
+            Reminder::Before(60 * 24),
+            Reminder::Before(60),
+            Reminder::Overdue,
+        ],
+        sinks: vec![
+            SinkConfig::Broadcast,
+            SinkConfig::File {
+                path: log_path.clone(),
+            },
+        ],
+        ..Default::default()
+    };
+
+    let mut rx = state.diffs.subscribe();
+    let notifier = Notifier::new(state.clone(), config.clone());
+
+    // both the 1d and 1h reminders are pending, only the latest should be sent
+    let fired = notifier.check_reminders().await.unwrap();
+    assert_eq!(fired.len(), 1);
+    assert_eq!(fired[0].task, task);
+    assert_eq!(fired[0].reminder, Reminder::Before(60));
+
+    assert!(notifier.check_reminders().await.unwrap().is_empty());
+
+    // a restarted notifier shouldn't send them again either
+    let restarted = Notifier::new(state.clone(), config);
+    assert!(restarted.check_reminders().await.unwrap().is_empty());
+
+    match rx.try_recv().unwrap().diff {
+        Diff::Notification(n) => assert_eq!(*n, fired[0]),
+        diff => panic!("expected a notification, got {diff:?}"),
+    }
+
+    let logged = std::fs::read_to_string(&log_path).unwrap();
+    assert!(logged.contains(&fired[0].message));
+    let _ = std::fs::remove_file(log_path);
+
+    x.await.unwrap()
+}
+
+#[tokio::test]
+async fn undelivered_reminders_fire_again() {
+    let (d, addr) = new_test_daemon().await;
+    let state = d.state().clone();
+
+    let x = tokio::spawn(async move {
+        timeout(Duration::from_secs(2), d.run())
+            .await
+            .unwrap_or_else(|_x| Ok(()))
+            .unwrap();
+    });
+
+    sleep(Duration::from_secs(1)).await;
+
+    let client = TarsClient::new(addr)
+        .await
+        .expect("failed to instantiate client");
+
+    let group = Group::new(
+        &client,
+        "testing",
+        None,
+        Default::default(),
+        Default::default(),
+    )
+    .await
+    .unwrap();
+
+    let due = Local::now().naive_local() + chrono::TimeDelta::minutes(30);
+    let task = Task::new(&client, &group, "test", Priority::Low, "nothing", Some(due))
+        .await
+        .unwrap();
+
+    let reminders = vec![Reminder::Before(60)];
+
+    // nothing listens there, so the reminder can't go anywhere
+    let broken = Notifier::new(
+        state.clone(),
+        NotifierConfig {
+            reminders: reminders.clone(),
+            sinks: vec![SinkConfig::Webhook {
+                url: "http://127.0.0.1:9/tars".to_owned(),
+            }],
+            ..Default::default()
+        },
+    );
+    assert!(broken.check_reminders().await.unwrap().is_empty());
+    assert!(broken.check_reminders().await.unwrap().is_empty());
+
+    // so it's still pending once there's a sink that works
+    let working = Notifier::new(
+        state.clone(),
+        NotifierConfig {
+            reminders,
+            sinks: vec![SinkConfig::Broadcast],
+            ..Default::default()
+        },
+    );
+    let fired = working.check_reminders().await.unwrap();
+    assert_eq!(fired.len(), 1);
+    assert_eq!(fired[0].task, task);
+    assert!(working.check_reminders().await.unwrap().is_empty());
+
+    x.await.unwrap()
+}
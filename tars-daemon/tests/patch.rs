@@ -0,0 +1,214 @@
+use std::time::Duration;
+
+use chrono::Local;
+use common::{
+    Diff, DiffInner, Patch, TarsClient, TarsError,
+    types::{Estimate, Group, GroupPatch, Name, Priority, Task, TaskFetchOptions, TaskPatch},
+};
+use reqwest::StatusCode;
+use tars_daemon::utils::new_test_daemon;
+use tokio::time::{sleep, timeout};
+
+#[tokio::test]
+async fn partial_updates() {
+    let (d, addr) = new_test_daemon().await;
+
+    let x = tokio::spawn(async move {
+        timeout(Duration::from_secs(2), d.run())
+            .await
+            .unwrap_or_else(|_x| Ok(()))
+            .unwrap();
+    });
+    sleep(Duration::from_secs(1)).await;
+
+    let client = TarsClient::new(addr.clone())
+        .await
+        .expect("failed to instantiate client");
+
+    let group = Group::new(
+        &client,
+        "testing",
+        None,
+        Default::default(),
+        Default::default(),
+    )
+    .await
+    .unwrap();
+
+    let due = Local::now().naive_local();
+    let task = Task::new(&client, &group, "test", Priority::Low, "", Some(due))
+        .await
+        .unwrap();
+
+    // someone renames the task, a patch from an older copy leaves that alone
+    let mut renamed = task.clone();
+    renamed.name = Name::from("renamed");
+    renamed.sync(&client).await.unwrap();
+
+    let patched = task
+        .patch()
+        .priority(Priority::High)
+        .estimate(Some(Estimate::from_minutes(30)))
+        .send(&client)
+        .await
+        .unwrap();
+    assert_eq!(*patched.name, "renamed");
+    assert_eq!(patched.priority, Priority::High);
+    assert_eq!(patched.estimate, Some(Estimate::from_minutes(30)));
+    assert_eq!(patched.due, task.due);
+    assert_eq!(patched.version, 2);
+    assert_eq!(
+        Task::fetch(&client, TaskFetchOptions::All).await.unwrap(),
+        vec![patched.clone()]
+    );
+
+    // leaving a field out keeps it, sending null clears it
+    let res = client
+        .conn
+        .post(format!("{addr}/task/patch"))
+        .json(&serde_json::json!({ "id": task.id, "due": null }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+    let cleared: Task = res.json().await.unwrap();
+    assert_eq!(cleared.due, None);
+    assert_eq!(cleared.estimate, Some(Estimate::from_minutes(30)));
+
+    // a patch made against an older version only goes through if asked to check it
+    let err = task
+        .patch()
+        .description("stale")
+        .if_version(patched.version)
+        .send(&client)
+        .await
+        .unwrap_err();
+    let TarsError::Conflict(current) = err else {
+        panic!("expected a conflict, got {err:?}");
+    };
+    assert_eq!(*current, DiffInner::Task(cleared.clone()));
+
+    // patches still go through validation
+    assert!(task.patch().name("").send(&client).await.is_err());
+
+    // and the same goes for groups
+    let patched_group = group
+        .patch()
+        .priority(Priority::Asap)
+        .send(&client)
+        .await
+        .unwrap();
+    assert_eq!(patched_group.name, group.name);
+    assert_eq!(patched_group.priority, Priority::Asap);
+    assert_eq!(patched_group.version, group.version + 1);
+    assert!(
+        group
+            .patch()
+            .name("stale")
+            .if_version(group.version)
+            .send(&client)
+            .await
+            .is_err()
+    );
+
+    x.await.unwrap();
+}
+
+#[tokio::test]
+async fn patches_are_sent_as_field_diffs() {
+    let (d, addr) = new_test_daemon().await;
+
+    let x = tokio::spawn(async move {
+        timeout(Duration::from_secs(2), d.run())
+            .await
+            .unwrap_or_else(|_x| Ok(()))
+            .unwrap();
+    });
+    sleep(Duration::from_secs(1)).await;
+
+    let client = TarsClient::new(addr.clone())
+        .await
+        .expect("failed to instantiate client");
+
+    let group = Group::new(
+        &client,
+        "testing",
+        None,
+        Default::default(),
+        Default::default(),
+    )
+    .await
+    .unwrap();
+    let task = Task::new(&client, &group, "test", Priority::Low, "", None)
+        .await
+        .unwrap();
+
+    let mut stream = client
+        .conn
+        .get(format!("{addr}/subscribe"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(stream.status(), StatusCode::OK);
+
+    // setting a field to what it already is isn't a change
+    let patched = task
+        .patch()
+        .name("test")
+        .priority(Priority::High)
+        .send(&client)
+        .await
+        .unwrap();
+
+    let chunk = timeout(Duration::from_millis(500), stream.chunk())
+        .await
+        .unwrap()
+        .unwrap()
+        .unwrap();
+    let event = std::str::from_utf8(&chunk).unwrap();
+    let data = event
+        .lines()
+        .find_map(|line| line.strip_prefix("data:"))
+        .unwrap();
+    let diff: Diff = serde_json::from_str(data).unwrap();
+    assert_eq!(
+        diff,
+        Diff::Patched(Patch::Task(
+            TaskPatch::new(task.id.clone())
+                .if_version(1)
+                .priority(Priority::High)
+        ))
+    );
+
+    // which is enough to bring an older copy up to date
+    let mut copy = task.clone();
+    let Diff::Patched(Patch::Task(patch)) = diff else {
+        unreachable!()
+    };
+    patch.apply_to(&mut copy);
+    assert_eq!(copy, patched);
+
+    group.patch().name("renamed").send(&client).await.unwrap();
+
+    let chunk = timeout(Duration::from_millis(500), stream.chunk())
+        .await
+        .unwrap()
+        .unwrap()
+        .unwrap();
+    let event = std::str::from_utf8(&chunk).unwrap();
+    let data = event
+        .lines()
+        .find_map(|line| line.strip_prefix("data:"))
+        .unwrap();
+    let diff: Diff = serde_json::from_str(data).unwrap();
+    assert_eq!(
+        diff,
+        Diff::Patched(Patch::Group(
+            GroupPatch::new(group.id.clone())
+                .if_version(1)
+                .name("renamed")
+        ))
+    );
+
+    x.await.unwrap();
+}
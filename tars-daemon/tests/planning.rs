@@ -0,0 +1,115 @@
+use std::time::Duration;
+
+use chrono::Local;
+use common::{
+    TarsClient,
+    types::{Estimate, Group, Plan, Priority, Task, TaskFetchOptions},
+};
+use tars_daemon::utils::new_test_daemon;
+use tokio::time::{sleep, timeout};
+
+#[tokio::test]
+async fn plan_fits_the_available_time() {
+    let (d, addr) = new_test_daemon().await;
+
+    let x = tokio::spawn(async move {
+        timeout(Duration::from_secs(2), d.run())
+            .await
+            .unwrap_or_else(|_x| Ok(()))
+            .unwrap();
+    });
+    sleep(Duration::from_secs(1)).await;
+
+    let client = TarsClient::new(addr)
+        .await
+        .expect("failed to instantiate client");
+
+    let work = Group::new(
+        &client,
+        "work",
+        None,
+        Default::default(),
+        Default::default(),
+    )
+    .await
+    .unwrap();
+    let home = Group::new(
+        &client,
+        "home",
+        None,
+        Default::default(),
+        Default::default(),
+    )
+    .await
+    .unwrap();
+
+    let estimated = |estimate: &str| Some(Estimate::parse_str(estimate).unwrap());
+
+    let mut report = Task::new(&client, &work, "report", Priority::Asap, "", None)
+        .await
+        .unwrap();
+    report.estimate = estimated("2h");
+    report.sync(&client).await.unwrap();
+
+    let mut review = Task::new(&client, &work, "review", Priority::High, "", None)
+        .await
+        .unwrap();
+    review.estimate = estimated("1h30m");
+    review.sync(&client).await.unwrap();
+
+    let mut dishes = Task::new(&client, &home, "dishes", Priority::Medium, "", None)
+        .await
+        .unwrap();
+    dishes.estimate = estimated("45m");
+    dishes.sync(&client).await.unwrap();
+
+    // none of these should ever be picked
+    Task::new(&client, &work, "someday", Priority::Asap, "", None)
+        .await
+        .unwrap();
+
+    let mut done = Task::new(&client, &work, "done", Priority::Asap, "", None)
+        .await
+        .unwrap();
+    done.estimate = estimated("30m");
+    done.finished_at = Some(Local::now().naive_local());
+    done.sync(&client).await.unwrap();
+
+    let mut waiting = Task::new(&client, &home, "waiting", Priority::Asap, "", None)
+        .await
+        .unwrap();
+    waiting.estimate = estimated("30m");
+    waiting.sync(&client).await.unwrap();
+    waiting.block_on(&client, &dishes.id).await.unwrap();
+
+    // the estimate survives a round trip
+    let fetched = Task::fetch(&client, TaskFetchOptions::All).await.unwrap();
+    let fetched = fetched.iter().find(|t| t.id == report.id).unwrap();
+    assert_eq!(fetched.estimate, Some(Estimate::from_minutes(120)));
+
+    // the review doesn't fit after the report, the dishes still do
+    let plan = Plan::fetch(&client, Estimate::from_minutes(180), None)
+        .await
+        .unwrap();
+    let picked: Vec<_> = plan.tasks.iter().map(|p| p.task.id.clone()).collect();
+    assert_eq!(picked, vec![report.id.clone(), dishes.id.clone()]);
+    assert_eq!(plan.planned, Estimate::from_minutes(165));
+    assert_eq!(plan.unestimated, 1);
+
+    let work_load = plan.groups.iter().find(|g| g.group_id == work.id).unwrap();
+    assert_eq!(work_load.planned, Estimate::from_minutes(120));
+    assert_eq!(work_load.open, Estimate::from_minutes(210));
+    let home_load = plan.groups.iter().find(|g| g.group_id == home.id).unwrap();
+    assert_eq!(home_load.planned, Estimate::from_minutes(45));
+    assert_eq!(home_load.open, Estimate::from_minutes(45));
+
+    // scoping to a group only plans its tasks
+    let plan = Plan::fetch(&client, Estimate::from_minutes(180), Some(home.id.clone()))
+        .await
+        .unwrap();
+    let picked: Vec<_> = plan.tasks.iter().map(|p| p.task.id.clone()).collect();
+    assert_eq!(picked, vec![dishes.id.clone()]);
+    assert_eq!(plan.unestimated, 0);
+
+    x.await.unwrap();
+}
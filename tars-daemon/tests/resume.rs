@@ -0,0 +1,234 @@
+use std::time::Duration;
+
+use common::{
+    Diff, DiffInner, RESYNC_EVENT, TarsClient,
+    types::{Group, Name, Priority, Snapshot, Task},
+};
+use reqwest::{Response, StatusCode};
+use tars_daemon::utils::new_test_daemon;
+use tokio::time::{sleep, timeout};
+
+/// A single server-sent event, only the fields `/subscribe` uses.
+#[derive(Debug, Default)]
+struct SseEvent {
+    id: Option<String>,
+    event: Option<String>,
+    data: String,
+}
+
+/// Opens `/subscribe`, resuming after `last_event_id` if provided.
+async fn subscribe(client: &TarsClient, last_event_id: Option<&str>) -> Response {
+    let mut req = client
+        .conn
+        .get(client.base_path.join("/subscribe").unwrap());
+    if let Some(id) = last_event_id {
+        req = req.header("Last-Event-ID", id);
+    }
+
+    let res = req.send().await.unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+    res
+}
+
+/// Reads the next `n` events off the stream, skipping keep-alives.
+async fn next_events(stream: &mut Response, n: usize) -> Vec<SseEvent> {
+    let mut buf = String::new();
+    let mut events = Vec::new();
+
+    while events.len() < n {
+        let chunk = timeout(Duration::from_millis(500), stream.chunk())
+            .await
+            .unwrap()
+            .unwrap()
+            .unwrap();
+        buf.push_str(std::str::from_utf8(&chunk).unwrap());
+
+        while let Some((raw, rest)) = buf.split_once("\n\n") {
+            let mut event = SseEvent::default();
+            for line in raw.lines() {
+                let Some((field, value)) = line.split_once(':') else {
+                    continue;
+                };
+                let value = value.trim().to_owned();
+                match field {
+                    "id" => event.id = Some(value),
+                    "event" => event.event = Some(value),
+                    "data" => event.data = value,
+                    _ => {}
+                }
+            }
+
+            if event.id.is_some() || event.event.is_some() {
+                events.push(event);
+            }
+            buf = rest.to_owned();
+        }
+    }
+
+    events
+}
+
+fn diff_of(event: &SseEvent) -> Diff {
+    serde_json::from_str(&event.data).unwrap()
+}
+
+#[tokio::test]
+async fn resume_from_last_event_id() {
+    let (d, addr) = new_test_daemon().await;
+    let state = d.state().clone();
+
+    let x = tokio::spawn(async move {
+        timeout(Duration::from_secs(2), d.run())
+            .await
+            .unwrap_or_else(|_x| Ok(()))
+            .unwrap();
+    });
+    sleep(Duration::from_secs(1)).await;
+
+    let client = TarsClient::new(addr)
+        .await
+        .expect("failed to instantiate client");
+
+    let group = Group::new(
+        &client,
+        "testing",
+        None,
+        Default::default(),
+        Default::default(),
+    )
+    .await
+    .unwrap();
+    let mut task = Task::new(&client, &group, "test", Priority::Low, "", None)
+        .await
+        .unwrap();
+
+    // a client that saw the group being added gets the task it missed, then live diffs
+    let mut stream = subscribe(&client, Some("1")).await;
+    let missed = next_events(&mut stream, 1).await;
+    assert_eq!(missed[0].id.as_deref(), Some("2"));
+    assert_eq!(
+        diff_of(&missed[0]),
+        Diff::Added(DiffInner::Task(task.clone()))
+    );
+
+    task.name = Name::from("renamed");
+    task.sync(&client).await.unwrap();
+    let live = next_events(&mut stream, 1).await;
+    assert_eq!(live[0].id.as_deref(), Some("3"));
+    assert_eq!(
+        diff_of(&live[0]),
+        Diff::Updated(DiffInner::Task(task.clone()))
+    );
+
+    // one that's up to date only gets live diffs
+    let mut stream = subscribe(&client, Some("3")).await;
+    task.priority = Priority::High;
+    task.sync(&client).await.unwrap();
+    let live = next_events(&mut stream, 1).await;
+    assert_eq!(live[0].id.as_deref(), Some("4"));
+    assert_eq!(
+        diff_of(&live[0]),
+        Diff::Updated(DiffInner::Task(task.clone()))
+    );
+
+    // ids this daemon never handed out can't be resumed from
+    for id in ["99", "garbage"] {
+        let mut stream = subscribe(&client, Some(id)).await;
+        let events = next_events(&mut stream, 1).await;
+        assert_eq!(events[0].event.as_deref(), Some(RESYNC_EVENT));
+        assert_eq!(events[0].id, None);
+    }
+
+    // and neither can ones that aren't kept anymore
+    sqlx::query("DELETE FROM Diffs WHERE seq <= 2")
+        .execute(&state.pool)
+        .await
+        .unwrap();
+    let mut stream = subscribe(&client, Some("1")).await;
+    let events = next_events(&mut stream, 1).await;
+    assert_eq!(events[0].event.as_deref(), Some(RESYNC_EVENT));
+
+    // what is still kept replays fine
+    let mut stream = subscribe(&client, Some("2")).await;
+    let missed = next_events(&mut stream, 2).await;
+    let ids: Vec<_> = missed.iter().map(|e| e.id.as_deref()).collect();
+    assert_eq!(ids, vec![Some("3"), Some("4")]);
+
+    x.await.unwrap();
+}
+
+#[tokio::test]
+async fn subscribe_from_snapshot() {
+    let (d, addr) = new_test_daemon().await;
+
+    let x = tokio::spawn(async move {
+        timeout(Duration::from_secs(2), d.run())
+            .await
+            .unwrap_or_else(|_x| Ok(()))
+            .unwrap();
+    });
+    sleep(Duration::from_secs(1)).await;
+
+    let client = TarsClient::new(addr)
+        .await
+        .expect("failed to instantiate client");
+
+    let group = Group::new(
+        &client,
+        "testing",
+        None,
+        Default::default(),
+        Default::default(),
+    )
+    .await
+    .unwrap();
+
+    let snapshot = Snapshot::fetch(&client).await.unwrap();
+    assert_eq!(snapshot.seq, 1);
+    assert_eq!(snapshot.groups, vec![group.clone()]);
+    assert!(snapshot.tasks.is_empty());
+
+    // tasks keep being added while the snapshot is taken
+    let creates = {
+        let (client, group) = (client.clone(), group.clone());
+        tokio::spawn(async move {
+            let mut created = Vec::new();
+            for i in 0..20 {
+                let task = Task::new(
+                    &client,
+                    &group,
+                    format!("task {i}"),
+                    Priority::Low,
+                    "",
+                    None,
+                )
+                .await
+                .unwrap();
+                created.push(task.id);
+            }
+            created
+        })
+    };
+    sleep(Duration::from_millis(10)).await;
+    let snapshot = Snapshot::fetch(&client).await.unwrap();
+    let mut stream = subscribe(&client, Some(&snapshot.seq.to_string())).await;
+
+    let mut created = creates.await.unwrap();
+
+    // every task is either in the snapshot or comes after it, never both
+    let mut seen: Vec<_> = snapshot.tasks.iter().map(|t| t.id.clone()).collect();
+    let events = next_events(&mut stream, created.len() - seen.len()).await;
+    for event in events.iter() {
+        assert!(event.id.as_ref().unwrap().parse::<i64>().unwrap() > snapshot.seq);
+        match diff_of(event) {
+            Diff::Added(DiffInner::Task(task)) => seen.push(task.id),
+            diff => panic!("expected an added task, got {diff:?}"),
+        }
+    }
+
+    seen.sort();
+    created.sort();
+    assert_eq!(seen, created);
+
+    x.await.unwrap();
+}
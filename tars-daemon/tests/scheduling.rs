@@ -0,0 +1,141 @@
+use std::time::Duration;
+
+use chrono::{NaiveDate, NaiveDateTime};
+use common::{
+    TarsClient,
+    types::{Estimate, Group, Priority, Schedule, ScheduleRequest, Task},
+};
+use tars_daemon::utils::new_test_daemon;
+use tokio::time::{sleep, timeout};
+
+fn at(day: u32, hour: u32, minute: u32) -> NaiveDateTime {
+    NaiveDate::from_ymd_opt(2030, 1, day)
+        .unwrap()
+        .and_hms_opt(hour, minute, 0)
+        .unwrap()
+}
+
+#[tokio::test]
+async fn schedule_over_working_days() {
+    let (d, addr) = new_test_daemon().await;
+
+    let x = tokio::spawn(async move {
+        timeout(Duration::from_secs(2), d.run())
+            .await
+            .unwrap_or_else(|_x| Ok(()))
+            .unwrap();
+    });
+    sleep(Duration::from_secs(1)).await;
+
+    let client = TarsClient::new(addr)
+        .await
+        .expect("failed to instantiate client");
+
+    let work = Group::new(
+        &client,
+        "work",
+        None,
+        Default::default(),
+        Default::default(),
+    )
+    .await
+    .unwrap();
+
+    let add = async |name: &str, priority, due, estimate: Option<&str>| {
+        let mut task = Task::new(&client, &work, name, priority, "", due)
+            .await
+            .unwrap();
+        task.estimate = estimate.map(|e| Estimate::parse_str(e).unwrap());
+        task.sync(&client).await.unwrap();
+        task
+    };
+
+    // monday the 7th of january, 09:00
+    let from = at(7, 9, 0);
+
+    let big = add("big", Priority::Asap, None, Some("10h")).await;
+    let report = add("report", Priority::Low, Some(at(8, 10, 0)), Some("2h")).await;
+    let slides = add("slides", Priority::Low, Some(at(8, 23, 59)), Some("7h")).await;
+    let later = add("later", Priority::Medium, None, Some("1h")).await;
+    let huge = add("huge", Priority::Far, None, Some("50h")).await;
+    add("someday", Priority::High, None, None).await;
+    let mut waiting = add("waiting", Priority::Asap, None, Some("1h")).await;
+    waiting.block_on(&client, &later.id).await.unwrap();
+
+    let schedule = Schedule::fetch(
+        &client,
+        ScheduleRequest {
+            scope: None,
+            from: Some(from),
+            days: Some(7),
+        },
+    )
+    .await
+    .unwrap();
+
+    // the weekend is left out
+    let dates: Vec<NaiveDate> = schedule.days.iter().map(|d| d.date).collect();
+    assert_eq!(
+        dates,
+        (7..=11)
+            .map(|day| NaiveDate::from_ymd_opt(2030, 1, day).unwrap())
+            .collect::<Vec<_>>()
+    );
+
+    let blocks = |day: usize| {
+        schedule.days[day]
+            .blocks
+            .iter()
+            .map(|b| (b.task_id.clone(), b.start, b.end, b.late))
+            .collect::<Vec<_>>()
+    };
+
+    // the big task spills over into tuesday, leaving the report no room before it's due
+    assert_eq!(
+        blocks(0),
+        vec![(big.id.clone(), at(7, 9, 0), at(7, 17, 0), false)]
+    );
+    assert_eq!(
+        blocks(1),
+        vec![
+            (big.id.clone(), at(8, 9, 0), at(8, 11, 0), false),
+            (report.id.clone(), at(8, 11, 0), at(8, 13, 0), true),
+            (slides.id.clone(), at(8, 13, 0), at(8, 17, 0), false),
+            (slides.id.clone(), at(8, 17, 0), at(8, 20, 0), true),
+        ]
+    );
+    assert_eq!(
+        blocks(2)[0],
+        (later.id.clone(), at(9, 9, 0), at(9, 10, 0), false)
+    );
+
+    let over: Vec<NaiveDate> = schedule.over_capacity().map(|d| d.date).collect();
+    assert_eq!(over, vec![NaiveDate::from_ymd_opt(2030, 1, 8).unwrap()]);
+    assert_eq!(schedule.days[1].booked, Estimate::from_minutes(660));
+
+    let late: Vec<_> = schedule
+        .late
+        .iter()
+        .map(|l| (l.task_id.clone(), l.missing))
+        .collect();
+    assert_eq!(
+        late,
+        vec![
+            (report.id.clone(), Estimate::from_minutes(120)),
+            (slides.id.clone(), Estimate::from_minutes(180)),
+        ]
+    );
+
+    // wednesday to friday only hold 23 of its 50 hours
+    assert_eq!(schedule.unplaced.len(), 1);
+    assert_eq!(schedule.unplaced[0].task_id, huge.id);
+    assert_eq!(
+        schedule.unplaced[0].remaining,
+        Estimate::from_minutes(27 * 60)
+    );
+
+    assert_eq!(schedule.unestimated, 1);
+    assert_eq!(schedule.blocked, 1);
+
+    x.await.unwrap();
+}
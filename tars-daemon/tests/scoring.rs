@@ -0,0 +1,205 @@
+use std::time::Duration;
+
+use chrono::{Local, TimeDelta};
+use common::{
+    TarsClient,
+    types::{ChecklistItem, Estimate, Group, Priority, ScoringStrategyKind, Task},
+};
+use tars_daemon::utils::new_test_daemon;
+use tokio::time::{sleep, timeout};
+
+#[tokio::test]
+async fn switching_strategies() {
+    let (d, addr) = new_test_daemon().await;
+
+    let x = tokio::spawn(async move {
+        timeout(Duration::from_secs(2), d.run())
+            .await
+            .unwrap_or_else(|_x| Ok(()))
+            .unwrap();
+    });
+    sleep(Duration::from_secs(1)).await;
+
+    let client = TarsClient::new(addr)
+        .await
+        .expect("failed to instantiate client");
+
+    assert_eq!(
+        client.scoring_strategy().await.unwrap(),
+        ScoringStrategyKind::Classic
+    );
+
+    let group = Group::new(
+        &client,
+        "work",
+        None,
+        Default::default(),
+        Default::default(),
+    )
+    .await
+    .unwrap();
+
+    let important = Task::new(&client, &group, "roadmap", Priority::Asap, "", None)
+        .await
+        .unwrap();
+    let due = Some(Local::now().naive_local() + TimeDelta::hours(1));
+    let urgent = Task::new(&client, &group, "expenses", Priority::Low, "", due)
+        .await
+        .unwrap();
+
+    // classic lets the nearing due date win
+    assert!(urgent.p_score(&client).await.unwrap() > important.p_score(&client).await.unwrap());
+
+    // eisenhower schedules the important task ahead of the merely urgent one
+    client
+        .set_scoring_strategy(ScoringStrategyKind::Eisenhower)
+        .await
+        .unwrap();
+    assert_eq!(
+        client.scoring_strategy().await.unwrap(),
+        ScoringStrategyKind::Eisenhower
+    );
+    assert!(important.p_score(&client).await.unwrap() > urgent.p_score(&client).await.unwrap());
+
+    // and the batch endpoint follows the switch too
+    let scores = Task::p_scores(&client, &[important.id.clone(), urgent.id.clone()])
+        .await
+        .unwrap();
+    assert!(scores[&important.id] > scores[&urgent.id]);
+
+    x.await.unwrap();
+}
+
+#[tokio::test]
+async fn explain_score() {
+    let (d, addr) = new_test_daemon().await;
+
+    let x = tokio::spawn(async move {
+        timeout(Duration::from_secs(2), d.run())
+            .await
+            .unwrap_or_else(|_x| Ok(()))
+            .unwrap();
+    });
+    sleep(Duration::from_secs(1)).await;
+
+    let client = TarsClient::new(addr)
+        .await
+        .expect("failed to instantiate client");
+
+    let parent = Group::new(&client, "school", None, Priority::High, Default::default())
+        .await
+        .unwrap();
+    let child = Group::new(
+        &client,
+        "CS101",
+        Some(parent.id.clone()),
+        Priority::Low,
+        Default::default(),
+    )
+    .await
+    .unwrap();
+
+    let due = Some(Local::now().naive_local() + TimeDelta::days(2));
+    let task = Task::new(&client, &child, "homework", Priority::Medium, "", due)
+        .await
+        .unwrap();
+
+    let explanation = task.explain_score(&client).await.unwrap();
+    assert_eq!(explanation.strategy, ScoringStrategyKind::Classic);
+    assert_eq!(explanation.priority, Priority::Medium);
+
+    // innermost group first
+    let groups: Vec<_> = explanation.groups.iter().map(|g| g.id.clone()).collect();
+    assert_eq!(groups, [child.id.clone(), parent.id.clone()]);
+    assert!((explanation.groups[1].multiplier - 1.0 / Priority::High as i32 as f64).abs() < 1e-9);
+
+    let days_left = explanation.days_left.unwrap();
+    assert!(days_left > 1.9 && days_left <= 2.0);
+
+    // the breakdown agrees with the score itself
+    assert!((explanation.score - task.p_score(&client).await.unwrap()).abs() < 1e-6);
+    let due_term = explanation
+        .terms
+        .iter()
+        .find(|t| t.name.contains("due"))
+        .unwrap();
+    assert!((due_term.value - (std::f64::consts::E / 3.0).powf(days_left)).abs() < 1e-3);
+
+    x.await.unwrap();
+}
+
+#[tokio::test]
+async fn wsjf_job_size() {
+    let (d, addr) = new_test_daemon().await;
+
+    let x = tokio::spawn(async move {
+        timeout(Duration::from_secs(2), d.run())
+            .await
+            .unwrap_or_else(|_x| Ok(()))
+            .unwrap();
+    });
+    sleep(Duration::from_secs(1)).await;
+
+    let client = TarsClient::new(addr)
+        .await
+        .expect("failed to instantiate client");
+
+    client
+        .set_scoring_strategy(ScoringStrategyKind::Wsjf)
+        .await
+        .unwrap();
+
+    let group = Group::new(&client, "work", None, Priority::Medium, Default::default())
+        .await
+        .unwrap();
+
+    let checklist = |done: &[bool]| -> Vec<ChecklistItem> {
+        done.iter()
+            .map(|&done| {
+                let mut item = ChecklistItem::new("step");
+                item.done = done;
+                item
+            })
+            .collect()
+    };
+
+    let mut steps = Task::new(&client, &group, "steps", Priority::Medium, "", None)
+        .await
+        .unwrap();
+    steps.checklist = checklist(&[false, false, true]);
+    steps.sync(&client).await.unwrap();
+
+    let mut quick = Task::new(&client, &group, "quick", Priority::Medium, "", None)
+        .await
+        .unwrap();
+    quick.checklist = checklist(&[false, false, false]);
+    quick.estimate = Some(Estimate::parse_str("30m").unwrap());
+    quick.sync(&client).await.unwrap();
+
+    let mut long = Task::new(&client, &group, "long", Priority::Medium, "", None)
+        .await
+        .unwrap();
+    long.estimate = Some(Estimate::parse_str("3h").unwrap());
+    long.sync(&client).await.unwrap();
+
+    // the cost of delay is 1/3 * 1/3 for each, divided by how big the job is
+    let value = 1.0 / 9.0;
+    let expected = [
+        // no estimate, so one step plus the two open items
+        (&steps, value / 3.0),
+        // the estimate wins over the checklist, one step plus half an hour's worth
+        (&quick, value / 1.5),
+        (&long, value / 4.0),
+    ];
+
+    for (task, expected) in expected {
+        let score = task.p_score(&client).await.unwrap();
+        assert!(
+            (score - expected).abs() < 1e-9,
+            "{}: {score} != {expected}",
+            *task.name
+        );
+    }
+
+    x.await.unwrap();
+}
@@ -0,0 +1,137 @@
+use std::time::Duration;
+
+use common::{
+    TarsClient,
+    types::{Group, Priority, SearchItem, Task},
+};
+use tars_daemon::utils::new_test_daemon;
+use tokio::time::{sleep, timeout};
+
+#[tokio::test]
+async fn search_ranking() {
+    let (d, addr) = new_test_daemon().await;
+
+    let x = tokio::spawn(async move {
+        timeout(Duration::from_secs(2), d.run())
+            .await
+            .unwrap_or_else(|_x| Ok(()))
+            .unwrap();
+    });
+    sleep(Duration::from_secs(1)).await;
+
+    let client = TarsClient::new(addr)
+        .await
+        .expect("failed to instantiate client");
+
+    let group = Group::new(
+        &client,
+        "errands",
+        None,
+        Default::default(),
+        Default::default(),
+    )
+    .await
+    .unwrap();
+
+    let mentioned = Task::new(
+        &client,
+        &group,
+        "weekend",
+        Priority::Low,
+        "pick up groceries on the way back",
+        None,
+    )
+    .await
+    .unwrap();
+    let named = Task::new(&client, &group, "groceries", Priority::Low, "", None)
+        .await
+        .unwrap();
+    let _ = Task::new(&client, &group, "laundry", Priority::Low, "", None)
+        .await
+        .unwrap();
+
+    // prefixes match, and a hit in the name beats one in the description
+    let matches = Task::search(&client, "grocer", None).await.unwrap();
+    assert_eq!(matches.len(), 2);
+    assert_eq!(matches[0].item, SearchItem::Task(named.clone()));
+    assert_eq!(matches[1].item, SearchItem::Task(mentioned.clone()));
+    assert!(matches[1].snippet.contains("[groceries]"));
+
+    // groups are searched too
+    let matches = Task::search(&client, "errands", None).await.unwrap();
+    assert_eq!(matches[0].item, SearchItem::Group(group.clone()));
+
+    // characters fts5 would choke on are taken literally
+    assert!(
+        Task::search(&client, "\"pick-up\" OR:", None)
+            .await
+            .unwrap()
+            .is_empty()
+    );
+    assert!(Task::search(&client, "   ", None).await.unwrap().is_empty());
+
+    x.await.unwrap();
+}
+
+#[tokio::test]
+async fn search_stays_in_sync() {
+    let (d, addr) = new_test_daemon().await;
+
+    let x = tokio::spawn(async move {
+        timeout(Duration::from_secs(2), d.run())
+            .await
+            .unwrap_or_else(|_x| Ok(()))
+            .unwrap();
+    });
+    sleep(Duration::from_secs(1)).await;
+
+    let client = TarsClient::new(addr)
+        .await
+        .expect("failed to instantiate client");
+
+    let group = Group::new(
+        &client,
+        "CS101",
+        None,
+        Default::default(),
+        Default::default(),
+    )
+    .await
+    .unwrap();
+
+    let mut task = Task::new(&client, &group, "homework", Priority::Low, "", None)
+        .await
+        .unwrap();
+
+    // renames replace the old name in the index
+    task.name = "essay".into();
+    task.sync(&client).await.unwrap();
+    assert!(
+        Task::search(&client, "homework", None)
+            .await
+            .unwrap()
+            .is_empty()
+    );
+    assert_eq!(Task::search(&client, "essay", None).await.unwrap().len(), 1);
+
+    // deleting the group takes its tasks out of the index along with it
+    group.delete(&client).await.unwrap();
+    assert!(
+        Task::search(&client, "essay", None)
+            .await
+            .unwrap()
+            .is_empty()
+    );
+    assert!(
+        Task::search(&client, "CS101", None)
+            .await
+            .unwrap()
+            .is_empty()
+    );
+
+    // and undoing brings them back
+    client.undo().await.unwrap();
+    assert_eq!(Task::search(&client, "essay", None).await.unwrap().len(), 1);
+
+    x.await.unwrap();
+}
@@ -0,0 +1,60 @@
+use std::{os::unix::fs::PermissionsExt, time::Duration};
+
+use common::{Diff, DiffInner, TarsClient, types::Group};
+use reqwest::StatusCode;
+use tars_daemon::utils::new_socket_test_daemon;
+use tokio::time::{sleep, timeout};
+
+#[tokio::test]
+async fn serve_over_unix_socket() {
+    let (d, addr) = new_socket_test_daemon().await;
+    let socket = addr.strip_prefix("unix://").unwrap().to_owned();
+
+    let x = tokio::spawn(async move {
+        timeout(Duration::from_secs(2), d.run())
+            .await
+            .unwrap_or_else(|_x| Ok(()))
+            .unwrap();
+    });
+    sleep(Duration::from_secs(1)).await;
+
+    let mode = std::fs::metadata(&socket).unwrap().permissions().mode();
+    assert_eq!(mode & 0o777, 0o600);
+
+    let client = TarsClient::with_token(addr, None).await.unwrap();
+
+    // the diff stream works over the socket too
+    let mut stream = client
+        .conn
+        .get(client.base_path.join("/subscribe").unwrap())
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(stream.status(), StatusCode::OK);
+
+    let work = Group::new(
+        &client,
+        "work",
+        None,
+        Default::default(),
+        Default::default(),
+    )
+    .await
+    .unwrap();
+    assert_eq!(Group::fetch_all(&client).await.unwrap(), vec![work.clone()]);
+
+    let chunk = timeout(Duration::from_millis(500), stream.chunk())
+        .await
+        .unwrap()
+        .unwrap()
+        .unwrap();
+    let event = String::from_utf8(chunk.to_vec()).unwrap();
+    let data = event
+        .lines()
+        .find_map(|line| line.strip_prefix("data:"))
+        .unwrap();
+    let diff: Diff = serde_json::from_str(data).unwrap();
+    assert_eq!(diff, Diff::Added(DiffInner::Group(work)));
+
+    x.await.unwrap();
+}
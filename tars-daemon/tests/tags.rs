@@ -0,0 +1,169 @@
+use std::time::Duration;
+
+use common::{
+    TarsClient,
+    types::{Color, Group, Name, Priority, Tag, Task, TaskFetchOptions},
+};
+use tars_daemon::utils::new_test_daemon;
+use tokio::time::{sleep, timeout};
+
+#[tokio::test]
+async fn tag_fetch() {
+    let (d, addr) = new_test_daemon().await;
+
+    let x = tokio::spawn(async move {
+        timeout(Duration::from_secs(2), d.run())
+            .await
+            .unwrap_or_else(|_x| Ok(()))
+            .unwrap();
+    });
+    sleep(Duration::from_secs(1)).await;
+
+    let client = TarsClient::new(addr)
+        .await
+        .expect("failed to instantiate client");
+
+    let g_1 = Group::new(
+        &client,
+        "CS101",
+        None,
+        Default::default(),
+        Default::default(),
+    )
+    .await
+    .unwrap();
+    let g_2 = Group::new(
+        &client,
+        "work",
+        None,
+        Default::default(),
+        Default::default(),
+    )
+    .await
+    .unwrap();
+
+    let urgent = Tag::new(&client, "urgent", Color::random()).await.unwrap();
+    let email = Tag::new(&client, "email", Color::random()).await.unwrap();
+
+    // tag names are unique
+    assert!(Tag::new(&client, "urgent", Color::random()).await.is_err());
+
+    let mut t_1 = Task::new(&client, &g_1, "homework", Priority::Low, "", None)
+        .await
+        .unwrap();
+    t_1.set_tags(vec![urgent.clone()]);
+    t_1.sync(&client).await.unwrap();
+
+    let mut t_2 = Task::new(&client, &g_2, "reply", Priority::Low, "", None)
+        .await
+        .unwrap();
+    t_2.set_tags(vec![urgent.clone(), email.clone()]);
+    t_2.sync(&client).await.unwrap();
+
+    let _ = Task::new(&client, &g_2, "untagged", Priority::Low, "", None)
+        .await
+        .unwrap();
+
+    // tasks across groups share a tag
+    let mut fetched = Task::fetch(
+        &client,
+        TaskFetchOptions::ByTag {
+            tag_id: urgent.id.clone(),
+        },
+    )
+    .await
+    .unwrap();
+    fetched.sort();
+    let mut expected = vec![t_1.clone(), t_2.clone()];
+    expected.sort();
+    assert_eq!(fetched, expected);
+
+    let fetched = Task::fetch(
+        &client,
+        TaskFetchOptions::ByTag {
+            tag_id: email.id.clone(),
+        },
+    )
+    .await
+    .unwrap();
+    assert_eq!(fetched, vec![t_2.clone()]);
+
+    // renaming a tag shows up on the tasks carrying it
+    let mut renamed = email.clone();
+    renamed.name = Name::from("inbox");
+    renamed.sync(&client).await.unwrap();
+
+    // which stays unique
+    let mut taken = renamed.clone();
+    taken.name = Name::from("urgent");
+    assert!(taken.sync(&client).await.is_err());
+
+    let fetched = Task::fetch(
+        &client,
+        TaskFetchOptions::ByTag {
+            tag_id: email.id.clone(),
+        },
+    )
+    .await
+    .unwrap();
+    assert!(fetched[0].tags.contains(&renamed));
+
+    x.await.unwrap();
+}
+
+#[tokio::test]
+async fn tag_delete_undo() {
+    let (d, addr) = new_test_daemon().await;
+
+    let x = tokio::spawn(async move {
+        timeout(Duration::from_secs(2), d.run())
+            .await
+            .unwrap_or_else(|_x| Ok(()))
+            .unwrap();
+    });
+    sleep(Duration::from_secs(1)).await;
+
+    let client = TarsClient::new(addr)
+        .await
+        .expect("failed to instantiate client");
+
+    let group = Group::new(
+        &client,
+        "testing",
+        None,
+        Default::default(),
+        Default::default(),
+    )
+    .await
+    .unwrap();
+
+    let tag = Tag::new(&client, "urgent", Color::random()).await.unwrap();
+
+    let mut task = Task::new(&client, &group, "test", Priority::Low, "nothing", None)
+        .await
+        .unwrap();
+    task.set_tags(vec![tag.clone()]);
+    task.sync(&client).await.unwrap();
+
+    // deleting the tag takes it off the task
+    tag.delete(&client).await.unwrap();
+    assert!(Tag::fetch_all(&client).await.unwrap().is_empty());
+
+    let fetched = Task::fetch(&client, TaskFetchOptions::All).await.unwrap();
+    assert!(fetched[0].tags.is_empty());
+
+    // and undoing puts it back
+    client.undo().await.unwrap();
+    assert_eq!(Tag::fetch_all(&client).await.unwrap(), vec![tag.clone()]);
+
+    let fetched = Task::fetch(&client, TaskFetchOptions::All).await.unwrap();
+    assert_eq!(
+        fetched,
+        vec![Task {
+            version: task.version + 1,
+            ..task
+        }]
+    );
+
+    x.await.unwrap();
+}
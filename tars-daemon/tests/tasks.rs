@@ -1,8 +1,9 @@
 use std::time::Duration;
 
+use chrono::{Local, TimeDelta};
 use common::{
     TarsClient,
-    types::{Group, Priority, Task, TaskFetchOptions},
+    types::{Group, Priority, Recurrence, Task, TaskFetchOptions},
 };
 use tars_daemon::utils::new_test_daemon;
 use tokio::time::{sleep, timeout};
@@ -205,3 +206,194 @@ async fn task_delete() {
 
     x.await.unwrap()
 }
+
+#[tokio::test]
+async fn task_move() {
+    let (d, addr) = new_test_daemon().await;
+
+    let x = tokio::spawn(async move {
+        timeout(Duration::from_secs(2), d.run())
+            .await
+            .unwrap_or_else(|_x| Ok(()))
+            .unwrap();
+    });
+
+    sleep(Duration::from_secs(1)).await;
+
+    let client = TarsClient::new(addr)
+        .await
+        .expect("failed to instantiate client");
+
+    let from = Group::new(
+        &client,
+        "from",
+        None,
+        Default::default(),
+        Default::default(),
+    )
+    .await
+    .unwrap();
+
+    let to = Group::new(&client, "to", None, Default::default(), Default::default())
+        .await
+        .unwrap();
+
+    let mut task = Task::new(&client, &from, "test", Priority::Low, "nothing", None)
+        .await
+        .unwrap();
+
+    task.group = to.clone();
+    task.sync(&client).await.unwrap();
+
+    let moved = Task::fetch(
+        &client,
+        TaskFetchOptions::ByGroup {
+            group_id: to.id.clone(),
+            recursive: false,
+        },
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(moved, vec![task]);
+
+    x.await.unwrap()
+}
+
+#[tokio::test]
+async fn recurring_task() {
+    let (d, addr) = new_test_daemon().await;
+
+    let x = tokio::spawn(async move {
+        timeout(Duration::from_secs(2), d.run())
+            .await
+            .unwrap_or_else(|_x| Ok(()))
+            .unwrap();
+    });
+
+    sleep(Duration::from_secs(1)).await;
+
+    let client = TarsClient::new(addr)
+        .await
+        .expect("failed to instantiate client");
+
+    let group = Group::new(
+        &client,
+        "testing",
+        None,
+        Default::default(),
+        Default::default(),
+    )
+    .await
+    .unwrap();
+
+    let due = Local::now().naive_local() + TimeDelta::days(1);
+    let mut task = Task::new(
+        &client,
+        &group,
+        "weekly report",
+        Priority::Medium,
+        "nothing",
+        Some(due),
+    )
+    .await
+    .unwrap();
+
+    task.recurrence = Some(Recurrence::parse_str("weekly").unwrap());
+    task.sync(&client).await.unwrap();
+
+    task.finished_at = Some(Local::now().naive_local());
+    task.sync(&client).await.unwrap();
+
+    // finishing it queued up the next week's report
+    let tasks = Task::fetch(&client, TaskFetchOptions::All).await.unwrap();
+    assert_eq!(tasks.len(), 2);
+
+    let next = tasks.iter().find(|t| t.id != task.id).unwrap();
+    assert_eq!(next.name, task.name);
+    assert_eq!(next.recurrence, task.recurrence);
+    assert_eq!(next.finished_at, None);
+    assert_eq!(next.due, Some(due + TimeDelta::days(7)));
+
+    // undoing the finish takes the next instance away with it
+    client.undo().await.unwrap();
+    let tasks = Task::fetch(&client, TaskFetchOptions::All).await.unwrap();
+    assert_eq!(tasks.len(), 1);
+    assert_eq!(tasks[0].finished_at, None);
+
+    x.await.unwrap()
+}
+
+#[tokio::test]
+async fn batch_scores() {
+    let (d, addr) = new_test_daemon().await;
+
+    let x = tokio::spawn(async move {
+        timeout(Duration::from_secs(2), d.run())
+            .await
+            .unwrap_or_else(|_x| Ok(()))
+            .unwrap();
+    });
+    sleep(Duration::from_secs(1)).await;
+
+    let client = TarsClient::new(addr)
+        .await
+        .expect("failed to instantiate client");
+
+    let parent = Group::new(&client, "school", None, Priority::High, Default::default())
+        .await
+        .unwrap();
+    let child = Group::new(
+        &client,
+        "CS101",
+        Some(parent.id.clone()),
+        Priority::Low,
+        Default::default(),
+    )
+    .await
+    .unwrap();
+
+    let due = Some(Local::now().naive_local() + TimeDelta::days(2));
+    let mut tasks = [
+        Task::new(&client, &parent, "apply", Priority::Asap, "", None)
+            .await
+            .unwrap(),
+        Task::new(&client, &child, "homework", Priority::High, "", due)
+            .await
+            .unwrap(),
+        Task::new(&client, &child, "reading", Priority::Far, "", None)
+            .await
+            .unwrap(),
+    ];
+    let blocker = tasks[1].id.clone();
+    tasks[2].block_on(&client, &blocker).await.unwrap();
+
+    let ids: Vec<_> = tasks.iter().map(|t| t.id.clone()).collect();
+    let scores = Task::p_scores(&client, &ids).await.unwrap();
+    assert_eq!(scores.len(), tasks.len());
+
+    // worked out by hand from `(e/3)^days_left + priority`, where the priority part is
+    // 1/priority of the task and every group above it
+    let expected = [
+        // asap tasks always score 1
+        1.0,
+        // 1/2 * 1/4 * 1/2, raised by a quarter for the one task waiting on it
+        0.0625 * 1.25 + (std::f64::consts::E / 3.0).powf(2.0),
+        // 1/2 * 1/4 * 1/5, being blocked doesn't change its own score
+        0.025,
+    ];
+
+    // due in two days less the few moments since, close enough
+    for (task, expected) in tasks.iter().zip(expected) {
+        let single = task.p_score(&client).await.unwrap();
+        assert!(
+            (scores[&task.id] - expected).abs() < 1e-3,
+            "{}: {} != {expected}",
+            *task.name,
+            scores[&task.id]
+        );
+        assert!((single - expected).abs() < 1e-3);
+    }
+
+    x.await.unwrap();
+}
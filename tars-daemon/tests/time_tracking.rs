@@ -0,0 +1,189 @@
+use std::time::Duration;
+
+use chrono::{Local, NaiveDate, TimeDelta};
+use common::{
+    TarsClient,
+    types::{Group, Id, Priority, Task, TimeEntry, TimeRange, Timesheet},
+};
+use tars_daemon::utils::new_test_daemon;
+use tokio::time::{sleep, timeout};
+
+#[tokio::test]
+async fn one_running_timer() {
+    let (d, addr) = new_test_daemon().await;
+
+    let x = tokio::spawn(async move {
+        timeout(Duration::from_secs(2), d.run())
+            .await
+            .unwrap_or_else(|_x| Ok(()))
+            .unwrap();
+    });
+    sleep(Duration::from_secs(1)).await;
+
+    let client = TarsClient::new(addr)
+        .await
+        .expect("failed to instantiate client");
+
+    let group = Group::new(
+        &client,
+        "work",
+        None,
+        Default::default(),
+        Default::default(),
+    )
+    .await
+    .unwrap();
+    let first = Task::new(&client, &group, "report", Priority::Low, "", None)
+        .await
+        .unwrap();
+    let second = Task::new(&client, &group, "review", Priority::Low, "", None)
+        .await
+        .unwrap();
+
+    assert_eq!(TimeEntry::running(&client).await.unwrap(), None);
+
+    let started = TimeEntry::start(&client, &first.id, None).await.unwrap();
+    assert!(started.is_running());
+    assert_eq!(
+        TimeEntry::running(&client).await.unwrap(),
+        Some(started.clone())
+    );
+
+    // starting another timer stops the first
+    let switched = TimeEntry::start(&client, &second.id, Some("PR #12".into()))
+        .await
+        .unwrap();
+    assert_eq!(TimeEntry::running(&client).await.unwrap(), Some(switched));
+
+    let stopped = TimeEntry::stop(&client, Some("PR #12 and #13".into()))
+        .await
+        .unwrap()
+        .expect("a timer was running");
+    assert_eq!(stopped.task_id, second.id);
+    assert_eq!(stopped.note, "PR #12 and #13");
+    assert!(!stopped.is_running());
+    assert_eq!(TimeEntry::stop(&client, None).await.unwrap(), None);
+
+    let now = Local::now().naive_local();
+    let entries = TimeEntry::fetch(
+        &client,
+        TimeRange {
+            from: now - TimeDelta::hours(1),
+            to: now + TimeDelta::hours(1),
+        },
+    )
+    .await
+    .unwrap();
+    let tasks: Vec<_> = entries.iter().map(|e| e.task_id.clone()).collect();
+    assert_eq!(tasks, [first.id.clone(), second.id.clone()]);
+    assert!(entries.iter().all(|e| !e.is_running()));
+
+    // timers only run on tasks that exist
+    assert!(
+        TimeEntry::start(&client, &Id::from("nope".to_owned()), None)
+            .await
+            .is_err()
+    );
+
+    x.await.unwrap();
+}
+
+#[tokio::test]
+async fn timesheet_rolls_up_groups() {
+    let (d, addr) = new_test_daemon().await;
+    let pool = d.state().pool.clone();
+
+    let x = tokio::spawn(async move {
+        timeout(Duration::from_secs(2), d.run())
+            .await
+            .unwrap_or_else(|_x| Ok(()))
+            .unwrap();
+    });
+    sleep(Duration::from_secs(1)).await;
+
+    let client = TarsClient::new(addr)
+        .await
+        .expect("failed to instantiate client");
+
+    let client_group = Group::new(
+        &client,
+        "acme",
+        None,
+        Default::default(),
+        Default::default(),
+    )
+    .await
+    .unwrap();
+    let project = Group::new(
+        &client,
+        "website",
+        Some(client_group.id.clone()),
+        Default::default(),
+        Default::default(),
+    )
+    .await
+    .unwrap();
+    let meeting = Task::new(&client, &client_group, "kickoff", Priority::Low, "", None)
+        .await
+        .unwrap();
+    let design = Task::new(&client, &project, "mockups", Priority::Low, "", None)
+        .await
+        .unwrap();
+
+    // backdated, the daemon only ever stamps entries with the current time
+    for (id, task, start, end) in [
+        ("a", &meeting, "2026-01-05 10:00:00", "2026-01-05 11:00:00"),
+        ("b", &design, "2026-01-05 11:00:00", "2026-01-05 11:30:00"),
+        // starts before the range, only the half inside counts
+        ("c", &design, "2026-01-05 08:30:00", "2026-01-05 09:30:00"),
+        // outside the range entirely
+        ("d", &design, "2026-01-04 10:00:00", "2026-01-04 12:00:00"),
+    ] {
+        sqlx::query(
+            "INSERT INTO TimeEntries (pub_id, task_id, started_at, ended_at) VALUES (?, ?, ?, ?)",
+        )
+        .bind(id)
+        .bind(&*task.id)
+        .bind(start)
+        .bind(end)
+        .execute(&pool)
+        .await
+        .unwrap();
+    }
+
+    let day = NaiveDate::from_ymd_opt(2026, 1, 5).unwrap();
+    let timesheet = Timesheet::fetch(
+        &client,
+        TimeRange {
+            from: day.and_hms_opt(9, 0, 0).unwrap(),
+            to: day.and_hms_opt(18, 0, 0).unwrap(),
+        },
+    )
+    .await
+    .unwrap();
+
+    let task_seconds = |id: &Id| {
+        timesheet
+            .tasks
+            .iter()
+            .find(|t| t.task_id == *id)
+            .map(|t| t.seconds)
+    };
+    assert_eq!(task_seconds(&meeting.id), Some(3600));
+    assert_eq!(task_seconds(&design.id), Some(3600));
+
+    let group_seconds = |id: &Id| {
+        timesheet
+            .groups
+            .iter()
+            .find(|g| g.group_id == *id)
+            .map(|g| (g.own_seconds, g.total_seconds))
+    };
+    assert_eq!(group_seconds(&client_group.id), Some((3600, 7200)));
+    assert_eq!(group_seconds(&project.id), Some((3600, 3600)));
+
+    // most time first
+    assert_eq!(timesheet.groups[0].group_id, client_group.id);
+
+    x.await.unwrap();
+}
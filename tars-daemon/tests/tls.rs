@@ -0,0 +1,129 @@
+use std::time::Duration;
+
+use common::{
+    ConnectOptions, TarsClient,
+    types::{Group, Id},
+};
+use tars_daemon::{
+    tls::{ServerTls, TlsConfig},
+    utils::new_tls_test_daemon,
+};
+use tokio::time::{sleep, timeout};
+
+#[tokio::test]
+async fn pinned_certificate() {
+    let (d, addr, fingerprint) = new_tls_test_daemon().await;
+
+    let x = tokio::spawn(async move {
+        timeout(Duration::from_secs(2), d.run())
+            .await
+            .unwrap_or_else(|_x| Ok(()))
+            .unwrap();
+    });
+    sleep(Duration::from_secs(1)).await;
+
+    let client = TarsClient::connect(
+        addr.clone(),
+        ConnectOptions {
+            fingerprint: Some(fingerprint.clone()),
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+    let work = Group::new(
+        &client,
+        "work",
+        None,
+        Default::default(),
+        Default::default(),
+    )
+    .await
+    .unwrap();
+    assert_eq!(Group::fetch_all(&client).await.unwrap(), vec![work]);
+
+    // the fingerprint may be written without colons, in either case
+    let bare = fingerprint.replace(':', "").to_lowercase();
+    let client = TarsClient::connect(
+        addr.clone(),
+        ConnectOptions {
+            fingerprint: Some(format!("sha256:{bare}")),
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+    assert_eq!(Group::fetch_all(&client).await.unwrap().len(), 1);
+
+    // any other certificate is refused, as is a self-signed one without a pin
+    let other = ServerTls::self_signed(&["localhost".to_owned()]).unwrap();
+    let client = TarsClient::connect(
+        addr.clone(),
+        ConnectOptions {
+            fingerprint: Some(other.fingerprint),
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+    assert!(Group::fetch_all(&client).await.is_err());
+
+    let client = TarsClient::with_token(addr.clone(), None).await.unwrap();
+    assert!(Group::fetch_all(&client).await.is_err());
+
+    // and so is plain http
+    let client = TarsClient::with_token(addr.replace("https://", "http://"), None)
+        .await
+        .unwrap();
+    assert!(Group::fetch_all(&client).await.is_err());
+
+    assert!(
+        TarsClient::connect(
+            addr,
+            ConnectOptions {
+                fingerprint: Some("AB:CD".to_owned()),
+                ..Default::default()
+            },
+        )
+        .await
+        .is_err()
+    );
+
+    x.await.unwrap();
+}
+
+#[tokio::test]
+async fn self_signed_on_first_run() {
+    let dir = format!("/tmp/tars/test-tls/{}", *Id::default());
+    let config = TlsConfig {
+        enabled: true,
+        cert: Some(format!("{dir}/cert.pem").into()),
+        key: Some(format!("{dir}/key.pem").into()),
+        ..Default::default()
+    };
+
+    // nothing to serve, and no permission to make something up
+    assert!(ServerTls::load(&config).is_err());
+
+    let config = TlsConfig {
+        self_sign: true,
+        ..config
+    };
+    let first = ServerTls::load(&config).unwrap();
+    assert!(config.cert_path().exists());
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+
+        let mode = std::fs::metadata(config.key_path())
+            .unwrap()
+            .permissions()
+            .mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+
+    // later runs keep serving the same certificate, so pins stay valid
+    let second = ServerTls::load(&config).unwrap();
+    assert_eq!(first.fingerprint, second.fingerprint);
+}
@@ -0,0 +1,188 @@
+use std::time::Duration;
+
+use common::{
+    Diff, DiffInner, TarsClient, TarsError,
+    auth::load_token,
+    types::{Access, ApiToken, Group, Snapshot, Task, TaskFetchOptions, TokenRequest},
+};
+use reqwest::StatusCode;
+use tars_daemon::utils::new_authed_test_daemon;
+use tokio::time::{sleep, timeout};
+
+#[tokio::test]
+async fn scoped_tokens() {
+    let (d, addr, token_path) = new_authed_test_daemon().await;
+
+    let x = tokio::spawn(async move {
+        timeout(Duration::from_secs(2), d.run())
+            .await
+            .unwrap_or_else(|_x| Ok(()))
+            .unwrap();
+    });
+    sleep(Duration::from_secs(1)).await;
+
+    let admin = TarsClient::with_token(addr.clone(), load_token(&token_path).unwrap())
+        .await
+        .unwrap();
+
+    let add_group = async |name: &str, parent: Option<&Group>| {
+        Group::new(
+            &admin,
+            name,
+            parent.map(|p| p.id.clone()),
+            Default::default(),
+            Default::default(),
+        )
+        .await
+        .unwrap()
+    };
+    let work = add_group("work", None).await;
+    let meetings = add_group("meetings", Some(&work)).await;
+    let home = add_group("home", None).await;
+
+    let add_task = async |group: &Group, name: &str| {
+        Task::new(&admin, group, name, Default::default(), "", None)
+            .await
+            .unwrap()
+    };
+    let standup = add_task(&meetings, "standup").await;
+    let dishes = add_task(&home, "dishes").await;
+
+    let token = async |name: &str, access, group: Option<&Group>| {
+        let created = ApiToken::create(
+            &admin,
+            TokenRequest {
+                name: name.into(),
+                access,
+                group_id: group.map(|g| g.id.clone()),
+            },
+        )
+        .await
+        .unwrap();
+        let client = TarsClient::with_token(addr.clone(), Some(created.secret.clone()))
+            .await
+            .unwrap();
+        (created, client)
+    };
+
+    // a read-only token sees everything, but can't change any of it
+    let (statusbar, reader) = token("statusbar", Access::Read, None).await;
+    assert_eq!(Group::fetch_all(&reader).await.unwrap().len(), 3);
+    assert!(
+        Group::new(
+            &reader,
+            "nope",
+            None,
+            Default::default(),
+            Default::default()
+        )
+        .await
+        .is_err()
+    );
+    assert!(ApiToken::fetch_all(&reader).await.is_err());
+
+    // a token limited to work only sees work and what's below it
+    let (dashboard, worker) = token("work dashboard", Access::Write, Some(&work)).await;
+    let mut visible: Vec<_> = Group::fetch_all(&worker)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|g| g.id)
+        .collect();
+    visible.sort();
+    let mut expected = vec![work.id.clone(), meetings.id.clone()];
+    expected.sort();
+    assert_eq!(visible, expected);
+    assert_eq!(
+        Task::fetch(&worker, TaskFetchOptions::All).await.unwrap(),
+        vec![standup.clone()]
+    );
+    let snapshot = Snapshot::fetch(&worker).await.unwrap();
+    assert_eq!(snapshot.groups.len(), 2);
+    assert_eq!(snapshot.tasks, vec![standup.clone()]);
+
+    // and may change things there, but nowhere else
+    let retro = Task::new(&worker, &meetings, "retro", Default::default(), "", None)
+        .await
+        .unwrap();
+    assert!(
+        Task::new(&worker, &home, "laundry", Default::default(), "", None)
+            .await
+            .is_err()
+    );
+    assert!(dishes.delete(&worker).await.is_err());
+    assert!(dishes.patch().name("nope").send(&worker).await.is_err());
+    assert!(home.patch().name("nope").send(&worker).await.is_err());
+    assert!(work.delete(&worker).await.is_err());
+    assert!(
+        meetings
+            .move_to(&worker, Some(home.id.clone()))
+            .await
+            .is_err()
+    );
+    assert!(meetings.move_to(&worker, None).await.is_err());
+
+    let res = worker
+        .conn
+        .post(format!("{addr}/task/delete"))
+        .json(&dishes.id)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::FORBIDDEN);
+    let res = worker.conn.get(format!("{addr}/tag")).send().await.unwrap();
+    assert_eq!(res.status(), StatusCode::FORBIDDEN);
+
+    // the diff stream only carries what the token may see
+    let mut stream = worker
+        .conn
+        .get(format!("{addr}/subscribe"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(stream.status(), StatusCode::OK);
+    add_task(&home, "laundry").await;
+    let mut retro_done = retro.clone();
+    retro_done.name = "retro!".into();
+    retro_done.sync(&admin).await.unwrap();
+
+    let chunk = timeout(Duration::from_millis(500), stream.chunk())
+        .await
+        .unwrap()
+        .unwrap()
+        .unwrap();
+    let event = String::from_utf8(chunk.to_vec()).unwrap();
+    let data = event
+        .lines()
+        .find_map(|line| line.strip_prefix("data:"))
+        .unwrap();
+    let diff: Diff = serde_json::from_str(data).unwrap();
+    assert_eq!(diff, Diff::Updated(DiffInner::Task(retro_done)));
+
+    // admin tokens always reach everything, and revoked tokens stop working
+    assert!(
+        ApiToken::create(
+            &admin,
+            TokenRequest {
+                name: "nope".into(),
+                access: Access::Admin,
+                group_id: Some(work.id.clone()),
+            },
+        )
+        .await
+        .is_err()
+    );
+    assert_eq!(ApiToken::fetch_all(&admin).await.unwrap().len(), 2);
+    ApiToken::revoke(&admin, &statusbar.token.id).await.unwrap();
+    assert!(Group::fetch_all(&reader).await.is_err());
+    assert_eq!(ApiToken::fetch_all(&admin).await.unwrap().len(), 1);
+
+    // a group can't be deleted while tokens are limited to it, they'd be gone for good
+    let err = work.delete(&admin).await.unwrap_err();
+    assert!(matches!(err, TarsError::Invalid { .. }), "{err:?}");
+
+    ApiToken::revoke(&admin, &dashboard.token.id).await.unwrap();
+    work.delete(&admin).await.unwrap();
+
+    x.await.unwrap();
+}
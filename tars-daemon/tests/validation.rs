@@ -0,0 +1,117 @@
+use std::time::Duration;
+
+use chrono::Local;
+use common::{
+    ApiError, ErrorCode, TarsClient, TarsError,
+    types::{Color, Group, Id, Task},
+};
+use reqwest::StatusCode;
+use tars_daemon::utils::new_test_daemon;
+use tokio::time::{sleep, timeout};
+
+/// Returns the field of an `Invalid` or `NotFound` error, panics on anything else.
+fn field_of(err: TarsError) -> (ErrorCode, String) {
+    match err {
+        TarsError::Invalid { field, .. } => (ErrorCode::Invalid, field),
+        TarsError::NotFound {
+            field: Some(field), ..
+        } => (ErrorCode::NotFound, field),
+        other => panic!("expected a typed error, got {other:?}"),
+    }
+}
+
+#[tokio::test]
+async fn typed_errors() {
+    let (d, addr) = new_test_daemon().await;
+
+    let x = tokio::spawn(async move {
+        timeout(Duration::from_secs(2), d.run())
+            .await
+            .unwrap_or_else(|_x| Ok(()))
+            .unwrap();
+    });
+    sleep(Duration::from_secs(1)).await;
+
+    let client = TarsClient::with_token(addr.clone(), None).await.unwrap();
+
+    let new_group = async |name: &str, parent_id: Option<Id>, color: Color| {
+        Group::new(&client, name, parent_id, Default::default(), color).await
+    };
+
+    let work = new_group("work", None, Color::default()).await.unwrap();
+
+    let err = new_group("  ", None, Color::default()).await.unwrap_err();
+    assert_eq!(field_of(err), (ErrorCode::Invalid, "name".to_owned()));
+
+    let err = new_group("home", None, Color("blurple".to_owned()))
+        .await
+        .unwrap_err();
+    assert_eq!(field_of(err), (ErrorCode::Invalid, "color".to_owned()));
+
+    let err = new_group("home", Some(Id::default()), Color::default())
+        .await
+        .unwrap_err();
+    assert_eq!(field_of(err), (ErrorCode::NotFound, "parent_id".to_owned()));
+
+    let id = Id::default();
+    let own_parent = Group::with_all_fields(
+        id.clone(),
+        "ouroboros",
+        Some(id),
+        Default::default(),
+        Local::now().naive_local(),
+        Color::default(),
+    );
+    let err = own_parent.raw_create(&client).await.unwrap_err();
+    assert_eq!(field_of(err), (ErrorCode::Invalid, "parent_id".to_owned()));
+
+    let meetings = new_group("meetings", Some(work.id.clone()), Color::default())
+        .await
+        .unwrap();
+    let err = work
+        .move_to(&client, Some(meetings.id.clone()))
+        .await
+        .unwrap_err();
+    assert_eq!(field_of(err), (ErrorCode::Invalid, "parent_id".to_owned()));
+
+    let err = Task::new(&client, &work, "", Default::default(), "", None)
+        .await
+        .unwrap_err();
+    assert_eq!(field_of(err), (ErrorCode::Invalid, "name".to_owned()));
+
+    let mut gone = work.clone();
+    gone.id = Id::default();
+    let err = Task::new(&client, &gone, "standup", Default::default(), "", None)
+        .await
+        .unwrap_err();
+    assert_eq!(field_of(err), (ErrorCode::NotFound, "group".to_owned()));
+
+    // nothing made it into the database along the way
+    assert_eq!(Group::fetch_all(&client).await.unwrap().len(), 2);
+
+    // even requests that never reach a handler get a body to go with their status
+    let res = client
+        .conn
+        .post(format!("{addr}/group/create"))
+        .header("content-type", "application/json")
+        .body("{ not json")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+    let body: ApiError = res.json().await.unwrap();
+    assert_eq!(body.code, ErrorCode::BadRequest);
+    assert!(!body.message.is_empty());
+
+    let res = client
+        .conn
+        .get(format!("{addr}/nowhere"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::NOT_FOUND);
+    let body: ApiError = res.json().await.unwrap();
+    assert_eq!(body.code, ErrorCode::NotFound);
+
+    x.await.unwrap();
+}
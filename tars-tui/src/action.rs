@@ -1,4 +1,7 @@
-use common::{Diff, types::Task};
+use common::{
+    Diff,
+    types::{Tag, Task},
+};
 use id_tree::NodeId;
 use serde::{Deserialize, Serialize};
 use strum::Display;
@@ -22,7 +25,14 @@ pub enum Signal {
     Refresh,
     RawText,
     EditDescriptionForTask(Task),
-    Diff(Diff),
+    /// Only show tasks carrying this tag, or everything if `None`.
+    TagFilter(Option<Tag>),
+    /// A diff from the daemon, along with its number in the diff stream.
+    Diff(i64, Diff),
+    /// Diffs were missed and can't be replayed, the tree has to catch up with the daemon.
+    Resync,
+    /// The diff stream was opened, the first time or after it broke.
+    Reconnected,
     // actions that the user inputs
     Action(Action),
 }
@@ -49,4 +59,14 @@ pub enum Action {
     RandomColor,
     EditPriority,
     ToggleFinishTask,
+    Undo,
+    Redo,
+    Cut,
+    Paste,
+    EditTags,
+    EditRecurrence,
+    EditEstimate,
+    EditChecklist,
+    CycleTagFilter,
+    ToggleTimer,
 }
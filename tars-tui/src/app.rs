@@ -6,16 +6,23 @@ use std::{
     rc::Rc,
     sync::Arc,
     thread::{self, spawn},
-    time::Duration,
+    time::{Duration, Instant},
 };
 
+use chrono::Local;
 use color_eyre::Result;
-use common::{Diff, TarsClient};
+use common::{
+    Diff, RESYNC_EVENT, TarsClient,
+    types::{Id, Tag, TimeEntry},
+};
 use crossterm::event::KeyEvent;
 use futures::StreamExt;
+use id_tree::NodeId;
 use ratatui::{
     layout::{Constraint, Direction, Layout},
     prelude::Rect,
+    style::{Color, Style, Stylize},
+    text::Line,
 };
 use reqwest_eventsource::{Event as EsEvent, EventSource};
 use serde::{Deserialize, Serialize};
@@ -26,17 +33,25 @@ use tokio::{
         oneshot,
     },
     task::JoinHandle,
+    time::sleep,
 };
 use tracing::{debug, error, info};
 
 use crate::{
     action::{Action, Signal},
-    components::{Component, explorer::Explorer, inspector::Inspector, todo_list::TodoList},
+    components::{
+        Component, agenda::Agenda, explorer::Explorer, inspector::Inspector, todo_list::TodoList,
+    },
     config::Config,
-    tree::{TarsTree, TarsTreeHandle},
+    tree::{TarsKind, TarsTree, TarsTreeHandle},
     tui::{Event, Tui},
 };
 
+/// How often the running timer is refetched, it may be started or stopped by another client.
+const TIMER_POLL: Duration = Duration::from_secs(30);
+/// How long a notification stays on screen.
+const NOTIFICATION_TIMEOUT: Duration = Duration::from_secs(10);
+
 pub struct App {
     config: Config,
     tick_rate: f64,
@@ -53,6 +68,18 @@ pub struct App {
 
     // state to keep track if we need to send keystrokes un-modified
     raw_text: bool,
+    // tag the explorer and todo list are currently filtered by
+    tag_filter: Option<Tag>,
+    // last thing selected in any component, the timer is toggled on it
+    selected: Option<NodeId>,
+    // the running timer, along with the name of the task it's running on
+    timer: Option<(TimeEntry, String)>,
+    // when the running timer was last fetched
+    timer_fetched: Instant,
+    // a resync that failed, it's tried again once the diff stream reconnects
+    resync_pending: bool,
+    // the last notification received, along with when it came in
+    notification: Option<(String, Instant)>,
     tree: TarsTreeHandle,
     _diff_handle: JoinHandle<()>,
 }
@@ -63,6 +90,7 @@ pub enum Mode {
     Explorer,
     TodoList,
     Inspector,
+    Agenda,
 }
 
 impl From<Mode> for u8 {
@@ -71,6 +99,7 @@ impl From<Mode> for u8 {
             Mode::Explorer => 1,
             Mode::TodoList => 2,
             Mode::Inspector => 3,
+            Mode::Agenda => 4,
         }
     }
 }
@@ -78,9 +107,11 @@ impl From<Mode> for u8 {
 impl App {
     pub async fn new(tick_rate: f64, frame_rate: f64) -> Result<Self> {
         let (signal_tx, signal_rx) = mpsc::unbounded_channel();
-        let client = TarsClient::default().await.unwrap();
+        let client = TarsClient::default().await?;
 
-        let tree = Arc::new(RwLock::new(TarsTree::generate(&client).await?));
+        let tree = TarsTree::generate(&client).await?;
+        let diff_handle = Self::spawn_diff_handler(&client, tree.seq(), signal_tx.clone());
+        let tree = Arc::new(RwLock::new(tree));
 
         let app = Self {
             tick_rate,
@@ -89,6 +120,7 @@ impl App {
                 Box::new(Explorer::new(&client, tree.clone()).await?),
                 Box::new(TodoList::new(&client, tree.clone()).await?),
                 Box::new(Inspector::new(&client, tree.clone()).await?),
+                Box::new(Agenda::new(&client)),
             ],
             tree,
             should_quit: false,
@@ -96,40 +128,84 @@ impl App {
             config: Config::new()?,
             mode: Mode::Explorer,
             last_tick_key_events: Vec::new(),
-            _diff_handle: Self::spawn_diff_handler(&client, signal_tx.clone()),
+            _diff_handle: diff_handle,
             signal_tx,
             signal_rx,
             raw_text: false,
+            tag_filter: None,
+            selected: None,
+            timer: None,
+            timer_fetched: Instant::now(),
+            resync_pending: false,
+            notification: None,
             client,
         };
 
         Ok(app)
     }
 
+    /// Streams the diffs after `seq` into `action_tx`. Whenever the stream breaks it's
+    /// reopened after the last diff received, so nothing is missed in between.
     pub fn spawn_diff_handler(
         client: &TarsClient,
+        seq: i64,
         action_tx: UnboundedSender<Signal>,
     ) -> JoinHandle<()> {
         let url = client.base_path.clone();
         let url = url.join("/subscribe").unwrap();
+        let client = client.clone();
 
         tokio::spawn(async move {
-            let mut es = EventSource::get(url);
-
-            while let Some(event) = es.next().await {
-                match event {
-                    Ok(EsEvent::Open) => info!("diff connection opened!"),
-                    Ok(EsEvent::Message(message)) => {
-                        let data: Diff = serde_json::from_str(message.data.as_str())
-                            .expect("message should be parseable as a Diff");
-                        debug!("message received: {data:?}");
-
-                        action_tx
-                            .send(Signal::Diff(data))
-                            .expect("sending action should not fail");
+            let mut last_event_id = seq.to_string();
+
+            loop {
+                // built from the client so the stream carries its token too
+                let request = client
+                    .conn
+                    .get(url.clone())
+                    .header("Last-Event-ID", &last_event_id);
+                let mut es =
+                    EventSource::new(request).expect("a plain GET request can always be cloned");
+
+                while let Some(event) = es.next().await {
+                    match event {
+                        Ok(EsEvent::Open) => {
+                            info!("diff connection opened!");
+
+                            action_tx
+                                .send(Signal::Reconnected)
+                                .expect("sending action should not fail");
+                        }
+                        Ok(EsEvent::Message(message)) if message.event == RESYNC_EVENT => {
+                            info!("missed diffs that can't be replayed, resyncing");
+
+                            action_tx
+                                .send(Signal::Resync)
+                                .expect("sending action should not fail");
+                        }
+                        Ok(EsEvent::Message(message)) => {
+                            let (Ok(seq), Ok(data)) = (
+                                message.id.parse::<i64>(),
+                                serde_json::from_str::<Diff>(message.data.as_str()),
+                            ) else {
+                                error!("unparseable message {message:?}");
+                                continue;
+                            };
+                            debug!("message {seq} received: {data:?}");
+                            last_event_id = message.id;
+
+                            action_tx
+                                .send(Signal::Diff(seq, data))
+                                .expect("sending action should not fail");
+                        }
+                        Err(e) => {
+                            error!("diff stream broke, reopening it: {e:#?}");
+                            es.close();
+                        }
                     }
-                    Err(e) => error!("error!: {e:#?}"),
                 }
+
+                sleep(Duration::from_secs(1)).await;
             }
         })
     }
@@ -151,6 +227,8 @@ impl App {
             component.init(tui.size()?, self.mode).await?;
         }
 
+        self.refresh_timer().await;
+
         let action_tx = self.signal_tx.clone();
         loop {
             self.handle_events(&mut tui).await?;
@@ -231,6 +309,16 @@ impl App {
             match action {
                 Signal::Tick => {
                     self.last_tick_key_events.drain(..);
+                    if self.timer_fetched.elapsed() >= TIMER_POLL {
+                        self.refresh_timer().await;
+                    }
+                    if self
+                        .notification
+                        .as_ref()
+                        .is_some_and(|(_, at)| at.elapsed() >= NOTIFICATION_TIMEOUT)
+                    {
+                        self.notification = None;
+                    }
                 }
 
                 Signal::Action(Action::Quit) => self.should_quit = true,
@@ -241,17 +329,96 @@ impl App {
                 Signal::Resize(w, h) => self.handle_resize(tui, w, h)?,
                 Signal::Render => self.render(tui)?,
                 Signal::Action(Action::SwitchTo(mode)) => self.mode = mode,
+                // the resulting diffs come back through the diff stream like any other change
+                Signal::Action(Action::Undo) => {
+                    if let Err(e) = self.client.undo().await {
+                        error!("unable to undo: {:?}", e);
+                    }
+                }
+                Signal::Action(Action::Redo) => {
+                    if let Err(e) = self.client.redo().await {
+                        error!("unable to redo: {:?}", e);
+                    }
+                }
+                // cycles through every tag in order, and then back to no filter at all
+                Signal::Action(Action::CycleTagFilter) => {
+                    match Tag::fetch_all(&self.client).await {
+                        Ok(tags) => {
+                            let next = match self.tag_filter {
+                                None => tags.first().cloned(),
+                                Some(ref current) => tags
+                                    .iter()
+                                    .skip_while(|t| t.id != current.id)
+                                    .nth(1)
+                                    .cloned(),
+                            };
+
+                            info!("filtering by tag: {next:?}");
+                            self.tag_filter = next.clone();
+                            self.signal_tx.send(Signal::TagFilter(next))?;
+                        }
+                        // the current filter stays as it is
+                        Err(e) => error!("unable to fetch tags: {:?}", e),
+                    }
+                }
+                Signal::Select(ref id) => self.selected = Some(id.clone()),
+                // starts the timer on the selected task, or stops it if it's running there already
+                Signal::Action(Action::ToggleTimer) => {
+                    let selected = match self.selected {
+                        Some(ref id) => {
+                            self.tree
+                                .read()
+                                .await
+                                .get(id)
+                                .map(|node| match node.data().kind {
+                                    TarsKind::Task(ref t) => Some(t.id.clone()),
+                                    _ => None,
+                                })
+                        }
+                        None => Ok(None),
+                    };
+
+                    match selected {
+                        Ok(selected_task) => self.toggle_timer(selected_task).await,
+                        // the selection went away with a delete, an undo or a resync
+                        Err(e) => error!("unable to find the selected node: {:?}", e),
+                    }
+                }
                 Signal::RawText => self.raw_text = true,
                 Signal::Refresh => {
                     self.raw_text = false;
+                    // the task the timer runs on may have been renamed or deleted
+                    let running = self.timer.take().map(|(entry, _)| entry);
+                    self.set_timer(running).await;
                 }
 
-                Signal::Diff(ref diff) => {
-                    info!("received diff");
-                    self.tree.write().await.apply_diff(diff.clone())?;
-                    info!("applied diff");
-                    self.signal_tx.send(Signal::Update)?;
-                    self.signal_tx.send(Signal::Refresh)?
+                Signal::Diff(_, Diff::Notification(ref notification)) => {
+                    info!("received notification: {}", notification.message);
+                    self.notification = Some((notification.message.clone(), Instant::now()));
+                }
+                Signal::Diff(seq, ref diff) => {
+                    info!("received diff {seq}");
+                    // already in the tree if it was resynced after the diff was sent
+                    if self.tree.write().await.apply_sequenced(seq, diff.clone())? {
+                        info!("applied diff");
+                        self.signal_tx.send(Signal::Update)?;
+                        self.signal_tx.send(Signal::Refresh)?
+                    }
+                }
+                Signal::Resync => match self.tree.write().await.resync(&self.client).await {
+                    Ok(()) => {
+                        info!("resynced tree");
+                        self.resync_pending = false;
+                        self.signal_tx.send(Signal::Update)?;
+                        self.signal_tx.send(Signal::Refresh)?
+                    }
+                    Err(e) => {
+                        error!("unable to resync, trying again on reconnect: {:?}", e);
+                        self.resync_pending = true;
+                    }
+                },
+                Signal::Reconnected if self.resync_pending => {
+                    self.signal_tx.send(Signal::Resync)?
                 }
                 Signal::EditDescriptionForTask(ref task) => {
                     tui.exit()?;
@@ -352,6 +519,43 @@ impl App {
         Ok(())
     }
 
+    /// Starts the timer on `selected_task`, or stops it if it's running there already or
+    /// nothing is selected.
+    async fn toggle_timer(&mut self, selected_task: Option<Id>) {
+        let running_on = self.timer.as_ref().map(|(entry, _)| &entry.task_id);
+        let toggled = match selected_task {
+            Some(ref id) if running_on != Some(id) => {
+                TimeEntry::start(&self.client, id, None).await.map(Some)
+            }
+            _ => TimeEntry::stop(&self.client, None).await.map(|_| None),
+        };
+
+        match toggled {
+            Ok(running) => self.set_timer(running).await,
+            Err(e) => error!("unable to toggle the timer: {:?}", e),
+        }
+    }
+
+    /// Refetches the running timer, it may have been started or stopped by another client.
+    async fn refresh_timer(&mut self) {
+        self.timer_fetched = Instant::now();
+        match TimeEntry::running(&self.client).await {
+            Ok(running) => self.set_timer(running).await,
+            Err(e) => error!("unable to fetch the running timer: {:?}", e),
+        }
+    }
+
+    /// Shows `running` as the running timer, named after the task it's running on. A timer
+    /// on a task that's no longer in the tree went away along with it.
+    async fn set_timer(&mut self, running: Option<TimeEntry>) {
+        let tree = self.tree.read().await;
+
+        self.timer = running.and_then(|entry| {
+            let name = (*tree.get_task(&entry.task_id)?.name).clone();
+            Some((entry, name))
+        });
+    }
+
     fn handle_resize(&mut self, tui: &mut Tui, w: u16, h: u16) -> Result<()> {
         tui.resize(Rect::new(0, 0, w, h))?;
         self.render(tui)?;
@@ -367,12 +571,18 @@ impl App {
             .direction(Direction::Vertical)
             .constraints([Constraint::Percentage(50), Constraint::Percentage(50)]);
 
+        // explorer above the agenda
+        let two_left = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Percentage(65), Constraint::Percentage(35)]);
+
         tui.draw(|frame| {
             let virt_split = virt_split.split(frame.area());
 
             let two_right = two_right.split(virt_split[1]);
+            let two_left = two_left.split(virt_split[0]);
 
-            let layout = [Rc::new([virt_split[0]]), two_right].concat();
+            let layout = [Rc::new([two_left[0]]), two_right, Rc::new([two_left[1]])].concat();
 
             for (component, rect) in self.components.iter_mut().zip(layout.iter()) {
                 if let Err(err) = component.draw(frame, *rect) {
@@ -381,6 +591,42 @@ impl App {
                         .send(Signal::Error(format!("Failed to draw: {err:?}")));
                 }
             }
+
+            // drawn over the top border, clear of the corner
+            if let Some((ref entry, ref name)) = self.timer {
+                let elapsed = entry.duration(Local::now().naive_local()).num_seconds();
+                let indicator = format!(
+                    " ⏱ {name} {:02}:{:02}:{:02} ",
+                    elapsed / 3600,
+                    elapsed % 3600 / 60,
+                    elapsed % 60
+                );
+
+                let area = frame.area();
+                let top = Rect::new(area.x, area.y, area.width.saturating_sub(2), 1);
+                frame.render_widget(
+                    Line::styled(indicator, Style::new().fg(Color::Green).bold()).right_aligned(),
+                    top,
+                );
+            }
+
+            // drawn over the bottom border, clear of the corners
+            if let Some((ref message, _)) = self.notification {
+                let area = frame.area();
+                let bottom = Rect::new(
+                    area.x + 2,
+                    area.bottom().saturating_sub(1),
+                    area.width.saturating_sub(4),
+                    1,
+                );
+                frame.render_widget(
+                    Line::styled(
+                        format!(" 🔔 {message} "),
+                        Style::new().fg(Color::Yellow).bold(),
+                    ),
+                    bottom,
+                );
+            }
         })?;
         Ok(())
     }
@@ -0,0 +1,180 @@
+use async_trait::async_trait;
+use color_eyre::Result;
+use common::{
+    TarsClient,
+    types::{Schedule, ScheduleRequest},
+};
+use ratatui::{
+    layout::{Constraint, Direction, Layout},
+    style::{Color, Style, Stylize},
+    text::{Line, Span},
+    widgets::Paragraph,
+};
+use tracing::error;
+
+use crate::{
+    action::{Action, Signal},
+    app::Mode,
+};
+
+use super::{Component, frame_block};
+
+#[derive(Debug)]
+/// Component that shows the schedule the daemon lays out over the coming working days.
+pub struct Agenda {
+    client: TarsClient,
+    schedule: Option<Schedule>,
+    /// How many lines the agenda is scrolled down by.
+    scroll: u16,
+    active: bool,
+}
+
+impl Agenda {
+    pub fn new(client: &TarsClient) -> Self {
+        Self {
+            client: client.clone(),
+            schedule: None,
+            scroll: 0,
+            active: false,
+        }
+    }
+
+    fn mode(&self) -> Mode {
+        Mode::Agenda
+    }
+
+    /// Refetches the schedule, keeping the old one around if the daemon can't be reached.
+    async fn refresh(&mut self) {
+        match Schedule::fetch(&self.client, ScheduleRequest::default()).await {
+            Ok(schedule) => self.schedule = Some(schedule),
+            Err(e) => error!("failed to fetch the schedule: {e:?}"),
+        }
+    }
+
+    fn lines(&self) -> Vec<Line<'static>> {
+        let Some(ref schedule) = self.schedule else {
+            return vec![Line::from("Loading...").italic()];
+        };
+
+        let mut lines = Vec::new();
+        for day in &schedule.days {
+            let header = format!(
+                "{} ({} / {})",
+                day.date.format("%a %m/%d"),
+                day.booked,
+                day.capacity
+            );
+            if day.is_over_capacity() {
+                lines.push(Line::from(vec![
+                    Span::styled(header, Style::new().fg(Color::Red).bold()),
+                    Span::styled(" over capacity", Style::new().fg(Color::Red)),
+                ]));
+            } else {
+                lines.push(Line::from(header).bold());
+            }
+
+            for block in &day.blocks {
+                let style = if block.late {
+                    Style::new().fg(Color::Red)
+                } else {
+                    Style::new()
+                };
+                lines.push(Line::styled(
+                    format!(
+                        "  {}-{} {}",
+                        block.start.format("%H:%M"),
+                        block.end.format("%H:%M"),
+                        *block.name
+                    ),
+                    style,
+                ));
+            }
+        }
+
+        if !schedule.late.is_empty() {
+            lines.push(Line::from(""));
+            lines.push(Line::styled("Late", Style::new().fg(Color::Red).bold()));
+        }
+        for late in &schedule.late {
+            lines.push(Line::styled(
+                format!(
+                    "  {} due {}, {} short",
+                    *late.name,
+                    late.due.format("%m/%d %H:%M"),
+                    late.missing
+                ),
+                Style::new().fg(Color::Red),
+            ));
+        }
+
+        if !schedule.unplaced.is_empty() {
+            lines.push(Line::from(""));
+            lines.push(Line::styled(
+                "Doesn't fit",
+                Style::new().fg(Color::Yellow).bold(),
+            ));
+        }
+        for unplaced in &schedule.unplaced {
+            lines.push(Line::styled(
+                format!("  {}, {} left over", *unplaced.name, unplaced.remaining),
+                Style::new().fg(Color::Yellow),
+            ));
+        }
+
+        lines
+    }
+}
+
+#[async_trait]
+impl Component for Agenda {
+    async fn init(&mut self, _area: ratatui::prelude::Size, default_mode: Mode) -> Result<()> {
+        self.active = default_mode == self.mode();
+        self.refresh().await;
+
+        Ok(())
+    }
+
+    async fn update(&mut self, action: Signal) -> Result<Option<Signal>> {
+        match action {
+            Signal::Action(Action::SwitchTo(mode)) => {
+                self.active = mode == self.mode();
+                Ok(None)
+            }
+
+            Signal::Refresh => {
+                self.refresh().await;
+                Ok(None)
+            }
+
+            Signal::Action(action) if self.active => {
+                match action {
+                    Action::MoveDown => self.scroll = self.scroll.saturating_add(1),
+                    Action::MoveUp => self.scroll = self.scroll.saturating_sub(1),
+                    _ => {}
+                }
+                Ok(None)
+            }
+
+            _ => Ok(None),
+        }
+    }
+
+    fn draw(&mut self, frame: &mut ratatui::Frame, area: ratatui::prelude::Rect) -> Result<()> {
+        frame.render_widget(frame_block(self.active, self.mode()), area);
+
+        let area = Layout::new(Direction::Vertical, [Constraint::Percentage(100)])
+            .horizontal_margin(2)
+            .vertical_margin(1)
+            .split(area)[0];
+
+        let lines = self.lines();
+        // never scroll past the last line
+        self.scroll = self
+            .scroll
+            .min(lines.len().saturating_sub(area.height as usize) as u16);
+
+        frame.render_widget(Paragraph::new(lines).scroll((self.scroll, 0)), area);
+
+        Ok(())
+    }
+}
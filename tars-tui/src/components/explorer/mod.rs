@@ -9,7 +9,7 @@ use id_tree::NodeId;
 use ratatui::layout::{Constraint, Direction, Layout, Size};
 use state::State;
 use tokio::sync::mpsc::UnboundedSender;
-use tracing::{debug, info};
+use tracing::{debug, error, info};
 use tui_scrollview::{ScrollView, ScrollbarVisibility};
 
 use crate::{
@@ -153,6 +153,11 @@ impl<'a> Component for Explorer<'a> {
                 Ok(None)
             }
 
+            Signal::TagFilter(tag) => {
+                self.state.set_tag_filter(tag).await;
+                Ok(None)
+            }
+
             Signal::Action(action) => {
                 if !self.state.active {
                     return Ok(None);
@@ -344,6 +349,68 @@ impl<'a> Component for Explorer<'a> {
                         };
                         Ok(None)
                     }
+                    Action::Cut => {
+                        let cut = {
+                            let tree = self.tree_handle.read().await;
+                            tree.get(self.state.get_selected_id())?.data().kind.id()
+                        };
+
+                        self.state.set_cut(cut).await;
+                        Ok(None)
+                    }
+                    Action::Paste => {
+                        let Some(cut_id) = self.state.get_cut().cloned() else {
+                            return Ok(None);
+                        };
+
+                        let tree = self.tree_handle.read().await;
+
+                        // paste into the selected group, falling back to the current scope
+                        let destination = match tree.get(self.state.get_selected_id())?.data().kind
+                        {
+                            TarsKind::Group(ref g) => Some(g.clone()),
+                            _ => match tree.get(self.state.get_scope())?.data().kind {
+                                TarsKind::Group(ref g) => Some(g.clone()),
+                                _ => None,
+                            },
+                        };
+
+                        let Some(cut_node) = tree.get_by_tars_id(&cut_id) else {
+                            // whatever was cut doesn't exist anymore
+                            drop(tree);
+                            self.state.set_cut(None).await;
+                            return Ok(None);
+                        };
+
+                        match cut_node.data().kind {
+                            TarsKind::Task(ref t) => {
+                                // tasks have to live inside of a group
+                                let Some(destination) = destination else {
+                                    return Ok(None);
+                                };
+
+                                let mut task = t.clone();
+                                task.group = destination;
+                                if let Err(e) = task.sync(&self.client).await {
+                                    error!("unable to move task: {:?}", e);
+                                    return Ok(None);
+                                }
+                            }
+                            TarsKind::Group(ref g) => {
+                                if let Err(e) =
+                                    g.move_to(&self.client, destination.map(|d| d.id)).await
+                                {
+                                    error!("unable to move group: {:?}", e);
+                                    return Ok(None);
+                                }
+                            }
+                            TarsKind::Root(_) => return Ok(None),
+                        };
+
+                        drop(tree);
+                        self.state.set_cut(None).await;
+                        Ok(Some(Signal::Refresh))
+                    }
                     _ => Ok(None),
                 }
             }
@@ -1,5 +1,5 @@
 use async_recursion::async_recursion;
-use common::types::Color;
+use common::types::{Color, Id, Tag, Task};
 use id_tree::{Node, NodeId};
 use ratatui::layout::Position;
 use std::collections::HashMap;
@@ -22,6 +22,10 @@ pub struct State<'a> {
     show_completed: bool,
     scope: NodeId,
     selection: Selection,
+    /// Item that has been cut, waiting to be pasted into another group.
+    cut: Option<Id>,
+    /// Only tasks carrying this tag are shown, along with the groups leading to them.
+    tag_filter: Option<Tag>,
     pub tree_handle: TarsTreeHandle,
     draw_info: Option<DrawInfo<'a>>,
     // pot: Vec<(NodeId, &'a Node<TarsNode>)>,
@@ -63,6 +67,8 @@ impl<'a> State<'a> {
             active,
             scope,
             selection,
+            cut: None,
+            tag_filter: None,
             tree_handle,
             draw_info: None,
             show_completed: false,
@@ -108,6 +114,21 @@ impl<'a> State<'a> {
                 })
                 .collect();
             ancestors.reverse();
+
+            if let Some(ref tag) = self.tag_filter {
+                let name = format!(" #{} ", *tag.name);
+                let len = name.len() as u16;
+                ancestors.push((
+                    Text::styled(
+                        name,
+                        Style::new()
+                            .bg(tag.color.as_ref().into())
+                            .fg(RatColor::Black),
+                    ),
+                    Constraint::Length(len),
+                ));
+            }
+
             ancestors
         };
 
@@ -147,14 +168,19 @@ impl<'a> State<'a> {
             render_list
                 .iter()
                 .map(|(i, (entry_id, entry))| {
-                    let (mut style, postfix) = if self.selection.id == entry_id.clone() {
+                    let (mut style, mut postfix) = if self.selection.id == entry_id.clone() {
                         self.selection.idx = *i;
 
-                        (Style::new().bold().italic(), "*")
+                        (Style::new().bold().italic(), "*".to_owned())
                     } else {
-                        (Style::new(), "")
+                        (Style::new(), "".to_owned())
                     };
 
+                    if self.cut.is_some() && self.cut == entry.data().kind.id() {
+                        style = style.add_modifier(Modifier::DIM);
+                        postfix.push_str(" (cut)");
+                    }
+
                     let widget = match entry.data().kind {
                         TarsKind::Root(_) => Paragraph::new("SHOULDNTBEPOSSIBLE"),
                         TarsKind::Task(ref t) => {
@@ -162,7 +188,12 @@ impl<'a> State<'a> {
                                 style = style.add_modifier(Modifier::CROSSED_OUT);
                             }
 
-                            Paragraph::new(format!("{}    {postfix}", *t.name))
+                            let progress = t
+                                .checklist_progress()
+                                .map(|(done, total)| format!(" {done}/{total}"))
+                                .unwrap_or_default();
+
+                            Paragraph::new(format!("{}{progress}    {postfix}", *t.name))
                                 .style(style.fg(t.group.color.as_ref().into()))
                         }
 
@@ -352,6 +383,29 @@ impl<'a> State<'a> {
         self.calculate_draw_info().await;
     }
 
+    pub async fn set_tag_filter(&mut self, tag_filter: Option<Tag>) {
+        self.tag_filter = tag_filter;
+        self.calculate_draw_info().await;
+    }
+
+    /// Whether a task passes both the finished and the tag filter.
+    fn show_task(&self, task: &Task) -> bool {
+        (self.show_completed || task.finished_at.is_none())
+            && self
+                .tag_filter
+                .as_ref()
+                .is_none_or(|tag| task.tags.iter().any(|t| t.id == tag.id))
+    }
+
+    pub fn get_cut(&self) -> Option<&Id> {
+        self.cut.as_ref()
+    }
+
+    pub async fn set_cut(&mut self, cut: Option<Id>) {
+        self.cut = cut;
+        self.calculate_draw_info().await;
+    }
+
     pub fn get_selected_id(&self) -> &NodeId {
         &self.selection.id
     }
@@ -379,12 +433,12 @@ impl<'a> State<'a> {
                 // we dont want to render the node
                 TarsKind::Root(_) => {}
                 TarsKind::Task(ref t) => {
-                    if self.show_completed || t.finished_at.is_none() {
+                    if self.show_task(t) {
                         pot.push((id, node.clone()));
                     }
                 }
                 TarsKind::Group(ref g) => {
-                    if self.show_completed
+                    if (self.show_completed && self.tag_filter.is_none())
                         || self
                             .render_group(&tree.translate_id_to_node_id(&g.id).unwrap(), &mut memo)
                             .await
@@ -420,7 +474,7 @@ impl<'a> State<'a> {
 
             match child.data().kind {
                 TarsKind::Task(ref t) => {
-                    if t.finished_at.is_none() {
+                    if self.show_task(t) {
                         exists_uncompleted_task = Some(true);
                     }
                 }
@@ -439,7 +493,8 @@ impl<'a> State<'a> {
             }
         }
 
-        let res = exists_uncompleted_task.unwrap_or(true);
+        // empty groups are only worth showing when we aren't looking for a tag
+        let res = exists_uncompleted_task.unwrap_or(self.tag_filter.is_none());
 
         memo.insert(group_id.clone(), res);
         res
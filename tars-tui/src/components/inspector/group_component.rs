@@ -1,7 +1,7 @@
 use async_trait::async_trait;
 use color_eyre::eyre::{OptionExt, Result};
 use common::{
-    ParseError, TarsClient,
+    DiffInner, ParseError, TarsClient, TarsError,
     types::{Color as MyColor, Group, Id, Priority, Task},
 };
 use crossterm::event::KeyEvent;
@@ -21,7 +21,7 @@ use crate::{
     tree::{TarsKind, TarsTreeHandle},
 };
 
-use super::TarsText;
+use super::{TarsText, draw_conflict};
 
 #[derive(Debug)]
 pub struct GroupComponent<'a> {
@@ -32,6 +32,8 @@ pub struct GroupComponent<'a> {
     tree_handle: TarsTreeHandle,
     reactive_widgets: ReactiveWidgets<'a>,
     on_update: OnUpdate,
+    /// The group as the daemon has it, when a sync was refused because it changed elsewhere.
+    conflict: Option<Group>,
     pub active: bool,
 }
 
@@ -42,6 +44,8 @@ enum EditMode {
     Name,
     Color,
     Priority,
+    /// Waiting on whether to reload the group or overwrite it after a refused sync.
+    Conflict,
 }
 
 #[derive(Debug)]
@@ -98,6 +102,7 @@ impl<'a> GroupComponent<'a> {
             signal_tx: None,
             tree_handle,
             on_update: OnUpdate::NoOp,
+            conflict: None,
             active: false,
         };
         Ok(comp)
@@ -123,11 +128,65 @@ impl<'a> GroupComponent<'a> {
             self.group.priority = new_prio.try_into()?;
         }
 
-        self.group.sync(&self.client).await?;
-        self.on_update = OnUpdate::ReRender;
+        match self.group.sync(&self.client).await {
+            Ok(()) => self.on_update = OnUpdate::ReRender,
+            // someone else got there first, the edits stay around until the user decides
+            Err(TarsError::Conflict(current)) => {
+                if let DiffInner::Group(current) = *current {
+                    self.conflict = Some(current);
+                }
+            }
+            Err(e) => return Err(e.into()),
+        }
 
         Ok(())
     }
+
+    /// Opens the conflict prompt if the last sync was refused.
+    fn prompt_conflict(&mut self) -> Option<Signal> {
+        self.conflict.as_ref()?;
+        self.edit_mode = EditMode::Conflict;
+        Some(Signal::RawText)
+    }
+
+    /// Leaves the current edit mode, for the conflict prompt if the sync was refused.
+    fn finish_edit(&mut self) -> Signal {
+        self.edit_mode = EditMode::Inactive;
+        self.prompt_conflict().unwrap_or(Signal::Refresh)
+    }
+
+    /// Handles a key while the conflict prompt is up, either taking on the group as the daemon
+    /// has it or syncing the edits on top of it.
+    async fn handle_conflict_key(&mut self, key: KeyEvent) -> Result<Option<Signal>> {
+        match key.into() {
+            Input {
+                key: Key::Char('r'),
+                ..
+            } => {
+                if let Some(current) = self.conflict.take() {
+                    self.reactive_widgets = ReactiveWidgets::from(&current);
+                    self.group = current;
+                }
+                Ok(Some(self.finish_edit()))
+            }
+            Input {
+                key: Key::Char('o'),
+                ..
+            } => {
+                if let Some(current) = self.conflict.take() {
+                    self.group.version = current.version;
+                    self.sync().await?;
+                }
+                Ok(Some(self.finish_edit()))
+            }
+            // leaves the edits as they are, the next sync asks again
+            Input { key: Key::Esc, .. } => {
+                self.conflict = None;
+                Ok(Some(self.finish_edit()))
+            }
+            _ => Ok(None),
+        }
+    }
 }
 
 #[async_trait]
@@ -150,6 +209,7 @@ impl Component for GroupComponent<'_> {
                 if let TarsKind::Group(ref group) = node.data().kind {
                     self.group = group.clone();
                     self.reactive_widgets = ReactiveWidgets::from(group);
+                    self.conflict = None;
                 }
                 Ok(None)
             }
@@ -255,7 +315,7 @@ impl Component for GroupComponent<'_> {
                         self.group.color = new_color;
 
                         self.sync().await?;
-                        Ok(None)
+                        Ok(self.prompt_conflict())
                     }
                     _ => Ok(None),
                 }
@@ -274,6 +334,7 @@ impl Component for GroupComponent<'_> {
     async fn handle_key_event(&mut self, key: KeyEvent) -> Result<Option<Signal>> {
         match self.edit_mode {
             EditMode::Inactive => {}
+            EditMode::Conflict => return self.handle_conflict_key(key).await,
             EditMode::Name => match key.into() {
                 Input { key: Key::Esc, .. }
                 | Input {
@@ -281,8 +342,7 @@ impl Component for GroupComponent<'_> {
                 } => {
                     self.reactive_widgets.name.deactivate();
                     self.sync().await?;
-                    self.edit_mode = EditMode::Inactive;
-                    return Ok(Some(Signal::Refresh));
+                    return Ok(Some(self.finish_edit()));
                 }
                 input => {
                     self.reactive_widgets.name.textarea.input(input);
@@ -303,8 +363,7 @@ impl Component for GroupComponent<'_> {
                             .priority
                             .textarea
                             .set_placeholder_text(self.group.priority);
-                        self.edit_mode = EditMode::Inactive;
-                        return Ok(Some(Signal::Refresh));
+                        return Ok(Some(self.finish_edit()));
                     }
                     input => {
                         if self.reactive_widgets.priority.textarea.input(input) {
@@ -346,8 +405,7 @@ impl Component for GroupComponent<'_> {
                     if self.reactive_widgets.color.is_valid {
                         self.sync().await?;
                     }
-                    self.edit_mode = EditMode::Inactive;
-                    return Ok(Some(Signal::Refresh));
+                    return Ok(Some(self.finish_edit()));
                 }
 
                 input => {
@@ -396,6 +454,10 @@ impl Component for GroupComponent<'_> {
         // Group priority:
         frame.render_widget(&self.reactive_widgets.priority.textarea, group_layout[2]);
 
+        if self.conflict.is_some() {
+            draw_conflict(frame, area, "group");
+        }
+
         Ok(())
     }
 }
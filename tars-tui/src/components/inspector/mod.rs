@@ -4,9 +4,10 @@ use common::TarsClient;
 use crossterm::event::KeyEvent;
 use group_component::GroupComponent;
 use ratatui::{
-    layout::{Constraint, Direction, Layout},
-    style::{Modifier, Style},
-    widgets::{Block, Paragraph},
+    Frame,
+    layout::{Constraint, Direction, Flex, Layout, Rect},
+    style::{Color, Modifier, Style},
+    widgets::{Block, BorderType, Borders, Clear, Paragraph},
 };
 use task_component::TaskComponent;
 use tokio::sync::mpsc::UnboundedSender;
@@ -85,6 +86,28 @@ impl<'a> TarsText<'a> {
     }
 }
 
+/// Draws the prompt asking what to do about a `what` that was changed elsewhere before the
+/// edits to it could be synced, along the bottom of `area`.
+fn draw_conflict(frame: &mut Frame, area: Rect, what: &str) {
+    let [prompt_area] = Layout::vertical([Constraint::Length(3)])
+        .flex(Flex::End)
+        .areas(area);
+
+    let prompt = Paragraph::new(format!(
+        "This {what} was changed elsewhere: [r]eload it, [o]verwrite it, or <esc> to decide later"
+    ))
+    .block(
+        Block::new()
+            .title_top("Conflict")
+            .borders(Borders::all())
+            .border_type(BorderType::Rounded)
+            .border_style(Style::new().fg(Color::Yellow)),
+    );
+
+    frame.render_widget(Clear, prompt_area);
+    frame.render_widget(prompt, prompt_area);
+}
+
 impl<'a> Inspector<'a> {
     pub async fn new(client: &TarsClient, tree_handle: TarsTreeHandle) -> Result<Self> {
         Ok(Self {
@@ -209,6 +232,7 @@ impl<'a> Component for Inspector<'a> {
                         if self.rendered_component.task_component.is_none() {
                             let mut task_component = TaskComponent::new(
                                 t,
+                                &tree,
                                 self.client.clone(),
                                 self.tree_handle.clone(),
                             )?;
@@ -2,14 +2,18 @@ use async_trait::async_trait;
 use chrono::Local;
 use color_eyre::Result;
 use common::{
-    ParseError, TarsClient,
-    types::{Priority, Task, parse_date_time},
+    DiffInner, ParseError, TarsClient, TarsError,
+    types::{
+        ChecklistItem, Color as MyColor, Estimate, Priority, Recurrence, ScoreExplanation, Tag,
+        Task, parse_date_time,
+    },
 };
 use crossterm::event::KeyEvent;
 use ratatui::{
-    layout::{Constraint, Direction, Layout},
-    style::{Color, Style},
-    widgets::{Block, BorderType, Borders, Paragraph},
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style, Stylize},
+    text::{Line, Span},
+    widgets::{Block, BorderType, Borders, Paragraph, Row, Table},
 };
 use tokio::sync::mpsc::UnboundedSender;
 use tracing::info;
@@ -18,10 +22,10 @@ use tui_textarea::{Input, Key};
 use crate::{
     action::{Action, Signal},
     components::Component,
-    tree::{TarsKind, TarsTreeHandle},
+    tree::{TarsKind, TarsTree, TarsTreeHandle},
 };
 
-use super::TarsText;
+use super::{TarsText, draw_conflict};
 
 #[derive(Debug)]
 pub struct TaskComponent<'a> {
@@ -34,9 +38,24 @@ pub struct TaskComponent<'a> {
     static_draw_info: StaticDrawInfo<'a>,
     reactive_widgets: ReactiveWidgets<'a>,
     on_update: OnUpdate,
+    checklist_cursor: usize,
+    checklist_entry: Option<ChecklistEntry>,
+    /// Why the task scored what it did, refetched whenever the task changes.
+    score: Option<ScoreExplanation>,
+    /// The task as the daemon has it, when a sync was refused because it changed elsewhere.
+    conflict: Option<Task>,
     pub active: bool,
 }
 
+/// What the text entered in checklist mode is for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ChecklistEntry {
+    /// A new item, inserted below the cursor.
+    Add,
+    /// A new name for the item under the cursor.
+    Rename,
+}
+
 #[derive(Debug)]
 enum OnUpdate {
     NoOp,
@@ -49,6 +68,12 @@ enum EditMode {
     Name,
     Priority,
     Due,
+    Tags,
+    Recurrence,
+    Estimate,
+    Checklist,
+    /// Waiting on whether to reload the task or overwrite it after a refused sync.
+    Conflict,
 }
 
 #[derive(Debug)]
@@ -56,6 +81,10 @@ struct ReactiveWidgets<'a> {
     name: TarsText<'a>,
     due: TarsText<'a>,
     priority: TarsText<'a>,
+    tags: TarsText<'a>,
+    recurrence: TarsText<'a>,
+    estimate: TarsText<'a>,
+    checklist_item: TarsText<'a>,
 }
 
 impl From<&Task> for ReactiveWidgets<'_> {
@@ -83,10 +112,59 @@ impl From<&Task> for ReactiveWidgets<'_> {
                 .border_type(BorderType::Rounded),
         );
 
+        // edited as a comma separated list of names
+        let tags = TarsText::new(
+            &value
+                .tags
+                .iter()
+                .map(|t| (*t.name).clone())
+                .collect::<Vec<_>>()
+                .join(", "),
+            Block::new()
+                .title_top("T[a]gs")
+                .borders(Borders::all())
+                .border_type(BorderType::Rounded),
+        );
+
+        let recurrence = TarsText::new(
+            &value
+                .recurrence
+                .as_ref()
+                .map(|r| r.to_string())
+                .unwrap_or_else(|| "None".to_string()),
+            Block::new()
+                .title_top("Rep[e]at")
+                .borders(Borders::all())
+                .border_type(BorderType::Rounded),
+        );
+
+        let estimate = TarsText::new(
+            &value
+                .estimate
+                .map(|e| e.to_string())
+                .unwrap_or_else(|| "None".to_string()),
+            Block::new()
+                .title_top("Esti[m]ate")
+                .borders(Borders::all())
+                .border_type(BorderType::Rounded),
+        );
+
+        let checklist_item = TarsText::new(
+            "",
+            Block::new()
+                .title_top("Item")
+                .borders(Borders::all())
+                .border_type(BorderType::Rounded),
+        );
+
         ReactiveWidgets {
             name,
             due,
             priority,
+            tags,
+            recurrence,
+            estimate,
+            checklist_item,
         }
     }
 }
@@ -97,19 +175,27 @@ struct StaticDrawInfo<'a> {
     task_layout: Layout,
     group_priority_layout: Layout,
     group: Paragraph<'a>,
+    tags: Paragraph<'a>,
+    dependencies_layout: Layout,
+    blockers: Paragraph<'a>,
+    dependents: Paragraph<'a>,
+    description_checklist_layout: Layout,
     description: Paragraph<'a>,
     completion_due_layout: Layout,
     completion: Paragraph<'a>,
 }
 
-impl From<&Task> for StaticDrawInfo<'_> {
-    fn from(value: &Task) -> Self {
+impl StaticDrawInfo<'_> {
+    /// The tree is needed to look up the tasks on either side of this one's dependencies.
+    fn new(value: &Task, tree: &TarsTree) -> Self {
         let task_layout = Layout::new(
             Direction::Vertical,
             [
                 Constraint::Percentage(15), // name
                 Constraint::Percentage(15), // group | Priority
-                Constraint::Percentage(50), // Description
+                Constraint::Percentage(10), // Tags
+                Constraint::Percentage(10), // blocked by | blocks
+                Constraint::Percentage(30), // Description | Checklist | Score
                 Constraint::Percentage(15), // completion | Due
             ],
         );
@@ -127,6 +213,87 @@ impl From<&Task> for StaticDrawInfo<'_> {
                 .style(Style::new().fg((&value.group.color).into())),
         );
 
+        let tags = {
+            let chips: Vec<Span> = value
+                .tags
+                .iter()
+                .flat_map(|t| {
+                    [
+                        Span::styled(
+                            format!(" {} ", *t.name),
+                            Style::new().bg((&t.color).into()).fg(Color::Black),
+                        ),
+                        Span::raw(" "),
+                    ]
+                })
+                .collect();
+
+            Paragraph::new(Line::from(chips)).block(
+                Block::new()
+                    .title_top("T[a]gs")
+                    .borders(Borders::all())
+                    .border_type(BorderType::Rounded),
+            )
+        };
+
+        let dependencies_layout = Layout::new(
+            Direction::Horizontal,
+            [Constraint::Percentage(50), Constraint::Percentage(50)],
+        );
+
+        let blockers = {
+            let names: Vec<Span> = value
+                .blocked_by
+                .iter()
+                .filter_map(|id| tree.get_task(id))
+                .map(|t| {
+                    // finished blockers don't hold anything up anymore
+                    let style = if t.finished_at.is_some() {
+                        Style::new().crossed_out()
+                    } else {
+                        Style::new()
+                    };
+                    Span::styled(format!("{} ", *t.name), style.fg((&t.group.color).into()))
+                })
+                .collect();
+
+            Paragraph::new(Line::from(names)).block(
+                Block::new()
+                    .title_top("Blocked by")
+                    .borders(Borders::all())
+                    .border_type(BorderType::Rounded),
+            )
+        };
+
+        let dependents = {
+            let names: Vec<Span> = tree
+                .dependents(&value.id)
+                .into_iter()
+                .map(|t| {
+                    Span::styled(
+                        format!("{} ", *t.name),
+                        Style::new().fg((&t.group.color).into()),
+                    )
+                })
+                .collect();
+
+            Paragraph::new(Line::from(names)).block(
+                Block::new()
+                    .title_top("Blocks")
+                    .borders(Borders::all())
+                    .border_type(BorderType::Rounded),
+            )
+        };
+
+        let description_checklist_layout = Layout::new(
+            Direction::Horizontal,
+            [
+                Constraint::Percentage(45), // description
+                Constraint::Percentage(30), // checklist
+                Constraint::Percentage(25), // score
+            ],
+        );
+
         let description = Paragraph::new(value.description.clone()).block(
             Block::new()
                 .title_top("[D]escription")
@@ -136,7 +303,12 @@ impl From<&Task> for StaticDrawInfo<'_> {
 
         let completion_due_layout = Layout::new(
             Direction::Horizontal,
-            [Constraint::Percentage(50), Constraint::Percentage(50)],
+            [
+                Constraint::Percentage(34), // completion
+                Constraint::Percentage(26), // due
+                Constraint::Percentage(22), // repeat
+                Constraint::Percentage(18), // estimate
+            ],
         );
 
         let completion = {
@@ -166,6 +338,11 @@ impl From<&Task> for StaticDrawInfo<'_> {
             task_layout,
             group_priority_layout,
             group,
+            tags,
+            dependencies_layout,
+            blockers,
+            dependents,
+            description_checklist_layout,
             description,
             completion_due_layout,
             completion,
@@ -174,11 +351,16 @@ impl From<&Task> for StaticDrawInfo<'_> {
 }
 
 impl<'a> TaskComponent<'a> {
-    pub fn new(task: &Task, client: TarsClient, tree_handle: TarsTreeHandle) -> Result<Self> {
+    pub fn new(
+        task: &Task,
+        tree: &TarsTree,
+        client: TarsClient,
+        tree_handle: TarsTreeHandle,
+    ) -> Result<Self> {
         //DEBUG
         info!("new task component! :{task:#?}");
         let reactive_draw_info = ReactiveWidgets::from(task);
-        let static_draw_info = StaticDrawInfo::from(task);
+        let static_draw_info = StaticDrawInfo::new(task, tree);
         Ok(Self {
             reactive_widgets: reactive_draw_info,
             client,
@@ -189,10 +371,19 @@ impl<'a> TaskComponent<'a> {
             static_draw_info,
             on_update: OnUpdate::NoOp,
             tree_handle,
+            checklist_cursor: 0,
+            checklist_entry: None,
+            score: None,
+            conflict: None,
             active: false,
         })
     }
 
+    /// Fetches why the task scored what it did, the table is left empty if the daemon can't say.
+    async fn refresh_score(&mut self) {
+        self.score = self.task.explain_score(&self.client).await.ok();
+    }
+
     async fn sync(&mut self) -> Result<()> {
         let new_name = self.reactive_widgets.name.textarea.lines()[0].clone();
 
@@ -200,12 +391,320 @@ impl<'a> TaskComponent<'a> {
             self.task.name = new_name.into();
         };
 
-        self.task.sync(&self.client).await?;
-
-        self.on_update = OnUpdate::ReRender;
+        match self.task.sync(&self.client).await {
+            Ok(()) => self.on_update = OnUpdate::ReRender,
+            // someone else got there first, the edits stay around until the user decides
+            Err(TarsError::Conflict(current)) => {
+                if let DiffInner::Task(current) = *current {
+                    self.conflict = Some(current);
+                }
+            }
+            Err(e) => return Err(e.into()),
+        }
 
         Ok(())
     }
+
+    /// Opens the conflict prompt if the last sync was refused.
+    fn prompt_conflict(&mut self) -> Option<Signal> {
+        self.conflict.as_ref()?;
+        self.edit_mode = EditMode::Conflict;
+        Some(Signal::RawText)
+    }
+
+    /// Leaves the current edit mode, for the conflict prompt if the sync was refused.
+    fn finish_edit(&mut self) -> Signal {
+        self.edit_mode = EditMode::Inactive;
+        self.prompt_conflict().unwrap_or(Signal::Refresh)
+    }
+
+    /// Handles a key while the conflict prompt is up, either taking on the task as the daemon
+    /// has it or syncing the edits on top of it.
+    async fn handle_conflict_key(&mut self, key: KeyEvent) -> Result<Option<Signal>> {
+        match key.into() {
+            Input {
+                key: Key::Char('r'),
+                ..
+            } => {
+                let Some(current) = self.conflict.take() else {
+                    return Ok(Some(self.finish_edit()));
+                };
+
+                let tree = self.tree_handle.read().await;
+                self.static_draw_info = StaticDrawInfo::new(&current, &tree);
+                drop(tree);
+
+                self.description = current.description.clone();
+                self.reactive_widgets = ReactiveWidgets::from(&current);
+                self.checklist_cursor = 0;
+                self.task = current;
+
+                self.refresh_score().await;
+                Ok(Some(self.finish_edit()))
+            }
+            Input {
+                key: Key::Char('o'),
+                ..
+            } => {
+                if let Some(current) = self.conflict.take() {
+                    self.task.version = current.version;
+                    self.sync().await?;
+                }
+                Ok(Some(self.finish_edit()))
+            }
+            // leaves the edits as they are, the next sync asks again
+            Input { key: Key::Esc, .. } => {
+                self.conflict = None;
+                Ok(Some(self.finish_edit()))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// Resolves the entered tag names, creating any tag that doesn't exist yet, and
+    /// syncs them onto the task.
+    async fn sync_tags(&mut self) -> Result<()> {
+        let entered = self.reactive_widgets.tags.textarea.lines()[0].clone();
+        let existing = Tag::fetch_all(&self.client).await?;
+
+        let mut tags = Vec::new();
+        for name in entered.split(',').map(str::trim).filter(|n| !n.is_empty()) {
+            let tag = match existing.iter().find(|t| *t.name == name) {
+                Some(tag) => tag.clone(),
+                None => Tag::new(&self.client, name, MyColor::random()).await?,
+            };
+            tags.push(tag);
+        }
+
+        self.task.set_tags(tags);
+        self.sync().await
+    }
+
+    /// Handles a key while in checklist mode, edits stay local until the mode is left.
+    async fn handle_checklist_key(&mut self, key: KeyEvent) -> Result<Option<Signal>> {
+        let checklist = &mut self.task.checklist;
+
+        if let Some(entry) = self.checklist_entry {
+            match key.into() {
+                Input { key: Key::Esc, .. } => {}
+                Input {
+                    key: Key::Enter, ..
+                } => {
+                    let name = self.reactive_widgets.checklist_item.textarea.lines()[0]
+                        .trim()
+                        .to_owned();
+
+                    if !name.is_empty() {
+                        match entry {
+                            ChecklistEntry::Add => {
+                                let at = (self.checklist_cursor + 1).min(checklist.len());
+                                checklist.insert(at, ChecklistItem::new(name));
+                                self.checklist_cursor = at;
+                            }
+                            ChecklistEntry::Rename => {
+                                if let Some(item) = checklist.get_mut(self.checklist_cursor) {
+                                    item.name = name;
+                                }
+                            }
+                        }
+                    }
+                }
+                input => {
+                    self.reactive_widgets.checklist_item.textarea.input(input);
+                    return Ok(None);
+                }
+            }
+
+            self.checklist_entry = None;
+            self.reactive_widgets = ReactiveWidgets::from(&self.task);
+            return Ok(None);
+        }
+
+        match key.into() {
+            Input { key: Key::Esc, .. }
+            | Input {
+                key: Key::Enter, ..
+            } => {
+                self.sync().await?;
+                return Ok(Some(self.finish_edit()));
+            }
+            Input {
+                key: Key::Char('j'),
+                ..
+            }
+            | Input { key: Key::Down, .. } => {
+                if self.checklist_cursor + 1 < checklist.len() {
+                    self.checklist_cursor += 1;
+                }
+            }
+            Input {
+                key: Key::Char('k'),
+                ..
+            }
+            | Input { key: Key::Up, .. } => {
+                self.checklist_cursor = self.checklist_cursor.saturating_sub(1);
+            }
+            Input {
+                key: Key::Char('J'),
+                ..
+            } => {
+                if self.checklist_cursor + 1 < checklist.len() {
+                    checklist.swap(self.checklist_cursor, self.checklist_cursor + 1);
+                    self.checklist_cursor += 1;
+                }
+            }
+            Input {
+                key: Key::Char('K'),
+                ..
+            } => {
+                if self.checklist_cursor > 0 && self.checklist_cursor < checklist.len() {
+                    checklist.swap(self.checklist_cursor, self.checklist_cursor - 1);
+                    self.checklist_cursor -= 1;
+                }
+            }
+            Input {
+                key: Key::Char(' '),
+                ..
+            } => {
+                if let Some(item) = checklist.get_mut(self.checklist_cursor) {
+                    item.done = !item.done;
+                }
+            }
+            Input {
+                key: Key::Char('a'),
+                ..
+            } => {
+                self.reactive_widgets.checklist_item.activate();
+                self.checklist_entry = Some(ChecklistEntry::Add);
+            }
+            Input {
+                key: Key::Char('r'),
+                ..
+            } => {
+                if let Some(item) = checklist.get(self.checklist_cursor) {
+                    self.reactive_widgets
+                        .checklist_item
+                        .textarea
+                        .insert_str(&item.name);
+                    self.reactive_widgets.checklist_item.activate();
+                    self.checklist_entry = Some(ChecklistEntry::Rename);
+                }
+            }
+            Input {
+                key: Key::Char('d'),
+                ..
+            } => {
+                if self.checklist_cursor < checklist.len() {
+                    checklist.remove(self.checklist_cursor);
+                    self.checklist_cursor =
+                        self.checklist_cursor.min(checklist.len().saturating_sub(1));
+                }
+            }
+            _ => {}
+        }
+
+        Ok(None)
+    }
+
+    /// Draws the factors behind the task's score, one row per factor.
+    fn draw_score(&self, frame: &mut ratatui::Frame, area: Rect) {
+        let mut block = Block::new()
+            .title_top("Score")
+            .borders(Borders::all())
+            .border_type(BorderType::Rounded);
+
+        let Some(ref explanation) = self.score else {
+            frame.render_widget(block, area);
+            return;
+        };
+        block = block.title_top(Line::from(explanation.strategy.to_string()).right_aligned());
+
+        let factor = |name: String, value: String| Row::new([name, value]);
+        let mut rows = vec![factor(
+            format!("prio {}", explanation.priority),
+            format!("{:.3}", explanation.priority_multiplier),
+        )];
+        rows.extend(
+            explanation
+                .groups
+                .iter()
+                .map(|g| factor(format!("× {}", *g.name), format!("{:.3}", g.multiplier))),
+        );
+        if let Some(days_left) = explanation.days_left {
+            rows.push(factor("days left".into(), format!("{days_left:.1}")));
+        }
+        if explanation.blocked_dependents > 0 {
+            rows.push(factor(
+                "blocks".into(),
+                explanation.blocked_dependents.to_string(),
+            ));
+        }
+
+        rows.extend(
+            explanation
+                .terms
+                .iter()
+                .map(|t| factor(t.name.clone(), format!("{:.3}", t.value)).dim()),
+        );
+        rows.push(factor("= score".into(), format!("{:.3}", explanation.score)).bold());
+
+        let table = Table::new(rows, [Constraint::Min(0), Constraint::Length(8)]).block(block);
+        frame.render_widget(table, area);
+    }
+
+    /// Draws the checklist, with the cursor and entry line while it's being edited.
+    fn draw_checklist(&self, frame: &mut ratatui::Frame, area: Rect) {
+        let editing = self.edit_mode == EditMode::Checklist;
+
+        let lines: Vec<Line> = self
+            .task
+            .checklist
+            .iter()
+            .enumerate()
+            .map(|(i, item)| {
+                let mark = if item.done { "[x] " } else { "[ ] " };
+                let mut style = if item.done {
+                    Style::new().add_modifier(Modifier::DIM | Modifier::CROSSED_OUT)
+                } else {
+                    Style::new()
+                };
+                if editing && i == self.checklist_cursor {
+                    style = style.bg(Color::Rgb(70, 70, 70));
+                }
+
+                Line::from(vec![
+                    Span::raw(mark),
+                    Span::styled(item.name.clone(), style),
+                ])
+            })
+            .collect();
+
+        let title = match self.task.checklist_progress() {
+            Some((done, total)) => format!("Check[l]ist {done}/{total}"),
+            None => "Check[l]ist".to_owned(),
+        };
+        let mut block = Block::new()
+            .title_top(title)
+            .borders(Borders::all())
+            .border_type(BorderType::Rounded);
+        if editing {
+            block = block.title_bottom("[a]dd [r]ename [d]elete <space> toggle J/K move");
+        }
+
+        let [list_area, entry_area] = Layout::new(
+            Direction::Vertical,
+            [
+                Constraint::Min(0),
+                Constraint::Length(if self.checklist_entry.is_some() { 3 } else { 0 }),
+            ],
+        )
+        .areas(area);
+
+        frame.render_widget(Paragraph::new(lines).block(block), list_area);
+        if self.checklist_entry.is_some() {
+            frame.render_widget(&self.reactive_widgets.checklist_item.textarea, entry_area);
+        }
+    }
 }
 
 #[async_trait]
@@ -228,11 +727,17 @@ impl Component for TaskComponent<'_> {
                 if let TarsKind::Task(ref task) = node.data().kind {
                     self.task = task.clone();
                     self.description = task.description.clone();
-                    self.static_draw_info = StaticDrawInfo::from(task);
+                    self.static_draw_info = StaticDrawInfo::new(task, &tree);
+                    self.checklist_cursor = 0;
+                    self.checklist_entry = None;
+                    self.conflict = None;
 
                     let reactive_draw_info = ReactiveWidgets::from(task);
                     self.reactive_widgets = reactive_draw_info;
                 }
+                drop(tree);
+
+                self.refresh_score().await;
                 Ok(None)
             }
 
@@ -244,10 +749,12 @@ impl Component for TaskComponent<'_> {
                     if let TarsKind::Task(ref task) = node.data().kind {
                         self.task = task.clone();
                         self.description = task.description.clone();
-                        self.static_draw_info = StaticDrawInfo::from(task);
+                        self.static_draw_info = StaticDrawInfo::new(task, &tree);
                         self.reactive_widgets = ReactiveWidgets::from(task);
                     }
+                    drop(tree);
 
+                    self.refresh_score().await;
                     self.on_update = OnUpdate::NoOp;
                     Ok(None)
                 }
@@ -288,13 +795,43 @@ impl Component for TaskComponent<'_> {
                             self.task.finished_at = Some(current_time);
                         }
 
-                        self.sync().await.map(|_| None)
+                        self.sync().await?;
+                        Ok(self.prompt_conflict())
                     }
                     Action::EditDue => {
                         self.reactive_widgets.due.activate();
                         self.edit_mode = EditMode::Due;
                         Ok(Some(Signal::RawText))
                     }
+                    Action::EditRecurrence => {
+                        self.reactive_widgets.recurrence.activate();
+                        self.edit_mode = EditMode::Recurrence;
+                        Ok(Some(Signal::RawText))
+                    }
+                    Action::EditEstimate => {
+                        self.reactive_widgets.estimate.activate();
+                        self.edit_mode = EditMode::Estimate;
+                        Ok(Some(Signal::RawText))
+                    }
+                    Action::EditChecklist => {
+                        self.checklist_cursor = 0;
+                        self.edit_mode = EditMode::Checklist;
+                        Ok(Some(Signal::RawText))
+                    }
+                    Action::EditTags => {
+                        // start from the current tags, so clearing the line removes them all
+                        let current = self
+                            .task
+                            .tags
+                            .iter()
+                            .map(|t| (*t.name).clone())
+                            .collect::<Vec<_>>()
+                            .join(", ");
+                        self.reactive_widgets.tags.textarea.insert_str(current);
+                        self.reactive_widgets.tags.activate();
+                        self.edit_mode = EditMode::Tags;
+                        Ok(Some(Signal::RawText))
+                    }
                     _ => Ok(None),
                 }
             }
@@ -311,6 +848,8 @@ impl Component for TaskComponent<'_> {
     async fn handle_key_event(&mut self, key: KeyEvent) -> Result<Option<Signal>> {
         match self.edit_mode {
             EditMode::Inactive => {}
+            EditMode::Conflict => return self.handle_conflict_key(key).await,
+            EditMode::Checklist => return self.handle_checklist_key(key).await,
             EditMode::Name => {
                 match key.into() {
                     Input { key: Key::Esc, .. }
@@ -319,8 +858,7 @@ impl Component for TaskComponent<'_> {
                     } => {
                         self.reactive_widgets.name.deactivate();
                         self.sync().await?;
-                        self.edit_mode = EditMode::Inactive;
-                        return Ok(Some(Signal::Refresh));
+                        return Ok(Some(self.finish_edit()));
                         // can validate here
                     }
                     input => {
@@ -346,8 +884,7 @@ impl Component for TaskComponent<'_> {
                             .priority
                             .textarea
                             .set_placeholder_text(self.task.priority);
-                        self.edit_mode = EditMode::Inactive;
-                        return Ok(Some(Signal::Refresh));
+                        return Ok(Some(self.finish_edit()));
                     }
                     input => {
                         if self.reactive_widgets.priority.textarea.input(input) {
@@ -378,6 +915,106 @@ impl Component for TaskComponent<'_> {
                     }
                 };
             }
+            EditMode::Tags => match key.into() {
+                Input { key: Key::Esc, .. } => {
+                    self.reactive_widgets = ReactiveWidgets::from(&self.task);
+                    self.edit_mode = EditMode::Inactive;
+                    return Ok(Some(Signal::Refresh));
+                }
+                Input {
+                    key: Key::Enter, ..
+                } => {
+                    self.reactive_widgets.tags.deactivate();
+                    self.sync_tags().await?;
+                    return Ok(Some(self.finish_edit()));
+                }
+                input => {
+                    self.reactive_widgets.tags.textarea.input(input);
+                }
+            },
+            EditMode::Recurrence => match key.into() {
+                Input { key: Key::Esc, .. }
+                | Input {
+                    key: Key::Enter, ..
+                } => {
+                    self.reactive_widgets.recurrence.deactivate();
+                    if self.reactive_widgets.recurrence.is_valid {
+                        self.sync().await?;
+                    }
+                    return Ok(Some(self.finish_edit()));
+                }
+                input => {
+                    if self.reactive_widgets.recurrence.textarea.input(input) {
+                        let entered = self.reactive_widgets.recurrence.textarea.lines()[0].as_str();
+                        let Some(block) =
+                            self.reactive_widgets.recurrence.textarea.block().cloned()
+                        else {
+                            return Ok(None);
+                        };
+
+                        // clearing the field stops the task from repeating
+                        let block = match Recurrence::parse_str(entered) {
+                            Ok(recurrence) => {
+                                self.task.recurrence = Some(recurrence);
+                                self.reactive_widgets.recurrence.is_valid = true;
+                                block.border_style(Style::new().fg(Color::Green))
+                            }
+                            Err(_) if entered.is_empty() => {
+                                self.task.recurrence = None;
+                                self.reactive_widgets.recurrence.is_valid = true;
+                                block.border_style(Style::new().fg(Color::Green))
+                            }
+                            Err(_) => {
+                                self.reactive_widgets.recurrence.is_valid = false;
+                                block.border_style(Style::new().fg(Color::Red))
+                            }
+                        };
+
+                        self.reactive_widgets.recurrence.textarea.set_block(block);
+                    }
+                }
+            },
+            EditMode::Estimate => match key.into() {
+                Input { key: Key::Esc, .. }
+                | Input {
+                    key: Key::Enter, ..
+                } => {
+                    self.reactive_widgets.estimate.deactivate();
+                    if self.reactive_widgets.estimate.is_valid {
+                        self.sync().await?;
+                    }
+                    return Ok(Some(self.finish_edit()));
+                }
+                input => {
+                    if self.reactive_widgets.estimate.textarea.input(input) {
+                        let entered = self.reactive_widgets.estimate.textarea.lines()[0].as_str();
+                        let Some(block) = self.reactive_widgets.estimate.textarea.block().cloned()
+                        else {
+                            return Ok(None);
+                        };
+
+                        // clearing the field removes the estimate
+                        let block = match Estimate::parse_str(entered) {
+                            Ok(estimate) => {
+                                self.task.estimate = Some(estimate);
+                                self.reactive_widgets.estimate.is_valid = true;
+                                block.border_style(Style::new().fg(Color::Green))
+                            }
+                            Err(_) if entered.is_empty() => {
+                                self.task.estimate = None;
+                                self.reactive_widgets.estimate.is_valid = true;
+                                block.border_style(Style::new().fg(Color::Green))
+                            }
+                            Err(_) => {
+                                self.reactive_widgets.estimate.is_valid = false;
+                                block.border_style(Style::new().fg(Color::Red))
+                            }
+                        };
+
+                        self.reactive_widgets.estimate.textarea.set_block(block);
+                    }
+                }
+            },
             EditMode::Due => {
                 match key.into() {
                     Input { key: Key::Esc, .. }
@@ -388,8 +1025,7 @@ impl Component for TaskComponent<'_> {
                         if self.reactive_widgets.due.is_valid {
                             self.sync().await?;
                         }
-                        self.edit_mode = EditMode::Inactive;
-                        return Ok(Some(Signal::Refresh));
+                        return Ok(Some(self.finish_edit()));
                     }
                     input => {
                         if self.reactive_widgets.due.textarea.input(input) {
@@ -457,16 +1093,44 @@ impl Component for TaskComponent<'_> {
         // Priority
         frame.render_widget(&self.reactive_widgets.priority.textarea, group_priority[1]);
 
-        // Description
-        frame.render_widget(&draw_info.description, task_rects[2]);
+        // Tags, shown as chips unless they're being edited
+        if self.edit_mode == EditMode::Tags {
+            frame.render_widget(&self.reactive_widgets.tags.textarea, task_rects[2]);
+        } else {
+            frame.render_widget(&draw_info.tags, task_rects[2]);
+        }
+
+        // blocked by | blocks
+        let dependencies = draw_info.dependencies_layout.split(task_rects[3]);
+        frame.render_widget(&draw_info.blockers, dependencies[0]);
+        frame.render_widget(&draw_info.dependents, dependencies[1]);
+
+        // Description | Checklist | Score
+        let description_checklist = draw_info.description_checklist_layout.split(task_rects[4]);
+        frame.render_widget(&draw_info.description, description_checklist[0]);
+        self.draw_checklist(frame, description_checklist[1]);
+        self.draw_score(frame, description_checklist[2]);
 
-        let completion_due = draw_info.completion_due_layout.split(task_rects[3]);
+        let completion_due = draw_info.completion_due_layout.split(task_rects[5]);
         // Completion status
         frame.render_widget(&draw_info.completion, completion_due[0]);
 
         // Due Date
         frame.render_widget(&self.reactive_widgets.due.textarea, completion_due[1]);
 
+        // Recurrence
+        frame.render_widget(
+            &self.reactive_widgets.recurrence.textarea,
+            completion_due[2],
+        );
+
+        // Estimate
+        frame.render_widget(&self.reactive_widgets.estimate.textarea, completion_due[3]);
+
+        if self.conflict.is_some() {
+            draw_conflict(frame, area, "task");
+        }
+
         Ok(())
     }
 }
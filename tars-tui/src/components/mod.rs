@@ -14,6 +14,7 @@ use crate::{action::Signal, app::Mode, config::Config, tui::Event};
 
 // pub mod fps;
 // pub mod home;
+pub mod agenda;
 pub mod explorer;
 pub mod inspector;
 pub mod todo_list;
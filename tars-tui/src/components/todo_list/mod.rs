@@ -101,6 +101,11 @@ impl Component for TodoList<'_> {
                 Ok(None)
             }
 
+            Signal::TagFilter(tag) => {
+                self.state.set_tag_filter(tag.map(|t| t.id)).await?;
+                Ok(None)
+            }
+
             Signal::Select(id) => {
                 if self.state.active {
                     self.state.set_selection(id).await?;
@@ -1,11 +1,13 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
-use common::{TarsClient, TarsError, types::Task};
-use futures::future::join_all;
+use common::{
+    TarsClient, TarsError,
+    types::{Id, Task},
+};
 use id_tree::NodeId;
 use ratatui::{
     layout::{Constraint, Direction, Layout},
-    style::{Color, Style},
+    style::{Color, Modifier, Style},
     widgets::Paragraph,
 };
 use tui_scrollview::ScrollViewState;
@@ -21,6 +23,10 @@ pub struct State<'a> {
     client: TarsClient,
     draw_info: Option<DrawInfo<'a>>,
     tasks: Vec<(NodeId, Task, f64)>,
+    /// Tasks still waiting on an unfinished blocker.
+    blocked: HashSet<NodeId>,
+    /// Only tasks carrying this tag are listed, if set.
+    tag_filter: Option<Id>,
     pub scroll_state: ScrollViewState,
     pub frame_height: u16,
 }
@@ -65,6 +71,8 @@ impl<'a> State<'a> {
             tree_handle,
             draw_info: None,
             tasks: vec![],
+            blocked: HashSet::new(),
+            tag_filter: None,
             scroll_state: Default::default(),
             frame_height: 50,
         };
@@ -88,6 +96,10 @@ impl<'a> State<'a> {
             .filter_map(|(id, node)| {
                 if let TarsKind::Task(ref t) = node.data().kind
                     && t.finished_at.is_none()
+                    && self
+                        .tag_filter
+                        .as_ref()
+                        .is_none_or(|tag_id| t.tags.iter().any(|tag| tag.id == *tag_id))
                 {
                     return Some((id.clone(), t.clone()));
                 }
@@ -97,24 +109,16 @@ impl<'a> State<'a> {
             .collect();
 
         let map: HashMap<NodeId, f64> = {
-            let x: Vec<(NodeId, Result<f64, TarsError>)> = join_all(
-                tasks_in_scope
-                    .clone()
-                    .into_iter()
-                    .map(async |(id, task)| (id, task.p_score(&self.client).await))
-                    .collect::<Vec<_>>(),
-            )
-            .await;
-
-            let mut map = HashMap::new();
-
-            for (id, score) in x {
-                let score = score?;
-
-                map.insert(id, score);
-            }
+            let ids: Vec<Id> = tasks_in_scope.iter().map(|(_, t)| t.id.clone()).collect();
+            let scores = Task::p_scores(&self.client, &ids).await?;
 
-            map
+            tasks_in_scope
+                .iter()
+                .map(|(id, task)| {
+                    let score = scores.get(&task.id).copied().unwrap_or_default();
+                    (id.clone(), score)
+                })
+                .collect()
         };
 
         // sort for least to most
@@ -123,6 +127,14 @@ impl<'a> State<'a> {
         // reverse so we see highest first
         tasks_in_scope.reverse();
 
+        // blocked tasks can't be worked on yet, so they sink to the bottom
+        self.blocked = tasks_in_scope
+            .iter()
+            .filter(|(_, t)| tree.is_blocked(t))
+            .map(|(id, _)| id.clone())
+            .collect();
+        tasks_in_scope.sort_by_key(|(id, _)| self.blocked.contains(id));
+
         self.tasks = tasks_in_scope
             .into_iter()
             .map(|(id, task)| {
@@ -164,7 +176,7 @@ impl<'a> State<'a> {
                     ],
                 );
 
-                let text_style = Style::new().fg((&task.group.color).into()).bg({
+                let mut text_style = Style::new().fg((&task.group.color).into()).bg({
                     if *id == self.selection.id {
                         new_sel_idx = i;
                         if self.active {
@@ -177,7 +189,16 @@ impl<'a> State<'a> {
                     }
                 });
 
-                let task_line = Paragraph::new((*task.name).to_string()).style(text_style);
+                if self.blocked.contains(id) {
+                    text_style = text_style.add_modifier(Modifier::DIM);
+                }
+
+                let progress = task
+                    .checklist_progress()
+                    .map(|(done, total)| format!(" {done}/{total}"))
+                    .unwrap_or_default();
+                let task_line =
+                    Paragraph::new(format!("{}{progress}", *task.name)).style(text_style);
 
                 let group_line = Paragraph::new((*task.group.name).to_string()).style(text_style);
                 let prio_date = {
@@ -227,6 +248,12 @@ impl<'a> State<'a> {
         Ok(())
     }
 
+    pub async fn set_tag_filter(&mut self, tag_filter: Option<Id>) -> Result<(), TarsError> {
+        self.tag_filter = tag_filter;
+        self.calculate_draw_info().await?;
+        Ok(())
+    }
+
     pub fn get_selected_id(&self) -> &NodeId {
         &self.selection.id
     }
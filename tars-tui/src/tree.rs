@@ -1,22 +1,29 @@
 use std::{
     cmp::Ordering,
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     fmt::Debug,
     ops::{Deref, DerefMut},
     sync::Arc,
 };
 
-use color_eyre::{Result, eyre::OptionExt};
+use color_eyre::{
+    Result,
+    eyre::{OptionExt, eyre},
+};
 use common::{
-    Diff, DiffInner, TarsClient,
-    types::{Group, Id, Task, TaskFetchOptions},
+    Diff, DiffInner, Patch, TarsClient,
+    types::{Group, Id, Snapshot, Task},
 };
 use id_tree::{InsertBehavior, MoveBehavior, Node, NodeId, RemoveBehavior, Tree, TreeBuilder};
 use tokio::sync::RwLock;
 use tracing::error;
 
 #[derive(Debug)]
-pub struct TarsTree(Tree<TarsNode>);
+pub struct TarsTree {
+    tree: Tree<TarsNode>,
+    /// Number of the newest diff the tree includes.
+    seq: i64,
+}
 
 pub type TarsTreeHandle = Arc<RwLock<TarsTree>>;
 
@@ -59,22 +66,25 @@ impl Deref for TarsTree {
     type Target = Tree<TarsNode>;
 
     fn deref(&self) -> &Self::Target {
-        &self.0
+        &self.tree
     }
 }
 impl DerefMut for TarsTree {
     fn deref_mut(&mut self) -> &mut Self::Target {
-        &mut self.0
+        &mut self.tree
     }
 }
 
 impl TarsTree {
+    /// Builds the tree from a `Snapshot`, so it lines up with an exact point in the diff stream.
     pub async fn generate(client: &TarsClient) -> Result<Self> {
+        let snapshot = Snapshot::fetch(client).await?;
+
         // create a hashmap from a group id to its children groups
         let g_to_g = {
             let mut map: HashMap<Id, Vec<Group>> = HashMap::new();
 
-            for group in Group::fetch_all(client).await? {
+            for group in snapshot.groups.iter().cloned() {
                 let Some(ref parent_id) = group.parent_id else {
                     continue;
                 };
@@ -97,7 +107,7 @@ impl TarsTree {
         let g_to_t = {
             let mut map: HashMap<Id, Vec<Task>> = HashMap::new();
 
-            for task in Task::fetch(client, TaskFetchOptions::All).await? {
+            for task in snapshot.tasks {
                 let children = match map.get_mut(&task.group.id) {
                     Some(e) => e,
                     None => {
@@ -112,7 +122,10 @@ impl TarsTree {
             map
         };
 
-        let mut tree = TarsTree(TreeBuilder::new().build());
+        let mut tree = TarsTree {
+            tree: TreeBuilder::new().build(),
+            seq: snapshot.seq,
+        };
 
         let root_id: NodeId = tree.insert(
             Node::new(TarsNode {
@@ -125,8 +138,8 @@ impl TarsTree {
 
         let mut inverted_map = HashMap::new();
 
-        let all_groups = Group::fetch_all(client).await?;
-        let root_groups: Vec<&Group> = all_groups
+        let root_groups: Vec<&Group> = snapshot
+            .groups
             .iter()
             .filter(|e| e.parent_id.is_none())
             .collect();
@@ -303,8 +316,9 @@ impl TarsTree {
                     .clone();
 
                 let parent_depth = self.get(&parent_node_id)?.data().depth;
+                let task_id = t.id.clone();
 
-                self.insert(
+                let inserted = self.insert(
                     Node::new(TarsNode::new(
                         TarsKind::Task(t),
                         Some(parent_node_id.clone()),
@@ -314,6 +328,9 @@ impl TarsTree {
                 )?;
 
                 self.reorder_children(&parent_node_id)?;
+
+                // the task may have moved to a different group, so the node is brand new
+                self.inverted_map_mut().insert(task_id, inserted);
             }
             Diff::Updated(DiffInner::Group(g)) => {
                 let curr_node_id = self
@@ -344,6 +361,20 @@ impl TarsTree {
 
                     self.move_node(&curr_node_id, MoveBehavior::ToParent(&new_parent_node_id))?;
                     self.reorder_children(&new_parent_node_id)?;
+
+                    // top level groups share the depth of the root
+                    let depth = match g.parent_id {
+                        Some(_) => self.get(&new_parent_node_id)?.data().depth + 1,
+                        None => 0,
+                    };
+
+                    self.get_mut(&curr_node_id)?.replace_data(TarsNode::new(
+                        TarsKind::Group(g.clone()),
+                        Some(new_parent_node_id),
+                        depth,
+                    ));
+
+                    self.update_descendant_depths(&curr_node_id)?;
                 } else {
                     // only the node data has changed
                     let curr_node = self.get_mut(&curr_node_id)?;
@@ -367,6 +398,34 @@ impl TarsTree {
                 }
             }
 
+            // a patch only carries what changed, so fill in the rest from what the tree has
+            Diff::Patched(Patch::Task(p)) => {
+                let node_id = self
+                    .inverted_map()
+                    .get(&p.id)
+                    .expect("node should exist")
+                    .clone();
+                let TarsKind::Task(mut t) = self.get(&node_id)?.data().kind.clone() else {
+                    return Err(eyre!("patched {} isn't a task", *p.id));
+                };
+
+                p.apply_to(&mut t);
+                self.apply_diff(Diff::Updated(DiffInner::Task(t)))?;
+            }
+            Diff::Patched(Patch::Group(p)) => {
+                let node_id = self
+                    .inverted_map()
+                    .get(&p.id)
+                    .expect("node should exist")
+                    .clone();
+                let TarsKind::Group(mut g) = self.get(&node_id)?.data().kind.clone() else {
+                    return Err(eyre!("patched {} isn't a group", *p.id));
+                };
+
+                p.apply_to(&mut g);
+                self.apply_diff(Diff::Updated(DiffInner::Group(g)))?;
+            }
+
             Diff::Deleted(id) => {
                 let node_id = self
                     .inverted_map_mut()
@@ -377,10 +436,120 @@ impl TarsTree {
                 self.recur_delete(id)?;
                 let _ = self.remove_node(node_id, RemoveBehavior::DropChildren)?;
             }
+
+            // reminders don't change the shape of the tree
+            Diff::Notification(_) => {}
         };
         Ok(())
     }
 
+    /// Brings the tree back in line with the daemon after diffs were missed, by applying
+    /// the difference between the two as diffs. Unlike generating it anew, this keeps the
+    /// `NodeId`s of whatever didn't change valid.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if
+    /// + The snapshot can't be fetched.
+    /// + A group or task belongs to a group that doesn't exist.
+    pub async fn resync(&mut self, client: &TarsClient) -> Result<()> {
+        let Snapshot { seq, groups, tasks } = Snapshot::fetch(client).await?;
+
+        let current: HashSet<&Id> = groups
+            .iter()
+            .map(|g| &g.id)
+            .chain(tasks.iter().map(|t| &t.id))
+            .collect();
+        let gone: Vec<Id> = self
+            .inverted_map()
+            .keys()
+            .filter(|id| !current.contains(id))
+            .cloned()
+            .collect();
+        for id in gone {
+            // deleting a group already took everything below it along
+            if self.inverted_map().contains_key(&id) {
+                self.apply_diff(Diff::Deleted(id))?;
+            }
+        }
+
+        // parents go first, so a group is never moved below one of its own descendants
+        let mut placed: HashSet<Id> = HashSet::new();
+        let mut pending = groups;
+        while !pending.is_empty() {
+            let (ready, rest): (Vec<Group>, Vec<Group>) = pending
+                .into_iter()
+                .partition(|g| g.parent_id.as_ref().is_none_or(|p| placed.contains(p)));
+            if ready.is_empty() {
+                return Err(eyre!("groups {rest:?} have no parent to go under"));
+            }
+
+            for group in ready {
+                placed.insert(group.id.clone());
+                self.reconcile(DiffInner::Group(group))?;
+            }
+            pending = rest;
+        }
+
+        for task in tasks {
+            self.reconcile(DiffInner::Task(task))?;
+        }
+        self.seq = seq;
+
+        Ok(())
+    }
+
+    /// Applies the diff numbered `seq`, unless the tree already includes it. Returns whether
+    /// it was applied.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the diff doesn't fit the tree, see `apply_diff`.
+    pub fn apply_sequenced(&mut self, seq: i64, diff: Diff) -> Result<bool> {
+        if seq <= self.seq {
+            return Ok(false);
+        }
+
+        self.apply_diff(diff)?;
+        self.seq = seq;
+
+        Ok(true)
+    }
+
+    /// Returns the number of the newest diff the tree includes.
+    pub fn seq(&self) -> i64 {
+        self.seq
+    }
+
+    /// Adds `inner` to the tree, or updates it if it's there but differs.
+    fn reconcile(&mut self, inner: DiffInner) -> Result<()> {
+        let (id, kind) = match &inner {
+            DiffInner::Task(t) => (&t.id, TarsKind::Task(t.clone())),
+            DiffInner::Group(g) => (&g.id, TarsKind::Group(g.clone())),
+        };
+
+        let diff = match self.get_by_tars_id(id) {
+            None => Diff::Added(inner),
+            Some(node) if node.data().kind != kind => Diff::Updated(inner),
+            Some(_) => return Ok(()),
+        };
+
+        self.apply_diff(diff)
+    }
+
+    /// Recalculates the depth of everything below this node, needed after it has been moved.
+    fn update_descendant_depths(&mut self, node_id: &NodeId) -> Result<()> {
+        let depth = self.get(node_id)?.data().depth;
+        let children = self.get(node_id)?.children().clone();
+
+        for child in children {
+            self.get_mut(&child)?.data_mut().depth = depth + 1;
+            self.update_descendant_depths(&child)?;
+        }
+
+        Ok(())
+    }
+
     fn recur_delete(&mut self, id: Id) -> Result<()> {
         let node_id = self
             .inverted_map_mut()
@@ -440,6 +609,33 @@ impl TarsTree {
         Some(node)
     }
 
+    /// Returns the task with this id, if it is in the tree.
+    pub fn get_task(&self, id: &Id) -> Option<&Task> {
+        match self.get_by_tars_id(id)?.data().kind {
+            TarsKind::Task(ref t) => Some(t),
+            _ => None,
+        }
+    }
+
+    /// A task is blocked while any of the tasks blocking it is unfinished.
+    pub fn is_blocked(&self, task: &Task) -> bool {
+        task.blocked_by
+            .iter()
+            .filter_map(|id| self.get_task(id))
+            .any(|blocker| blocker.finished_at.is_none())
+    }
+
+    /// Returns the tasks waiting on the task with this id.
+    pub fn dependents(&self, id: &Id) -> Vec<&Task> {
+        self.traverse_root()
+            .into_iter()
+            .filter_map(|(_, node)| match node.data().kind {
+                TarsKind::Task(ref t) if t.blocked_by.contains(id) => Some(t),
+                _ => None,
+            })
+            .collect()
+    }
+
     pub fn translate_id_to_node_id(&self, id: &Id) -> Option<NodeId> {
         self.inverted_map().get(id).cloned()
     }